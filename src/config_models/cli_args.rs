@@ -19,6 +19,8 @@ use crate::job_queue::triton_vm::TritonVmJobPriority;
 use crate::models::blockchain::type_scripts::native_currency_amount::NativeCurrencyAmount;
 use crate::models::proof_abstractions::tasm::program::TritonVmProofJobOptions;
 use crate::models::proof_abstractions::tasm::prover_job::ProverJobSettings;
+use crate::models::proof_abstractions::timestamp::Timestamp;
+use crate::models::state::mempool::MempoolEvictionPolicy;
 use crate::models::state::tx_proving_capability::TxProvingCapability;
 use crate::models::state::wallet::scan_mode_configuration::ScanModeConfiguration;
 
@@ -38,6 +40,39 @@ pub struct Args {
     #[clap(long, value_name = "DIR")]
     pub data_dir: Option<PathBuf>,
 
+    /// Import blocks from a file, then exit.
+    ///
+    /// The file holds consecutive blocks, each one encoded with bincode and
+    /// preceded by the length of its encoding as a little-endian u64, followed
+    /// by a manifest. The file is checked against the manifest before anything
+    /// is imported. Blocks are validated as if received from a peer. Blocks
+    /// that are already known are skipped, so an interrupted import can be
+    /// resumed by running it again with the same file.
+    #[clap(long, value_name = "FILE")]
+    pub(crate) import_blocks_from_file: Option<PathBuf>,
+
+    /// Export the canonical blocks to a file, after any import, then exit.
+    ///
+    /// The file has the format read by `--import-blocks-from-file`. Which
+    /// blocks are exported is set with `--export-blocks-range`.
+    #[clap(long, value_name = "FILE")]
+    pub(crate) export_blocks_to_file: Option<PathBuf>,
+
+    /// The heights of the blocks exported with `--export-blocks-to-file`, in
+    /// the format of `--scan-blocks`. A range extending beyond the tip stops
+    /// at the tip. If no range is supplied, the whole chain is exported.
+    #[clap(long, value_name = "RANGE", value_parser = parse_range)]
+    pub(crate) export_blocks_range: Option<RangeInclusive<u64>>,
+
+    /// Check at startup that the stored blocks, the block index, and the
+    /// archival block MMR and mutator set agree with each other, and refuse to
+    /// start if they do not. The lowest inconsistent height is reported.
+    ///
+    /// Reads the index entry of every canonical block, so it takes a while on
+    /// a long chain.
+    #[clap(long)]
+    pub(crate) check_db: bool,
+
     /// Ban connections to this node from IP address.
     ///
     /// This node can still make outgoing connections to IP address.
@@ -62,6 +97,22 @@ pub struct Args {
     )]
     pub(crate) peer_tolerance: u16,
 
+    /// Peers whose standing falls below minus this value are disconnected and
+    /// banned for `--peer-ban-duration`. Unlike the ban at `--peer-tolerance`,
+    /// this ban expires by itself.
+    #[clap(
+        long,
+        default_value = "500",
+        value_name = "VALUE",
+        value_parser = clap::value_parser!(u16).range(1..),
+    )]
+    pub(crate) peer_ban_threshold: u16,
+
+    /// The duration (in seconds) during which connections to and from a peer
+    /// are refused after its standing fell below `--peer-ban-threshold`.
+    #[clap(long, default_value = "3600", value_parser = duration_from_seconds_str)]
+    pub(crate) peer_ban_duration: Duration,
+
     /// Maximum number of peers to accept connections from.
     ///
     /// Will not prevent outgoing connections made with `--peers`.
@@ -83,6 +134,14 @@ pub struct Args {
     #[clap(long)]
     pub(crate) max_connections_per_ip: Option<usize>,
 
+    /// Maximum number of new potential peers to learn per round of peer
+    /// discovery.
+    ///
+    /// Peers closer to this node in terms of discovery distance are
+    /// preferred.
+    #[clap(long, default_value = "20", value_name = "COUNT")]
+    pub(crate) max_discovered_peers_per_round: usize,
+
     /// Whether to act as bootstrapper node.
     ///
     /// Bootstrapper nodes ensure that the maximum number of peers is never
@@ -154,6 +213,17 @@ pub struct Args {
     #[clap(long, default_value = "0.01", value_parser = NativeCurrencyAmount::coins_from_str)]
     pub(crate) min_gobbling_fee: NativeCurrencyAmount,
 
+    /// The minimum fee a transaction must pay to be accepted through the REST
+    /// API, both into the transaction pool and into the mempool.
+    #[clap(long, default_value = "0.1", value_parser = NativeCurrencyAmount::coins_from_str)]
+    pub(crate) min_tx_fee: NativeCurrencyAmount,
+
+    /// How many seconds a write to the transaction pool's database waits for
+    /// another connection to release its lock before it fails with "database
+    /// is locked".
+    #[clap(long, default_value = "5", value_parser = duration_from_seconds_str)]
+    pub(crate) tx_pool_busy_timeout: Duration,
+
     /// Whether to keep the UTXO notifications for composer fees and
     /// proof-upgrader fees off chain.
     ///
@@ -199,6 +269,22 @@ pub struct Args {
     #[clap(long)]
     pub(crate) max_mempool_num_tx: Option<usize>,
 
+    /// Which transaction to drop when the mempool is full.
+    ///
+    /// E.g. --mempool-eviction-policy=oldest
+    #[clap(long, value_enum, default_value = "lowest-fee")]
+    pub(crate) mempool_eviction_policy: MempoolEvictionPolicy,
+
+    /// By how many percent the fee density of a transaction must exceed that
+    /// of the mempool transactions it conflicts with in order to replace them.
+    ///
+    /// Transactions with a higher proof quality replace conflicting
+    /// transactions regardless of their fee.
+    ///
+    /// E.g. --replace-by-fee-margin=25
+    #[clap(long, default_value = "10", value_name = "PERCENT")]
+    pub(crate) replace_by_fee_margin: u32,
+
     /// Port on which to listen for peer connections.
     #[clap(long, default_value = "9798", value_name = "PORT")]
     pub peer_port: u16,
@@ -210,6 +296,42 @@ pub struct Args {
     #[clap(long, value_name = "PORT")]
     pub(crate) rest_port: Option<u16>,
 
+    /// Maximum number of blocks returned by one batch block request to the
+    /// REST API. Larger requests are rejected.
+    ///
+    /// E.g. --max-rest-batch-blocks=50
+    #[clap(long, default_value = "100", value_name = "COUNT")]
+    pub(crate) max_rest_batch_blocks: u64,
+
+    /// Maximum number of requests the REST API serves concurrently. Requests
+    /// beyond this limit are answered with 503 Service Unavailable.
+    ///
+    /// E.g. --max-rest-connections=64
+    #[clap(long, default_value = "256", value_name = "COUNT")]
+    pub(crate) max_rest_connections: usize,
+
+    /// Size of the REST API's queue of connections that have not yet been
+    /// accepted.
+    #[clap(long, default_value = "1024", value_name = "SIZE")]
+    pub(crate) rest_listen_backlog: u32,
+
+    /// Number of recently fetched blocks the REST API keeps in memory, to
+    /// answer repeated requests for the same block without reading it from
+    /// disk again. 0 disables the cache.
+    #[clap(long, default_value = "64", value_name = "COUNT")]
+    pub(crate) rest_block_cache_size: usize,
+
+    /// How many seconds a REST API request waits for read access to the node
+    /// state before it is answered with 503 Service Unavailable, e.g. while
+    /// the node is busy applying a block.
+    #[clap(long, default_value = "10", value_parser = duration_from_seconds_str)]
+    pub(crate) rest_state_lock_timeout: Duration,
+
+    /// Pretty-print the JSON responses of the REST API. Requests can override
+    /// this with `?pretty=true` or `?pretty=false`.
+    #[clap(long)]
+    pub(crate) pretty_json: bool,
+
     /// IP on which to listen for peer connections. Will default to all network interfaces, IPv4 and IPv6.
     #[clap(short, long, default_value = "::")]
     pub(crate) listen_addr: IpAddr,
@@ -229,6 +351,29 @@ pub struct Args {
     #[clap(long, default_value = "1000", value_parser(RangedI64ValueParser::<usize>::new().range(10..100000)))]
     pub(crate) sync_mode_threshold: usize,
 
+    /// Maximum number of blocks to hold on to whose parent is not known yet.
+    ///
+    /// Such orphan blocks are validated once their parent arrives, possibly
+    /// from a different peer. When the buffer is full, the oldest orphan is
+    /// dropped. Set to 0 to disable the buffer.
+    #[clap(long, default_value = "16", value_name = "COUNT")]
+    pub(crate) max_orphan_blocks: usize,
+
+    /// Discard the bodies of blocks more than this many blocks below the tip.
+    ///
+    /// Block headers are always kept, so new blocks can still be validated and
+    /// the fork choice rule is unaffected. Pruned blocks can no longer be
+    /// served to peers or over the REST API, and reorganizations deeper than
+    /// this depth fail. If not set, all blocks are kept.
+    ///
+    /// E.g. --prune-depth=10000
+    #[clap(
+        long,
+        value_name = "DEPTH",
+        value_parser = clap::value_parser!(u64).range(100..),
+    )]
+    pub(crate) prune_depth: Option<u64>,
+
     /// IPs of nodes to connect to, e.g.: --peers 8.8.8.8:9798 --peers 8.8.4.4:1337.
     #[structopt(long)]
     pub peers: Vec<SocketAddr>,
@@ -317,6 +462,13 @@ pub struct Args {
     #[clap(long, default_value = "1800", value_parser = duration_from_seconds_str)]
     pub reconnect_cooldown: Duration,
 
+    /// How many seconds a block's timestamp may be ahead of the local clock.
+    /// Blocks from further into the future are rejected.
+    ///
+    /// Raise this on networks where clocks are poorly synchronized.
+    #[clap(long, default_value = "300", value_parser = duration_from_seconds_str)]
+    pub(crate) allowed_future_drift: Duration,
+
     /// Scan incoming blocks for inbound transactions.
     ///
     /// Keys are generated deterministically from the secret seed and a
@@ -502,6 +654,12 @@ impl Args {
         }
     }
 
+    /// How far into the future a block's timestamp may be, as block
+    /// timestamps measure it.
+    pub(crate) fn future_drift_limit(&self) -> Timestamp {
+        Timestamp::millis(self.allowed_future_drift.as_millis() as u64)
+    }
+
     /// Returns how often we should attempt to upgrade transaction proofs.
     pub(crate) fn tx_upgrade_interval(&self) -> Option<Duration> {
         match self.tx_proof_upgrade_interval {
@@ -515,6 +673,12 @@ impl Args {
         self.guess || self.compose
     }
 
+    /// Whether to import or export blocks, and exit once done, instead of
+    /// running the node.
+    pub fn imports_or_exports_blocks(&self) -> bool {
+        self.import_blocks_from_file.is_some() || self.export_blocks_to_file.is_some()
+    }
+
     pub(crate) fn proof_job_options(
         &self,
         job_priority: TritonVmJobPriority,
@@ -584,14 +748,44 @@ mod cli_args_tests {
         let default_args = Args::default();
 
         assert_eq!(1000, default_args.peer_tolerance);
+        assert_eq!(500, default_args.peer_ban_threshold);
+        assert_eq!(Duration::from_secs(3600), default_args.peer_ban_duration);
         assert_eq!(10, default_args.max_num_peers);
+        assert_eq!(20, default_args.max_discovered_peers_per_round);
+        assert_eq!(16, default_args.max_orphan_blocks);
+        assert!(default_args.prune_depth.is_none());
+        assert!(!default_args.check_db);
+        assert_eq!(
+            NativeCurrencyAmount::coins_from_str("0.1").unwrap(),
+            default_args.min_tx_fee
+        );
+        assert_eq!(Duration::from_secs(5), default_args.tx_pool_busy_timeout);
         assert_eq!(9798, default_args.peer_port);
         assert_eq!(9799, default_args.rpc_port);
+        assert_eq!(100, default_args.max_rest_batch_blocks);
+        assert_eq!(256, default_args.max_rest_connections);
+        assert_eq!(1024, default_args.rest_listen_backlog);
+        assert_eq!(64, default_args.rest_block_cache_size);
+        assert_eq!(
+            Duration::from_secs(10),
+            default_args.rest_state_lock_timeout
+        );
+        assert!(!default_args.pretty_json);
+        assert_eq!(Duration::from_secs(300), default_args.allowed_future_drift);
+        assert_eq!(None, default_args.import_blocks_from_file);
+        assert_eq!(None, default_args.export_blocks_to_file);
+        assert!(!default_args.imports_or_exports_blocks());
+        assert_eq!(None, default_args.export_blocks_range);
         assert_eq!(
             IpAddr::from(Ipv6Addr::UNSPECIFIED),
             default_args.listen_addr
         );
         assert_eq!(None, default_args.max_mempool_num_tx);
+        assert_eq!(
+            MempoolEvictionPolicy::LowestFee,
+            default_args.mempool_eviction_policy
+        );
+        assert_eq!(10, default_args.replace_by_fee_margin);
         assert_eq!(1800, default_args.tx_proof_upgrade_interval);
     }
 
@@ -22,6 +22,8 @@ use crate::models::state::wallet::wallet_file::WALLET_OUTPUT_COUNT_DB_NAME;
 
 const UTXO_TRANSFER_DIRECTORY: &str = "utxo-transfer";
 const RPC_COOKIE_FILE_NAME: &str = ".cookie"; // matches bitcoin-core name.
+const TX_POOL_DIRECTORY: &str = "tx_pool";
+const TX_POOL_DB_NAME: &str = "transactions.db";
 
 // TODO: Add `rusty_leveldb::Options` and `fs::OpenOptions` here too, since they keep being repeated.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -175,6 +177,21 @@ impl DataDirectory {
             .join(Path::new(BLOCK_INDEX_DB_NAME))
     }
 
+    /// The transaction pool directory path.
+    ///
+    /// This directory lives within `DataDirectory::root_dir_path()`.
+    pub fn tx_pool_dir_path(&self) -> PathBuf {
+        self.data_dir.join(Path::new(TX_POOL_DIRECTORY))
+    }
+
+    /// The transaction pool database file path. The database also holds the
+    /// mempool snapshot.
+    ///
+    /// This file lives within `DataDirectory::tx_pool_dir_path()`.
+    pub fn tx_pool_database_file_path(&self) -> PathBuf {
+        self.tx_pool_dir_path().join(Path::new(TX_POOL_DB_NAME))
+    }
+
     /// The file path that contains block(s) with `file_index`.
     ///
     /// Note that multiple blocks can be stored in one block file.
@@ -110,6 +110,16 @@ async fn check_if_connection_is_allowed(
         return InternalConnectionStatus::Refused(ConnectionRefusedReason::BadStanding);
     }
 
+    // Disallow connection if peer is temporarily banned because of low standing
+    if global_state
+        .net
+        .is_temporarily_banned(peer_address.ip(), SystemTime::now())
+    {
+        let ip = peer_address.ip();
+        warn!("Peer {ip}, temporarily banned for low standing, attempted to connect. Disallowing.");
+        return InternalConnectionStatus::Refused(ConnectionRefusedReason::BadStanding);
+    }
+
     if let Some(time) = global_state
         .net
         .last_disconnection_time_of_peer(other_handshake.instance_id)
@@ -755,6 +765,40 @@ mod connect_tests {
         .await;
         assert_eq!(InternalConnectionStatus::Accepted, status);
 
+        // Check that temporarily banned peers are refused until the ban expires
+        let ban_expiry = SystemTime::now() + Duration::from_secs(3600);
+        state_lock
+            .lock_guard_mut()
+            .await
+            .net
+            .ban_temporarily(peer_sa.ip(), ban_expiry);
+        status = check_if_connection_is_allowed(
+            state_lock.clone(),
+            &own_handshake,
+            &other_handshake,
+            &peer_sa,
+        )
+        .await;
+        assert_eq!(
+            InternalConnectionStatus::Refused(ConnectionRefusedReason::BadStanding),
+            status,
+        );
+
+        let expired_ban = SystemTime::now() - Duration::from_secs(1);
+        state_lock
+            .lock_guard_mut()
+            .await
+            .net
+            .ban_temporarily(peer_sa.ip(), expired_ban);
+        status = check_if_connection_is_allowed(
+            state_lock.clone(),
+            &own_handshake,
+            &other_handshake,
+            &peer_sa,
+        )
+        .await;
+        assert_eq!(InternalConnectionStatus::Accepted, status);
+
         // Then check that peers can be banned by bad behavior
         let bad_standing: PeerStanding = PeerStanding::init(
             i32::MIN,
@@ -1,72 +1,209 @@
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::net::IpAddr;
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::Mutex;
 use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 use crate::api::export::{Network, ReceivingAddress};
+use crate::locks::tokio::AtomicRwReadGuard;
+use crate::models::blockchain::shared::Hash;
 use crate::models::blockchain::transaction::utxo::Utxo;
+use crate::models::blockchain::transaction::PublicAnnouncement;
 use crate::models::blockchain::transaction::Transaction;
+use crate::models::blockchain::transaction::TransactionProof;
 use crate::models::peer::transaction_notification::TransactionNotification;
+use crate::models::peer::PeerStanding;
 use crate::models::proof_abstractions::timestamp::Timestamp;
+use crate::models::state::archival_state::BlockBodyPruned;
+use crate::models::state::mempool::Mempool;
+use crate::models::state::mempool::MempoolEvictionPolicy;
+use crate::models::state::mempool::TransactionIngress;
 use crate::models::state::mempool::TransactionOrigin;
+use crate::models::state::mining_state::MAX_NUM_EXPORTED_BLOCK_PROPOSAL_STORED;
+use crate::models::state::mining_status::MiningStatus;
+use crate::models::state::reorganization::ReorgNotification;
+use crate::models::state::transaction_kernel_id::TransactionKernelId;
 use crate::models::state::wallet::transaction_output::TxOutput;
-use crate::models::state::wallet::utxo_notification::UtxoNotifyMethod;
-use crate::tx_pool::{self, PoolState};
+use crate::models::state::wallet::utxo_notification::UtxoNotificationMedium;
+use crate::models::state::GlobalState;
+use crate::tx_pool::{self, check_min_fee, FeeBelowMinimum, InvalidPoolTxId, PoolError, PoolState};
 use crate::util_types::mutator_set::addition_record::AdditionRecord;
 use crate::util_types::mutator_set::archival_mutator_set::{
     MsMembershipProofEx, RequestMsMembershipProofEx,
 };
+use crate::util_types::mutator_set::mutator_set_accumulator::MutatorSetAccumulator;
 use crate::RPCServerToMain;
 use anyhow::Context;
 use axum::body::Body;
-use axum::extract::{DefaultBodyLimit, Path, Request, State};
+use axum::body::HttpBody;
+use axum::extract::ws::Message;
+use axum::extract::ws::WebSocket;
+use axum::extract::ws::WebSocketUpgrade;
+use axum::extract::{ConnectInfo, DefaultBodyLimit, MatchedPath, Path, Query, Request, State};
+use axum::middleware::Next;
+use axum::Extension;
 use axum::Json;
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
 use axum_extra::response::ErasedJson;
+use bincode::Options;
 use block_selector::BlockSelectorExtended;
-use bytes::Buf;
 use itertools::Itertools;
 use num_traits::Zero;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use tasm_lib::prelude::BFieldElement;
 use tasm_lib::prelude::Digest;
+use tasm_lib::twenty_first::math::bfield_codec::BFieldCodec;
+use tasm_lib::twenty_first::util_types::mmr::mmr_trait::Mmr;
 use tokio::net::TcpListener;
+use tokio::net::TcpSocket;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::OwnedSemaphorePermit;
+use tokio::sync::Semaphore;
+use tower_http::classify::ServerErrorsAsFailures;
+use tower_http::classify::SharedClassifier;
 use tower_http::cors::CorsLayer;
 use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::trace::DefaultOnRequest;
 use tower_http::trace::TraceLayer;
 use tracing::{info, error};
 
+use crate::models::blockchain::block::block_header::BlockHeader;
 use crate::models::blockchain::block::block_height::BlockHeight;
 use crate::models::blockchain::block::block_info::BlockInfo;
+use crate::models::blockchain::block::difficulty_control::Difficulty;
+use crate::models::blockchain::block::mutator_set_update::MutatorSetUpdate;
+use crate::models::blockchain::block::Block;
+use crate::models::blockchain::type_scripts::native_currency_amount;
+use crate::models::blockchain::type_scripts::native_currency_amount::AmountParseError;
 use crate::models::blockchain::type_scripts::native_currency_amount::NativeCurrencyAmount;
+use crate::models::channel::BlockProposalNotification;
+use crate::rpc_auth;
+use crate::rpc_auth::error::AuthError;
 use crate::rpc_server::MempoolTransactionInfo;
+use crate::rpc_server::ProofOfWorkPuzzle;
 use crate::{
     models::blockchain::block::block_selector::BlockSelector, rpc_server::NeptuneRPCServer,
 };
 
+/// How long the node waits after accepting a shutdown request before shutting
+/// down, so that the response reaches the client first.
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_millis(100);
 
 const FEE_ADDRESS: &str = "nolgam1nurfm22evhpscn5ddwgwa96z0048454c84hwapmvqq6rqqwqx4w34kudq6q5adjvgch8f8v9dsfz3h0vk60npzya04248umqq2xs9n9cznxzl92nh65k6pg60jesff6wu77l8e3c2h8yyjtwwd9kz00m6z7nl5vxk5929q34837shxn4x5t6p9wgheljlfs3kp7lnrl2z0an80y50lwzm704svvpw3ze5k9fkccttuhunjn96cr3jcgt80qggj5x9ltta5z3qmyxhxxmz9ns7kddcrtun0mfd5fz2d05xnkhjzp3pphc83jytrecc437gf7e9czqh9qfhw5000f43ghyc2dfa5vcl38rwzax27kuv0e0gtkj7q2ar3dt0q6y32fdp9nhtm9l4crg7ud7w6vlg28ncns5q4f86teneuu8ezs2zur30gscw5qk9dgmter2nzryph5k2r68k5xf5pf7lkjas9km6eu6jjl2ujfjv5572xqrdrymm3mne6gptpvg54qxfwp3kkm45fvc5knjecsv7w5dfx82u9kcl5mrdd39k8dgc6gddty49f4yy32nfczhxq0k5dx5qmyet273mz6ggthrtvsxtteg3ceg366pnhmgaplejmjgq7qyyc0vz43ecvry8k7p7ddysqutxgpm6w950mzcxcppe5rm6pkjv9tv5uxyx3kz8lpd744udfc8h0575lfkxuwfp4y3uf9nu3fzj8x2r4gt8y3wtwdlf3flldp0m289jc3lh0dv9372dxk7fddx3ns9acfz7cdxsluucxnrn7e8p7lx5h3ngztft68ae5fcnplekay90kvnqjnxr3e80q4xl0nufucchr66p6swa2gkptf85304wwjktllz7f2sswpx3qkpld8mku900jz0g6e2q9y806enem49qud89uqu6z8d98v9sux5anr2v88hr80jqz7t7g4dcj5spgnc0l996lrq0hfswzfwldx7klsxk82zlpfzwpfgkmu3gkdyqnh9salfwrckn95tk0k0kyhrkchhaplehldfj5wf6dnkhapaxhzwfzu8gglp2rf3jtpx7ew3hlq6yqtxtrfxu0ctwsycj9eqccnlpg77mjs292t39kz4n99vjd2yejuxztk4828yk2wk5urejc3fd00gwqmcxl4k2pw85vmxrvv8n9dv6amcgkmuhgfzfcy3wm0p5yhtvdhs4l0447au6x7kwdhmuxjgk7x80gtdmgd74zswdw0jkngwef2zctxnuktxp4e5fqftgw0yplq0d3lcrcqg6q3rw5ljc654adhee53xmmeaazg0avtzkt2q0ngsq8xuxxcax8u2x9zhcxjltcsewhe7ffzqrkznv3z3vuhar4whazsergmymz4jx2d3l8qwrlhcducztkkeygm8luwnrmh2fcrpkg79gj34u88e72ljt94aapkn5uunu457h2kc3czpgekjl2wjyuz9wcpyfk3z22xx7lx7etchn5mfqxpvjf63wcy0sd9qap8mwnmfzs5j4zh9jv8n8jdwvjyk5d3x0j42cdvh5zhq00g429j0vrvm8097vfq2fg2axhrzfuy6qv97swl39dm3q859guyk4pqv9a82kz5wgnvs84l9g3g5wjf9z888spenf97ddaprkxvxluhg268hst8jgfa78t4nrqklgvw6f630nt4yrsddwahmfcfux9gmt0zjyg9vkfrfct8qtg9lehrvgmwq4e7h6ys6r34l2xn82fy2ey5wwq0jn6vk52vugmzlpgc0aywltxqzn7dvz6dlec98en9f482vdmhf33th0k5nrpwq3qj6xg7ve09nna3kp3ff4nhknt4etqhzauc8v2047yl72yefh4zddc6g9s4ye4hvukulhhu37gqrll7qyg0sx6gtgalwgwcc50gd00m90vzca8mxykdqjhfesxre99ahmfcpa2xtqftzlvu8ag55wqm84rqapa06774v876lms39y5mx0r67mus4n45crh4j99f6wptmcmy9q8hqlnl8qgvxetx3ce3kla74uwuleh7jkzdpafgcvl7amv0s8usgg6z2nr3utc4xg5qgzaf5zw3tjnak72e0ptl86k5d2667pkzauq35c7x83tms2ysev6x20h5am89qu6mm77f8f7cemtd4hhxh4qp6ae55krpst59656mqzpzc8uup42mxrarc298n7y86ekgrgft3nkasfa30u9w50dxt6gx3rpyvpgsyv8nz3d0dhzgdtkt7gxd6nj02awyesdmncj0pwzdp59gh2c09rqfm7x8t7le70ej2dd7ncq2z2qwl0cphu8ds5hxzegur3mlrrqx0zdvmje79s86ads9v6srn2skztz7mlr47f2xs43tt2eejx0j66ukqusg2ltjjxe79efggq022u9j8dqd6qcuedrfhhm8rqg6na9rcuq35aqn40q4llseyrdz68x5enuyt7yhk3d3kqxwjfullcrqhtc82vzraw0pdgjxpjtxgjvrqeqfdn7j9ck57w2u5dppfuvkk52cc3mn28nnshn87j84vfd3tdkqu9wl037yn49l829gftaky623476hw4wc7x26al8q7mfsg56pmzlyzdmgqsa33r37k0thurnjasahp3c9z5mwk3zgtgtfvj2qydgz5su6wvewhh7yeqft8z2ze4j99qha32wagywmjuqhtff3v7wpdmrcu84zmlxd5zhf5lngp4t070uup93w7lv95uk6ckhrqq4fx8epcuynh6qwh86a03nvnjf7vxvmkae2l2qzu24pjz8wdtwqs87pfdhzcwj29ruzh9ag54zqe8qzw46azds62ug7qxgf3z00rgu5q28newruew6pcvv7w7uvs9fzchha5awsfk2xfjtyu3ml5y98m2fs7peusgwv9r78uy8w6stzgc9prtsa57l03l7sfhakkt40va06uwva5qc6vy8mztwkdw2z69xpzuf4qaz9rk83wtjqjj5xvxp4xjpeple9dxgxp0tqhqzt2f8t8r03dn0vx9tl6tnh7mn6k2tnatwqkjx0csz5fj7a3g4fs07rv2p2hxag0hc8p29hx4skh0xp6x2y6afwrs5jx8hagl8pm320wwwfeh2zsernkgul5jhpy2ea5tjf934z6qgwsxezex94w935z2txr8gw3fcsrpp4m94nmwmap3pe6xyw5qlz7yyjg9merzckv6lxe5k8rtysn7fgzy3f5ug99hzq29gpllklmja7sdjg2wwgxee6m5nqercjx48cta7qp4q6hyerdts4fc5ly0hemn9rnygwng4hckqc7le3u7jpemgjxjc4rudzdekqllkg88k9p3m0gadjm4s2ha5r42p0cv5ss44n7kfyzw4scpyjw0alt2rmuwckvezejusxsxdqu6c8ad0ja7fqh2e4";
 /// An enum of error handlers for the REST API server.
 #[derive(Debug)]
-pub struct RestError(pub String);
+pub enum RestError {
+    /// The server failed to handle a valid request. Maps to 500.
+    Internal(String),
+
+    /// The request itself is invalid and should not be retried as-is. Maps
+    /// to 400.
+    BadRequest(String),
+
+    /// The request lacks valid authentication. Maps to 401.
+    Unauthorized(String),
+
+    /// The request conflicts with the current state of the node, e.g. because
+    /// it refers to a block that is no longer canonical. Maps to 409.
+    Conflict(String),
+
+    /// The requested resource does not exist (yet). Maps to 404.
+    NotFound(String),
+
+    /// The server cannot handle the request right now, but may later. Maps to
+    /// 503.
+    ServiceUnavailable(String),
+}
 
 impl IntoResponse for RestError {
     fn into_response(self) -> Response {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Something went wrong: {}", self.0),
-        )
-            .into_response()
+        match self {
+            Self::Internal(msg) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Something went wrong: {}", msg),
+            )
+                .into_response(),
+            Self::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg).into_response(),
+            Self::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg).into_response(),
+            Self::NotFound(msg) => (StatusCode::NOT_FOUND, msg).into_response(),
+            Self::Conflict(msg) => (StatusCode::CONFLICT, msg).into_response(),
+            Self::ServiceUnavailable(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg).into_response(),
+        }
     }
 }
 
 impl From<anyhow::Error> for RestError {
     fn from(err: anyhow::Error) -> Self {
-        Self(err.to_string())
+        if err.is::<BlockBodyPruned>() {
+            return Self::NotFound(err.to_string());
+        }
+
+        Self::Internal(err.to_string())
+    }
+}
+
+impl From<FeeBelowMinimum> for RestError {
+    fn from(err: FeeBelowMinimum) -> Self {
+        Self::BadRequest(err.to_string())
+    }
+}
+
+impl From<PoolError> for RestError {
+    fn from(err: PoolError) -> Self {
+        match err {
+            PoolError::FeeTooLow(_) => Self::BadRequest(err.to_string()),
+            PoolError::Duplicate(_) => Self::Conflict(err.to_string()),
+            PoolError::NotFound(_) => Self::NotFound(err.to_string()),
+            PoolError::Db(_)
+            | PoolError::Io(_)
+            | PoolError::Corrupt(_)
+            | PoolError::Serialize(_) => Self::Internal(err.to_string()),
+        }
+    }
+}
+
+impl From<InvalidPoolTxId> for RestError {
+    fn from(err: InvalidPoolTxId) -> Self {
+        Self::BadRequest(err.to_string())
+    }
+}
+
+impl From<AuthError> for RestError {
+    fn from(err: AuthError) -> Self {
+        Self::Unauthorized(err.to_string())
+    }
+}
+
+impl From<AmountParseError> for RestError {
+    fn from(err: AmountParseError) -> Self {
+        Self::BadRequest(format!("invalid amount: {}", err))
     }
 }
 
+/// Bind the REST API's listener to `address`, keeping at most `backlog`
+/// connections queued for acceptance.
+pub(crate) fn bind_rest_listener(
+    address: SocketAddr,
+    backlog: u32,
+) -> std::io::Result<TcpListener> {
+    let socket = match address {
+        SocketAddr::V4(_) => TcpSocket::new_v4()?,
+        SocketAddr::V6(_) => TcpSocket::new_v6()?,
+    };
+    socket.set_reuseaddr(true)?;
+    socket.bind(address)?;
+    socket.listen(backlog)
+}
+
 pub(crate) async fn run_rpc_server(
     rest_listener: TcpListener,
     rpcstate: NeptuneRPCServer,
@@ -81,8 +218,19 @@ pub(crate) async fn run_rpc_server(
         ])
         .allow_headers([axum::http::header::CONTENT_TYPE]);
 
+    let request_metrics = RequestMetrics::default();
+    let metrics_state = MetricsState {
+        rpcstate: rpcstate.clone(),
+        pool_state: pool_state.clone(),
+        request_metrics: request_metrics.clone(),
+    };
+
     let router = {
         let routes = axum::Router::new()
+            .route(
+                "/metrics",
+                axum::routing::get(get_metrics).with_state(metrics_state),
+            )
             .route(
                 "/rpc/block/{*block_selector}",
                 axum::routing::get(get_block),
@@ -95,18 +243,73 @@ pub(crate) async fn run_rpc_server(
                 "/rpc/block_info/{*block_selector}",
                 axum::routing::get(get_block_info),
             )
+            .route(
+                "/rpc/block_tx_count/{*block_selector}",
+                axum::routing::get(get_block_tx_count),
+            )
+            .route("/rpc/genesis", axum::routing::get(get_genesis))
             .route(
                 "/rpc/utxo_digest/{leaf_index}",
                 axum::routing::get(get_utxo_digest),
             )
+            .route(
+                "/rpc/utxo_auth_path/{leaf_index}",
+                axum::routing::get(get_utxo_auth_path),
+            )
             .route(
                 "/rpc/mempool/{start_index}/{number}",
                 axum::routing::get(get_mempool),
             )
+            .route("/rpc/mempool_stats", axum::routing::get(get_mempool_stats))
+            .route("/rpc/in_mempool/{txid}", axum::routing::get(get_in_mempool))
+            .route(
+                "/rpc/block_template",
+                axum::routing::get(get_block_template),
+            )
+            .route(
+                "/rpc/peer_standings",
+                axum::routing::get(get_peer_standings),
+            )
+            .route(
+                "/rpc/mempool_changes/{since_seq}",
+                axum::routing::get(get_mempool_changes),
+            )
+            .route(
+                "/rpc/block_proposal",
+                axum::routing::get(get_block_proposal),
+            )
+            .route(
+                "/rpc/recent_proposals",
+                axum::routing::get(get_recent_proposals),
+            )
+            .route("/rpc/ws/reorgs", axum::routing::get(stream_reorgs))
+            .route(
+                "/rpc/ws/proposals",
+                axum::routing::get(stream_block_proposals),
+            )
+            .route(
+                "/rpc/submit_pow_solution",
+                axum::routing::post(submit_pow_solution),
+            )
+            .route("/rpc/miner/pause", axum::routing::post(pause_miner))
+            .route("/rpc/miner/resume", axum::routing::post(resume_miner))
+            .route("/rpc/shutdown", axum::routing::post(shutdown))
             .route(
                 "/rpc/blocks_time/{start}/{end}",
                 axum::routing::get(get_blocks_time),
             )
+            .route(
+                "/rpc/difficulty_history/{start}/{end}",
+                axum::routing::get(get_difficulty_history),
+            )
+            .route(
+                "/rpc/next_difficulty",
+                axum::routing::get(get_next_difficulty),
+            )
+            .route(
+                "/rpc/timestamp_anomalies/{start}/{end}",
+                axum::routing::get(get_timestamp_anomalies),
+            )
             .route(
                 "/rpc/tx/submit_tx",
                 axum::routing::post(tx_pool::router::submit_transaction)
@@ -118,7 +321,31 @@ pub(crate) async fn run_rpc_server(
             )
             .route(
                 "/rpc/tx_job_status/{id}",
-                axum::routing::get(tx_pool::router::get_transaction_status).with_state(pool_state),
+                axum::routing::get(tx_pool::router::get_transaction_status)
+                    .with_state(pool_state.clone()),
+            )
+            .route(
+                "/rpc/tx/executing_raw/{id}",
+                axum::routing::get(tx_pool::router::get_executing_transaction_raw)
+                    .with_state(pool_state.clone()),
+            )
+            .route(
+                "/rpc/tx/queue_eta",
+                axum::routing::get(tx_pool::router::get_queue_eta).with_state(pool_state.clone()),
+            )
+            .route(
+                "/rpc/health",
+                axum::routing::get(get_health).with_state((rpcstate.clone(), pool_state.clone())),
+            )
+            .route(
+                "/rpc/tx/purge_finished",
+                axum::routing::post(tx_pool::router::purge_finished)
+                    .with_state((rpcstate.clone(), pool_state.clone())),
+            )
+            .route(
+                "/rpc/tx/maintenance",
+                axum::routing::post(tx_pool::router::run_maintenance)
+                    .with_state((rpcstate.clone(), pool_state)),
             )
             .route(
                 "/rpc/tx/broadcast",
@@ -134,20 +361,47 @@ pub(crate) async fn run_rpc_server(
                 "/rpc/owner_blocks/{start}/{end}",
                 axum::routing::get(get_owner_blocks),
             )
+            .route(
+                "/rpc/blocks_guessed_by",
+                axum::routing::post(blocks_guessed_by),
+            )
             .route(
                 "/rpc/generate_membership_proof",
                 axum::routing::post(generate_restore_membership_proof),
             )
+            .route(
+                "/rpc/membership_proof_updates",
+                axum::routing::post(membership_proof_updates),
+            )
             .route(
                 "/rpc/build_utxo_index",
                 axum::routing::post(build_utxo_index),
-            );
+            )
+            .route(
+                "/rpc/scan_commitments",
+                axum::routing::post(scan_commitments),
+            )
+            .route_layer(axum::middleware::from_fn_with_state(
+                request_metrics,
+                record_request_metrics,
+            ));
 
+        let max_connections = rpcstate.state.cli().max_rest_connections;
+        let pretty_json = rpcstate.state.cli().pretty_json;
         routes
             // Pass in `Rest` to make things convenient.
             .with_state(rpcstate)
-            // Enable tower-http tracing.
-            .layer(TraceLayer::new_for_http())
+            .layer(axum::middleware::from_fn_with_state(
+                pretty_json,
+                select_json_format,
+            ))
+            // Shed load beyond the configured number of concurrent requests.
+            .layer(axum::middleware::from_fn_with_state(
+                Arc::new(Semaphore::new(max_connections)),
+                limit_concurrent_requests,
+            ))
+            // Enable tower-http tracing, logging every request.
+            .layer(access_log_layer())
             .layer(DefaultBodyLimit::disable())
             // .layer(RequestBodyLimitLayer::new(200 * 1000 * 1000))
             // Enable CORS.
@@ -162,265 +416,615 @@ pub(crate) async fn run_rpc_server(
     Ok(())
 }
 
-async fn get_block(
-    State(rpcstate): State<NeptuneRPCServer>,
-    Path(block_selector): Path<BlockSelectorExtended>,
-) -> Result<ErasedJson, RestError> {
-    let block_selector = BlockSelector::from(block_selector);
-    let state = rpcstate.state.lock_guard().await;
-    let Some(digest) = block_selector.as_digest(&state).await else {
-        return Ok(ErasedJson::pretty(Option::<crate::Block>::None));
-    };
-    let archival_state = state.chain.archival_state();
-    let Some(block) = archival_state.get_block(digest).await? else {
-        return Ok(ErasedJson::pretty(Option::<crate::Block>::None));
-    };
+type AccessLogLayer = TraceLayer<
+    SharedClassifier<ServerErrorsAsFailures>,
+    fn(&Request) -> tracing::Span,
+    DefaultOnRequest,
+    fn(&Response, Duration, &tracing::Span),
+>;
 
-    Ok(ErasedJson::pretty(block.block_with_invalid_proof()))
+/// Traces every request, logging one access log entry per response with the
+/// method, path, client IP, status, and number of bytes sent.
+fn access_log_layer() -> AccessLogLayer {
+    TraceLayer::new_for_http()
+        .make_span_with(access_log_span as fn(&Request) -> tracing::Span)
+        .on_response(log_access as fn(&Response, Duration, &tracing::Span))
 }
 
-async fn get_batch_block(
-    State(rpcstate): State<NeptuneRPCServer>,
-    Path((height, batch_size)): Path<(u64, u64)>,
-) -> Result<Vec<u8>, RestError> {
-    let mut blocks = Vec::with_capacity(batch_size as usize);
-    for cur_height in height..height + batch_size {
-        let block_selector = BlockSelector::Height(cur_height.into());
-        let state = rpcstate.state.lock_guard().await;
-        let Some(digest) = block_selector.as_digest(&state).await else {
-            break;
-        };
-        let archival_state = state.chain.archival_state();
-        let Some(block) = archival_state.get_block(digest).await? else {
-            break;
-        };
+fn access_log_span(request: &Request) -> tracing::Span {
+    let client_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map_or("unknown".to_string(), |ConnectInfo(address)| {
+            address.ip().to_string()
+        });
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        path = %request.uri().path(),
+        client_ip = %client_ip,
+    )
+}
+
+fn log_access(response: &Response, latency: Duration, _span: &tracing::Span) {
+    let bytes_sent = response.body().size_hint().exact().or_else(|| {
+        response
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok()?.parse().ok())
+    });
+    info!(
+        status = response.status().as_u16(),
+        bytes_sent,
+        latency_ms = latency.as_millis() as u64,
+        "handled request"
+    );
+}
+
+/// Upper bounds, in seconds, of the buckets of the request latency histogram.
+const REQUEST_LATENCY_BUCKETS: [f64; 9] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 1.0, 2.5, 10.0];
+
+/// Number and latency of the requests with one route and status code.
+#[derive(Debug, Clone, Copy, Default)]
+struct RouteStats {
+    count: u64,
+    total_latency: Duration,
 
-        blocks.push(block.block_with_invalid_proof());
+    /// The number of requests per latency bucket, not cumulative.
+    bucket_counts: [u64; REQUEST_LATENCY_BUCKETS.len()],
+}
+
+/// Request statistics, keyed by route and status code. Routes are identified
+/// by their path template, e.g. `/rpc/utxo_digest/{leaf_index}`, so that path
+/// parameters do not inflate the number of distinct labels.
+#[derive(Debug, Clone, Default)]
+struct RequestMetrics(Arc<Mutex<BTreeMap<(String, u16), RouteStats>>>);
+
+impl RequestMetrics {
+    fn record(&self, route: &str, status: StatusCode, latency: Duration) {
+        let mut metrics = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let stats = metrics
+            .entry((route.to_owned(), status.as_u16()))
+            .or_default();
+        stats.count += 1;
+        stats.total_latency += latency;
+        if let Some(bucket) = REQUEST_LATENCY_BUCKETS
+            .iter()
+            .position(|&upper_bound| latency.as_secs_f64() <= upper_bound)
+        {
+            stats.bucket_counts[bucket] += 1;
+        }
     }
 
-    bincode::serialize(&blocks).map_err(|e| RestError(e.to_string()))
+    fn snapshot(&self) -> BTreeMap<(String, u16), RouteStats> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
 }
 
-async fn get_utxo_digest(
-    State(rpcstate): State<NeptuneRPCServer>,
-    Path(leaf_index): Path<u64>,
-) -> Result<ErasedJson, RestError> {
-    let state = rpcstate.state.lock_guard().await;
-    let aocl = &state.chain.archival_state().archival_mutator_set.ams().aocl;
+/// Middleware recording the status code and latency of every request that was
+/// routed to one of the REST routes.
+async fn record_request_metrics(
+    State(request_metrics): State<RequestMetrics>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|route| route.as_str().to_owned());
+    let start = std::time::Instant::now();
+    let response = next.run(request).await;
+    if let Some(route) = route {
+        request_metrics.record(&route, response.status(), start.elapsed());
+    }
 
-    let digest = match leaf_index > 0 && leaf_index < aocl.num_leafs().await {
-        true => Some(aocl.get_leaf_async(leaf_index).await),
-        false => None,
-    };
+    response
+}
 
-    Ok(ErasedJson::pretty(digest))
+tokio::task_local! {
+    /// Whether JSON responses to the request being handled are pretty-printed.
+    static PRETTY_JSON: bool;
 }
-async fn get_block_info(
-    State(rpcstate): State<NeptuneRPCServer>,
-    Path(block_selector): Path<BlockSelectorExtended>,
-) -> Result<ErasedJson, RestError> {
-    let block_selector = BlockSelector::from(block_selector);
-    let state = rpcstate.state.lock_guard().await;
-    let Some(digest) = block_selector.as_digest(&state).await else {
-        return Ok(ErasedJson::pretty(Option::<BlockInfo>::None));
+
+#[derive(Debug, Deserialize, Default)]
+struct JsonFormatParams {
+    pretty: Option<bool>,
+}
+
+/// Pretty-print the JSON responses to a request if it asks for it with
+/// `?pretty=true`, or if `pretty_by_default` and it does not opt out with
+/// `?pretty=false`.
+async fn select_json_format(
+    State(pretty_by_default): State<bool>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let params = Query::<JsonFormatParams>::try_from_uri(request.uri())
+        .map(|Query(params)| params)
+        .unwrap_or_default();
+    let pretty = params.pretty.unwrap_or(pretty_by_default);
+
+    PRETTY_JSON.scope(pretty, next.run(request)).await
+}
+
+/// A JSON response, pretty-printed or compact as selected by
+/// [`select_json_format`]. Compact outside of a request.
+pub(crate) fn json_response<T: Serialize>(value: T) -> ErasedJson {
+    if PRETTY_JSON.try_with(|pretty| *pretty).unwrap_or(false) {
+        ErasedJson::pretty(value)
+    } else {
+        ErasedJson::new(value)
+    }
+}
+
+/// The permit taken by [`limit_concurrent_requests`], handed to the request's
+/// handler as an extension. WebSocket handlers move it into the socket task so
+/// that an open socket keeps counting against the limit after the upgrade
+/// response was sent.
+#[derive(Clone)]
+struct RequestPermit(#[expect(dead_code, reason = "held for its drop")] Arc<OwnedSemaphorePermit>);
+
+/// Answer 503 Service Unavailable, instead of queueing the request, when all
+/// permits of `semaphore` are taken.
+async fn limit_concurrent_requests(
+    State(semaphore): State<Arc<Semaphore>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Ok(permit) = semaphore.try_acquire_owned() else {
+        return RestError::ServiceUnavailable("too many concurrent requests".to_string())
+            .into_response();
     };
-    let tip_digest = state.chain.light_state().hash();
-    let archival_state = state.chain.archival_state();
+    let permit = Arc::new(permit);
+    request
+        .extensions_mut()
+        .insert(RequestPermit(permit.clone()));
 
-    let Some(block) = archival_state
-        .get_block(digest)
-        .await
-        .context("Failed to get block")?
-    else {
-        return Ok(ErasedJson::pretty(Option::<BlockInfo>::None));
+    next.run(request).await
+}
+
+#[derive(Clone)]
+struct MetricsState {
+    rpcstate: NeptuneRPCServer,
+    pool_state: PoolState,
+    request_metrics: RequestMetrics,
+}
+
+/// Append one metric in the Prometheus text exposition format. Each sample's
+/// series is appended to `name`; it holds the sample's labels and, for
+/// histograms, the series suffix.
+fn write_metric<V: std::fmt::Display>(
+    out: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    samples: impl IntoIterator<Item = (String, V)>,
+) {
+    out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} {kind}\n"));
+    for (series, value) in samples {
+        out.push_str(&format!("{name}{series} {value}\n"));
+    }
+}
+
+async fn render_metrics(metrics_state: &MetricsState) -> Result<String, RestError> {
+    let pending_count = metrics_state.pool_state.pending_count()?;
+    let executing_count = metrics_state.pool_state.executing_count()?;
+    let (tip_height, mempool_count, peer_count) = {
+        let state = read_state(&metrics_state.rpcstate).await?;
+        (
+            u64::from(state.chain.light_state().header().height),
+            state.mempool.len() as u64,
+            state.net.peer_map.len() as u64,
+        )
     };
-    let is_canonical = archival_state
-        .block_belongs_to_canonical_chain(digest)
-        .await;
 
-    // sibling blocks are those at the same height, with different digest
-    let sibling_blocks = archival_state
-        .block_height_to_block_digests(block.header().height)
-        .await
-        .into_iter()
-        .filter(|d| *d != digest)
-        .collect();
+    let mut out = String::new();
+    let gauges = [
+        ("neptune_tip_height", "Height of the tip.", tip_height),
+        (
+            "neptune_mempool_transactions",
+            "Number of transactions in the mempool.",
+            mempool_count,
+        ),
+        (
+            "neptune_tx_pool_pending",
+            "Number of pool transactions waiting for a prover.",
+            pending_count,
+        ),
+        (
+            "neptune_tx_pool_executing",
+            "Number of pool transactions being proven.",
+            executing_count,
+        ),
+        ("neptune_peers", "Number of connected peers.", peer_count),
+    ];
+    for (name, help, value) in gauges {
+        write_metric(&mut out, name, "gauge", help, [(String::new(), value)]);
+    }
 
-    let block_info = BlockInfo::new(
-        &block,
-        archival_state.genesis_block().hash(),
-        tip_digest,
-        sibling_blocks,
-        is_canonical,
+    let request_stats = metrics_state.request_metrics.snapshot();
+    let labels = |route: &str, status: u16| format!("route=\"{route}\",status=\"{status}\"");
+    write_metric(
+        &mut out,
+        "neptune_rpc_requests_total",
+        "counter",
+        "Number of REST requests handled, per route and status code.",
+        request_stats.iter().map(|((route, status), stats)| {
+            (format!("{{{}}}", labels(route, *status)), stats.count)
+        }),
+    );
+
+    let mut latency_samples = vec![];
+    for ((route, status), stats) in &request_stats {
+        let labels = labels(route, *status);
+        let mut cumulative_count = 0;
+        for (upper_bound, count) in REQUEST_LATENCY_BUCKETS.iter().zip(stats.bucket_counts) {
+            cumulative_count += count;
+            latency_samples.push((
+                format!("_bucket{{{labels},le=\"{upper_bound}\"}}"),
+                cumulative_count.to_string(),
+            ));
+        }
+        latency_samples.push((
+            format!("_bucket{{{labels},le=\"+Inf\"}}"),
+            stats.count.to_string(),
+        ));
+        latency_samples.push((
+            format!("_sum{{{labels}}}"),
+            stats.total_latency.as_secs_f64().to_string(),
+        ));
+        latency_samples.push((format!("_count{{{labels}}}"), stats.count.to_string()));
+    }
+    write_metric(
+        &mut out,
+        "neptune_rpc_request_duration_seconds",
+        "histogram",
+        "Latency of REST requests, per route and status code.",
+        latency_samples,
     );
 
-    Ok(ErasedJson::pretty(block_info))
+    Ok(out)
 }
 
-async fn get_mempool(
-    State(rpcstate): State<NeptuneRPCServer>,
-    Path((start_index, number)): Path<(usize, usize)>,
-) -> Result<ErasedJson, RestError> {
-    let global_state = rpcstate.state.lock_guard().await;
-    let mempool_txkids = global_state
-        .mempool
-        .get_sorted_iter()
-        .skip(start_index)
-        .take(number)
-        .map(|(txkid, _)| txkid)
-        .collect_vec();
+/// Node metrics in the Prometheus text exposition format.
+async fn get_metrics(State(metrics_state): State<MetricsState>) -> Result<Response, RestError> {
+    let body = render_metrics(&metrics_state).await?;
 
-    let (incoming, outgoing): (HashMap<_, _>, HashMap<_, _>) = {
-        let (incoming_iter, outgoing_iter) = global_state.wallet_state.mempool_balance_updates();
-        (incoming_iter.collect(), outgoing_iter.collect())
+    Ok(([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response())
+}
+
+/// Reports whether the node's stores are usable. Responds with 503 if any
+/// check fails. Also reports the number of buffered blocks whose parent is not
+/// known yet, or null if the node state is busy.
+async fn get_health(
+    State((rpcstate, pool_state)): State<(NeptuneRPCServer, PoolState)>,
+) -> (StatusCode, ErasedJson) {
+    let tx_pool_ok = match pool_state.ping() {
+        Ok(()) => true,
+        Err(e) => {
+            error!("tx pool health check failed: {e:#}");
+            false
+        }
     };
 
-    let tip_msah = global_state
-        .chain
-        .light_state()
-        .mutator_set_accumulator_after()
-        .hash();
+    let orphan_blocks = read_state(&rpcstate)
+        .await
+        .ok()
+        .map(|state| state.net.orphan_blocks.len());
 
-    let mempool_transactions = mempool_txkids
-        .iter()
-        .filter_map(|id| {
-            let mut mptxi = global_state
-                .mempool
-                .get(*id)
-                .map(|tx| (MempoolTransactionInfo::from(tx), tx.kernel.mutator_set_hash))
-                .map(|(mptxi, tx_msah)| {
-                    if tx_msah == tip_msah {
-                        mptxi.synced()
-                    } else {
-                        mptxi
-                    }
-                });
-            if mptxi.is_some() {
-                if let Some(pos_effect) = incoming.get(id) {
-                    mptxi = Some(mptxi.unwrap().with_positive_effect_on_balance(*pos_effect));
-                }
-                if let Some(neg_effect) = outgoing.get(id) {
-                    mptxi = Some(mptxi.unwrap().with_negative_effect_on_balance(*neg_effect));
-                }
-            }
+    let status = if tx_pool_ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        json_response(serde_json::json!({
+            "tx_pool_ok": tx_pool_ok,
+            "orphan_blocks": orphan_blocks,
+        })),
+    )
+}
 
-            mptxi
-        })
-        .collect_vec();
+/// A sanction applied to a peer, and when.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SanctionEvent {
+    reason: String,
+    timestamp: Timestamp,
+}
 
-    Ok(ErasedJson::pretty(mempool_transactions))
+impl SanctionEvent {
+    fn new(reason: impl std::fmt::Display, time: SystemTime) -> Self {
+        let millis = time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        Self {
+            reason: reason.to_string(),
+            timestamp: Timestamp::millis(millis as u64),
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Clone, Copy)]
-struct BlockTime {
-    height: u64,
-    time: u64,
+/// The standing of a peer. Only the latest punishment and the latest reward
+/// are recorded, not a full history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PeerStandingInfo {
+    ip: IpAddr,
+
+    /// The address of the connection, if the peer is connected.
+    connected_address: Option<SocketAddr>,
+    standing: i32,
+    latest_punishment: Option<SanctionEvent>,
+    latest_reward: Option<SanctionEvent>,
 }
 
-async fn get_blocks_time(
+impl PeerStandingInfo {
+    fn new(ip: IpAddr, connected_address: Option<SocketAddr>, standing: PeerStanding) -> Self {
+        Self {
+            ip,
+            connected_address,
+            standing: standing.standing,
+            latest_punishment: standing
+                .latest_punishment
+                .map(|(reason, time)| SanctionEvent::new(reason, time)),
+            latest_reward: standing
+                .latest_reward
+                .map(|(reason, time)| SanctionEvent::new(reason, time)),
+        }
+    }
+}
+
+/// Standings of connected peers, and of previously connected peers whose
+/// standing is stored in the database. For connected peers the live standing
+/// is reported. Worst standing first.
+async fn get_peer_standings(
     State(rpcstate): State<NeptuneRPCServer>,
-    Path((start, end)): Path<(u64, u64)>,
 ) -> Result<ErasedJson, RestError> {
-    let mut block_time_list = Vec::with_capacity((end - start + 1) as usize);
-    let state = rpcstate.state.lock_guard().await;
-    for cur_height in start..=end {
-        let block_selector = BlockSelector::Height(cur_height.into());
-        let Some(digest) = block_selector.as_digest(&state).await else {
-            break;
-        };
-        let archival_state = state.chain.archival_state();
-        let Some(block) = archival_state.get_block(digest).await? else {
-            break;
-        };
+    let global_state = read_state(&rpcstate).await?;
 
-        block_time_list.push(BlockTime {
-            height: block.header().height.into(),
-            time: block.header().timestamp.to_millis() / 1000,
-        });
+    let mut standings = global_state
+        .net
+        .peer_map
+        .iter()
+        .map(|(address, peer_info)| {
+            PeerStandingInfo::new(address.ip(), Some(*address), peer_info.standing())
+        })
+        .collect_vec();
+    for (ip, standing) in global_state.net.all_peer_standings_in_database() {
+        if standings.iter().all(|info| info.ip != ip) {
+            standings.push(PeerStandingInfo::new(ip, None, standing));
+        }
     }
+    standings.sort_by_key(|info| (info.standing, info.ip, info.connected_address));
 
-    Ok(ErasedJson::pretty(block_time_list))
+    Ok(json_response(standings))
 }
 
-async fn get_nonces(
-    State(rpcstate): State<NeptuneRPCServer>,
-    Path(count): Path<u64>,
-) -> Result<ErasedJson, RestError> {
-    let state = rpcstate.state.lock_guard().await;
-    let end: u64 = state
-        .chain
-        .archival_state()
-        .get_tip()
-        .await
-        .header()
-        .height
-        .into();
-    let start = end - count + 1;
+/// Optional tip that a block request is relative to, letting a client fetch a
+/// range of blocks over several requests without straddling a reorganization.
+#[derive(Debug, Deserialize, Default)]
+struct BlockPin {
+    /// Hex-encoded digest of a tip the client fetched earlier.
+    pin: Option<String>,
+}
 
-    let mut block_time_list = Vec::with_capacity((end - start + 1) as usize);
-    let mut count = 0;
-    for cur_height in start..=end {
-        let block_selector = BlockSelector::Height(cur_height.into());
-        let Some(digest) = block_selector.as_digest(&state).await else {
-            break;
-        };
-        let archival_state = state.chain.archival_state();
-        let Some(block) = archival_state.get_block(digest).await? else {
-            break;
-        };
+/// The height of the pinned tip `pin`. Fails with 409 if `pin` is no longer
+/// canonical.
+async fn pinned_tip_height(state: &GlobalState, pin: Digest) -> Result<BlockHeight, RestError> {
+    let archival_state = state.chain.archival_state();
+    match archival_state.get_block_header(pin).await {
+        Some(header) if archival_state.block_belongs_to_canonical_chain(pin).await => {
+            Ok(header.height)
+        }
+        _ => Err(RestError::Conflict(format!(
+            "pinned tip {pin} is not canonical"
+        ))),
+    }
+}
 
-        block_time_list.push(block.header().nonce.to_hex());
-        if block
-            .header()
-            .nonce
-            .to_hex()
-            .starts_with("0000000000000000")
+/// Resolve `block_selector` on the chain that ends in the canonical block
+/// `pin`, at height `pin_height`. The tip selector selects `pin` itself, and
+/// blocks that are not ancestors of `pin` are not found.
+async fn pinned_block_digest(
+    state: &GlobalState,
+    block_selector: BlockSelector,
+    pin: Digest,
+    pin_height: BlockHeight,
+) -> Option<Digest> {
+    let digest = match block_selector {
+        BlockSelector::Tip => pin,
+        _ => block_selector.as_digest(state).await?,
+    };
+
+    // the canonical blocks no higher than the canonical pin are its ancestors
+    let archival_state = state.chain.archival_state();
+    let is_ancestor = match archival_state.get_block_header(digest).await {
+        Some(header) => {
+            header.height <= pin_height
+                && archival_state
+                    .block_belongs_to_canonical_chain(digest)
+                    .await
+        }
+        None => false,
+    };
+
+    is_ancestor.then_some(digest)
+}
+
+/// The entity tag of a block's representation. Weak, since the JSON formatting
+/// can differ between requests for the same block.
+fn block_etag(digest: Digest) -> String {
+    format!("W/\"{}\"", digest.to_hex())
+}
+
+/// Whether the `If-None-Match` header lists `etag`, using weak comparison.
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque_tag = |tag: &str| tag.trim().trim_start_matches("W/").to_owned();
+    let etag = opaque_tag(etag);
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || opaque_tag(tag) == etag)
+}
+
+/// Number of confirmations after which a canonical block is considered buried,
+/// and responses with it may be cached for [`BURIED_BLOCK_MAX_AGE`].
+const BURIED_BLOCK_CONFIRMATIONS: u64 = 10;
+
+const BURIED_BLOCK_MAX_AGE: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// The `Cache-Control` of a response with the block selected by
+/// `block_selector`, which has `confirmations` confirmations, or `None` if the
+/// block is not canonical. Only buried blocks requested by digest are
+/// cacheable. Shallower blocks, in particular the tip, can be reorganized away,
+/// and any other selector, e.g. `tip-N` or a height, picks a different block as
+/// the chain grows or reorganizes.
+fn block_cache_control(block_selector: BlockSelector, confirmations: Option<u64>) -> String {
+    match (block_selector, confirmations) {
+        (BlockSelector::Digest(_), Some(confirmations))
+            if confirmations >= BURIED_BLOCK_CONFIRMATIONS =>
         {
-            count += 1;
+            format!("public, max-age={}", BURIED_BLOCK_MAX_AGE.as_secs())
         }
+        _ => "no-cache".to_string(),
     }
+}
 
-    let aaa = (
-        format!(
-            "block ({}-{}), {}/{} = {}%",
-            start,
-            end,
-            count,
-            end - start + 1,
-            (count * 100) / (end - start + 1)
+/// Returned instead of a block whose body this node has pruned, see
+/// `--prune-depth`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PrunedBlock {
+    digest: Digest,
+    header: BlockHeader,
+
+    /// Always `true`, such that clients can tell this apart from a block.
+    body_pruned: bool,
+}
+
+/// Returns the block, without its proof. The response carries the block digest
+/// as `ETag`; a request whose `If-None-Match` lists it is answered with
+/// 304 Not Modified, as the block stored under a digest never changes. See
+/// [`block_cache_control`] for how long the response may be cached.
+///
+/// If the block's body has been pruned, a [`PrunedBlock`] is returned instead.
+async fn get_block(
+    State(rpcstate): State<NeptuneRPCServer>,
+    Path(block_selector): Path<BlockSelectorExtended>,
+    Query(block_pin): Query<BlockPin>,
+    headers: HeaderMap,
+) -> Result<Response, RestError> {
+    let state = read_state(&rpcstate).await?;
+    let pin = match block_pin.pin {
+        Some(pin) => {
+            let pin = parse_hex_digest("pin", &pin)?;
+            Some((pin, pinned_tip_height(&state, pin).await?))
+        }
+        None => None,
+    };
+
+    // `tip-N` counts down from the pinned tip, if there is one
+    let tip_height = match pin {
+        Some((_, pin_height)) => pin_height,
+        None => state.chain.light_state().header().height,
+    };
+    let Some(block_selector) = block_selector.resolve_below(tip_height) else {
+        return Ok(json_response(Option::<crate::Block>::None).into_response());
+    };
+    let digest = match pin {
+        Some((pin, pin_height)) => {
+            pinned_block_digest(&state, block_selector, pin, pin_height).await
+        }
+        None => block_selector.as_digest(&state).await,
+    };
+    let Some(digest) = digest else {
+        return Ok(json_response(Option::<crate::Block>::None).into_response());
+    };
+    let archival_state = state.chain.archival_state();
+    let Some(block_header) = archival_state.get_block_header(digest).await else {
+        return Ok(json_response(Option::<crate::Block>::None).into_response());
+    };
+    let tip_height = state.chain.light_state().header().height;
+    let confirmations = archival_state
+        .block_belongs_to_canonical_chain(digest)
+        .await
+        .then(|| u64::from(tip_height) - u64::from(block_header.height) + 1);
+    let response_headers = [
+        (header::ETAG, block_etag(digest)),
+        (
+            header::CACHE_CONTROL,
+            block_cache_control(block_selector, confirmations),
         ),
-        block_time_list,
-    );
+    ];
+
+    if if_none_match(&headers, &response_headers[0].1) {
+        return Ok((StatusCode::NOT_MODIFIED, response_headers).into_response());
+    }
+    if archival_state.body_is_pruned(digest).await {
+        let pruned = PrunedBlock {
+            digest,
+            header: block_header,
+            body_pruned: true,
+        };
+        return Ok((response_headers, json_response(pruned)).into_response());
+    }
+    let load = || archival_state.get_block(digest);
+    let Some(block) = rpcstate.block_cache.get_or_load(digest, load).await? else {
+        return Ok(json_response(Option::<crate::Block>::None).into_response());
+    };
 
-    Ok(ErasedJson::pretty(aaa))
+    Ok((response_headers, json_response(block.without_proof())).into_response())
 }
 
-#[derive(Debug, Serialize, Clone)]
-struct SimpleBlock {
-    height: u64,
-    hash: String,
-    fee: String,
-    timestamp: u64,
+/// Version of the encoding of the blocks returned by [`get_batch_block`]. Must
+/// be bumped whenever [`Block`], or any type it contains, changes.
+pub const BATCH_BLOCK_RESPONSE_VERSION: u16 = 1;
+
+/// Decode the response of `/rpc/batch_block`. Responses of any version other
+/// than [`BATCH_BLOCK_RESPONSE_VERSION`] are rejected before their blocks are
+/// decoded.
+pub fn decode_batch_block_response(bytes: &[u8]) -> Result<Vec<Block>, VersionedResponseError> {
+    from_versioned_bytes("batch block response", BATCH_BLOCK_RESPONSE_VERSION, bytes)
 }
 
-async fn get_last_blocks(
+/// Whether `/rpc/batch_block` includes block proofs.
+#[derive(Debug, Deserialize, Default)]
+struct BatchBlockParams {
+    #[serde(default)]
+    with_proofs: bool,
+}
+
+/// Returns up to `batch_size` consecutive blocks starting at `height`,
+/// bincode-encoded and prefixed with [`BATCH_BLOCK_RESPONSE_VERSION`].
+///
+/// Blocks are returned without their proofs, see [`Block::without_proof`],
+/// unless `?with_proofs=true` is given.
+///
+/// Requests for more than `--max-rest-batch-blocks` blocks are rejected.
+async fn get_batch_block(
     State(rpcstate): State<NeptuneRPCServer>,
-    Path(count): Path<u64>,
-) -> Result<ErasedJson, RestError> {
-    let state = rpcstate.state.lock_guard().await;
-    let end: u64 = state
-        .chain
-        .archival_state()
-        .get_tip()
-        .await
-        .header()
-        .height
-        .into();
-    let start = end - count + 1;
+    Path((height, batch_size)): Path<(u64, u64)>,
+    Query(params): Query<BatchBlockParams>,
+) -> Result<Vec<u8>, RestError> {
+    let max_batch_size = rpcstate.state.cli().max_rest_batch_blocks;
+    if batch_size > max_batch_size {
+        return Err(RestError::BadRequest(format!(
+            "batch size {batch_size} exceeds maximum of {max_batch_size}"
+        )));
+    }
 
-    let mut block_time_list = Vec::with_capacity((end - start + 1) as usize);
-    for cur_height in start..=end {
+    let mut blocks = Vec::with_capacity(batch_size as usize);
+    for cur_height in height..height.saturating_add(batch_size) {
         let block_selector = BlockSelector::Height(cur_height.into());
+        let state = read_state(&rpcstate).await?;
         let Some(digest) = block_selector.as_digest(&state).await else {
             break;
         };
@@ -429,429 +1033,5008 @@ async fn get_last_blocks(
             break;
         };
 
-        block_time_list.push(SimpleBlock {
-            height: block.header().height.into(),
-            hash: block.hash().to_hex(),
-            fee: block.body().transaction_kernel.fee.to_string(),
-            timestamp: block.header().timestamp.to_millis(),
-        });
+        if params.with_proofs {
+            blocks.push(block);
+        } else {
+            blocks.push(block.without_proof());
+        }
     }
 
-    Ok(ErasedJson::pretty(block_time_list))
+    to_versioned_bytes(BATCH_BLOCK_RESPONSE_VERSION, &blocks)
+        .map_err(|e| RestError::Internal(e.to_string()))
 }
 
-async fn get_owner_blocks(
-    State(rpcstate): State<NeptuneRPCServer>,
-    Path((start, end)): Path<(u64, u64)>,
-) -> Result<ErasedJson, RestError> {
-    let state = rpcstate.state.lock_guard().await;
+/// A block proposal handed out to an external guesser.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ExportedBlockProposal {
+    /// Identifies the proposal when submitting a solution. Same as the ID of
+    /// the corresponding [`ProofOfWorkPuzzle`].
+    proposal_id: Digest,
 
-    let mut owner_block_list = Vec::new();
-    let mut reward = NativeCurrencyAmount::zero();
-    for cur_height in start..=end {
-        let block_selector = BlockSelector::Height(cur_height.into());
-        let Some(digest) = block_selector.as_digest(&state).await else {
-            break;
-        };
-        let archival_state = state.chain.archival_state();
-        let Some(block) = archival_state.get_block(digest).await? else {
-            break;
+    body_mast_hash: Digest,
+
+    /// The proposal's hash must be less than or equal to this value.
+    threshold: Digest,
+
+    proposal: Block,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct BlockProposalParams {
+    /// The hex-encoded guesser digest to put in the proposal's header, i.e.,
+    /// the after-image of the guesser's key. Defaults to the after-image of
+    /// this node's guesser key.
+    guesser_digest: Option<String>,
+}
+
+/// Returns the current block proposal with the requested guesser digest,
+/// bincode-encoded as an [`ExportedBlockProposal`], and remembers it so that a
+/// solution for it can be accepted later. Requires authentication.
+///
+/// Mirrors [`RPC::pow_puzzle_external_key`](crate::rpc_server::RPC::pow_puzzle_external_key)
+/// and [`RPC::pow_puzzle_internal_key`](crate::rpc_server::RPC::pow_puzzle_internal_key).
+async fn get_block_proposal(
+    State(mut rpcstate): State<NeptuneRPCServer>,
+    Query(params): Query<BlockProposalParams>,
+    headers: HeaderMap,
+) -> Result<Vec<u8>, RestError> {
+    authenticate(&rpcstate, &headers)?;
+    let guesser_digest = params
+        .guesser_digest
+        .map(|hex| parse_hex_digest("guesser_digest", &hex))
+        .transpose()?;
+
+    let (mut proposal, latest_block_header, guesser_digest) = {
+        let state = read_state(&rpcstate).await?;
+        let Some(proposal) = state.mining_state.block_proposal.map(|block| block.clone()) else {
+            return Err(RestError::NotFound("no block proposal known".to_string()));
         };
+        let guesser_digest = guesser_digest.unwrap_or_else(|| {
+            state
+                .wallet_state
+                .wallet_entropy
+                .guesser_spending_key(proposal.header().prev_block_digest)
+                .after_image()
+        });
 
-        let guesser_digest = state
-            .wallet_state
-            .wallet_entropy
-            .guesser_spending_key(block.header().prev_block_digest)
-            .after_image();
+        (
+            proposal,
+            *state.chain.light_state().header(),
+            guesser_digest,
+        )
+    };
 
-        if guesser_digest == block.header().guesser_digest {
-            reward = reward + block.body().transaction_kernel.fee;
-            owner_block_list.push(RewardCard {
-                block_id: block.hash(),
-                block_height: block.header().height,
-                timestamp: block.header().timestamp,
-                amount: block.body().transaction_kernel.fee.to_string(),
-            });
+    proposal.set_header_guesser_digest(guesser_digest);
+    let puzzle = ProofOfWorkPuzzle::new(proposal.clone(), latest_block_header);
+    let exported = ExportedBlockProposal {
+        proposal_id: puzzle.id,
+        body_mast_hash: BlockProposalNotification::from(&proposal).body_mast_hash,
+        threshold: puzzle.threshold,
+        proposal,
+    };
+
+    let mut state = rpcstate.state.lock_guard_mut().await;
+    let exported_block_proposals = &mut state.mining_state.exported_block_proposals;
+    if !exported_block_proposals.contains_key(&exported.proposal_id) {
+        if exported_block_proposals.len() >= MAX_NUM_EXPORTED_BLOCK_PROPOSAL_STORED {
+            return Err(RestError::ServiceUnavailable(
+                "too many exported block proposals".to_string(),
+            ));
         }
+        exported_block_proposals.insert(exported.proposal_id, exported.proposal.clone());
     }
+    drop(state);
 
-    let guess_reward = GuessReward {
-        start: start.into(),
-        end: end.into(),
-        reward: reward.to_string(),
-        records: owner_block_list,
-    };
+    bincode::serialize(&exported).map_err(|e| RestError::Internal(e.to_string()))
+}
 
-    Ok(ErasedJson::pretty(guess_reward))
+/// A block proposal notification recently sent to peers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct RecentProposal {
+    height: BlockHeight,
+    #[serde(with = "native_currency_amount::api_json")]
+    guesser_fee: NativeCurrencyAmount,
+    #[serde(with = "native_currency_amount::api_json")]
+    composer_fee: NativeCurrencyAmount,
+    body_mast_hash: Digest,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ResponseMsMembershipProofEx {
-    pub height: BlockHeight,
-    pub block_id: Digest,
-    pub proofs: Vec<MsMembershipProofEx>,
+impl From<&BlockProposalNotification> for RecentProposal {
+    fn from(notification: &BlockProposalNotification) -> Self {
+        Self {
+            height: notification.height,
+            guesser_fee: notification.guesser_fee,
+            composer_fee: notification.composer_fee,
+            body_mast_hash: notification.body_mast_hash,
+        }
+    }
 }
 
-async fn generate_restore_membership_proof(
+/// Returns the most recent block proposal notifications, most recent first.
+async fn get_recent_proposals(
     State(rpcstate): State<NeptuneRPCServer>,
-    body: axum::body::Bytes,
-) -> Result<Vec<u8>, RestError> {
-    let r_datas: Vec<RequestMsMembershipProofEx> =
-        bincode::deserialize_from(body.reader()).context("deserialize error")?;
-    let state = rpcstate.state.lock_guard().await;
+) -> Result<ErasedJson, RestError> {
+    let state = read_state(&rpcstate).await?;
+    let recent_proposals = state
+        .mining_state
+        .recent_proposal_notifications()
+        .map(RecentProposal::from)
+        .collect_vec();
 
-    let ams = state.chain.archival_state().archival_mutator_set.ams();
+    Ok(json_response(recent_proposals))
+}
 
-    let mut proofs = Vec::with_capacity(r_datas.len());
-    for r_data in r_datas {
-        if let Ok(p) = ams.restore_membership_proof_ex(r_data).await {
-            proofs.push(p);
+/// Upgrades to a WebSocket on which every reorganization is sent as a JSON
+/// [`ReorgNotification`], from the moment of the upgrade. The socket is closed
+/// if the client falls so far behind that notifications were dropped, so that
+/// a client never misses a reorganization silently. An open socket counts
+/// against the limit on concurrent requests until it is closed.
+async fn stream_reorgs(
+    State(rpcstate): State<NeptuneRPCServer>,
+    permit: Option<Extension<RequestPermit>>,
+    websocket: WebSocketUpgrade,
+) -> Result<Response, RestError> {
+    let reorgs = read_state(&rpcstate).await?.subscribe_to_reorgs();
+    Ok(websocket.on_upgrade(move |socket| async move {
+        send_reorgs(socket, reorgs).await;
+        drop(permit);
+    }))
+}
+
+async fn send_reorgs(
+    mut socket: WebSocket,
+    mut reorgs: tokio::sync::broadcast::Receiver<ReorgNotification>,
+) {
+    loop {
+        let reorg = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => continue,
+            },
+            reorg = reorgs.recv() => match reorg {
+                Ok(reorg) => reorg,
+                Err(RecvError::Lagged(num_missed)) => {
+                    tracing::warn!(
+                        "Closing reorg stream of client that missed {num_missed} notifications"
+                    );
+                    let _ = socket.send(Message::Close(None)).await;
+                    return;
+                }
+                Err(RecvError::Closed) => return,
+            },
+        };
+        let Ok(json) = serde_json::to_string(&reorg) else {
+            return;
+        };
+        if socket.send(Message::Text(json.into())).await.is_err() {
+            return;
         }
     }
+}
 
-    let cur_block = state.chain.archival_state().get_tip().await;
+/// Sent by the client on the block proposal stream to start receiving
+/// proposals, and again whenever it wants to change the filter.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ProposalSubscription {
+    /// Only send proposals with a public announcement that starts with this
+    /// prefix, e.g. a wallet's notification tag. Every proposal is sent if
+    /// absent.
+    #[serde(default)]
+    announcement_prefix: Option<Vec<BFieldElement>>,
+}
 
-    let height = cur_block.header().height;
-    let block_id = cur_block.hash();
+/// A block proposal as sent on the block proposal stream.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+struct ProposedBlock {
+    header: BlockHeader,
+    public_announcements: Vec<PublicAnnouncement>,
+}
 
-    let response = ResponseMsMembershipProofEx {
-        height,
-        block_id,
-        proofs,
-    };
-    bincode::serialize(&response).map_err(|e| RestError(e.to_string()))
+/// Upgrades to a WebSocket on which block proposals that are shared with peers
+/// are sent as JSON [`ProposedBlock`]s. Nothing is sent until the client has
+/// sent a [`ProposalSubscription`]. As with [`stream_reorgs`], the socket is
+/// closed if the client falls behind, and counts against the limit on
+/// concurrent requests until it is closed.
+async fn stream_block_proposals(
+    State(rpcstate): State<NeptuneRPCServer>,
+    permit: Option<Extension<RequestPermit>>,
+    websocket: WebSocketUpgrade,
+) -> Result<Response, RestError> {
+    let proposals = read_state(&rpcstate).await?.subscribe_to_block_proposals();
+    Ok(websocket.on_upgrade(move |socket| async move {
+        send_block_proposals(socket, proposals).await;
+        drop(permit);
+    }))
 }
 
-#[derive(Debug, Serialize, Clone)]
-struct GuessReward {
-    start: BlockHeight,
-    end: BlockHeight,
-    reward: String,
-    records: Vec<RewardCard>,
+async fn send_block_proposals(
+    mut socket: WebSocket,
+    mut proposals: tokio::sync::broadcast::Receiver<Arc<Block>>,
+) {
+    let mut subscription: Option<ProposalSubscription> = None;
+    loop {
+        tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let Ok(new_subscription) = serde_json::from_str(text.as_str()) else {
+                        let _ = socket.send(Message::Close(None)).await;
+                        return;
+                    };
+                    subscription = Some(new_subscription);
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+            proposal = proposals.recv(), if subscription.is_some() => {
+                let proposal = match proposal {
+                    Ok(proposal) => proposal,
+                    Err(RecvError::Lagged(num_missed)) => {
+                        tracing::warn!(
+                            "Closing proposal stream of client that missed {num_missed} proposals"
+                        );
+                        let _ = socket.send(Message::Close(None)).await;
+                        return;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+
+                let kernel = &proposal.body().transaction_kernel;
+                let prefix = subscription
+                    .as_ref()
+                    .and_then(|subscription| subscription.announcement_prefix.as_deref());
+                let is_wanted = match prefix {
+                    Some(prefix) => kernel.has_public_announcement_with_prefix(prefix),
+                    None => true,
+                };
+                if !is_wanted {
+                    continue;
+                }
+
+                let proposed_block = ProposedBlock {
+                    header: *proposal.header(),
+                    public_announcements: kernel.public_announcements.clone(),
+                };
+                let Ok(json) = serde_json::to_string(&proposed_block) else {
+                    return;
+                };
+                if socket.send(Message::Text(json.into())).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Clone)]
-struct RewardCard {
-    block_id: Digest,
-    block_height: BlockHeight,
-    timestamp: Timestamp,
-    amount: String,
+#[derive(Debug, Deserialize, Clone)]
+struct PowSolutionRequest {
+    /// The `proposal_id` of an [`ExportedBlockProposal`], hex-encoded.
+    proposal_id: String,
+
+    /// The nonce that makes the proposal's hash meet the threshold,
+    /// hex-encoded.
+    nonce: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct BroadcastTx {
-    transaction: Transaction,
-    origin: TransactionOrigin,
-    notification: TransactionNotification,
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+struct PowSolutionResponse {
+    accepted: bool,
 }
-async fn broadcast_transaction(
-    State(mut rpcstate): State<NeptuneRPCServer>,
-    body: axum::body::Bytes,
+
+/// Accepts a proof-of-work solution for a proposal previously handed out by
+/// [`get_block_proposal`]. If the nonce makes the proposal's hash meet the
+/// threshold, the block is passed on to the main loop.
+///
+/// Mirrors [`RPC::provide_pow_solution`](crate::rpc_server::RPC::provide_pow_solution).
+async fn submit_pow_solution(
+    State(rpcstate): State<NeptuneRPCServer>,
+    headers: HeaderMap,
+    Json(body): Json<PowSolutionRequest>,
 ) -> Result<ErasedJson, RestError> {
-    let tx: BroadcastTx = bincode::deserialize_from(body.reader()).context("deserialize error")?;
-    let tx_id = tx.transaction.kernel.txid();
-    let mut state = rpcstate.state.lock_guard_mut().await;
-    state.mempool_insert(tx.transaction, tx.origin).await;
+    authenticate(&rpcstate, &headers)?;
+    let proposal_id = parse_hex_digest("proposal_id", &body.proposal_id)?;
+    let nonce = parse_hex_digest("nonce", &body.nonce)?;
+
+    let (proposal, latest_block_header) = {
+        let state = read_state(&rpcstate).await?;
+        let proposal = state
+            .mining_state
+            .exported_block_proposals
+            .get(&proposal_id)
+            .cloned();
+        (proposal, *state.chain.light_state().header())
+    };
+
+    let Some(mut proposal) = proposal else {
+        tracing::warn!("Got PoW solution for unknown block proposal {proposal_id}");
+        return Ok(json_response(PowSolutionResponse { accepted: false }));
+    };
+
+    proposal.set_header_nonce(nonce);
+    let threshold = latest_block_header.difficulty.target();
+    let solution_digest = proposal.hash();
+    if solution_digest > threshold {
+        tracing::warn!(
+            "Got PoW solution that does not meet threshold.\n\
+            Claimed solution: {solution_digest};\nthreshold: {threshold}"
+        );
+        return Ok(json_response(PowSolutionResponse { accepted: false }));
+    }
+
     let _ = rpcstate
         .rpc_server_to_main_tx
-        .send(RPCServerToMain::BroadcastNotification(tx.notification))
+        .send(RPCServerToMain::ProofOfWorkSolution(Box::new(proposal)))
         .await;
 
-    Ok(ErasedJson::pretty(tx_id.to_string()))
+    Ok(json_response(PowSolutionResponse { accepted: true }))
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct SendTx {
-    broadcast_tx: BroadcastTx,
-    amount: String,
-    sender_randomness: String,
-    fee_address: String,
-    block_height: u64,
+/// Checks the RPC cookie presented as `Authorization: Bearer <hex cookie>`.
+pub(crate) fn authenticate(
+    rpcstate: &NeptuneRPCServer,
+    headers: &HeaderMap,
+) -> Result<(), RestError> {
+    let Some(cookie) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return Err(RestError::Unauthorized(
+            "missing bearer authentication cookie".to_string(),
+        ));
+    };
+
+    let token = rpc_auth::Token::from(rpc_auth::Cookie::try_from_hex(cookie.trim())?);
+    rpcstate.auth(&token)?;
+
+    Ok(())
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct ResponseSendTx {
-    status: u64,
-    message: String,
+/// Read access to the node state, or 503 Service Unavailable if it cannot be
+/// had within the configured `rest_state_lock_timeout`, so that a write lock
+/// held by the main loop does not make requests queue up indefinitely.
+pub(crate) async fn read_state(
+    rpcstate: &NeptuneRPCServer,
+) -> Result<AtomicRwReadGuard<'_, GlobalState>, RestError> {
+    let timeout = rpcstate.state.cli().rest_state_lock_timeout;
+    tokio::time::timeout(timeout, rpcstate.state.lock_guard())
+        .await
+        .map_err(|_| RestError::ServiceUnavailable("node state is busy".to_string()))
 }
-async fn send_transaction(
-    State(mut rpcstate): State<NeptuneRPCServer>,
-    body: axum::body::Bytes,
+
+/// Asks the node to shut down gracefully. Requires authentication.
+///
+/// Mirrors [`RPC::shutdown`](crate::rpc_server::RPC::shutdown), except that the
+/// shutdown message is only sent after [`SHUTDOWN_GRACE_PERIOD`], so that the
+/// 202 response is flushed before the node tears down the server.
+async fn shutdown(
+    State(rpcstate): State<NeptuneRPCServer>,
+    headers: HeaderMap,
+) -> Result<StatusCode, RestError> {
+    authenticate(&rpcstate, &headers)?;
+
+    info!("Received REST shutdown request.");
+    tokio::spawn(async move {
+        tokio::time::sleep(SHUTDOWN_GRACE_PERIOD).await;
+        let _ = rpcstate
+            .rpc_server_to_main_tx
+            .send(RPCServerToMain::Shutdown)
+            .await;
+    });
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// The miner's state after a request to pause or resume it.
+///
+/// The miner reacts to the request asynchronously, so `mining_status` may still
+/// show the work that was in progress when the request was made.
+#[derive(Debug, Clone, Serialize)]
+struct MinerControlResponse {
+    paused: bool,
+    mining_status: MiningStatus,
+}
+
+/// Mirrors [`RPC::pause_miner`](crate::rpc_server::RPC::pause_miner). Requires
+/// authentication.
+async fn pause_miner(
+    State(rpcstate): State<NeptuneRPCServer>,
+    headers: HeaderMap,
 ) -> Result<ErasedJson, RestError> {
-    let send_tx: SendTx = bincode::deserialize_from(body.reader()).context("deserialize error")?;
-    // 判断fee_address是否是合法的地址
-    if send_tx.fee_address != FEE_ADDRESS.to_string() {
-        return Ok(ErasedJson::pretty(ResponseSendTx {
-            status: 1,
-            message: "fee_address is not valid".to_string(),
-        }));
+    authenticate(&rpcstate, &headers)?;
+    set_miner_paused(rpcstate, true).await
+}
+
+/// Mirrors [`RPC::restart_miner`](crate::rpc_server::RPC::restart_miner).
+/// Requires authentication.
+async fn resume_miner(
+    State(rpcstate): State<NeptuneRPCServer>,
+    headers: HeaderMap,
+) -> Result<ErasedJson, RestError> {
+    authenticate(&rpcstate, &headers)?;
+    set_miner_paused(rpcstate, false).await
+}
+
+async fn set_miner_paused(
+    mut rpcstate: NeptuneRPCServer,
+    pause: bool,
+) -> Result<ErasedJson, RestError> {
+    if !rpcstate.state.cli().mine() {
+        return Err(RestError::BadRequest("miner was never started".to_string()));
+    }
+
+    let message = if pause {
+        RPCServerToMain::PauseMiner
+    } else {
+        RPCServerToMain::RestartMiner
+    };
+    let _ = rpcstate.rpc_server_to_main_tx.send(message).await;
+
+    let mut state = rpcstate.state.lock_guard_mut().await;
+    state.mining_state.paused = pause;
+
+    Ok(json_response(MinerControlResponse {
+        paused: pause,
+        mining_status: state.mining_state.mining_status,
+    }))
+}
+
+async fn get_utxo_digest(
+    State(rpcstate): State<NeptuneRPCServer>,
+    Path(leaf_index): Path<u64>,
+) -> Result<ErasedJson, RestError> {
+    let state = read_state(&rpcstate).await?;
+    let aocl = &state.chain.archival_state().archival_mutator_set.ams().aocl;
+
+    let digest = match leaf_index > 0 && leaf_index < aocl.num_leafs().await {
+        true => Some(aocl.get_leaf_async(leaf_index).await),
+        false => None,
+    };
+
+    Ok(json_response(digest))
+}
+
+/// The authentication path of an AOCL leaf, along with what a client needs to
+/// verify it against the mutator set hash of the tip: the path authenticates
+/// `leaf` against `aocl_peaks`, and the bagged AOCL peaks hash together with
+/// `swbf_inactive_bagged` and `swbf_active_hash` to `mutator_set_hash`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UtxoAuthPath {
+    leaf_index: u64,
+    leaf: Digest,
+    authentication_path: Vec<Digest>,
+    aocl_peaks: Vec<Digest>,
+    aocl_num_leafs: u64,
+    swbf_inactive_bagged: Digest,
+    swbf_active_hash: Digest,
+    mutator_set_hash: Digest,
+    tip_digest: Digest,
+}
+
+/// Returns the [`UtxoAuthPath`] of the AOCL leaf at `leaf_index` as of the
+/// tip, or null if the AOCL has no such leaf.
+async fn get_utxo_auth_path(
+    State(rpcstate): State<NeptuneRPCServer>,
+    Path(leaf_index): Path<u64>,
+) -> Result<ErasedJson, RestError> {
+    let state = read_state(&rpcstate).await?;
+    let tip = state.chain.light_state();
+    let mutator_set = tip.mutator_set_accumulator_after();
+    let aocl = &state.chain.archival_state().archival_mutator_set.ams().aocl;
+    if leaf_index >= aocl.num_leafs().await {
+        return Ok(json_response(Option::<UtxoAuthPath>::None));
+    }
+
+    let auth_path = UtxoAuthPath {
+        leaf_index,
+        leaf: aocl.get_leaf_async(leaf_index).await,
+        authentication_path: aocl
+            .prove_membership_async(leaf_index)
+            .await
+            .authentication_path,
+        aocl_peaks: mutator_set.aocl.peaks(),
+        aocl_num_leafs: mutator_set.aocl.num_leafs(),
+        swbf_inactive_bagged: mutator_set.swbf_inactive.bag_peaks(),
+        swbf_active_hash: Hash::hash(&mutator_set.swbf_active),
+        mutator_set_hash: mutator_set.hash(),
+        tip_digest: tip.hash(),
+    };
+
+    Ok(json_response(Some(auth_path)))
+}
+
+/// The genesis block, which clients anchor their verification of the chain to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GenesisResponse {
+    digest: Digest,
+    block_info: BlockInfo,
+}
+
+async fn get_genesis(State(rpcstate): State<NeptuneRPCServer>) -> Result<ErasedJson, RestError> {
+    let state = read_state(&rpcstate).await?;
+    let tip_digest = state.chain.light_state().hash();
+    let genesis = state.chain.archival_state().genesis_block();
+    let digest = genesis.hash();
+
+    Ok(json_response(GenesisResponse {
+        digest,
+        block_info: BlockInfo::new(genesis, digest, tip_digest, vec![], true),
+    }))
+}
+
+async fn get_block_info(
+    State(rpcstate): State<NeptuneRPCServer>,
+    Path(block_selector): Path<BlockSelectorExtended>,
+) -> Result<ErasedJson, RestError> {
+    let state = read_state(&rpcstate).await?;
+    let Some(block_selector) = block_selector.resolve(&state) else {
+        return Ok(json_response(Option::<BlockInfo>::None));
+    };
+    let Some(digest) = block_selector.as_digest(&state).await else {
+        return Ok(json_response(Option::<BlockInfo>::None));
+    };
+    let tip_digest = state.chain.light_state().hash();
+    let archival_state = state.chain.archival_state();
+
+    let Some(block) = rpcstate
+        .block_cache
+        .get_or_load(digest, || archival_state.get_block(digest))
+        .await
+        .context("Failed to get block")?
+    else {
+        return Ok(json_response(Option::<BlockInfo>::None));
+    };
+    let is_canonical = archival_state
+        .block_belongs_to_canonical_chain(digest)
+        .await;
+
+    // sibling blocks are those at the same height, with different digest
+    let sibling_blocks = archival_state
+        .block_height_to_block_digests(block.header().height)
+        .await
+        .into_iter()
+        .filter(|d| *d != digest)
+        .collect();
+
+    let block_info = BlockInfo::new(
+        &block,
+        archival_state.genesis_block().hash(),
+        tip_digest,
+        sibling_blocks,
+        is_canonical,
+    );
+
+    Ok(json_response(block_info))
+}
+
+/// Returns the number of transactions merged into the block, as reported by
+/// [`BlockInfo::num_transactions`], or null for an unknown block.
+async fn get_block_tx_count(
+    State(rpcstate): State<NeptuneRPCServer>,
+    Path(block_selector): Path<BlockSelectorExtended>,
+) -> Result<ErasedJson, RestError> {
+    let state = read_state(&rpcstate).await?;
+    let Some(block_selector) = block_selector.resolve(&state) else {
+        return Ok(json_response(Option::<usize>::None));
+    };
+    let Some(digest) = block_selector.as_digest(&state).await else {
+        return Ok(json_response(Option::<usize>::None));
+    };
+    let archival_state = state.chain.archival_state();
+    let block = rpcstate
+        .block_cache
+        .get_or_load(digest, || archival_state.get_block(digest))
+        .await
+        .context("Failed to get block")?;
+
+    Ok(json_response(block.map(|block| block.num_transactions())))
+}
+
+/// Optional filter on the origin of listed mempool transactions, given as
+/// `?origin=Own` or `?origin=Foreign`, and the order in which they are listed,
+/// given as `?sort=fee`, `?sort=feerate` or `?sort=age`.
+#[derive(Debug, Deserialize, Default)]
+struct MempoolParams {
+    origin: Option<TransactionOrigin>,
+
+    #[serde(default)]
+    sort: MempoolSort,
+}
+
+/// The order in which mempool transactions are listed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum MempoolSort {
+    /// Highest fee first, regardless of size.
+    Fee,
+
+    /// Highest fee per byte first, the order in which a miner would pick them.
+    #[default]
+    Feerate,
+
+    /// Oldest timestamp first.
+    Age,
+}
+
+/// Returns the mempool transactions as pretty JSON, or as bincode if the
+/// request's `Accept` header asks for `application/octet-stream`.
+async fn get_mempool(
+    State(rpcstate): State<NeptuneRPCServer>,
+    Path((start_index, number)): Path<(usize, usize)>,
+    Query(params): Query<MempoolParams>,
+    headers: HeaderMap,
+) -> Result<Response, RestError> {
+    let global_state = read_state(&rpcstate).await?;
+    let mempool_transactions =
+        mempool_transaction_infos(&global_state, params, start_index, number);
+    drop(global_state);
+
+    if accepts_bincode(&headers) {
+        let bytes = bincode::serialize(&mempool_transactions)
+            .map_err(|e| RestError::Internal(e.to_string()))?;
+        return Ok(([(header::CONTENT_TYPE, BINCODE_CONTENT_TYPE)], bytes).into_response());
+    }
+
+    Ok(json_response(mempool_transactions).into_response())
+}
+
+/// Content type of bincode-encoded responses.
+const BINCODE_CONTENT_TYPE: &str = "application/octet-stream";
+
+fn accepts_bincode(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media_type| {
+            media_type
+                .split(';')
+                .next()
+                .is_some_and(|t| t.trim().eq_ignore_ascii_case(BINCODE_CONTENT_TYPE))
+        })
+}
+
+/// The mempool transactions in the given window of the ordering selected by
+/// `params`, annotated with their sync status and their effect on the wallet's
+/// balance. If an origin is given, only transactions of that origin are
+/// counted.
+fn mempool_transaction_infos(
+    global_state: &GlobalState,
+    params: MempoolParams,
+    start_index: usize,
+    number: usize,
+) -> Vec<MempoolTransactionInfo> {
+    let mempool = &global_state.mempool;
+    let sorted_txkids: Box<dyn Iterator<Item = TransactionKernelId> + '_> = match params.sort {
+        MempoolSort::Fee => Box::new(mempool.get_sorted_iter_by_fee().map(|(txkid, _)| txkid)),
+        MempoolSort::Feerate => Box::new(mempool.get_sorted_iter().map(|(txkid, _)| txkid)),
+        MempoolSort::Age => Box::new(mempool.get_sorted_iter_by_age().map(|(txkid, _)| txkid)),
+    };
+    let mempool_txkids = sorted_txkids
+        .filter(|txkid| {
+            params
+                .origin
+                .is_none_or(|origin| mempool.origin(*txkid) == Some(origin))
+        })
+        .skip(start_index)
+        .take(number)
+        .collect_vec();
+
+    mempool_transaction_infos_for(global_state, &mempool_txkids)
+}
+
+/// The given mempool transactions, annotated with their sync status and their
+/// effect on the wallet's balance. Unknown transaction IDs are skipped.
+fn mempool_transaction_infos_for(
+    global_state: &GlobalState,
+    mempool_txkids: &[TransactionKernelId],
+) -> Vec<MempoolTransactionInfo> {
+    let (incoming, outgoing): (HashMap<_, _>, HashMap<_, _>) = {
+        let (incoming_iter, outgoing_iter) = global_state.wallet_state.mempool_balance_updates();
+        (incoming_iter.collect(), outgoing_iter.collect())
+    };
+
+    let tip_msah = global_state
+        .chain
+        .light_state()
+        .mutator_set_accumulator_after()
+        .hash();
+
+    mempool_txkids
+        .iter()
+        .filter_map(|id| {
+            let mut mptxi = global_state
+                .mempool
+                .get(*id)
+                .map(|tx| (MempoolTransactionInfo::from(tx), tx.kernel.mutator_set_hash))
+                .map(|(mptxi, tx_msah)| {
+                    if tx_msah == tip_msah {
+                        mptxi.synced()
+                    } else {
+                        mptxi
+                    }
+                });
+            if mptxi.is_some() {
+                if let Some(pos_effect) = incoming.get(id) {
+                    mptxi = Some(mptxi.unwrap().with_positive_effect_on_balance(*pos_effect));
+                }
+                if let Some(neg_effect) = outgoing.get(id) {
+                    mptxi = Some(mptxi.unwrap().with_negative_effect_on_balance(*neg_effect));
+                }
+                if let Some(ingress) = global_state.mempool.ingress(*id) {
+                    mptxi = Some(mptxi.unwrap().with_ingress(ingress));
+                }
+            }
+
+            mptxi
+        })
+        .collect_vec()
+}
+
+/// Additions to and removals from the mempool after a client-supplied cursor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MempoolChangesResponse {
+    /// Cursor for the next request.
+    sequence: u64,
+
+    /// False if the node cannot tell all changes since the cursor. The client
+    /// must then fetch the entire mempool again.
+    complete: bool,
+    added: Vec<MempoolTransactionInfo>,
+    removed: Vec<TransactionKernelId>,
+}
+
+async fn get_mempool_changes(
+    State(rpcstate): State<NeptuneRPCServer>,
+    Path(since_seq): Path<u64>,
+) -> Result<ErasedJson, RestError> {
+    let global_state = read_state(&rpcstate).await?;
+    let changes = global_state.mempool.changes_since(since_seq);
+
+    Ok(json_response(MempoolChangesResponse {
+        sequence: changes.sequence,
+        complete: changes.complete,
+        added: mempool_transaction_infos_for(&global_state, &changes.added),
+        removed: changes.removed,
+    }))
+}
+
+/// One bucket of the mempool's fee histogram. A bucket spans one order of
+/// magnitude: from `min_fee_per_byte` (inclusive) to ten times that value
+/// (exclusive). The bucket with a lower bound of zero holds transactions paying
+/// less than one nau per byte.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct FeeHistogramBucket {
+    #[serde(with = "native_currency_amount::api_json")]
+    min_fee_per_byte: NativeCurrencyAmount,
+    count: usize,
+}
+
+/// Aggregate information about the mempool, letting wallets pick a
+/// competitive fee.
+///
+/// Sizes are measured as the length of the bincode-serialized transaction, the
+/// same measure used for fee density. The fee statistics are zero if the
+/// mempool is empty. The histogram only contains non-empty buckets, in
+/// ascending order.
+#[derive(Debug, Clone, Serialize)]
+struct MempoolStats {
+    eviction_policy: MempoolEvictionPolicy,
+    num_evicted: u64,
+    count: usize,
+    total_size: usize,
+    #[serde(with = "native_currency_amount::api_json")]
+    total_fees: NativeCurrencyAmount,
+    #[serde(with = "native_currency_amount::api_json")]
+    min_fee: NativeCurrencyAmount,
+    #[serde(with = "native_currency_amount::api_json")]
+    median_fee: NativeCurrencyAmount,
+    #[serde(with = "native_currency_amount::api_json")]
+    max_fee: NativeCurrencyAmount,
+    fee_histogram: Vec<FeeHistogramBucket>,
+}
+
+impl MempoolStats {
+    fn new(mempool: &Mempool) -> Self {
+        let fees_and_sizes = mempool.iter_with_origin().map(|(tx, _)| {
+            let size = bincode::serialized_size(tx).unwrap_or_default();
+            let size = usize::try_from(size).unwrap_or(usize::MAX);
+            (tx.kernel.fee, size, tx.fee_per_byte())
+        });
+
+        Self::from_fees_and_sizes(
+            mempool.eviction_policy(),
+            mempool.num_evicted(),
+            fees_and_sizes,
+        )
+    }
+
+    /// Takes the fee, size and [`Transaction::fee_per_byte`] of each
+    /// transaction.
+    fn from_fees_and_sizes(
+        eviction_policy: MempoolEvictionPolicy,
+        num_evicted: u64,
+        fees_and_sizes: impl IntoIterator<Item = (NativeCurrencyAmount, usize, NativeCurrencyAmount)>,
+    ) -> Self {
+        let (mut fees, sizes, fees_per_byte): (Vec<_>, Vec<_>, Vec<_>) =
+            fees_and_sizes.into_iter().multiunzip();
+
+        let mut buckets = std::collections::BTreeMap::<i128, usize>::new();
+        for fee_per_byte in fees_per_byte {
+            let min_fee_per_byte = match fee_per_byte.to_nau().max(0) {
+                0 => 0,
+                fee_per_byte => 10i128.pow(fee_per_byte.ilog10()),
+            };
+            *buckets.entry(min_fee_per_byte).or_default() += 1;
+        }
+        let fee_histogram = buckets
+            .into_iter()
+            .map(|(min_fee_per_byte, count)| FeeHistogramBucket {
+                min_fee_per_byte: NativeCurrencyAmount::from_nau(min_fee_per_byte),
+                count,
+            })
+            .collect_vec();
+
+        fees.sort();
+        let median_fee = match fees.len() {
+            0 => NativeCurrencyAmount::zero(),
+            n if n % 2 == 1 => fees[n / 2],
+            n => {
+                let (low, high) = (fees[n / 2 - 1].to_nau(), fees[n / 2].to_nau());
+                NativeCurrencyAmount::from_nau(low + (high - low) / 2)
+            }
+        };
+
+        Self {
+            eviction_policy,
+            num_evicted,
+            count: fees.len(),
+            total_size: sizes.iter().sum(),
+            total_fees: fees.iter().copied().sum(),
+            min_fee: fees
+                .first()
+                .copied()
+                .unwrap_or_else(NativeCurrencyAmount::zero),
+            median_fee,
+            max_fee: fees
+                .last()
+                .copied()
+                .unwrap_or_else(NativeCurrencyAmount::zero),
+            fee_histogram,
+        }
+    }
+}
+
+async fn get_mempool_stats(
+    State(rpcstate): State<NeptuneRPCServer>,
+) -> Result<ErasedJson, RestError> {
+    let global_state = read_state(&rpcstate).await?;
+
+    Ok(json_response(MempoolStats::new(&global_state.mempool)))
+}
+
+/// Whether a transaction is in the mempool and, if so, whether it is synced to
+/// the tip and the fee it pays per byte.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct InMempoolResponse {
+    in_mempool: bool,
+    synced: Option<bool>,
+    #[serde(with = "native_currency_amount::api_json::option")]
+    fee_per_byte: Option<NativeCurrencyAmount>,
+}
+
+/// Tells whether the transaction with the given ID is in the mempool. Cheaper
+/// than paging through the mempool listing to find it.
+async fn get_in_mempool(
+    State(rpcstate): State<NeptuneRPCServer>,
+    Path(txid): Path<String>,
+) -> Result<ErasedJson, RestError> {
+    let txid = TransactionKernelId::new(parse_hex_digest("txid", &txid)?);
+    let global_state = read_state(&rpcstate).await?;
+    let info = mempool_transaction_infos_for(&global_state, &[txid]).pop();
+
+    Ok(json_response(InMempoolResponse {
+        in_mempool: info.is_some(),
+        synced: info.as_ref().map(|info| info.synced),
+        fee_per_byte: info.map(|info| info.fee_per_byte),
+    }))
+}
+
+/// Reject height ranges `start..=end` that are empty or span more than
+/// `--max-rest-batch-blocks` blocks.
+fn check_height_range(rpcstate: &NeptuneRPCServer, start: u64, end: u64) -> Result<(), RestError> {
+    if end < start {
+        return Err(RestError::BadRequest(format!(
+            "end {end} is below start {start}"
+        )));
+    }
+    let max_range = rpcstate.state.cli().max_rest_batch_blocks;
+    if end - start >= max_range {
+        return Err(RestError::BadRequest(format!(
+            "range of {} blocks exceeds maximum of {max_range}",
+            end - start + 1
+        )));
     }
 
-    let network = Network::Main;
-    let receiving_address = ReceivingAddress::from_bech32m(&send_tx.fee_address, network)?;
-    let amount = NativeCurrencyAmount::coins_from_str(&send_tx.amount)?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+struct DifficultyHistoryEntry {
+    height: BlockHeight,
+    difficulty: Difficulty,
+    timestamp: Timestamp,
+
+    /// Milliseconds since the previous block. `None` for the genesis block.
+    block_time: Option<u64>,
+}
+
+/// Returns the difficulty of the canonical blocks at heights `start..=end`,
+/// along with the time it took to find each of them.
+///
+/// Ranges of more than `--max-rest-batch-blocks` blocks are rejected.
+async fn get_difficulty_history(
+    State(rpcstate): State<NeptuneRPCServer>,
+    Path((start, end)): Path<(u64, u64)>,
+) -> Result<ErasedJson, RestError> {
+    check_height_range(&rpcstate, start, end)?;
+
+    let state = read_state(&rpcstate).await?;
+    let archival_state = state.chain.archival_state();
+    let canonical_header = |height: u64| {
+        let state = &state;
+        async move {
+            let digest = BlockSelector::Height(height.into())
+                .as_digest(state)
+                .await?;
+            archival_state.get_block_header(digest).await
+        }
+    };
+
+    let mut previous_timestamp = match start.checked_sub(1) {
+        Some(height) => canonical_header(height).await.map(|h| h.timestamp),
+        None => None,
+    };
+    let mut history = vec![];
+    for height in start..=end {
+        let Some(header) = canonical_header(height).await else {
+            break;
+        };
+
+        history.push(DifficultyHistoryEntry {
+            height: header.height,
+            difficulty: header.difficulty,
+            timestamp: header.timestamp,
+            block_time: previous_timestamp.map(|previous| {
+                header
+                    .timestamp
+                    .to_millis()
+                    .saturating_sub(previous.to_millis())
+            }),
+        });
+        previous_timestamp = Some(header.timestamp);
+    }
+
+    Ok(json_response(history))
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+struct NextDifficulty {
+    /// Height of the tip that the next block builds on.
+    tip_height: BlockHeight,
+    tip_digest: Digest,
+
+    /// The difficulty of the next block, as stored in the tip's header.
+    difficulty: Difficulty,
+
+    /// The next block's hash must be less than or equal to this value.
+    target: Digest,
+}
+
+/// Returns the difficulty and proof-of-work target of the block that builds on
+/// the current tip.
+async fn get_next_difficulty(
+    State(rpcstate): State<NeptuneRPCServer>,
+) -> Result<ErasedJson, RestError> {
+    let state = read_state(&rpcstate).await?;
+    let tip = state.chain.light_state();
+    let difficulty = tip.header().difficulty;
+
+    Ok(json_response(NextDifficulty {
+        tip_height: tip.header().height,
+        tip_digest: tip.hash(),
+        difficulty,
+        target: difficulty.target(),
+    }))
+}
+
+/// Returns the heights in `start..=end` of canonical blocks whose timestamp is
+/// not later than their parent's.
+///
+/// Ranges of more than `--max-rest-batch-blocks` blocks are rejected.
+async fn get_timestamp_anomalies(
+    State(rpcstate): State<NeptuneRPCServer>,
+    Path((start, end)): Path<(u64, u64)>,
+) -> Result<ErasedJson, RestError> {
+    check_height_range(&rpcstate, start, end)?;
+
+    let state = read_state(&rpcstate).await?;
+    let anomalies = state
+        .chain
+        .archival_state()
+        .check_timestamp_monotonic(start.into(), end.into())
+        .await;
+
+    Ok(json_response(anomalies))
+}
+
+/// Optional size budget of a block template, in number of BFieldElements.
+#[derive(Debug, Deserialize, Default)]
+struct BlockTemplateParams {
+    max_size: Option<usize>,
+}
+
+/// The mempool transactions that best pay for a block's space, and what the
+/// block would earn.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct BlockTemplate {
+    height: BlockHeight,
+    txids: Vec<TransactionKernelId>,
+
+    /// Combined size of the selected transaction kernels, in number of
+    /// BFieldElements, as counted against the block size limit.
+    total_size: usize,
+    #[serde(with = "native_currency_amount::api_json")]
+    total_fee: NativeCurrencyAmount,
+    #[serde(with = "native_currency_amount::api_json")]
+    coinbase: NativeCurrencyAmount,
+}
+
+/// Select single-proof transactions synced to the tip, highest fee per byte
+/// first, until the room for transactions in the next block or the requested
+/// `max_size` is used up.
+///
+/// Every transaction is scored on its own. Mempool transactions never spend
+/// one another's outputs, see [`Mempool`](crate::models::state::mempool::Mempool),
+/// so there are no packages whose combined fee rate would matter.
+async fn get_block_template(
+    State(rpcstate): State<NeptuneRPCServer>,
+    Query(params): Query<BlockTemplateParams>,
+) -> Result<ErasedJson, RestError> {
+    let state = read_state(&rpcstate).await?;
+    let tip = state.chain.light_state();
+    let height = tip.header().height.next();
+    let tip_mutator_set_hash = tip.mutator_set_accumulator_after().hash();
+
+    let capacity = block_template_capacity(tip, height);
+    let budget = params
+        .max_size
+        .map_or(capacity, |max_size| max_size.min(capacity));
+
+    let mut template = BlockTemplate {
+        height,
+        txids: vec![],
+        total_size: 0,
+        total_fee: NativeCurrencyAmount::zero(),
+        coinbase: Block::block_subsidy(height),
+    };
+    for (transaction, _fee_per_byte) in state.mempool.get_sorted_iter_by_fee_rate() {
+        if transaction.kernel.mutator_set_hash != tip_mutator_set_hash
+            || !matches!(transaction.proof, TransactionProof::SingleProof(_))
+        {
+            continue;
+        }
+
+        // only the kernel ends up in the block; the proof is merged away
+        let size = transaction.kernel.encode().len();
+        if template.total_size + size > budget {
+            continue;
+        }
+
+        template.txids.push(transaction.kernel.txid());
+        template.total_size += size;
+        template.total_fee = template.total_fee + transaction.kernel.fee;
+    }
+
+    Ok(json_response(template))
+}
+
+/// Room for transaction kernels in the block after `tip`, in number of
+/// BFieldElements: the size limit less the header, the appendix, and the
+/// composer's two coinbase outputs. The tip's header and appendix stand in for
+/// those of the next block.
+fn block_template_capacity(tip: &Block, height: BlockHeight) -> usize {
+    let coinbase_outputs = 2 * AdditionRecord::new(Digest::default()).encode().len();
+    let overhead = tip.header().encode().len() + tip.appendix().encode().len() + coinbase_outputs;
+    Block::max_size(height).saturating_sub(overhead)
+}
+
+#[derive(Debug, Serialize, Clone, Copy)]
+struct BlockTime {
+    #[serde(rename = "height")]
+    height: u64,
+    #[serde(rename = "time")]
+    time: u64,
+}
+
+async fn get_blocks_time(
+    State(rpcstate): State<NeptuneRPCServer>,
+    Path((start, end)): Path<(u64, u64)>,
+) -> Result<ErasedJson, RestError> {
+    let mut block_time_list = Vec::with_capacity((end - start + 1) as usize);
+    let state = read_state(&rpcstate).await?;
+    for cur_height in start..=end {
+        let block_selector = BlockSelector::Height(cur_height.into());
+        let Some(digest) = block_selector.as_digest(&state).await else {
+            break;
+        };
+        let archival_state = state.chain.archival_state();
+        let Some(block) = archival_state.get_block(digest).await? else {
+            break;
+        };
+
+        block_time_list.push(BlockTime {
+            height: block.header().height.into(),
+            time: block.header().timestamp.to_millis() / 1000,
+        });
+    }
+
+    Ok(json_response(block_time_list))
+}
+
+async fn get_nonces(
+    State(rpcstate): State<NeptuneRPCServer>,
+    Path(count): Path<u64>,
+) -> Result<ErasedJson, RestError> {
+    let state = read_state(&rpcstate).await?;
+    let end: u64 = state
+        .chain
+        .archival_state()
+        .get_tip()
+        .await
+        .header()
+        .height
+        .into();
+    let start = end - count + 1;
+
+    let mut block_time_list = Vec::with_capacity((end - start + 1) as usize);
+    let mut count = 0;
+    for cur_height in start..=end {
+        let block_selector = BlockSelector::Height(cur_height.into());
+        let Some(digest) = block_selector.as_digest(&state).await else {
+            break;
+        };
+        let archival_state = state.chain.archival_state();
+        let Some(block) = archival_state.get_block(digest).await? else {
+            break;
+        };
+
+        block_time_list.push(block.header().nonce.to_hex());
+        if block
+            .header()
+            .nonce
+            .to_hex()
+            .starts_with("0000000000000000")
+        {
+            count += 1;
+        }
+    }
+
+    let aaa = (
+        format!(
+            "block ({}-{}), {}/{} = {}%",
+            start,
+            end,
+            count,
+            end - start + 1,
+            (count * 100) / (end - start + 1)
+        ),
+        block_time_list,
+    );
+
+    Ok(json_response(aaa))
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct SimpleBlock {
+    #[serde(rename = "height")]
+    height: u64,
+    #[serde(rename = "hash")]
+    hash: String,
+    #[serde(rename = "fee", with = "native_currency_amount::api_json")]
+    fee: NativeCurrencyAmount,
+    #[serde(rename = "timestamp")]
+    timestamp: u64,
+}
+
+/// The blocks returned by `get_last_blocks`, in ascending height order.
+///
+/// `first_height` and `last_height` are the heights of the first and last
+/// block actually returned. Any height in between for which no block could be
+/// loaded is listed in `missing_heights`, so a client can tell a gap apart
+/// from a short chain.
+#[derive(Debug, Serialize, Clone)]
+struct SimpleBlockRange {
+    first_height: Option<u64>,
+    last_height: Option<u64>,
+    missing_heights: Vec<u64>,
+    blocks: Vec<SimpleBlock>,
+}
+
+impl SimpleBlockRange {
+    /// Assemble the range from per-height lookups, which must be in ascending
+    /// height order. `None` marks a height whose block could not be loaded.
+    fn from_lookups(lookups: impl IntoIterator<Item = (u64, Option<SimpleBlock>)>) -> Self {
+        let mut blocks = vec![];
+        let mut missing_heights = vec![];
+        for (height, block) in lookups {
+            match block {
+                Some(block) => blocks.push(block),
+                None => missing_heights.push(height),
+            }
+        }
+
+        // heights after the last returned block are not a gap but the end of
+        // the known chain, and heights before the first are out of range.
+        let first_height = blocks.first().map(|b| b.height);
+        let last_height = blocks.last().map(|b| b.height);
+        missing_heights.retain(|h| Some(*h) > first_height && Some(*h) < last_height);
+
+        Self {
+            first_height,
+            last_height,
+            missing_heights,
+            blocks,
+        }
+    }
+}
+
+async fn get_last_blocks(
+    State(rpcstate): State<NeptuneRPCServer>,
+    Path(count): Path<u64>,
+) -> Result<ErasedJson, RestError> {
+    let state = read_state(&rpcstate).await?;
+    let end: u64 = state.chain.light_state().header().height.into();
+    let start = (end + 1).saturating_sub(count);
+
+    let mut lookups = Vec::with_capacity((end + 1 - start) as usize);
+    for cur_height in start..=end {
+        let block_selector = BlockSelector::Height(cur_height.into());
+        let Some(digest) = block_selector.as_digest(&state).await else {
+            lookups.push((cur_height, None));
+            continue;
+        };
+        let archival_state = state.chain.archival_state();
+        let block = match archival_state.get_block(digest).await {
+            Ok(block) => block,
+            Err(e) => {
+                tracing::warn!("failed to load block at height {cur_height}: {e}");
+                None
+            }
+        };
+
+        lookups.push((
+            cur_height,
+            block.map(|block| SimpleBlock {
+                height: block.header().height.into(),
+                hash: block.hash().to_hex(),
+                fee: block.body().transaction_kernel.fee,
+                timestamp: block.header().timestamp.to_millis(),
+            }),
+        ));
+    }
+
+    Ok(json_response(SimpleBlockRange::from_lookups(lookups)))
+}
+
+async fn get_owner_blocks(
+    State(rpcstate): State<NeptuneRPCServer>,
+    Path((start, end)): Path<(u64, u64)>,
+) -> Result<ErasedJson, RestError> {
+    let state = read_state(&rpcstate).await?;
+    let wallet_entropy = &state.wallet_state.wallet_entropy;
+    let guess_reward = blocks_guessed_with(&state, start, end, |header| {
+        let guesser_key = wallet_entropy.guesser_spending_key(header.prev_block_digest);
+        guesser_key.after_image() == header.guesser_digest
+    })
+    .await?;
+
+    Ok(json_response(guess_reward))
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct BlocksGuessedByRequest {
+    /// Hex-encoded guesser digests to match.
+    guesser_digests: Vec<String>,
+    start: u64,
+    end: u64,
+}
+
+/// Like [`get_owner_blocks`], but matches the blocks whose guesser digest is
+/// one of the given ones instead of one derived from this node's wallet.
+///
+/// A guesser derives the digest for each block from its wallet secret and the
+/// block's predecessor, so it has to supply the digests it guessed with; the
+/// secret itself never leaves the guesser.
+///
+/// Ranges of more than `--max-rest-batch-blocks` blocks are rejected.
+async fn blocks_guessed_by(
+    State(rpcstate): State<NeptuneRPCServer>,
+    Json(body): Json<BlocksGuessedByRequest>,
+) -> Result<ErasedJson, RestError> {
+    check_height_range(&rpcstate, body.start, body.end)?;
+    let guesser_digests = body
+        .guesser_digests
+        .iter()
+        .map(|hex| parse_hex_digest("guesser_digests", hex))
+        .collect::<Result<HashSet<_>, _>>()?;
+
+    let state = read_state(&rpcstate).await?;
+    let guess_reward = blocks_guessed_with(&state, body.start, body.end, |header| {
+        guesser_digests.contains(&header.guesser_digest)
+    })
+    .await?;
+
+    Ok(json_response(guess_reward))
+}
+
+/// The canonical blocks of heights `start..=end` whose header satisfies
+/// `is_guesser`, and the sum of the guesser rewards of these blocks. See
+/// [`Block::total_guesser_reward`] for how a block's reward is split between
+/// composer and guesser.
+async fn blocks_guessed_with(
+    state: &GlobalState,
+    start: u64,
+    end: u64,
+    is_guesser: impl Fn(&BlockHeader) -> bool,
+) -> Result<GuessReward, RestError> {
+    let mut owner_block_list = Vec::new();
+    let mut reward = NativeCurrencyAmount::zero();
+    for cur_height in start..=end {
+        let block_selector = BlockSelector::Height(cur_height.into());
+        let Some(digest) = block_selector.as_digest(state).await else {
+            break;
+        };
+        let archival_state = state.chain.archival_state();
+        let Some(block) = archival_state.get_block(digest).await? else {
+            break;
+        };
+
+        if is_guesser(block.header()) {
+            let amount = block.total_guesser_reward();
+            reward = reward + amount;
+            owner_block_list.push(RewardCard {
+                block_id: block.hash(),
+                block_height: block.header().height,
+                timestamp: block.header().timestamp,
+                amount,
+            });
+        }
+    }
+
+    Ok(GuessReward {
+        start: start.into(),
+        end: end.into(),
+        reward,
+        records: owner_block_list,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseMsMembershipProofEx {
+    pub height: BlockHeight,
+    pub block_id: Digest,
+    pub proofs: Vec<MsMembershipProofEx>,
+}
+
+/// Version of the bincode encoding of [`ResponseMsMembershipProofEx`]. Must be
+/// bumped whenever that struct, or any type it contains, changes.
+pub const MS_MEMBERSHIP_PROOF_RESPONSE_VERSION: u16 = 1;
+
+/// The bincode encoding of a versioned response is not one this node can read.
+#[derive(Debug, thiserror::Error)]
+pub enum VersionedResponseError {
+    #[error("unsupported {payload} version {found}, expected version {expected}")]
+    UnsupportedVersion {
+        payload: &'static str,
+        found: u16,
+        expected: u16,
+    },
+
+    #[error("malformed response: {0}")]
+    Malformed(#[from] bincode::Error),
+}
+
+/// Encode `payload` with bincode, prefixed with `version`.
+fn to_versioned_bytes<T: Serialize>(version: u16, payload: &T) -> Result<Vec<u8>, bincode::Error> {
+    bincode::serialize(&(version, payload))
+}
+
+/// Decode the output of [`to_versioned_bytes`]. Payloads of any version other
+/// than `expected` are rejected before their body is decoded.
+fn from_versioned_bytes<T: DeserializeOwned>(
+    payload: &'static str,
+    expected: u16,
+    bytes: &[u8],
+) -> Result<T, VersionedResponseError> {
+    let found: u16 = bincode::deserialize(bytes)?;
+    if found != expected {
+        return Err(VersionedResponseError::UnsupportedVersion {
+            payload,
+            found,
+            expected,
+        });
+    }
+
+    let (_version, decoded): (u16, T) = bincode::deserialize(bytes)?;
+    Ok(decoded)
+}
+
+impl ResponseMsMembershipProofEx {
+    /// Encode with bincode, prefixed with
+    /// [`MS_MEMBERSHIP_PROOF_RESPONSE_VERSION`].
+    pub fn to_versioned_bytes(&self) -> Result<Vec<u8>, bincode::Error> {
+        to_versioned_bytes(MS_MEMBERSHIP_PROOF_RESPONSE_VERSION, self)
+    }
+
+    /// Decode the output of [`Self::to_versioned_bytes`]. Payloads of any
+    /// other version are rejected before their body is decoded.
+    pub fn from_versioned_bytes(bytes: &[u8]) -> Result<Self, VersionedResponseError> {
+        from_versioned_bytes(
+            "membership proof response",
+            MS_MEMBERSHIP_PROOF_RESPONSE_VERSION,
+            bytes,
+        )
+    }
+}
+
+async fn generate_restore_membership_proof(
+    State(rpcstate): State<NeptuneRPCServer>,
+    body: axum::body::Bytes,
+) -> Result<Vec<u8>, RestError> {
+    let r_datas: Vec<RequestMsMembershipProofEx> = deserialize_body(&body)?;
+    let state = read_state(&rpcstate).await?;
+
+    if !state.chain.archival_state().mutator_set_consistent().await {
+        return Err(RestError::ServiceUnavailable(
+            "archival mutator set is not consistent with the tip".to_string(),
+        ));
+    }
+
+    let ams = state.chain.archival_state().archival_mutator_set.ams();
+
+    let mut proofs = Vec::with_capacity(r_datas.len());
+    for r_data in r_datas {
+        if let Ok(p) = ams.restore_membership_proof_ex(r_data).await {
+            proofs.push(p);
+        }
+    }
+
+    let cur_block = state.chain.archival_state().get_tip().await;
+
+    let height = cur_block.header().height;
+    let block_id = cur_block.hash();
+
+    let response = ResponseMsMembershipProofEx {
+        height,
+        block_id,
+        proofs,
+    };
+    response
+        .to_versioned_bytes()
+        .map_err(|e| RestError::Internal(e.to_string()))
+}
+
+/// Maximum number of blocks [`membership_proof_updates`] covers. Clients
+/// further behind should restore their membership proofs from scratch.
+pub const MAX_MEMBERSHIP_PROOF_UPDATE_BLOCKS: u64 = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestMsMembershipProofUpdates {
+    /// Height of the block that the client's membership proofs are valid for.
+    pub base_height: BlockHeight,
+}
+
+/// What a client needs to advance membership proofs that are valid for the
+/// block at `base_height` to the block at `height`: the mutator set after the
+/// base block, and the mutator set update of every later block, in order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseMsMembershipProofUpdates {
+    pub base_height: BlockHeight,
+    pub base_block_id: Digest,
+    pub height: BlockHeight,
+    pub block_id: Digest,
+    pub base_mutator_set: MutatorSetAccumulator,
+    pub updates: Vec<MutatorSetUpdate>,
+}
+
+async fn membership_proof_updates(
+    State(rpcstate): State<NeptuneRPCServer>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> Result<Vec<u8>, RestError> {
+    authenticate(&rpcstate, &headers)?;
+    let request: RequestMsMembershipProofUpdates = deserialize_body(&body)?;
+    let state = read_state(&rpcstate).await?;
+    let archival_state = state.chain.archival_state();
+
+    let tip_height = state.chain.light_state().header().height;
+    let base_height = request.base_height;
+    if base_height > tip_height {
+        return Err(RestError::BadRequest(format!(
+            "base height {base_height} is above the tip at height {tip_height}"
+        )));
+    }
+    let num_blocks = u64::from(tip_height) - u64::from(base_height);
+    if num_blocks > MAX_MEMBERSHIP_PROOF_UPDATE_BLOCKS {
+        return Err(RestError::BadRequest(format!(
+            "base height {base_height} is {num_blocks} blocks behind the tip, more than the \
+             maximum of {MAX_MEMBERSHIP_PROOF_UPDATE_BLOCKS}; restore membership proofs instead"
+        )));
+    }
+
+    // Only the kernels are needed, so the block proofs are never read from disk.
+    let mut kernels = Vec::with_capacity(num_blocks as usize + 1);
+    for height in u64::from(base_height)..=u64::from(tip_height) {
+        let Some(digest) = BlockSelector::Height(height.into()).as_digest(&state).await else {
+            return Err(RestError::NotFound(format!("no block at height {height}")));
+        };
+        let Some(kernel) = archival_state.get_block_kernel(digest).await? else {
+            return Err(RestError::NotFound(format!(
+                "block {} not found",
+                digest.to_hex()
+            )));
+        };
+        kernels.push((digest, kernel));
+    }
+
+    let (base_block_id, base_kernel) = &kernels[0];
+    let (block_id, _) = kernels[kernels.len() - 1];
+    let response = ResponseMsMembershipProofUpdates {
+        base_height,
+        base_block_id: *base_block_id,
+        height: tip_height,
+        block_id,
+        base_mutator_set: base_kernel.mutator_set_accumulator_after(*base_block_id),
+        updates: kernels[1..]
+            .iter()
+            .map(|(digest, kernel)| kernel.mutator_set_update(*digest))
+            .collect(),
+    };
+    bincode::serialize(&response).map_err(|e| RestError::Internal(e.to_string()))
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct GuessReward {
+    #[serde(rename = "start")]
+    start: BlockHeight,
+    #[serde(rename = "end")]
+    end: BlockHeight,
+    #[serde(rename = "reward", with = "native_currency_amount::api_json")]
+    reward: NativeCurrencyAmount,
+    #[serde(rename = "records")]
+    records: Vec<RewardCard>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct RewardCard {
+    #[serde(rename = "block_id")]
+    block_id: Digest,
+    #[serde(rename = "block_height")]
+    block_height: BlockHeight,
+    #[serde(rename = "timestamp")]
+    timestamp: Timestamp,
+    #[serde(rename = "amount", with = "native_currency_amount::api_json")]
+    amount: NativeCurrencyAmount,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct BroadcastTx {
+    transaction: Transaction,
+    origin: TransactionOrigin,
+    notification: TransactionNotification,
+}
+
+impl BroadcastTx {
+    /// Check that the notification describes the transaction, such that a
+    /// mismatched pair cannot pollute gossip.
+    fn check_notification(&self) -> Result<(), RestError> {
+        let expected = TransactionNotification::try_from(&self.transaction)
+            .map_err(|e| RestError::BadRequest(e.to_string()))?;
+        if self.notification != expected {
+            return Err(RestError::BadRequest(format!(
+                "notification does not match transaction {}",
+                self.transaction.kernel.txid()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+async fn broadcast_transaction(
+    State(mut rpcstate): State<NeptuneRPCServer>,
+    body: axum::body::Bytes,
+) -> Result<ErasedJson, RestError> {
+    let tx: BroadcastTx = deserialize_body(&body)?;
+    tx.check_notification()?;
+    check_min_fee(tx.transaction.kernel.fee, rpcstate.state.cli().min_tx_fee)?;
+    let tx_id = tx.transaction.kernel.txid();
+    let mut state = rpcstate.state.lock_guard_mut().await;
+    state
+        .mempool_insert_with_ingress(tx.transaction, tx.origin, TransactionIngress::RpcBroadcast)
+        .await;
+    let _ = rpcstate
+        .rpc_server_to_main_tx
+        .send(RPCServerToMain::BroadcastNotification(tx.notification))
+        .await;
+
+    Ok(json_response(tx_id.to_string()))
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct SendTx {
+    broadcast_tx: BroadcastTx,
+    amount: String,
+    sender_randomness: String,
+    fee_address: String,
+    block_height: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct ResponseSendTx {
+    #[serde(rename = "status")]
+    status: u64,
+    #[serde(rename = "message")]
+    message: String,
+}
+async fn send_transaction(
+    State(mut rpcstate): State<NeptuneRPCServer>,
+    body: axum::body::Bytes,
+) -> Result<ErasedJson, RestError> {
+    let send_tx: SendTx = deserialize_body(&body)?;
+    // 判断fee_address是否是合法的地址
+    if send_tx.fee_address != FEE_ADDRESS.to_string() {
+        return Ok(json_response(ResponseSendTx {
+            status: 1,
+            message: "fee_address is not valid".to_string(),
+        }));
+    }
+
+    let network = Network::Main;
+    let receiving_address = ReceivingAddress::from_bech32m(&send_tx.fee_address, network)?;
+    let amount = NativeCurrencyAmount::coins_from_str_checked(&send_tx.amount)?;
+
+    let sender_randomness = parse_sender_randomness(&send_tx.sender_randomness)?;
+
+    if !contains_fee_output(
+        &send_tx.broadcast_tx.transaction.kernel.outputs,
+        &receiving_address,
+        amount,
+        sender_randomness,
+    ) {
+        return Ok(json_response(ResponseSendTx {
+            status: 2,
+            message: "Failed to pay the priority fee".to_string(),
+        }));
+    }
+
+    {
+        let state = read_state(&rpcstate).await?;
+        let end: u64 = state
+            .chain
+            .archival_state()
+            .get_tip()
+            .await
+            .header()
+            .height
+            .into();
+        if end != send_tx.block_height {
+            return Ok(json_response(ResponseSendTx {
+                status: 3,
+                message: format!("Transaction expired. Please sync to the latest block height first. current block height: {}", end),
+            }))
+        }
+    }
+
+    let insert = false;
+
+    if insert {
+        let tx = send_tx.broadcast_tx;
+        let mut state = rpcstate.state.lock_guard_mut().await;
+        state
+            .mempool_insert_with_ingress(tx.transaction, tx.origin, TransactionIngress::FeePaidSend)
+            .await;
+        let _ = rpcstate
+            .rpc_server_to_main_tx
+            .send(RPCServerToMain::BroadcastNotification(tx.notification))
+            .await;
+    } else {
+        // todo: 将数据发送给proof机器，确保proof机器可以工作
+        let mut busy = true;
+
+        let base_url = std::env::var("PROOF_RPC").unwrap_or("172.16.64.12:9800".to_string());
+        let tx_b = bincode::serialize(&send_tx.broadcast_tx).unwrap();
+        let client = reqwest::Client::new();
+        if let Ok(response) = client
+            .post(format!("http://{}/rpc/tx/broadcast", base_url))
+            .body(tx_b)
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+        {
+            if response.status().is_success() {
+                info!(
+                    "Start task successfully: {:?}",
+                    response.json::<serde_json::Value>().await.unwrap()
+                );
+
+                // todo
+                busy = false;
+            } else {
+                error!("Failed to start task: {:?}", response.status());
+                busy = true;
+            }
+        } else {
+            error!("Failed to send request");
+            busy = true;
+        }
+
+        if busy {
+            return Ok(json_response(ResponseSendTx {
+                status: 4,
+                message: "proof machine is busy".to_string(),
+            }));
+        }
+    }
+
+    Ok(json_response(ResponseSendTx {
+        status: 0,
+        message: "success".to_string(),
+    }))
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct UtxoIndexRequest {
+    pub address: String,
+    pub amount: String,
+    pub sender_randomness: String,
+
+    /// How the recipient is notified of the UTXO. Defaults to on-chain.
+    #[serde(default)]
+    pub notify_method: Option<UtxoNotificationMedium>,
+
+    /// Whether the UTXO is a change output of the sender's wallet.
+    #[serde(default)]
+    pub is_change: bool,
+}
+
+async fn build_utxo_index(
+    State(_rpcstate): State<NeptuneRPCServer>,
+    Json(body): Json<UtxoIndexRequest>,
+) -> Result<ErasedJson, RestError> {
+    let network = Network::Main;
+    let receiving_address = ReceivingAddress::from_bech32m(&body.address, network)?;
+    let amount = NativeCurrencyAmount::coins_from_str_checked(&body.amount)?;
+
+    let sender_randomness = parse_sender_randomness(&body.sender_randomness)?;
+
+    let output_index = calculate_utxo_commitment(
+        receiving_address,
+        amount,
+        sender_randomness,
+        body.notify_method.unwrap_or_default(),
+        body.is_change,
+    );
+
+    tracing::info!("output: {}", output_index);
+    Ok(json_response(output_index))
+}
+
+/// Maximum number of sender randomness values in one commitment scan request.
+const MAX_SCAN_COMMITMENTS: usize = 10_000;
+
+/// Like [`UtxoIndexRequest`], but for many sender randomness values at once.
+#[derive(Debug, Deserialize, Clone)]
+struct ScanCommitmentsRequest {
+    address: String,
+    amount: String,
+    sender_randomness: Vec<String>,
+
+    /// How the recipient is notified of the UTXOs. Defaults to on-chain.
+    #[serde(default)]
+    notify_method: Option<UtxoNotificationMedium>,
+
+    /// Whether the UTXOs are change outputs of the sender's wallet.
+    #[serde(default)]
+    is_change: bool,
+}
+
+/// Returns the commitment of the UTXO paying `amount` to `address` for each
+/// sender randomness, in request order, so a wallet can match them against
+/// on-chain outputs in one round trip.
+async fn scan_commitments(
+    State(_rpcstate): State<NeptuneRPCServer>,
+    Json(body): Json<ScanCommitmentsRequest>,
+) -> Result<ErasedJson, RestError> {
+    if body.sender_randomness.len() > MAX_SCAN_COMMITMENTS {
+        return Err(RestError::BadRequest(format!(
+            "at most {MAX_SCAN_COMMITMENTS} sender randomness values can be scanned at once"
+        )));
+    }
+
+    let network = Network::Main;
+    let receiving_address = ReceivingAddress::from_bech32m(&body.address, network)
+        .map_err(|e| RestError::BadRequest(format!("invalid address: {e}")))?;
+    let amount = NativeCurrencyAmount::coins_from_str_checked(&body.amount)?;
+    let notification_medium = body.notify_method.unwrap_or_default();
+
+    let commitments = body
+        .sender_randomness
+        .iter()
+        .map(|sender_randomness| {
+            Ok(calculate_utxo_commitment(
+                receiving_address.clone(),
+                amount,
+                parse_sender_randomness(sender_randomness)?,
+                notification_medium,
+                body.is_change,
+            ))
+        })
+        .collect::<Result<Vec<_>, RestError>>()?;
+
+    Ok(json_response(commitments))
+}
+
+/// Maximum number of bytes decoded from a bincode request body. Guards against
+/// length prefixes that would make the decoder allocate huge buffers.
+const MAX_BINCODE_BODY_SIZE: u64 = 32 * 1024 * 1024;
+
+/// Decode a bincode request body, rejecting it with a 400 if it is malformed or
+/// exceeds [`MAX_BINCODE_BODY_SIZE`].
+pub(crate) fn deserialize_body<T: DeserializeOwned>(body: &[u8]) -> Result<T, RestError> {
+    deserialize_body_with_limit(body, MAX_BINCODE_BODY_SIZE)
+}
+
+fn deserialize_body_with_limit<T: DeserializeOwned>(
+    body: &[u8],
+    limit: u64,
+) -> Result<T, RestError> {
+    // same encoding as `bincode::serialize`, plus the limit
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(limit)
+        .deserialize(body)
+        .map_err(|e| match *e {
+            bincode::ErrorKind::SizeLimit => RestError::BadRequest(format!(
+                "request body exceeds the size limit of {} bytes",
+                limit
+            )),
+            e => RestError::BadRequest(format!(
+                "malformed request body ({}): {}",
+                bincode_error_kind(&e),
+                e
+            )),
+        })
+}
+
+/// A short description of what kind of decoding failure `error` is, so that
+/// clients can tell a truncated body from one of the wrong type.
+fn bincode_error_kind(error: &bincode::ErrorKind) -> &'static str {
+    match error {
+        bincode::ErrorKind::Io(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+            "unexpected end of body"
+        }
+        bincode::ErrorKind::Io(_) => "read error",
+        bincode::ErrorKind::SizeLimit => "size limit exceeded",
+        bincode::ErrorKind::InvalidTagEncoding(_) => "invalid variant",
+        bincode::ErrorKind::InvalidBoolEncoding(_)
+        | bincode::ErrorKind::InvalidCharEncoding
+        | bincode::ErrorKind::InvalidUtf8Encoding(_) => "invalid encoding",
+        // serde reports unknown enum variants through a custom error
+        bincode::ErrorKind::Custom(msg) if msg.contains("variant index") => "invalid variant",
+        _ => "invalid value",
+    }
+}
+
+/// Number of bytes in a [`Digest`], such as a sender randomness. Each of its
+/// [`BFieldElement`](tasm_lib::prelude::BFieldElement)s takes 8 bytes.
+const DIGEST_NUM_BYTES: usize = Digest::LEN * 8;
+
+/// Parse a hex-encoded sender randomness, rejecting malformed input with a 400
+/// that states the expected length.
+fn parse_sender_randomness(hex: &str) -> Result<Digest, RestError> {
+    parse_hex_digest("sender_randomness", hex)
+}
+
+/// Parse the hex-encoded [`Digest`] in request field `field`, rejecting
+/// malformed input with a 400 that names the field.
+fn parse_hex_digest(field: &str, hex: &str) -> Result<Digest, RestError> {
+    if let Some(c) = hex.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(RestError::BadRequest(format!(
+            "{} contains non-hex character '{}'",
+            field, c
+        )));
+    }
+    if hex.len() != 2 * DIGEST_NUM_BYTES {
+        return Err(RestError::BadRequest(format!(
+            "{} must be {} bytes ({} hex characters), got {} hex characters",
+            field,
+            DIGEST_NUM_BYTES,
+            2 * DIGEST_NUM_BYTES,
+            hex.len()
+        )));
+    }
+
+    Digest::try_from_hex(hex)
+        .map_err(|e| RestError::BadRequest(format!("invalid {}: {}", field, e)))
+}
+
+/// Compute the canonical commitment of the native-currency UTXO that a wallet
+/// produces when sending `amount` to `receiving_address`.
+///
+/// The [`TxOutput`] is built with the same constructors the wallet uses for
+/// the given notification medium and change flag. Note that the commitment
+/// itself only binds the UTXO, the sender randomness and the receiver's privacy
+/// digest, so neither the notification medium nor the change flag alter it.
+///
+/// The lock script and privacy digest are taken from `receiving_address`, so
+/// this works for every key type, including symmetric keys.
+fn calculate_utxo_commitment(
+    receiving_address: ReceivingAddress,
+    amount: NativeCurrencyAmount,
+    sender_randomness: Digest,
+    notification_medium: UtxoNotificationMedium,
+    is_change: bool,
+) -> String {
+    let output = match (notification_medium, is_change) {
+        (_, false) => TxOutput::native_currency(
+            amount,
+            sender_randomness,
+            receiving_address,
+            notification_medium,
+            false,
+        ),
+        (UtxoNotificationMedium::OnChain, true) => TxOutput::onchain_native_currency_as_change(
+            amount,
+            sender_randomness,
+            receiving_address,
+        ),
+        (UtxoNotificationMedium::OffChain, true) => TxOutput::offchain_native_currency_as_change(
+            amount,
+            sender_randomness,
+            receiving_address,
+        ),
+    };
+    let output_record: AdditionRecord = (&output).into();
+    output_record.canonical_commitment.to_hex()
+}
+
+/// Whether `outputs` pays the priority fee `amount` to [`FEE_ADDRESS`], as
+/// claimed by the submitter.
+pub(crate) fn pays_priority_fee(
+    outputs: &[AdditionRecord],
+    amount: &str,
+    sender_randomness: &str,
+) -> Result<bool, RestError> {
+    let fee_address = ReceivingAddress::from_bech32m(FEE_ADDRESS, Network::Main)?;
+    let amount = NativeCurrencyAmount::coins_from_str_checked(amount)?;
+    let sender_randomness = parse_sender_randomness(sender_randomness)?;
+
+    Ok(contains_fee_output(
+        outputs,
+        &fee_address,
+        amount,
+        sender_randomness,
+    ))
+}
+
+/// Whether `outputs` contains the UTXO paying `amount` to `fee_address`. The
+/// commitment depends neither on the notification medium nor on whether the
+/// sender's wallet built the output as change, so a single commitment covers
+/// every output the wallet may have built.
+fn contains_fee_output(
+    outputs: &[AdditionRecord],
+    fee_address: &ReceivingAddress,
+    amount: NativeCurrencyAmount,
+    sender_randomness: Digest,
+) -> bool {
+    let commitment = calculate_utxo_commitment(
+        fee_address.clone(),
+        amount,
+        sender_randomness,
+        UtxoNotificationMedium::OnChain,
+        false,
+    );
+    outputs
+        .iter()
+        .any(|output| output.canonical_commitment.to_hex() == commitment)
+}
+
+mod block_selector {
+    use std::str::FromStr;
+
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer};
+
+    use crate::models::blockchain::block::block_height::BlockHeight;
+    use crate::models::blockchain::block::block_selector::{
+        BlockSelector, BlockSelectorParseError,
+    };
+    use crate::models::state::GlobalState;
+
+    use height_or_digest::HeightOrDigest;
+
+    /// newtype for `BlockSelector` that provides ability to parse `height_or_digest/value`,
+    /// `tip-<N>`, and `genesis+<N>`.
+    ///
+    /// This is useful for HTML form(s) that allow user to enter either height or
+    /// digest into the same text input field.
+    ///
+    /// In particular it is necessary to support javascript-free website with such
+    /// an html form.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct BlockSelectorExtended(Selector);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Selector {
+        Block(BlockSelector),
+
+        /// The canonical block `N` blocks below the tip, which is only known
+        /// at request time.
+        TipMinus(u64),
+
+        /// The canonical block `N` blocks above genesis, i.e. at height `N`.
+        GenesisPlus(u64),
+    }
+
+    impl BlockSelectorExtended {
+        /// The [`BlockSelector`] this selects given the current tip, or `None`
+        /// if it selects a block below genesis.
+        pub fn resolve(self, state: &GlobalState) -> Option<BlockSelector> {
+            self.resolve_below(state.chain.light_state().header().height)
+        }
+
+        /// Like [`Self::resolve`], but with `tip-<N>` counting down from
+        /// `tip_height` instead of the current tip.
+        pub fn resolve_below(self, tip_height: BlockHeight) -> Option<BlockSelector> {
+            match self.0 {
+                Selector::Block(bs) => Some(bs),
+                Selector::TipMinus(n) => {
+                    let height = u64::from(tip_height).checked_sub(n)?;
+                    Some(BlockSelector::Height(height.into()))
+                }
+                Selector::GenesisPlus(n) => Some(BlockSelector::Height(n.into())),
+            }
+        }
+    }
+
+    impl std::fmt::Display for BlockSelectorExtended {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self.0 {
+                Selector::Block(bs) => write!(f, "{}", bs),
+                Selector::TipMinus(n) => write!(f, "tip-{}", n),
+                Selector::GenesisPlus(n) => write!(f, "genesis+{}", n),
+            }
+        }
+    }
+
+    impl FromStr for BlockSelectorExtended {
+        type Err = BlockSelectorParseError;
+
+        // note: this parses BlockSelector, plus height_or_digest/<value>, tip-<N>
+        //       and genesis+<N>
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match BlockSelector::from_str(s) {
+                Ok(bs) => Ok(Self::from(bs)),
+                Err(e) => {
+                    let parts: Vec<_> = s.split('/').collect();
+                    if parts.len() == 2 && parts[0] == "height_or_digest" {
+                        Ok(Self::from(HeightOrDigest::from_str(parts[1])?))
+                    } else if let Some(depth) = s.strip_prefix("tip-") {
+                        Ok(Self(Selector::TipMinus(depth.parse()?)))
+                    } else if let Some(height) = s.strip_prefix("genesis+") {
+                        Ok(Self(Selector::GenesisPlus(height.parse()?)))
+                    } else {
+                        Err(e)
+                    }
+                }
+            }
+        }
+    }
+
+    // note: axum uses serde Deserialize for Path elements.
+    impl<'de> Deserialize<'de> for BlockSelectorExtended {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let s = String::deserialize(deserializer)?;
+            Self::from_str(&s).map_err(D::Error::custom)
+        }
+    }
+
+    impl From<HeightOrDigest> for BlockSelectorExtended {
+        fn from(hd: HeightOrDigest) -> Self {
+            Self::from(BlockSelector::from(hd))
+        }
+    }
+
+    impl From<BlockSelector> for BlockSelectorExtended {
+        fn from(v: BlockSelector) -> Self {
+            Self(Selector::Block(v))
+        }
+    }
+
+    mod height_or_digest {
+        use crate::models::blockchain::block::block_height::BlockHeight;
+        use crate::models::blockchain::block::block_selector::BlockSelector;
+        use crate::models::blockchain::block::block_selector::BlockSelectorParseError;
+        use crate::prelude::tasm_lib::prelude::Digest;
+        use serde::{Deserialize, Serialize};
+        use std::str::FromStr;
+
+        /// Number of hex characters encoding a [`Digest`].
+        const DIGEST_HEX_LEN: usize = Digest::LEN * 8 * 2;
+
+        /// represents either a block-height or a block digest
+        #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+        pub enum HeightOrDigest {
+            /// Identifies block by Digest (hash)
+            Digest(Digest),
+            /// Identifies block by Height (count from genesis)
+            Height(BlockHeight),
+        }
+
+        impl std::fmt::Display for HeightOrDigest {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    Self::Digest(d) => write!(f, "{}", d),
+                    Self::Height(h) => write!(f, "{}", h),
+                }
+            }
+        }
+
+        impl FromStr for HeightOrDigest {
+            type Err = BlockSelectorParseError;
+
+            // note: this parses the output of impl Display for HeightOrDigest
+            // note: this is used by clap parser in neptune-cli for block-info command
+            //       and probably future commands as well.
+            //
+            // A string of exactly the length of a hex-encoded digest is always a
+            // digest, even if it consists of digits only. Anything else is a
+            // height if it is a decimal number, and a digest otherwise.
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                if s.len() == DIGEST_HEX_LEN {
+                    return Ok(Self::Digest(Digest::try_from_hex(s)?));
+                }
+
+                Ok(match s.parse::<u64>() {
+                    Ok(h) => Self::Height(h.into()),
+                    Err(_) => Self::Digest(Digest::try_from_hex(s)?),
+                })
+            }
+        }
+
+        impl From<HeightOrDigest> for BlockSelector {
+            fn from(hd: HeightOrDigest) -> Self {
+                match hd {
+                    HeightOrDigest::Height(h) => Self::Height(h),
+                    HeightOrDigest::Digest(d) => Self::Digest(d),
+                }
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            #[test]
+            fn short_number_is_height() {
+                assert!(matches!(
+                    HeightOrDigest::from_str("12345"),
+                    Ok(HeightOrDigest::Height(h)) if h == 12345u64.into()
+                ));
+            }
+
+            #[test]
+            fn full_length_hex_is_digest() {
+                let digest: Digest = rand::random();
+                assert!(matches!(
+                    HeightOrDigest::from_str(&digest.to_hex()),
+                    Ok(HeightOrDigest::Digest(d)) if d == digest
+                ));
+            }
+
+            #[test]
+            fn full_length_digits_are_digest() {
+                let all_digits = "1".repeat(DIGEST_HEX_LEN);
+                let digest = Digest::try_from_hex(&all_digits).unwrap();
+                assert!(matches!(
+                    HeightOrDigest::from_str(&all_digits),
+                    Ok(HeightOrDigest::Digest(d)) if d == digest
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+    use tasm_lib::triton_vm::prelude::bfe_vec;
+    use tasm_lib::triton_vm::prelude::Tip5;
+    use tasm_lib::twenty_first::util_types::mmr::mmr_accumulator::MmrAccumulator;
+    use tasm_lib::twenty_first::util_types::mmr::mmr_membership_proof::MmrMembershipProof;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::config_models::cli_args;
+    use crate::database::storage::storage_vec::traits::*;
+    use crate::mine_loop::fast_kernel_mast_hash;
+    use crate::models::blockchain::block::block_selector::BlockSelectorParseError;
+    use crate::models::blockchain::transaction::transaction_kernel::TransactionKernelModifier;
+    use crate::models::peer::NegativePeerSanction;
+    use crate::models::peer::PeerSanction;
+    use crate::models::state::block_proposal::BlockProposal;
+    use crate::models::state::wallet::address::generation_address::GenerationReceivingAddress;
+    use crate::models::state::wallet::address::symmetric_key::SymmetricKey;
+    use crate::models::state::wallet::wallet_entropy::WalletEntropy;
+    use crate::models::state::GlobalStateLock;
+    use crate::tests::shared::fake_valid_block_proposal_successor_for_test;
+    use crate::tests::shared::invalid_block_with_transaction;
+    use crate::tests::shared::invalid_empty_block;
+    use crate::tests::shared::invalid_empty_block_with_timestamp;
+    use crate::tests::shared::make_mock_block_guesser_preimage_and_guesser_fraction;
+    use crate::tests::shared::make_mock_transaction;
+    use crate::tests::shared::make_plenty_mock_transaction_supported_by_invalid_single_proofs;
+    use crate::tests::shared::mock_genesis_global_state;
+    use crate::tests::shared::unit_test_data_directory;
+    use crate::util_types::mutator_set::authenticated_item::AuthenticatedItem;
+
+    fn simple_block(height: u64) -> SimpleBlock {
+        SimpleBlock {
+            height,
+            hash: format!("hash{height}"),
+            fee: NativeCurrencyAmount::zero(),
+            timestamp: height * 1000,
+        }
+    }
+
+    #[test]
+    fn last_blocks_range_reports_gap() {
+        let lookups = (5..=9).map(|h| (h, (h != 7).then(|| simple_block(h))));
+        let range = SimpleBlockRange::from_lookups(lookups);
+
+        assert_eq!(Some(5), range.first_height);
+        assert_eq!(Some(9), range.last_height);
+        assert_eq!(vec![7], range.missing_heights);
+        assert_eq!(
+            vec![5, 6, 8, 9],
+            range.blocks.iter().map(|b| b.height).collect_vec()
+        );
+    }
+
+    #[test]
+    fn last_blocks_range_without_gaps() {
+        let lookups = (0..=3).map(|h| (h, Some(simple_block(h))));
+        let range = SimpleBlockRange::from_lookups(lookups);
+
+        assert_eq!(Some(0), range.first_height);
+        assert_eq!(Some(3), range.last_height);
+        assert!(range.missing_heights.is_empty());
+        assert_eq!(4, range.blocks.len());
+    }
+
+    #[test]
+    fn mempool_stats_median_and_histogram() {
+        let nau = NativeCurrencyAmount::from_nau;
+        let fees_and_sizes = [
+            (nau(5_000), 100, nau(50)),
+            (nau(0), 100, nau(0)),
+            (nau(100_000), 100, nau(1000)),
+            (nau(900), 100, nau(9)),
+            (nau(500), 100, nau(5)),
+        ];
+        let stats =
+            MempoolStats::from_fees_and_sizes(MempoolEvictionPolicy::LowestFee, 0, fees_and_sizes);
+
+        assert_eq!(5, stats.count);
+        assert_eq!(500, stats.total_size);
+        assert_eq!(nau(106_400), stats.total_fees);
+        assert_eq!(nau(0), stats.min_fee);
+        assert_eq!(nau(900), stats.median_fee);
+        assert_eq!(nau(100_000), stats.max_fee);
+
+        let bucket = |min_fee_per_byte, count| FeeHistogramBucket {
+            min_fee_per_byte: nau(min_fee_per_byte),
+            count,
+        };
+        assert_eq!(
+            vec![bucket(0, 1), bucket(1, 2), bucket(10, 1), bucket(1000, 1)],
+            stats.fee_histogram
+        );
+
+        // even number of transactions: median is the mean of the middle two
+        let stats = MempoolStats::from_fees_and_sizes(
+            MempoolEvictionPolicy::LowestFee,
+            0,
+            fees_and_sizes.into_iter().take(4),
+        );
+        assert_eq!(nau((900 + 5_000) / 2), stats.median_fee);
+    }
+
+    #[test]
+    fn mempool_stats_of_empty_mempool() {
+        let stats = MempoolStats::from_fees_and_sizes(MempoolEvictionPolicy::Oldest, 3, []);
+
+        assert_eq!(MempoolEvictionPolicy::Oldest, stats.eviction_policy);
+        assert_eq!(3, stats.num_evicted);
+        assert_eq!(0, stats.count);
+        assert!(stats.median_fee.is_zero());
+        assert!(stats.fee_histogram.is_empty());
+    }
+
+    async fn test_rpcstate(global_state_lock: GlobalStateLock) -> NeptuneRPCServer {
+        let network = global_state_lock.cli().network;
+        let data_directory = unit_test_data_directory(network).unwrap();
+        let valid_tokens = vec![crate::rpc_auth::Cookie::try_new(&data_directory)
+            .await
+            .unwrap()
+            .into()];
+        let rpc_server_to_main_tx = global_state_lock.rpc_server_to_main_tx();
+
+        NeptuneRPCServer::new(
+            global_state_lock,
+            rpc_server_to_main_tx,
+            data_directory,
+            valid_tokens,
+        )
+    }
+
+    /// A REST server state, and the headers that authenticate with it.
+    fn authenticated_rpcstate(global_state_lock: GlobalStateLock) -> (NeptuneRPCServer, HeaderMap) {
+        let rpc_server_to_main_tx = global_state_lock.rpc_server_to_main_tx();
+        authenticated_rpcstate_sending_to(global_state_lock, rpc_server_to_main_tx)
+    }
+
+    /// Like [`authenticated_rpcstate`], but the server sends its messages for
+    /// the main loop to `rpc_server_to_main_tx`.
+    fn authenticated_rpcstate_sending_to(
+        global_state_lock: GlobalStateLock,
+        rpc_server_to_main_tx: tokio::sync::mpsc::Sender<RPCServerToMain>,
+    ) -> (NeptuneRPCServer, HeaderMap) {
+        let network = global_state_lock.cli().network;
+        let cookie = rpc_auth::Cookie::new_in_mem();
+        let rpcstate = NeptuneRPCServer::new(
+            global_state_lock,
+            rpc_server_to_main_tx,
+            unit_test_data_directory(network).unwrap(),
+            vec![cookie.into()],
+        );
+        let mut headers = HeaderMap::new();
+        let bearer = format!("Bearer {}", cookie.as_hex()).parse().unwrap();
+        headers.insert(header::AUTHORIZATION, bearer);
+
+        (rpcstate, headers)
+    }
+
+    async fn json_value(response: impl IntoResponse) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_response().into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn miner_can_be_paused_and_resumed() {
+        let network = Network::Main;
+        let cli = cli_args::Args {
+            guess: true,
+            ..Default::default()
+        };
+        let mut global_state_lock =
+            mock_genesis_global_state(network, 0, WalletEntropy::new_random(), cli).await;
+        let (to_main_tx, mut to_main_rx) = tokio::sync::mpsc::channel(1);
+        let (rpcstate, headers) =
+            authenticated_rpcstate_sending_to(global_state_lock.clone(), to_main_tx);
+        global_state_lock
+            .set_mining_status_to_guessing(&Block::genesis(network))
+            .await;
+
+        let unauthenticated = pause_miner(State(rpcstate.clone()), HeaderMap::new()).await;
+        assert!(matches!(unauthenticated, Err(RestError::Unauthorized(_))));
+        let unauthenticated = resume_miner(State(rpcstate.clone()), HeaderMap::new()).await;
+        assert!(matches!(unauthenticated, Err(RestError::Unauthorized(_))));
+        assert!(to_main_rx.try_recv().is_err());
+        assert!(!global_state_lock.lock_guard().await.mining_state.paused);
+
+        let paused = pause_miner(State(rpcstate.clone()), headers.clone()).await;
+        let paused = json_value(paused.unwrap()).await;
+        assert!(matches!(
+            to_main_rx.recv().await,
+            Some(RPCServerToMain::PauseMiner)
+        ));
+        assert_eq!(serde_json::Value::Bool(true), paused["paused"]);
+        assert!(paused["mining_status"].get("Guessing").is_some());
+        assert!(global_state_lock.lock_guard().await.mining_state.paused);
+
+        // the miner stops guessing in reaction to the pause request
+        global_state_lock.set_mining_status_to_inactive().await;
+
+        let resumed = json_value(resume_miner(State(rpcstate), headers).await.unwrap()).await;
+        assert!(matches!(
+            to_main_rx.recv().await,
+            Some(RPCServerToMain::RestartMiner)
+        ));
+        assert_eq!(serde_json::Value::Bool(false), resumed["paused"]);
+        assert_eq!("Inactive", resumed["mining_status"]);
+        assert!(!global_state_lock.lock_guard().await.mining_state.paused);
+    }
+
+    #[tokio::test]
+    async fn miner_that_was_never_started_cannot_be_paused() {
+        let global_state_lock = mock_genesis_global_state(
+            Network::Main,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let (rpcstate, headers) = authenticated_rpcstate(global_state_lock);
+
+        let pause = pause_miner(State(rpcstate.clone()), headers.clone()).await;
+        assert!(bad_request_message(pause).contains("never started"));
+        let resume = resume_miner(State(rpcstate), headers).await;
+        assert!(bad_request_message(resume).contains("never started"));
+    }
+
+    #[tokio::test]
+    async fn authenticated_shutdown_request_is_forwarded_to_main_loop() {
+        let network = Network::Main;
+        let global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let (to_main_tx, mut to_main_rx) = tokio::sync::mpsc::channel(1);
+        let (rpcstate, headers) =
+            authenticated_rpcstate_sending_to(global_state_lock.clone(), to_main_tx);
+
+        let unauthenticated = shutdown(State(rpcstate.clone()), HeaderMap::new()).await;
+        assert!(matches!(unauthenticated, Err(RestError::Unauthorized(_))));
+        let (_, other_cookie) = authenticated_rpcstate(global_state_lock);
+        let wrong_cookie = shutdown(State(rpcstate.clone()), other_cookie).await;
+        assert!(matches!(wrong_cookie, Err(RestError::Unauthorized(_))));
+        assert!(to_main_rx.try_recv().is_err());
+
+        let accepted = shutdown(State(rpcstate), headers).await;
+        assert_eq!(StatusCode::ACCEPTED, accepted.unwrap());
+        assert!(matches!(
+            to_main_rx.recv().await,
+            Some(RPCServerToMain::Shutdown)
+        ));
+    }
+
+    #[tokio::test]
+    async fn peer_standings_reflect_applied_sanctions() {
+        let global_state_lock = mock_genesis_global_state(
+            Network::Main,
+            2,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+
+        let genesis = Block::genesis(Network::Main);
+        let invalid_block = NegativePeerSanction::InvalidBlock((1u64.into(), genesis.hash()));
+        let disconnected_ip: IpAddr = "8.8.8.8".parse().unwrap();
+        let sanctioned_address = {
+            let mut state = global_state_lock.lock_guard_mut().await;
+            let (address, peer_info) = state.net.peer_map.iter_mut().next().unwrap();
+            let _ = peer_info
+                .standing
+                .sanction(PeerSanction::Negative(invalid_block));
+            let address = *address;
+
+            let mut standing = PeerStanding::new(cli_args::Args::default().peer_tolerance);
+            for _ in 0..2 {
+                let _ = standing.sanction(PeerSanction::Negative(invalid_block));
+            }
+            state
+                .net
+                .peer_databases
+                .peer_standings
+                .put(disconnected_ip, standing)
+                .await;
+            address
+        };
+
+        let standings = json_value(get_peer_standings(State(rpcstate)).await.unwrap()).await;
+        let standings = standings.as_array().unwrap();
+        assert_eq!(3, standings.len());
+
+        assert_eq!(serde_json::json!(disconnected_ip), standings[0]["ip"]);
+        assert!(standings[0]["connected_address"].is_null());
+        assert_eq!(serde_json::json!(-20), standings[0]["standing"]);
+
+        assert_eq!(
+            serde_json::json!(sanctioned_address),
+            standings[1]["connected_address"]
+        );
+        assert_eq!(serde_json::json!(-10), standings[1]["standing"]);
+        assert_eq!(
+            serde_json::json!(invalid_block.to_string()),
+            standings[1]["latest_punishment"]["reason"]
+        );
+        assert!(standings[1]["latest_reward"].is_null());
+
+        assert_eq!(serde_json::json!(0), standings[2]["standing"]);
+        assert!(standings[2]["latest_punishment"].is_null());
+    }
+
+    #[tokio::test]
+    async fn health_reports_number_of_orphan_blocks() {
+        let global_state_lock = mock_genesis_global_state(
+            Network::Main,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let data_directory = unit_test_data_directory(Network::Main).unwrap();
+        let pool_state = PoolState::new(&data_directory, tx_pool::DEFAULT_BUSY_TIMEOUT).unwrap();
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+
+        let health = |rpcstate: NeptuneRPCServer, pool_state: PoolState| async move {
+            let (status, body) = get_health(State((rpcstate, pool_state))).await;
+            assert_eq!(StatusCode::OK, status);
+            json_value(body).await
+        };
+        let body = health(rpcstate.clone(), pool_state.clone()).await;
+        assert_eq!(serde_json::json!(0), body["orphan_blocks"]);
+
+        let genesis = Block::genesis(Network::Main);
+        let orphan = invalid_empty_block(&invalid_empty_block(&genesis));
+        global_state_lock
+            .lock_guard_mut()
+            .await
+            .net
+            .orphan_blocks
+            .insert(orphan);
+        let body = health(rpcstate, pool_state).await;
+        assert_eq!(serde_json::json!(1), body["orphan_blocks"]);
+        assert_eq!(serde_json::json!(true), body["tx_pool_ok"]);
+    }
+
+    #[tokio::test]
+    async fn metrics_are_reported_in_prometheus_format() {
+        let num_peers = 2;
+        let global_state_lock = mock_genesis_global_state(
+            Network::Main,
+            num_peers,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let data_directory = unit_test_data_directory(Network::Main).unwrap();
+        let pool_state = PoolState::new(&data_directory, tx_pool::DEFAULT_BUSY_TIMEOUT).unwrap();
+        let fee = pool_state.min_fee().to_nau();
+        for n in 0..3u64 {
+            let id = tx_pool::PoolTxId::from(TransactionKernelId::new(Tip5::hash(&n)));
+            pool_state
+                .add_transaction(&id, &[0; 8], fee, false)
+                .unwrap();
+        }
+        pool_state.get_most_worth_transaction().unwrap();
+
+        let request_metrics = RequestMetrics::default();
+        let latency = Duration::from_millis(20);
+        for route in ["/rpc/mempool_stats", "/rpc/mempool_stats", "/metrics"] {
+            request_metrics.record(route, StatusCode::OK, latency);
+        }
+        let metrics_state = MetricsState {
+            rpcstate: test_rpcstate(global_state_lock).await,
+            pool_state,
+            request_metrics,
+        };
+        let metrics = render_metrics(&metrics_state).await.unwrap();
+
+        // every line is a comment or a sample: `<name>[{<labels>}] <value>`
+        let samples: HashMap<String, f64> = metrics
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| {
+                let (series, value) = line.rsplit_once(' ').unwrap();
+                (series.to_owned(), value.parse().unwrap())
+            })
+            .collect();
+
+        let expected = [
+            ("neptune_tip_height", 0.0),
+            ("neptune_mempool_transactions", 0.0),
+            ("neptune_tx_pool_pending", 2.0),
+            ("neptune_tx_pool_executing", 1.0),
+            ("neptune_peers", f64::from(num_peers)),
+            (
+                r#"neptune_rpc_requests_total{route="/rpc/mempool_stats",status="200"}"#,
+                2.0,
+            ),
+            (
+                r#"neptune_rpc_requests_total{route="/metrics",status="200"}"#,
+                1.0,
+            ),
+            (
+                r#"neptune_rpc_request_duration_seconds_bucket{route="/metrics",status="200",le="0.01"}"#,
+                0.0,
+            ),
+            (
+                r#"neptune_rpc_request_duration_seconds_bucket{route="/metrics",status="200",le="0.025"}"#,
+                1.0,
+            ),
+            (
+                r#"neptune_rpc_request_duration_seconds_count{route="/rpc/mempool_stats",status="200"}"#,
+                2.0,
+            ),
+        ];
+        for (series, value) in expected {
+            assert_eq!(Some(&value), samples.get(series), "{series}");
+        }
+    }
+
+    #[tokio::test]
+    async fn requests_are_counted_per_route_template_and_status() {
+        let request_metrics = RequestMetrics::default();
+        let utxo_digest = |Path(leaf_index): Path<u64>| async move {
+            match leaf_index {
+                0 => StatusCode::OK,
+                _ => StatusCode::NOT_FOUND,
+            }
+        };
+        let router = axum::Router::new()
+            .route("/rpc/mempool_stats", axum::routing::get(|| async { "{}" }))
+            .route(
+                "/rpc/utxo_digest/{leaf_index}",
+                axum::routing::get(utxo_digest),
+            )
+            .route_layer(axum::middleware::from_fn_with_state(
+                request_metrics.clone(),
+                record_request_metrics,
+            ));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let paths = [
+            "/rpc/mempool_stats",
+            "/rpc/utxo_digest/0",
+            "/rpc/utxo_digest/0",
+            "/rpc/utxo_digest/7",
+            "/no_such_route",
+        ];
+        for path in paths {
+            reqwest::get(format!("http://{address}{path}"))
+                .await
+                .unwrap();
+        }
+
+        let counts = request_metrics
+            .snapshot()
+            .into_iter()
+            .map(|(route_and_status, stats)| (route_and_status, stats.count))
+            .collect::<BTreeMap<_, _>>();
+        let utxo_digest_route = "/rpc/utxo_digest/{leaf_index}".to_string();
+        let expected = BTreeMap::from([
+            (("/rpc/mempool_stats".to_string(), 200), 1),
+            ((utxo_digest_route.clone(), 200), 2),
+            ((utxo_digest_route, 404), 1),
+        ]);
+        assert_eq!(expected, counts);
+    }
+
+    #[tokio::test]
+    async fn json_responses_are_compact_unless_pretty_is_selected() {
+        let serve = |pretty_by_default: bool| async move {
+            let handler = || async {
+                json_response(serde_json::json!({
+                    "height": 7,
+                    "digests": ["aa", "bb"],
+                    "nested": { "fee": { "display": "1.5", "nau": "6" } },
+                }))
+            };
+            let router = axum::Router::new()
+                .route("/rpc/value", axum::routing::get(handler))
+                .layer(axum::middleware::from_fn_with_state(
+                    pretty_by_default,
+                    select_json_format,
+                ));
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, router).await });
+            address
+        };
+        let get = |address: SocketAddr, query: &'static str| async move {
+            let url = format!("http://{address}/rpc/value{query}");
+            reqwest::get(url).await.unwrap().text().await.unwrap()
+        };
+
+        let compact_by_default = serve(false).await;
+        let compact = get(compact_by_default, "").await;
+        let pretty = get(compact_by_default, "?pretty=true").await;
+        assert!(compact.len() < pretty.len());
+        assert!(!compact.contains('\n'));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&pretty).unwrap(),
+            serde_json::from_str::<serde_json::Value>(&compact).unwrap()
+        );
+
+        let pretty_by_default = serve(true).await;
+        assert_eq!(pretty, get(pretty_by_default, "").await);
+        assert_eq!(compact, get(pretty_by_default, "?pretty=false").await);
+
+        // an unparsable choice falls back to the default
+        assert_eq!(pretty, get(pretty_by_default, "?pretty=maybe").await);
+    }
+
+    #[tokio::test]
+    async fn requests_beyond_concurrency_limit_are_rejected_promptly() {
+        let max_connections = 2;
+        let release = Arc::new(tokio::sync::Notify::new());
+        let slow = {
+            let release = release.clone();
+            move || {
+                let release = release.clone();
+                async move {
+                    release.notified().await;
+                    "done"
+                }
+            }
+        };
+        let router = axum::Router::new()
+            .route("/rpc/slow", axum::routing::get(slow))
+            .layer(axum::middleware::from_fn_with_state(
+                Arc::new(Semaphore::new(max_connections)),
+                limit_concurrent_requests,
+            ));
+        let listener = bind_rest_listener("127.0.0.1:0".parse().unwrap(), 16).unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let url = format!("http://{address}/rpc/slow");
+        let in_flight = (0..max_connections)
+            .map(|_| tokio::spawn(reqwest::get(url.clone())))
+            .collect_vec();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        for _ in 0..3 {
+            let excess = tokio::time::timeout(Duration::from_secs(5), reqwest::get(&url))
+                .await
+                .expect("excess request must not hang")
+                .unwrap();
+            assert_eq!(503, excess.status().as_u16());
+        }
+
+        release.notify_waiters();
+        for request in in_flight {
+            let response = request.await.unwrap().unwrap();
+            assert_eq!("done", response.text().await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn block_template_respects_size_budget_and_sums_fees() {
+        let global_state_lock = mock_genesis_global_state(
+            Network::Main,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+        let tip_mutator_set_hash = Block::genesis(Network::Main)
+            .mutator_set_accumulator_after()
+            .hash();
+
+        let mut txs = make_plenty_mock_transaction_supported_by_invalid_single_proofs(4);
+        for (fee, tx) in txs.iter_mut().enumerate() {
+            tx.kernel = TransactionKernelModifier::default()
+                .mutator_set_hash(tip_mutator_set_hash)
+                .fee(NativeCurrencyAmount::coins(fee as u32 + 1))
+                .modify(tx.kernel.clone());
+            global_state_lock
+                .lock_guard_mut()
+                .await
+                .mempool_insert(tx.clone(), TransactionOrigin::Foreign)
+                .await;
+        }
+
+        // too small for all transactions
+        let max_size = txs.iter().map(|tx| tx.kernel.encode().len()).sum::<usize>() - 1;
+        let params = BlockTemplateParams {
+            max_size: Some(max_size),
+        };
+        let template = get_block_template(State(rpcstate), Query(params))
+            .await
+            .unwrap();
+        let template: BlockTemplate = serde_json::from_value(json_value(template).await).unwrap();
+
+        assert_eq!(BlockHeight::from(1u64), template.height);
+        assert!(!template.txids.is_empty());
+        assert!(template.txids.len() < txs.len());
+        assert!(template.total_size <= max_size);
+
+        let selected = txs
+            .iter()
+            .filter(|tx| template.txids.contains(&tx.kernel.txid()))
+            .collect_vec();
+        assert_eq!(template.txids.len(), selected.len());
+        assert_eq!(
+            selected
+                .iter()
+                .map(|tx| tx.kernel.encode().len())
+                .sum::<usize>(),
+            template.total_size
+        );
+        let total_fee = selected
+            .iter()
+            .fold(NativeCurrencyAmount::zero(), |acc, tx| acc + tx.kernel.fee);
+        assert_eq!(total_fee, template.total_fee);
+        assert_eq!(Block::block_subsidy(1u64.into()), template.coinbase);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn access_log_contains_client_ip_and_bytes_sent() {
+        let router = axum::Router::new()
+            .route("/rpc/greeting", axum::routing::get(|| async { "hello" }))
+            .layer(access_log_layer());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let service = router.into_make_service_with_connect_info::<SocketAddr>();
+            axum::serve(listener, service).await
+        });
+
+        let response = reqwest::get(format!("http://{address}/rpc/greeting"))
+            .await
+            .unwrap();
+        assert_eq!("hello", response.text().await.unwrap());
+
+        assert!(logs_contain("client_ip=127.0.0.1"));
+        assert!(logs_contain("path=/rpc/greeting"));
+        assert!(logs_contain("status=200"));
+        assert!(logs_contain("bytes_sent=5"));
+    }
+
+    /// Connect to the WebSocket at `path` and complete the opening handshake.
+    async fn websocket_handshake(address: SocketAddr, path: &str) -> tokio::net::TcpStream {
+        use tokio::io::AsyncReadExt;
+        use tokio::io::AsyncWriteExt;
+
+        let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+        let handshake = format!(
+            "GET {path} HTTP/1.1\r\nHost: {address}\r\nUpgrade: websocket\r\n\
+            Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+            Sec-WebSocket-Version: 13\r\n\r\n"
+        );
+        stream.write_all(handshake.as_bytes()).await.unwrap();
+        let mut response = vec![];
+        while !response.ends_with(b"\r\n\r\n") {
+            response.push(stream.read_u8().await.unwrap());
+        }
+        assert!(response.starts_with(b"HTTP/1.1 101"));
+
+        stream
+    }
+
+    /// Read a single, unmasked text frame sent by the server.
+    async fn read_websocket_text(stream: &mut tokio::net::TcpStream) -> Vec<u8> {
+        use tokio::io::AsyncReadExt;
+
+        assert_eq!(0x81, stream.read_u8().await.unwrap());
+        let payload_len = match stream.read_u8().await.unwrap() {
+            126 => usize::from(stream.read_u16().await.unwrap()),
+            127 => stream.read_u64().await.unwrap() as usize,
+            len => usize::from(len),
+        };
+        let mut payload = vec![0; payload_len];
+        stream.read_exact(&mut payload).await.unwrap();
+
+        payload
+    }
+
+    /// Send a single text frame, masked as clients must.
+    async fn write_websocket_text(stream: &mut tokio::net::TcpStream, text: &str) {
+        use tokio::io::AsyncWriteExt;
+
+        let mask = [0x12, 0x34, 0x56, 0x78];
+        let mut frame = vec![0x81];
+        match u16::try_from(text.len()) {
+            Ok(len) if len < 126 => frame.push(0x80 | len as u8),
+            Ok(len) => {
+                frame.push(0x80 | 126);
+                frame.extend(len.to_be_bytes());
+            }
+            Err(_) => {
+                frame.push(0x80 | 127);
+                frame.extend((text.len() as u64).to_be_bytes());
+            }
+        }
+        frame.extend(mask);
+        frame.extend(text.bytes().zip(mask.iter().cycle()).map(|(b, m)| b ^ m));
+        stream.write_all(&frame).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reorg_stream_delivers_common_ancestor() {
+        let network = Network::Main;
+        let mut global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let genesis = Block::genesis(network);
+        let block_1 = invalid_empty_block(&genesis);
+        let block_2a = invalid_empty_block(&block_1);
+        let later = block_2a.header().timestamp + Timestamp::hours(1);
+        let block_2b = invalid_empty_block_with_timestamp(&block_1, later);
+        for block in [&block_1, &block_2a] {
+            global_state_lock.set_new_tip(block.clone()).await.unwrap();
+        }
+
+        let router = axum::Router::new()
+            .route("/rpc/ws/reorgs", axum::routing::get(stream_reorgs))
+            .with_state(test_rpcstate(global_state_lock.clone()).await);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let mut stream = websocket_handshake(address, "/rpc/ws/reorgs").await;
+
+        global_state_lock
+            .set_new_tip(block_2b.clone())
+            .await
+            .unwrap();
+
+        let payload = read_websocket_text(&mut stream).await;
+        let reorg: ReorgNotification = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(
+            ReorgNotification {
+                common_ancestor_height: block_1.header().height,
+                old_tip: block_2a.hash(),
+                new_tip: block_2b.hash(),
+            },
+            reorg
+        );
+    }
+
+    #[tokio::test]
+    async fn open_reorg_stream_counts_against_concurrency_limit() {
+        let global_state_lock = mock_genesis_global_state(
+            Network::Main,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let router = axum::Router::new()
+            .route("/rpc/ws/reorgs", axum::routing::get(stream_reorgs))
+            .with_state(test_rpcstate(global_state_lock).await)
+            .layer(axum::middleware::from_fn_with_state(
+                Arc::new(Semaphore::new(1)),
+                limit_concurrent_requests,
+            ));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let stream = websocket_handshake(address, "/rpc/ws/reorgs").await;
+        let url = format!("http://{address}/rpc/ws/reorgs");
+        let excess = reqwest::get(&url).await.unwrap();
+        assert_eq!(503, excess.status().as_u16());
+
+        // Closing the socket returns the permit.
+        drop(stream);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while reqwest::get(&url).await.unwrap().status().as_u16() == 503 {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("permit must be returned once the socket is closed");
+    }
+
+    #[tokio::test]
+    async fn proposal_stream_only_delivers_proposals_matching_prefix() {
+        let network = Network::Main;
+        let global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let router = axum::Router::new()
+            .route(
+                "/rpc/ws/proposals",
+                axum::routing::get(stream_block_proposals),
+            )
+            .with_state(test_rpcstate(global_state_lock.clone()).await);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let genesis = Block::genesis(network);
+        let proposal_with_announcements = |message: Vec<BFieldElement>| {
+            let transaction = make_mock_transaction(vec![], vec![]);
+            let kernel = TransactionKernelModifier::default()
+                .public_announcements(vec![PublicAnnouncement::new(message)])
+                .clone_modify(&transaction.kernel);
+            let transaction = Transaction {
+                kernel,
+                ..transaction
+            };
+            invalid_block_with_transaction(&genesis, transaction)
+        };
+        let other = proposal_with_announcements(bfe_vec![7, 9, 1]);
+        let matching = proposal_with_announcements(bfe_vec![7, 8, 1, 2]);
+
+        let mut stream = websocket_handshake(address, "/rpc/ws/proposals").await;
+        let subscription = ProposalSubscription {
+            announcement_prefix: Some(bfe_vec![7, 8]),
+        };
+        write_websocket_text(&mut stream, &serde_json::to_string(&subscription).unwrap()).await;
+        for proposal in [&other, &matching] {
+            global_state_lock
+                .lock_guard()
+                .await
+                .publish_block_proposal(proposal);
+        }
+
+        let payload = read_websocket_text(&mut stream).await;
+        let proposed_block: ProposedBlock = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(*matching.header(), proposed_block.header);
+        assert_eq!(
+            matching.body().transaction_kernel.public_announcements,
+            proposed_block.public_announcements
+        );
+    }
+
+    #[tokio::test]
+    async fn block_request_with_matching_etag_is_not_modified() {
+        let network = Network::Main;
+        let mut global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+        let block_1 = invalid_empty_block(&Block::genesis(network));
+        global_state_lock
+            .set_new_tip(block_1.clone())
+            .await
+            .unwrap();
+
+        let request = |if_none_match: Option<&HeaderValue>| {
+            let rpcstate = rpcstate.clone();
+            let mut headers = HeaderMap::new();
+            if let Some(etag) = if_none_match {
+                headers.insert(header::IF_NONE_MATCH, etag.clone());
+            }
+            let block_selector = BlockSelectorExtended::from(BlockSelector::Digest(block_1.hash()));
+            async move {
+                get_block(
+                    State(rpcstate),
+                    Path(block_selector),
+                    Query(BlockPin::default()),
+                    headers,
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        let response = request(None).await;
+        assert_eq!(StatusCode::OK, response.status());
+        let etag = response.headers().get(header::ETAG).unwrap().clone();
+        assert!(etag.to_str().unwrap().contains(&block_1.hash().to_hex()));
+        assert_eq!(
+            serde_json::to_value(block_1.without_proof()).unwrap(),
+            json_value(response).await
+        );
+
+        let conditional = request(Some(&etag)).await;
+        assert_eq!(StatusCode::NOT_MODIFIED, conditional.status());
+        assert_eq!(Some(&etag), conditional.headers().get(header::ETAG));
+        let body = axum::body::to_bytes(conditional.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+
+        let other_etag = HeaderValue::from_str(&block_etag(Digest::default())).unwrap();
+        assert_eq!(StatusCode::OK, request(Some(&other_etag)).await.status());
+    }
+
+    #[tokio::test]
+    async fn pruned_block_is_served_as_marker_with_header() {
+        let network = Network::Main;
+        let cli = cli_args::Args {
+            prune_depth: Some(2),
+            ..Default::default()
+        };
+        let mut global_state_lock =
+            mock_genesis_global_state(network, 0, WalletEntropy::new_random(), cli).await;
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+        let mut blocks = vec![Block::genesis(network)];
+        for _ in 0..4 {
+            let block = invalid_empty_block(blocks.last().unwrap());
+            global_state_lock.set_new_tip(block.clone()).await.unwrap();
+            blocks.push(block);
+        }
+
+        let request = |digest: Digest| {
+            let rpcstate = rpcstate.clone();
+            let block_selector = BlockSelectorExtended::from(BlockSelector::Digest(digest));
+            async move {
+                let response = get_block(
+                    State(rpcstate),
+                    Path(block_selector),
+                    Query(BlockPin::default()),
+                    HeaderMap::new(),
+                )
+                .await
+                .unwrap();
+                assert_eq!(StatusCode::OK, response.status());
+                json_value(response).await
+            }
+        };
+
+        // only block 1 lies more than 2 blocks below the tip
+        let pruned: PrunedBlock = serde_json::from_value(request(blocks[1].hash()).await).unwrap();
+        assert!(pruned.body_pruned);
+        assert_eq!(blocks[1].hash(), pruned.digest);
+        assert_eq!(*blocks[1].header(), pruned.header);
+        for block in [&blocks[0], &blocks[2], &blocks[3], &blocks[4]] {
+            assert_eq!(
+                serde_json::to_value(block.without_proof()).unwrap(),
+                request(block.hash()).await
+            );
+        }
+        assert_eq!(
+            blocks[4].hash(),
+            global_state_lock
+                .lock_guard()
+                .await
+                .chain
+                .light_state()
+                .hash()
+        );
+    }
+
+    #[tokio::test]
+    async fn only_buried_canonical_blocks_requested_by_digest_are_cacheable() {
+        let network = Network::Main;
+        let mut global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+        let genesis = Block::genesis(network);
+        let orphan = invalid_empty_block(&genesis);
+        global_state_lock.set_new_tip(orphan.clone()).await.unwrap();
+        let mut blocks = vec![genesis];
+        for _ in 0..BURIED_BLOCK_CONFIRMATIONS {
+            let later = blocks.last().unwrap().header().timestamp + Timestamp::hours(1);
+            let block = invalid_empty_block_with_timestamp(blocks.last().unwrap(), later);
+            global_state_lock.set_new_tip(block.clone()).await.unwrap();
+            blocks.push(block);
+        }
+
+        let cache_control_of = |block_selector: BlockSelectorExtended| {
+            let rpcstate = rpcstate.clone();
+            async move {
+                let response = get_block(
+                    State(rpcstate),
+                    Path(block_selector),
+                    Query(BlockPin::default()),
+                    HeaderMap::new(),
+                )
+                .await
+                .unwrap();
+                let cache_control = response.headers().get(header::CACHE_CONTROL).unwrap();
+                cache_control.to_str().unwrap().to_owned()
+            }
+        };
+
+        let cache_control = |block: &Block| {
+            let block_selector = BlockSelectorExtended::from(BlockSelector::Digest(block.hash()));
+            cache_control_of(block_selector)
+        };
+
+        // block 1 has exactly the number of confirmations required
+        let long_max_age = format!("public, max-age={}", BURIED_BLOCK_MAX_AGE.as_secs());
+        assert_eq!(long_max_age, cache_control(&blocks[0]).await);
+        assert_eq!(long_max_age, cache_control(&blocks[1]).await);
+        assert_eq!("no-cache", cache_control(&blocks[2]).await);
+        assert_eq!("no-cache", cache_control(blocks.last().unwrap()).await);
+        assert_eq!("no-cache", cache_control(&orphan).await);
+
+        // selectors other than digests select another block as the chain grows
+        let tip_minus = format!("tip-{BURIED_BLOCK_CONFIRMATIONS}");
+        for selector in [tip_minus.as_str(), "genesis", "height/0", "genesis+0"] {
+            let block_selector = selector.parse().unwrap();
+            let response_cache_control = cache_control_of(block_selector).await;
+            assert_eq!("no-cache", response_cache_control, "{selector}");
+        }
+    }
+
+    #[tokio::test]
+    async fn block_request_pinned_to_orphaned_tip_is_conflict() {
+        let network = Network::Main;
+        let mut global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+        let genesis = Block::genesis(network);
+        let block_1a = invalid_empty_block(&genesis);
+        let later = block_1a.header().timestamp + Timestamp::hours(1);
+        let block_1b = invalid_empty_block_with_timestamp(&genesis, later);
+        assert_ne!(block_1a.hash(), block_1b.hash());
+
+        let get_pinned = |block_selector: BlockSelector, pin: Digest| {
+            let rpcstate = rpcstate.clone();
+            let block_pin = BlockPin {
+                pin: Some(pin.to_hex()),
+            };
+            async move {
+                get_block(
+                    State(rpcstate),
+                    Path(block_selector.into()),
+                    Query(block_pin),
+                    HeaderMap::new(),
+                )
+                .await
+            }
+        };
+        let genesis_selector = BlockSelector::Height(0u64.into());
+
+        global_state_lock
+            .set_new_tip(block_1a.clone())
+            .await
+            .unwrap();
+        let pinned_tip = get_pinned(BlockSelector::Tip, block_1a.hash()).await;
+        assert_eq!(
+            serde_json::to_value(block_1a.without_proof()).unwrap(),
+            json_value(pinned_tip.unwrap()).await
+        );
+        let pinned_genesis = get_pinned(genesis_selector, block_1a.hash()).await;
+        assert!(json_value(pinned_genesis.unwrap()).await.is_object());
+
+        // reorganize to a sibling of the pinned tip
+        global_state_lock.set_new_tip(block_1b).await.unwrap();
+        for block_selector in [BlockSelector::Tip, genesis_selector] {
+            let result = get_pinned(block_selector, block_1a.hash()).await;
+            assert!(matches!(result, Err(RestError::Conflict(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn pinned_tip_relative_selector_counts_down_from_pin() {
+        let network = Network::Main;
+        let mut global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+        let genesis = Block::genesis(network);
+        let block_1 = invalid_empty_block(&genesis);
+        let block_2 = invalid_empty_block(&block_1);
+        for block in [&block_1, &block_2] {
+            global_state_lock.set_new_tip(block.clone()).await.unwrap();
+        }
+
+        let get_pinned = |block_selector: &str| {
+            let rpcstate = rpcstate.clone();
+            let block_selector = block_selector.parse().unwrap();
+            let block_pin = BlockPin {
+                pin: Some(block_1.hash().to_hex()),
+            };
+            async move {
+                let response = get_block(
+                    State(rpcstate),
+                    Path(block_selector),
+                    Query(block_pin),
+                    HeaderMap::new(),
+                )
+                .await;
+                json_value(response.unwrap()).await
+            }
+        };
+
+        // the live tip is block 2, but `tip-1` below the pin is genesis
+        assert_eq!(
+            serde_json::to_value(genesis.without_proof()).unwrap(),
+            get_pinned("tip-1").await
+        );
+        assert_eq!(
+            serde_json::to_value(block_1.without_proof()).unwrap(),
+            get_pinned("tip-0").await
+        );
+        assert!(get_pinned("tip-2").await.is_null());
+    }
+
+    #[test]
+    fn tip_relative_selectors_are_parsed() {
+        let tip: BlockSelectorExtended = "tip".parse().unwrap();
+        assert_eq!(BlockSelectorExtended::from(BlockSelector::Tip), tip);
+
+        let tip_minus_5: BlockSelectorExtended = "tip-5".parse().unwrap();
+        assert_eq!("tip-5", tip_minus_5.to_string());
+        assert_ne!(tip, tip_minus_5);
+
+        assert!(matches!(
+            "tip-abc".parse::<BlockSelectorExtended>(),
+            Err(BlockSelectorParseError::BadHeight(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn tip_relative_selectors_resolve_against_current_tip() {
+        let network = Network::Main;
+        let mut global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let block_1 = invalid_empty_block(&Block::genesis(network));
+        global_state_lock
+            .set_new_tip(block_1.clone())
+            .await
+            .unwrap();
+
+        let state = global_state_lock.lock_guard().await;
+        let resolve = |s: &str| s.parse::<BlockSelectorExtended>().unwrap().resolve(&state);
+        assert_eq!(Some(BlockSelector::Tip), resolve("tip"));
+        assert_eq!(Some(BlockSelector::Height(1u64.into())), resolve("tip-0"));
+        assert_eq!(Some(BlockSelector::Height(0u64.into())), resolve("tip-1"));
+        assert_eq!(None, resolve("tip-2"));
+    }
+
+    #[test]
+    fn genesis_relative_selectors_are_parsed() {
+        let genesis_plus_10: BlockSelectorExtended = "genesis+10".parse().unwrap();
+        assert_eq!("genesis+10", genesis_plus_10.to_string());
+
+        assert!(matches!(
+            "genesis+ten".parse::<BlockSelectorExtended>(),
+            Err(BlockSelectorParseError::BadHeight(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn genesis_relative_selectors_resolve_to_canonical_heights() {
+        let network = Network::Main;
+        let mut global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let mut blocks = vec![Block::genesis(network)];
+        for _ in 0..10 {
+            let block = invalid_empty_block(blocks.last().unwrap());
+            global_state_lock.set_new_tip(block.clone()).await.unwrap();
+            blocks.push(block);
+        }
+
+        let state = global_state_lock.lock_guard().await;
+        let digest = |s: &str| {
+            let block_selector = s.parse::<BlockSelectorExtended>().unwrap().resolve(&state);
+            let state = &state;
+            async move { block_selector?.as_digest(state).await }
+        };
+        assert_eq!(Some(blocks[0].hash()), digest("genesis+0").await);
+        assert_eq!(Some(blocks[10].hash()), digest("genesis+10").await);
+        assert_eq!(None, digest(&format!("genesis+{}", u64::MAX)).await);
+    }
+
+    #[tokio::test]
+    async fn blocks_guessed_by_external_wallet_are_matched() {
+        let network = Network::Main;
+        let own_wallet = WalletEntropy::new_random();
+        let mut global_state_lock =
+            mock_genesis_global_state(network, 0, own_wallet.clone(), cli_args::Args::default())
+                .await;
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+
+        // blocks 1 and 3 are guessed by the external wallet, 2 and 4 by our own
+        let external_wallet = WalletEntropy::new_pseudorandom([3; 32]);
+        let mut external_guesser_digests = vec![];
+        let mut predecessor = Block::genesis(network);
+        for height in 1..=4 {
+            let is_external = height % 2 == 1;
+            let guesser = if is_external {
+                &external_wallet
+            } else {
+                &own_wallet
+            };
+            let guesser_key = guesser.guesser_spending_key(predecessor.hash());
+            let mut block = invalid_empty_block(&predecessor);
+            block.set_header_guesser_digest(guesser_key.after_image());
+            if is_external {
+                external_guesser_digests.push(guesser_key.after_image().to_hex());
+            }
+            global_state_lock.set_new_tip(block.clone()).await.unwrap();
+            predecessor = block;
+        }
+
+        let guessed_heights = |response: serde_json::Value| {
+            response["records"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|record| {
+                    serde_json::from_value::<BlockHeight>(record["block_height"].clone()).unwrap()
+                })
+                .collect_vec()
+        };
+        let request = |guesser_digests: Vec<String>| BlocksGuessedByRequest {
+            guesser_digests,
+            start: 0,
+            end: 10,
+        };
+
+        let response = blocks_guessed_by(
+            State(rpcstate.clone()),
+            Json(request(external_guesser_digests)),
+        )
+        .await
+        .unwrap();
+        let expected: Vec<BlockHeight> = vec![1u64.into(), 3u64.into()];
+        assert_eq!(expected, guessed_heights(json_value(response).await));
+
+        let own = get_owner_blocks(State(rpcstate.clone()), Path((0, 10)))
+            .await
+            .unwrap();
+        let expected: Vec<BlockHeight> = vec![2u64.into(), 4u64.into()];
+        assert_eq!(expected, guessed_heights(json_value(own).await));
+
+        let invalid_digest = request(vec!["not hex".to_string()]);
+        let response = blocks_guessed_by(State(rpcstate.clone()), Json(invalid_digest)).await;
+        assert!(bad_request_message(response).contains("guesser_digests"));
+
+        let max_range = global_state_lock.cli().max_rest_batch_blocks;
+        let too_long = BlocksGuessedByRequest {
+            end: max_range,
+            ..request(vec![])
+        };
+        let response = blocks_guessed_by(State(rpcstate), Json(too_long)).await;
+        assert!(bad_request_message(response).contains("exceeds maximum"));
+    }
+
+    #[tokio::test]
+    async fn guess_reward_is_the_transaction_fee() {
+        let network = Network::Main;
+        let wallet = WalletEntropy::new_random();
+        let mut global_state_lock =
+            mock_genesis_global_state(network, 0, wallet.clone(), cli_args::Args::default()).await;
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+
+        // The composer keeps 3/4 of the block subsidy and leaves 1/4 as
+        // transaction fee, which is all the guesser gets. So the fee is the
+        // guesser's share.
+        let genesis = Block::genesis(network);
+        let (block, composer_utxos) = make_mock_block_guesser_preimage_and_guesser_fraction(
+            &genesis,
+            None,
+            wallet.nth_generation_spending_key_for_tests(0),
+            [4; 32],
+            0.25,
+            wallet.guesser_preimage(genesis.hash()),
+            network,
+        )
+        .await;
+        global_state_lock.set_new_tip(block.clone()).await.unwrap();
+
+        let composer_share: NativeCurrencyAmount = composer_utxos
+            .iter()
+            .map(|expected_utxo| expected_utxo.utxo.get_native_currency_amount())
+            .sum();
+        let guesser_share: NativeCurrencyAmount = block
+            .guesser_fee_utxos()
+            .iter()
+            .map(|utxo| utxo.get_native_currency_amount())
+            .sum();
+        assert!(!guesser_share.is_zero());
+        assert_eq!(block.body().transaction_kernel.fee, guesser_share);
+        assert_eq!(block.total_guesser_reward(), guesser_share);
+        assert_eq!(
+            Block::block_subsidy(block.header().height),
+            composer_share + guesser_share
+        );
+
+        let response = get_owner_blocks(State(rpcstate), Path((0, 1)))
+            .await
+            .unwrap();
+        let response = json_value(response).await;
+        let amount = |value: &serde_json::Value| {
+            NativeCurrencyAmount::from_nau(value["nau"].as_str().unwrap().parse().unwrap())
+        };
+        assert_eq!(1, response["records"].as_array().unwrap().len());
+        assert_eq!(guesser_share, amount(&response["records"][0]["amount"]));
+        assert_eq!(guesser_share, amount(&response["reward"]));
+    }
+
+    #[tokio::test]
+    async fn difficulty_history_reports_block_times() {
+        let network = Network::Main;
+        let mut global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+        let mut blocks = vec![Block::genesis(network)];
+        for minutes in [10, 3, 25] {
+            let predecessor = blocks.last().unwrap();
+            let timestamp = predecessor.header().timestamp + Timestamp::minutes(minutes);
+            let block = invalid_empty_block_with_timestamp(predecessor, timestamp);
+            global_state_lock.set_new_tip(block.clone()).await.unwrap();
+            blocks.push(block);
+        }
+
+        let history = |start: u64, end: u64| {
+            let rpcstate = rpcstate.clone();
+            async move {
+                let history = get_difficulty_history(State(rpcstate), Path((start, end)))
+                    .await
+                    .unwrap();
+                serde_json::from_value::<Vec<DifficultyHistoryEntry>>(json_value(history).await)
+                    .unwrap()
+            }
+        };
+
+        let full = history(0, 3).await;
+        assert_eq!(
+            vec![None, Some(10 * 60_000), Some(3 * 60_000), Some(25 * 60_000)],
+            full.iter().map(|entry| entry.block_time).collect_vec()
+        );
+        for (entry, block) in full.iter().zip(&blocks) {
+            assert_eq!(block.header().height, entry.height);
+            assert_eq!(block.header().difficulty, entry.difficulty);
+            assert_eq!(block.header().timestamp, entry.timestamp);
+        }
+
+        // the first block time of a later range is relative to its predecessor
+        assert_eq!(full[2..], history(2, 5).await);
+        assert!(matches!(
+            get_difficulty_history(State(rpcstate), Path((3, 2))).await,
+            Err(RestError::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn next_difficulty_is_that_of_the_tip() {
+        let network = Network::Main;
+        let mut global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+
+        let genesis = Block::genesis(network);
+        let block_1 = invalid_empty_block(&genesis);
+        for block in [None, Some(block_1)] {
+            if let Some(block) = block {
+                global_state_lock.set_new_tip(block).await.unwrap();
+            }
+
+            let next = get_next_difficulty(State(rpcstate.clone())).await.unwrap();
+            let next: NextDifficulty = serde_json::from_value(json_value(next).await).unwrap();
+
+            let tip = global_state_lock
+                .lock_guard()
+                .await
+                .chain
+                .light_state()
+                .clone();
+            assert_eq!(tip.header().height, next.tip_height);
+            assert_eq!(tip.hash(), next.tip_digest);
+            assert_eq!(tip.header().difficulty, next.difficulty);
+            assert_eq!(tip.header().difficulty.target(), next.target);
+        }
+    }
+
+    #[tokio::test]
+    async fn genesis_is_served_with_its_digest() {
+        let network = Network::Main;
+        let global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let genesis_digest = global_state_lock
+            .lock_guard()
+            .await
+            .chain
+            .archival_state()
+            .genesis_block()
+            .hash();
+        let rpcstate = test_rpcstate(global_state_lock).await;
+
+        let response = json_value(get_genesis(State(rpcstate)).await.unwrap()).await;
+        let response: GenesisResponse = serde_json::from_value(response).unwrap();
+        assert_eq!(genesis_digest, response.digest);
+        assert_eq!(genesis_digest, response.block_info.digest);
+        assert_eq!(BlockHeight::genesis(), response.block_info.height);
+        assert!(response.block_info.is_genesis);
+    }
+
+    #[tokio::test]
+    async fn requests_give_up_on_a_state_lock_held_too_long() {
+        let mut cli = cli_args::Args::default();
+        cli.rest_state_lock_timeout = Duration::from_millis(100);
+        let mut global_state_lock =
+            mock_genesis_global_state(Network::Main, 0, WalletEntropy::new_random(), cli).await;
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+
+        let write_guard = global_state_lock.lock_guard_mut().await;
+        let response = get_genesis(State(rpcstate.clone()))
+            .await
+            .unwrap_err()
+            .into_response();
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+
+        drop(write_guard);
+        assert!(get_genesis(State(rpcstate)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn utxo_auth_path_authenticates_leaf_against_mutator_set_hash() {
+        let network = Network::Main;
+        let global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let rpcstate = test_rpcstate(global_state_lock).await;
+        let auth_path = |leaf_index| get_utxo_auth_path(State(rpcstate.clone()), Path(leaf_index));
+
+        let response = json_value(auth_path(1).await.unwrap()).await;
+        let response: UtxoAuthPath = serde_json::from_value(response).unwrap();
+        let membership_proof = MmrMembershipProof::new(response.authentication_path);
+        assert!(membership_proof.verify(
+            response.leaf_index,
+            response.leaf,
+            &response.aocl_peaks,
+            response.aocl_num_leafs,
+        ));
+        assert!(!membership_proof.verify(
+            response.leaf_index,
+            Digest::default(),
+            &response.aocl_peaks,
+            response.aocl_num_leafs,
+        ));
+
+        let aocl = MmrAccumulator::init(response.aocl_peaks, response.aocl_num_leafs);
+        let mutator_set_hash = Tip5::hash_pair(
+            Tip5::hash_pair(aocl.bag_peaks(), response.swbf_inactive_bagged),
+            Tip5::hash_pair(response.swbf_active_hash, Digest::default()),
+        );
+        assert_eq!(response.mutator_set_hash, mutator_set_hash);
+
+        let past_end = json_value(auth_path(response.aocl_num_leafs).await.unwrap()).await;
+        assert!(past_end.is_null());
+    }
+
+    #[tokio::test]
+    async fn block_tx_count_counts_merged_transactions() {
+        let network = Network::Main;
+        let mut global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let genesis = Block::genesis(network);
+
+        // the composer's coinbase transaction merged with a nop transaction
+        let timestamp = genesis.header().timestamp + Timestamp::hours(1);
+        let block_1 =
+            fake_valid_block_proposal_successor_for_test(&genesis, timestamp, [7; 32], network)
+                .await;
+        global_state_lock
+            .set_new_tip(block_1.clone())
+            .await
+            .unwrap();
+        let rpcstate = test_rpcstate(global_state_lock).await;
+
+        let tx_count = |block_selector: BlockSelector| {
+            let rpcstate = rpcstate.clone();
+            async move {
+                let block_selector = BlockSelectorExtended::from(block_selector);
+                json_value(
+                    get_block_tx_count(State(rpcstate), Path(block_selector))
+                        .await
+                        .unwrap(),
+                )
+                .await
+            }
+        };
+
+        for (block, expected) in [(&genesis, 1), (&block_1, 2)] {
+            assert_eq!(
+                serde_json::json!(expected),
+                tx_count(BlockSelector::Digest(block.hash())).await
+            );
+        }
+        let unknown = BlockSelector::Digest(invalid_empty_block(&block_1).hash());
+        assert!(tx_count(unknown).await.is_null());
+    }
+
+    #[tokio::test]
+    async fn mempool_changes_since_cursor() {
+        let network = Network::Main;
+        let mut global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+
+        let changes_since = |since_seq: u64| {
+            let rpcstate = rpcstate.clone();
+            async move {
+                let response = get_mempool_changes(State(rpcstate), Path(since_seq))
+                    .await
+                    .unwrap()
+                    .into_response();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                serde_json::from_slice::<MempoolChangesResponse>(&body).unwrap()
+            }
+        };
+        let ids = |infos: &[MempoolTransactionInfo]| infos.iter().map(|i| i.id).collect_vec();
+
+        let [tx_a, tx_b, tx_c] = make_plenty_mock_transaction_supported_by_invalid_single_proofs(3)
+            .try_into()
+            .unwrap();
+
+        let initial = changes_since(0).await;
+        assert_eq!(0, initial.sequence);
+        assert!(initial.complete);
+
+        for tx in [&tx_a, &tx_b] {
+            global_state_lock
+                .lock_guard_mut()
+                .await
+                .mempool_insert(tx.clone(), TransactionOrigin::Foreign)
+                .await;
+        }
+        let first = changes_since(initial.sequence).await;
+        assert!(first.complete);
+        assert_eq!(
+            vec![tx_a.kernel.txid(), tx_b.kernel.txid()],
+            ids(&first.added)
+        );
+        assert!(first.removed.is_empty());
+
+        global_state_lock
+            .lock_guard_mut()
+            .await
+            .mempool_insert(tx_c.clone(), TransactionOrigin::Foreign)
+            .await;
+        global_state_lock
+            .lock_guard_mut()
+            .await
+            .mempool_remove(tx_a.kernel.txid())
+            .await;
+        let second = changes_since(first.sequence).await;
+        assert!(second.complete);
+        assert_eq!(vec![tx_c.kernel.txid()], ids(&second.added));
+        assert_eq!(vec![tx_a.kernel.txid()], second.removed);
+        assert!(second.sequence > first.sequence);
+
+        // nothing happened since the last call
+        let third = changes_since(second.sequence).await;
+        assert!(third.complete);
+        assert!(third.added.is_empty());
+        assert!(third.removed.is_empty());
+
+        // a cursor from the future, e.g., from before a restart, is not trusted
+        assert!(!changes_since(second.sequence + 1).await.complete);
+    }
+
+    #[tokio::test]
+    async fn block_proposal_is_exported() {
+        let network = Network::Main;
+        let mut global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let (rpcstate, headers) = authenticated_rpcstate(global_state_lock.clone());
+        let guesser_digest: Digest = rand::random();
+        let request = |headers: HeaderMap| {
+            let params = BlockProposalParams {
+                guesser_digest: Some(guesser_digest.to_hex()),
+            };
+            get_block_proposal(State(rpcstate.clone()), Query(params), headers)
+        };
+
+        let Err(RestError::NotFound(_)) = request(headers.clone()).await else {
+            panic!("no proposal must be served before one is known");
+        };
+
+        let block1 = invalid_empty_block(&Block::genesis(network));
+        global_state_lock
+            .lock_mut(|state| {
+                state.mining_state.block_proposal =
+                    BlockProposal::ForeignComposition(block1.clone())
+            })
+            .await;
+
+        let Err(RestError::Unauthorized(_)) = request(HeaderMap::new()).await else {
+            panic!("proposal must not be served without authentication");
+        };
+
+        let bytes = request(headers).await.unwrap();
+        let exported: ExportedBlockProposal = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(
+            BlockProposalNotification::from(&block1).body_mast_hash,
+            exported.body_mast_hash
+        );
+        assert_eq!(guesser_digest, exported.proposal.header().guesser_digest);
+
+        // the node's own proposal is left as it was
+        let state = global_state_lock.lock_guard().await;
+        assert_eq!(
+            Some(block1.hash()),
+            state.mining_state.block_proposal.map(|block| block.hash())
+        );
+        assert_eq!(
+            Some(exported.proposal.hash()),
+            state
+                .mining_state
+                .exported_block_proposals
+                .get(&exported.proposal_id)
+                .map(|block| block.hash())
+        );
+    }
+
+    async fn submit(
+        rpcstate: &NeptuneRPCServer,
+        headers: &HeaderMap,
+        proposal_id: Digest,
+        nonce: Digest,
+    ) -> Result<bool, RestError> {
+        let body = PowSolutionRequest {
+            proposal_id: proposal_id.to_hex(),
+            nonce: nonce.to_hex(),
+        };
+        let response = submit_pow_solution(State(rpcstate.clone()), headers.clone(), Json(body))
+            .await?
+            .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        Ok(serde_json::from_slice::<PowSolutionResponse>(&body)
+            .unwrap()
+            .accepted)
+    }
+
+    /// Set up a node with an exported block proposal on top of genesis, and
+    /// return a nonce that solves it and one that does not, along with headers
+    /// that authenticate requests to the node.
+    async fn rpcstate_with_exported_proposal(
+        network: Network,
+    ) -> (
+        NeptuneRPCServer,
+        HeaderMap,
+        ExportedBlockProposal,
+        Digest,
+        Digest,
+    ) {
+        let mut global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let (rpcstate, headers) = authenticated_rpcstate(global_state_lock.clone());
+
+        let genesis = Block::genesis(network);
+        let block1 = invalid_empty_block(&genesis);
+        global_state_lock
+            .lock_mut(|state| {
+                state.mining_state.block_proposal = BlockProposal::ForeignComposition(block1)
+            })
+            .await;
+        let no_params = Query(BlockProposalParams::default());
+        let bytes = get_block_proposal(State(rpcstate.clone()), no_params, headers.clone())
+            .await
+            .unwrap();
+        let exported: ExportedBlockProposal = bincode::deserialize(&bytes).unwrap();
+
+        let puzzle = ProofOfWorkPuzzle::new(exported.proposal.clone(), *genesis.header());
+        let hash_with = |nonce: Digest| {
+            fast_kernel_mast_hash(puzzle.kernel_auth_path, puzzle.header_auth_path, nonce)
+        };
+        let mut good_nonce: Digest = rand::random();
+        while hash_with(good_nonce) > exported.threshold {
+            good_nonce = rand::random();
+        }
+        let mut bad_nonce: Digest = rand::random();
+        while hash_with(bad_nonce) <= exported.threshold {
+            bad_nonce = rand::random();
+        }
+
+        (rpcstate, headers, exported, good_nonce, bad_nonce)
+    }
+
+    #[tokio::test]
+    async fn pow_solution_with_valid_nonce_is_accepted() {
+        let (rpcstate, headers, exported, good_nonce, _) =
+            rpcstate_with_exported_proposal(Network::Main).await;
+        let accepted = submit(&rpcstate, &headers, exported.proposal_id, good_nonce).await;
+        assert!(accepted.unwrap());
+    }
+
+    #[tokio::test]
+    async fn pow_solution_with_invalid_nonce_is_rejected() {
+        let (rpcstate, headers, exported, _, bad_nonce) =
+            rpcstate_with_exported_proposal(Network::Main).await;
+        let accepted = submit(&rpcstate, &headers, exported.proposal_id, bad_nonce).await;
+        assert!(!accepted.unwrap());
+    }
+
+    #[tokio::test]
+    async fn pow_solution_for_unknown_proposal_is_rejected() {
+        let (rpcstate, headers, exported, good_nonce, _) =
+            rpcstate_with_exported_proposal(Network::Main).await;
+        let unknown_id: Digest = rand::random();
+        assert_ne!(exported.proposal_id, unknown_id);
+        let accepted = submit(&rpcstate, &headers, unknown_id, good_nonce).await;
+        assert!(!accepted.unwrap());
+    }
+
+    #[tokio::test]
+    async fn pow_solution_requires_authentication() {
+        let (rpcstate, _, exported, good_nonce, _) =
+            rpcstate_with_exported_proposal(Network::Main).await;
+        let no_auth = HeaderMap::new();
+        let response = submit(&rpcstate, &no_auth, exported.proposal_id, good_nonce).await;
+        assert!(matches!(response, Err(RestError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn mempool_bincode_response_matches_json_response() {
+        let network = Network::Main;
+        let mut global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        {
+            let mut global_state = global_state_lock.lock_guard_mut().await;
+            for tx in make_plenty_mock_transaction_supported_by_invalid_single_proofs(3) {
+                global_state
+                    .mempool_insert(tx, TransactionOrigin::Foreign)
+                    .await;
+            }
+        }
+
+        let rpcstate = test_rpcstate(global_state_lock).await;
+
+        let fetch = |accept: &'static str| {
+            let rpcstate = rpcstate.clone();
+            async move {
+                let mut headers = HeaderMap::new();
+                headers.insert(header::ACCEPT, accept.parse().unwrap());
+                let no_params = Query(MempoolParams::default());
+                let response = get_mempool(State(rpcstate), Path((0, 10)), no_params, headers)
+                    .await
+                    .unwrap();
+                let content_type = response.headers()[header::CONTENT_TYPE].clone();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (content_type, body)
+            }
+        };
+
+        let (json_content_type, json_body) = fetch("application/json").await;
+        let (bincode_content_type, bincode_body) =
+            fetch("application/octet-stream;q=0.9, application/json;q=0.1").await;
+        assert_eq!("application/json", json_content_type);
+        assert_eq!(BINCODE_CONTENT_TYPE, bincode_content_type);
+
+        let from_json: Vec<MempoolTransactionInfo> = serde_json::from_slice(&json_body).unwrap();
+        let from_bincode: Vec<MempoolTransactionInfo> =
+            bincode::deserialize(&bincode_body).unwrap();
+        assert_eq!(3, from_json.len());
+        assert_eq!(from_json, from_bincode);
+        assert!(bincode_body.len() < json_body.len());
+    }
+
+    #[tokio::test]
+    async fn mempool_listing_is_filtered_by_origin() {
+        let mut global_state_lock = mock_genesis_global_state(
+            Network::Main,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let [own, foreign_1, foreign_2]: [Transaction; 3] =
+            make_plenty_mock_transaction_supported_by_invalid_single_proofs(3)
+                .try_into()
+                .unwrap();
+        {
+            let mut global_state = global_state_lock.lock_guard_mut().await;
+            global_state
+                .mempool_insert(own.clone(), TransactionOrigin::Own)
+                .await;
+            for tx in [&foreign_1, &foreign_2] {
+                global_state
+                    .mempool_insert(tx.clone(), TransactionOrigin::Foreign)
+                    .await;
+            }
+        }
+
+        let rpcstate = test_rpcstate(global_state_lock).await;
+        let list = |query: &'static str| {
+            let rpcstate = rpcstate.clone();
+            async move {
+                let uri: axum::http::Uri = format!("/rpc/mempool/0/10{query}").parse().unwrap();
+                let params = Query::<MempoolParams>::try_from_uri(&uri).unwrap();
+                let response =
+                    get_mempool(State(rpcstate), Path((0, 10)), params, HeaderMap::new())
+                        .await
+                        .unwrap();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                let infos: Vec<MempoolTransactionInfo> = serde_json::from_slice(&body).unwrap();
+                infos
+                    .into_iter()
+                    .map(|info| info.id)
+                    .collect::<HashSet<_>>()
+            }
+        };
+
+        let foreign_ids = HashSet::from([foreign_1.kernel.txid(), foreign_2.kernel.txid()]);
+        assert_eq!(
+            HashSet::from([own.kernel.txid()]),
+            list("?origin=Own").await
+        );
+        assert_eq!(foreign_ids, list("?origin=Foreign").await);
+        assert_eq!(3, list("").await.len());
+    }
+
+    #[tokio::test]
+    async fn mempool_listing_is_sorted_as_requested() {
+        let mut global_state_lock = mock_genesis_global_state(
+            Network::Main,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        {
+            let mut global_state = global_state_lock.lock_guard_mut().await;
+            for tx in make_plenty_mock_transaction_supported_by_invalid_single_proofs(3) {
+                global_state
+                    .mempool_insert(tx, TransactionOrigin::Foreign)
+                    .await;
+            }
+        }
+
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+        let list = |query: &'static str| {
+            let rpcstate = rpcstate.clone();
+            async move {
+                let uri: axum::http::Uri = format!("/rpc/mempool/0/10{query}").parse().unwrap();
+                let params = Query::<MempoolParams>::try_from_uri(&uri).unwrap();
+                let response =
+                    get_mempool(State(rpcstate), Path((0, 10)), params, HeaderMap::new())
+                        .await
+                        .unwrap();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                serde_json::from_slice::<Vec<MempoolTransactionInfo>>(&body).unwrap()
+            }
+        };
+        let ids = |infos: Vec<MempoolTransactionInfo>| infos.iter().map(|i| i.id).collect_vec();
+
+        // the orders themselves are tested on the mempool
+        let by_fee_rate = list("?sort=feerate").await;
+        assert!(by_fee_rate.is_sorted_by(|a, b| a.fee_per_byte >= b.fee_per_byte));
+        let by_fee_rate = ids(by_fee_rate);
+        assert_eq!(by_fee_rate, ids(list("").await));
+        let by_fee = ids(list("?sort=fee").await);
+        let by_age = ids(list("?sort=age").await);
+        {
+            let global_state = global_state_lock.lock_guard().await;
+            let mempool = &global_state.mempool;
+            let expected_by_fee_rate = mempool.get_sorted_iter().map(|(id, _)| id).collect_vec();
+            assert_eq!(expected_by_fee_rate, by_fee_rate);
+            let expected_by_fee = mempool.get_sorted_iter_by_fee().map(|(id, _)| id);
+            assert_eq!(expected_by_fee.collect_vec(), by_fee);
+            let expected_by_age = mempool.get_sorted_iter_by_age().map(|(id, _)| id);
+            assert_eq!(expected_by_age.collect_vec(), by_age);
+        }
+
+        let uri: axum::http::Uri = "/rpc/mempool/0/10?sort=size".parse().unwrap();
+        assert!(Query::<MempoolParams>::try_from_uri(&uri).is_err());
+    }
+
+    #[tokio::test]
+    async fn in_mempool_tells_whether_transaction_is_known() {
+        let mut global_state_lock = mock_genesis_global_state(
+            Network::Main,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let [tx, unknown]: [Transaction; 2] =
+            make_plenty_mock_transaction_supported_by_invalid_single_proofs(2)
+                .try_into()
+                .unwrap();
+        global_state_lock
+            .lock_guard_mut()
+            .await
+            .mempool_insert(tx.clone(), TransactionOrigin::Foreign)
+            .await;
+        let rpcstate = test_rpcstate(global_state_lock).await;
+        let in_mempool = |tx: &Transaction| {
+            let txid = tx.kernel.txid().to_string();
+            let rpcstate = rpcstate.clone();
+            async move {
+                let response = get_in_mempool(State(rpcstate), Path(txid)).await.unwrap();
+                serde_json::from_value::<InMempoolResponse>(json_value(response).await).unwrap()
+            }
+        };
+
+        let known = in_mempool(&tx).await;
+        assert!(known.in_mempool);
+        assert!(known.synced.is_some());
+        assert_eq!(Some(tx.fee_per_byte()), known.fee_per_byte);
+
+        let unknown = in_mempool(&unknown).await;
+        assert!(!unknown.in_mempool);
+        assert_eq!(None, unknown.synced);
+        assert_eq!(None, unknown.fee_per_byte);
+
+        let invalid = get_in_mempool(State(rpcstate), Path("not-a-txid".to_string())).await;
+        assert!(matches!(invalid, Err(RestError::BadRequest(_))));
+    }
+
+    #[test]
+    fn last_blocks_range_empty() {
+        let range = SimpleBlockRange::from_lookups((0..3).map(|h| (h, None)));
+
+        assert_eq!(None, range.first_height);
+        assert_eq!(None, range.last_height);
+        assert!(range.missing_heights.is_empty());
+        assert!(range.blocks.is_empty());
+    }
+
+    fn json_keys<T: Serialize>(value: &T) -> Vec<String> {
+        let serde_json::Value::Object(map) = serde_json::to_value(value).unwrap() else {
+            panic!("expected a JSON object");
+        };
+        map.keys().sorted().cloned().collect()
+    }
+
+    #[test]
+    fn api_response_json_keys_are_stable() {
+        let reward_card = RewardCard {
+            block_id: Digest::default(),
+            block_height: 1u64.into(),
+            timestamp: Timestamp::now(),
+            amount: NativeCurrencyAmount::coins(1),
+        };
+        let guess_reward = GuessReward {
+            start: 1u64.into(),
+            end: 2u64.into(),
+            reward: NativeCurrencyAmount::coins(1),
+            records: vec![reward_card.clone()],
+        };
+        let response_send_tx = ResponseSendTx {
+            status: 0,
+            message: String::new(),
+        };
+
+        assert_eq!(
+            vec!["height", "time"],
+            json_keys(&BlockTime { height: 1, time: 2 })
+        );
+        assert_eq!(
+            vec!["fee", "hash", "height", "timestamp"],
+            json_keys(&simple_block(1))
+        );
+        assert_eq!(
+            vec!["amount", "block_height", "block_id", "timestamp"],
+            json_keys(&reward_card)
+        );
+        assert_eq!(
+            vec!["end", "records", "reward", "start"],
+            json_keys(&guess_reward)
+        );
+        assert_eq!(vec!["message", "status"], json_keys(&response_send_tx));
+    }
+
+    #[test]
+    fn invalid_amount_is_bad_request() {
+        let error: RestError = NativeCurrencyAmount::coins_from_str_checked("-1")
+            .unwrap_err()
+            .into();
+        let response = error.into_response();
+        assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    }
+
+    fn bad_request_message<T: std::fmt::Debug>(result: Result<T, RestError>) -> String {
+        match result {
+            Err(RestError::BadRequest(msg)) => msg,
+            other => panic!("expected bad request, got {:?}", other),
+        }
+    }
+
+    fn broadcast_tx(transaction: Transaction) -> BroadcastTx {
+        let notification = (&transaction).try_into().unwrap();
+        BroadcastTx {
+            transaction,
+            origin: TransactionOrigin::Foreign,
+            notification,
+        }
+    }
+
+    #[test]
+    fn oversized_length_prefix_is_rejected() {
+        // a length prefix announcing an absurdly large string or vector
+        let mut body = u64::MAX.to_le_bytes().to_vec();
+        body.extend([0u8; 32]);
+
+        let Err(RestError::BadRequest(msg)) = deserialize_body::<String>(&body) else {
+            panic!("oversized string must be rejected");
+        };
+        assert!(msg.contains("size limit"), "{msg}");
+
+        let Err(RestError::BadRequest(_)) = deserialize_body::<BroadcastTx>(&body) else {
+            panic!("crafted transaction body must be rejected");
+        };
+    }
+
+    #[test]
+    fn truncated_body_is_reported_as_such() {
+        let tx = broadcast_tx(
+            make_plenty_mock_transaction_supported_by_invalid_single_proofs(1)
+                .pop()
+                .unwrap(),
+        );
+        let body = bincode::serialize(&tx).unwrap();
+
+        let decoded: BroadcastTx = deserialize_body(&body).unwrap();
+        assert_eq!(
+            tx.transaction.kernel.txid(),
+            decoded.transaction.kernel.txid()
+        );
+
+        let Err(RestError::BadRequest(msg)) =
+            deserialize_body::<BroadcastTx>(&body[..body.len() / 2])
+        else {
+            panic!("truncated body must be rejected");
+        };
+        assert!(msg.contains("unexpected end of body"), "{msg}");
+    }
+
+    #[test]
+    fn unknown_variant_is_reported_as_such() {
+        let Err(RestError::BadRequest(msg)) = deserialize_body::<Option<u8>>(&[7, 0]) else {
+            panic!("unknown variant must be rejected");
+        };
+        assert!(msg.contains("invalid variant"), "{msg}");
+
+        let body = 99u32.to_le_bytes();
+        let Err(RestError::BadRequest(msg)) = deserialize_body::<UtxoNotificationMedium>(&body)
+        else {
+            panic!("unknown variant must be rejected");
+        };
+        assert!(msg.contains("invalid variant"), "{msg}");
+    }
 
-    let sender_randomness: Digest = Digest::try_from_hex(&send_tx.sender_randomness)
-        .context("failed to parse sender_randomness as hex digest")?;
+    #[test]
+    fn transaction_body_is_decoded_within_limit() {
+        let transaction = make_plenty_mock_transaction_supported_by_invalid_single_proofs(1)
+            .pop()
+            .unwrap();
+        let tx = broadcast_tx(transaction);
+        let body = bincode::serialize(&tx).unwrap();
+        let size = body.len() as u64;
 
-    let output_index = calculate_utxo_commitment(receiving_address, amount, sender_randomness);
+        let decoded: BroadcastTx = deserialize_body_with_limit(&body, size).unwrap();
+        assert_eq!(
+            tx.transaction.kernel.txid(),
+            decoded.transaction.kernel.txid()
+        );
 
-    let outputs: Vec<String> = send_tx
-        .broadcast_tx
-        .transaction
-        .kernel
-        .outputs
-        .iter()
-        .map(|output| output.canonical_commitment.to_hex())
-        .collect();
-    if !outputs.contains(&output_index) {
-        return Ok(ErasedJson::pretty(ResponseSendTx {
-            status: 2,
-            message: "Failed to pay the priority fee".to_string(),
-        }));
+        let Err(RestError::BadRequest(msg)) =
+            deserialize_body_with_limit::<BroadcastTx>(&body, size - 1)
+        else {
+            panic!("body exceeding the limit must be rejected");
+        };
+        assert!(msg.contains("size limit"), "{msg}");
     }
 
-    {
-        let state = rpcstate.state.lock_guard().await;
-        let end: u64 = state
-            .chain
-            .archival_state()
-            .get_tip()
+    #[tokio::test]
+    async fn incremental_membership_proof_updates_match_full_restore() {
+        let network = Network::Main;
+        let mut global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::devnet_wallet(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let (rpcstate, headers) = authenticated_rpcstate(global_state_lock.clone());
+
+        let (item, base_proof) = {
+            let state = global_state_lock.lock_guard().await;
+            let mutxo = state
+                .wallet_state
+                .wallet_db
+                .monitored_utxos()
+                .get_all()
+                .await
+                .pop()
+                .expect("devnet wallet must own a premine UTXO");
+            let (_, proof) = mutxo.get_latest_membership_proof_entry().unwrap();
+            (Tip5::hash(&mutxo.utxo), proof)
+        };
+
+        let mut tip = Block::genesis(network);
+        for _ in 0..3 {
+            tip = invalid_empty_block(&tip);
+            global_state_lock.set_new_tip(tip.clone()).await.unwrap();
+        }
+
+        // incremental: apply the updates since genesis to the genesis proof
+        let request = RequestMsMembershipProofUpdates {
+            base_height: 0u64.into(),
+        };
+        let body = axum::body::Bytes::from(bincode::serialize(&request).unwrap());
+        let response = membership_proof_updates(State(rpcstate.clone()), headers, body)
             .await
-            .header()
-            .height
-            .into();
-        if end != send_tx.block_height {
-            return Ok(ErasedJson::pretty(ResponseSendTx {
-                status: 3,
-                message: format!("Transaction expired. Please sync to the latest block height first. current block height: {}", end),
-            }))
+            .unwrap();
+        let response: ResponseMsMembershipProofUpdates = bincode::deserialize(&response).unwrap();
+        assert_eq!(tip.hash(), response.block_id);
+        assert_eq!(3, response.updates.len());
+
+        let mut mutator_set = response.base_mutator_set;
+        let mut authenticated_item = AuthenticatedItem {
+            item,
+            ms_membership_proof: base_proof.clone(),
+        };
+        for update in &response.updates {
+            update
+                .apply_to_accumulator_and_records(
+                    &mut mutator_set,
+                    &mut [],
+                    &mut [&mut authenticated_item],
+                )
+                .unwrap();
         }
+        let updated = authenticated_item.ms_membership_proof;
+        assert_eq!(
+            tip.mutator_set_accumulator_after().hash(),
+            mutator_set.hash()
+        );
+        assert!(mutator_set.verify(item, &updated));
+
+        // full restore at the tip
+        let request = vec![RequestMsMembershipProofEx {
+            swbf_indices: base_proof.compute_indices(item).to_vec(),
+            aocl_leaf_index: base_proof.aocl_leaf_index,
+        }];
+        let body = axum::body::Bytes::from(bincode::serialize(&request).unwrap());
+        let restored = generate_restore_membership_proof(State(rpcstate), body)
+            .await
+            .unwrap();
+        let restored = ResponseMsMembershipProofEx::from_versioned_bytes(&restored).unwrap();
+        assert_eq!(response.block_id, restored.block_id);
+        let [restored] = <[MsMembershipProofEx; 1]>::try_from(restored.proofs).unwrap();
+
+        assert_eq!(updated.auth_path_aocl, restored.auth_path_aocl);
+        assert_eq!(updated.target_chunks, restored.target_chunks);
     }
 
-    let insert = false;
+    #[tokio::test]
+    async fn membership_proof_restore_is_unavailable_while_mutator_set_is_inconsistent() {
+        let mut global_state_lock = mock_genesis_global_state(
+            Network::Main,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+        let restore = || {
+            let request: Vec<RequestMsMembershipProofEx> = vec![];
+            let body = axum::body::Bytes::from(bincode::serialize(&request).unwrap());
+            generate_restore_membership_proof(State(rpcstate.clone()), body)
+        };
 
-    if insert {
-        let tx = send_tx.broadcast_tx;
-        let mut state = rpcstate.state.lock_guard_mut().await;
-        state.mempool_insert(tx.transaction, tx.origin).await;
-        let _ = rpcstate
-            .rpc_server_to_main_tx
-            .send(RPCServerToMain::BroadcastNotification(tx.notification))
-            .await;
-    } else {
-        // todo: 将数据发送给proof机器，确保proof机器可以工作
-        let mut busy = true;
+        assert!(restore().await.is_ok());
 
-        let base_url = std::env::var("PROOF_RPC").unwrap_or("172.16.64.12:9800".to_string());
-        let tx_b = bincode::serialize(&send_tx.broadcast_tx).unwrap();
-        let client = reqwest::Client::new();
-        if let Ok(response) = client
-            .post(format!("http://{}/rpc/tx/broadcast", base_url))
-            .body(tx_b)
-            .timeout(Duration::from_secs(5))
-            .send()
+        // simulate an interrupted update: an addition not matched by the tip
+        global_state_lock
+            .lock_guard_mut()
             .await
-        {
-            if response.status().is_success() {
-                info!(
-                    "Start task successfully: {:?}",
-                    response.json::<serde_json::Value>().await.unwrap()
-                );
+            .chain
+            .archival_state_mut()
+            .archival_mutator_set
+            .ams_mut()
+            .add(&AdditionRecord::new(Digest::default()))
+            .await;
 
-                // todo
-                busy = false;
+        assert!(
+            !global_state_lock
+                .lock_guard()
+                .await
+                .chain
+                .archival_state()
+                .mutator_set_consistent()
+                .await
+        );
+        assert!(matches!(
+            restore().await,
+            Err(RestError::ServiceUnavailable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn membership_proof_updates_reject_base_above_tip() {
+        let global_state_lock = mock_genesis_global_state(
+            Network::Main,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let (rpcstate, headers) = authenticated_rpcstate(global_state_lock);
+        let request = RequestMsMembershipProofUpdates {
+            base_height: 1u64.into(),
+        };
+        let body = axum::body::Bytes::from(bincode::serialize(&request).unwrap());
+
+        let response = membership_proof_updates(State(rpcstate), headers, body).await;
+        let msg = bad_request_message(response);
+        assert!(msg.contains("above the tip"), "{msg}");
+    }
+
+    #[tokio::test]
+    async fn membership_proof_updates_require_authentication() {
+        let global_state_lock = mock_genesis_global_state(
+            Network::Main,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let (rpcstate, _) = authenticated_rpcstate(global_state_lock);
+        let request = RequestMsMembershipProofUpdates {
+            base_height: 0u64.into(),
+        };
+        let body = axum::body::Bytes::from(bincode::serialize(&request).unwrap());
+
+        let response = membership_proof_updates(State(rpcstate), HeaderMap::new(), body).await;
+        assert!(matches!(response, Err(RestError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn broadcast_transaction_checks_notification() {
+        let cli = cli_args::Args {
+            min_tx_fee: NativeCurrencyAmount::zero(),
+            ..Default::default()
+        };
+        let global_state_lock =
+            mock_genesis_global_state(Network::Main, 0, WalletEntropy::new_random(), cli).await;
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+        let broadcast = |tx: &BroadcastTx| {
+            let body = axum::body::Bytes::from(bincode::serialize(tx).unwrap());
+            broadcast_transaction(State(rpcstate.clone()), body)
+        };
+
+        let [tx_a, tx_b] = make_plenty_mock_transaction_supported_by_invalid_single_proofs(2)
+            .try_into()
+            .unwrap();
+        let matching = broadcast_tx(tx_a);
+        let mismatched = BroadcastTx {
+            notification: matching.notification,
+            ..broadcast_tx(tx_b)
+        };
+        let Err(RestError::BadRequest(msg)) = broadcast(&mismatched).await else {
+            panic!("mismatched notification must be rejected");
+        };
+        assert!(msg.contains("does not match"), "{msg}");
+
+        broadcast(&matching).await.unwrap();
+
+        let state = global_state_lock.lock_guard().await;
+        assert!(state.mempool.contains(matching.transaction.kernel.txid()));
+        assert!(!state.mempool.contains(mismatched.transaction.kernel.txid()));
+    }
+
+    #[tokio::test]
+    async fn broadcast_transaction_enforces_min_fee() {
+        let transaction = make_plenty_mock_transaction_supported_by_invalid_single_proofs(1)
+            .pop()
+            .unwrap();
+        let fee = transaction.kernel.fee;
+        let tx = broadcast_tx(transaction);
+        let txid = tx.transaction.kernel.txid();
+
+        let just_above = fee + NativeCurrencyAmount::from_nau(1);
+        for (min_tx_fee, is_accepted) in [(fee, true), (just_above, false)] {
+            let cli = cli_args::Args {
+                min_tx_fee,
+                ..Default::default()
+            };
+            let global_state_lock =
+                mock_genesis_global_state(Network::Main, 0, WalletEntropy::new_random(), cli).await;
+            let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+            let body = axum::body::Bytes::from(bincode::serialize(&tx).unwrap());
+
+            let result = broadcast_transaction(State(rpcstate), body).await;
+            if is_accepted {
+                assert!(result.is_ok(), "fee at the floor must be accepted");
             } else {
-                error!("Failed to start task: {:?}", response.status());
-                busy = true;
+                let Err(RestError::BadRequest(msg)) = result else {
+                    panic!("fee below the floor must be rejected");
+                };
+                assert!(msg.contains("below the minimum fee"), "{msg}");
             }
-        } else {
-            error!("Failed to send request");
-            busy = true;
+            assert_eq!(
+                is_accepted,
+                global_state_lock.lock_guard().await.mempool.contains(txid)
+            );
         }
+    }
 
-        if busy {
-            return Ok(ErasedJson::pretty(ResponseSendTx {
-                status: 4,
-                message: "proof machine is busy".to_string(),
-            }));
-        }
+    #[tokio::test]
+    async fn mempool_transaction_info_reports_ingress() {
+        let cli = cli_args::Args {
+            min_tx_fee: NativeCurrencyAmount::zero(),
+            ..Default::default()
+        };
+        let global_state_lock =
+            mock_genesis_global_state(Network::Main, 0, WalletEntropy::new_random(), cli).await;
+        let rpcstate = test_rpcstate(global_state_lock.clone()).await;
+
+        let [broadcast, gossiped]: [Transaction; 2] =
+            make_plenty_mock_transaction_supported_by_invalid_single_proofs(2)
+                .try_into()
+                .unwrap();
+        let body = bincode::serialize(&broadcast_tx(broadcast.clone())).unwrap();
+        let body = axum::body::Bytes::from(body);
+        broadcast_transaction(State(rpcstate), body).await.unwrap();
+        global_state_lock
+            .lock_guard_mut()
+            .await
+            .mempool_insert(gossiped.clone(), TransactionOrigin::Foreign)
+            .await;
+
+        let state = global_state_lock.lock_guard().await;
+        let infos = mempool_transaction_infos_for(
+            &state,
+            &[broadcast.kernel.txid(), gossiped.kernel.txid()],
+        );
+        let ingresses = infos.iter().map(|info| info.ingress).collect_vec();
+        assert_eq!(
+            vec![
+                Some(TransactionIngress::RpcBroadcast),
+                Some(TransactionIngress::Peer)
+            ],
+            ingresses
+        );
     }
 
-    Ok(ErasedJson::pretty(ResponseSendTx {
-        status: 0,
-        message: "success".to_string(),
-    }))
-}
+    #[test]
+    fn membership_proof_response_round_trips_current_version() {
+        let response = ResponseMsMembershipProofEx {
+            height: 7u64.into(),
+            block_id: Digest::default(),
+            proofs: vec![],
+        };
+        let bytes = response.to_versioned_bytes().unwrap();
 
-#[derive(Debug, Deserialize, Clone)]
-struct UtxoIndexRequest {
-    pub address: String,
-    pub amount: String,
-    pub sender_randomness: String,
-}
+        let decoded = ResponseMsMembershipProofEx::from_versioned_bytes(&bytes).unwrap();
+        assert_eq!(response.height, decoded.height);
+        assert_eq!(response.block_id, decoded.block_id);
+        assert!(decoded.proofs.is_empty());
+    }
 
-async fn build_utxo_index(
-    State(_rpcstate): State<NeptuneRPCServer>,
-    Json(body): Json<UtxoIndexRequest>,
-) -> Result<ErasedJson, RestError> {
-    let network = Network::Main;
-    let receiving_address = ReceivingAddress::from_bech32m(&body.address, network)?;
-    let amount = NativeCurrencyAmount::coins_from_str(&body.amount)?;
+    #[test]
+    fn membership_proof_response_of_other_version_is_rejected() {
+        let response = ResponseMsMembershipProofEx {
+            height: 7u64.into(),
+            block_id: Digest::default(),
+            proofs: vec![],
+        };
+        let bumped = MS_MEMBERSHIP_PROOF_RESPONSE_VERSION + 1;
+        let bytes = bincode::serialize(&(bumped, &response)).unwrap();
 
-    let sender_randomness: Digest = Digest::try_from_hex(&body.sender_randomness)
-        .context("failed to parse sender_randomness as hex digest")?;
+        let err = ResponseMsMembershipProofEx::from_versioned_bytes(&bytes).unwrap_err();
+        assert!(matches!(
+            err,
+            VersionedResponseError::UnsupportedVersion { found, .. } if found == bumped
+        ));
+        assert!(err.to_string().contains("unsupported"), "{err}");
+    }
 
-    let output_index = calculate_utxo_commitment(receiving_address, amount, sender_randomness);
+    #[tokio::test]
+    async fn batch_block_response_is_decoded_if_version_matches() {
+        let network = Network::Main;
+        let global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let rpcstate = test_rpcstate(global_state_lock).await;
 
-    tracing::info!("output: {}", output_index);
-    Ok(ErasedJson::pretty(output_index))
-}
+        let no_params = Query(BatchBlockParams::default());
+        let bytes = get_batch_block(State(rpcstate), Path((0, 2)), no_params)
+            .await
+            .unwrap();
+        let blocks = decode_batch_block_response(&bytes).unwrap();
+        assert_eq!(
+            vec![Block::genesis(network).hash()],
+            blocks.iter().map(|block| block.hash()).collect_vec()
+        );
+    }
 
-fn calculate_utxo_commitment(
-    receiving_address: ReceivingAddress,
-    amount: NativeCurrencyAmount,
-    sender_randomness: Digest,
-) -> String {
-    let receiver_digest = receiving_address.privacy_digest();
-    let notification_method = UtxoNotifyMethod::OnChain(receiving_address.clone());
-    let utxo = Utxo::new_native_currency(receiving_address.lock_script(), amount);
+    #[tokio::test]
+    async fn oversized_batch_block_request_is_rejected() {
+        let global_state_lock = mock_genesis_global_state(
+            Network::Main,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let max_batch_size = global_state_lock.cli().max_rest_batch_blocks;
+        let rpcstate = test_rpcstate(global_state_lock).await;
 
-    let output = TxOutput {
-        utxo,
-        sender_randomness,
-        receiver_digest,
-        notification_method,
-        owned: false,
-        is_change: false,
-    };
-    let output_record: AdditionRecord = (&output).into();
-    output_record.canonical_commitment.to_hex()
-}
+        // would abort on allocation failure if the capacity were reserved
+        let no_params = || Query(BatchBlockParams::default());
+        let huge = Path((0, u64::MAX));
+        let huge = get_batch_block(State(rpcstate.clone()), huge, no_params()).await;
+        assert!(bad_request_message(huge).contains("exceeds maximum"));
+        let just_too_big = Path((u64::MAX, max_batch_size + 1));
+        let just_too_big =
+            get_batch_block(State(rpcstate.clone()), just_too_big, no_params()).await;
+        assert!(bad_request_message(just_too_big).contains("exceeds maximum"));
 
-mod block_selector {
-    use std::str::FromStr;
+        let at_limit = Path((u64::MAX, max_batch_size));
+        let at_limit = get_batch_block(State(rpcstate), at_limit, no_params())
+            .await
+            .unwrap();
+        assert!(decode_batch_block_response(&at_limit).unwrap().is_empty());
+    }
 
-    use serde::de::Error;
-    use serde::{Deserialize, Deserializer};
+    #[test]
+    fn batch_block_response_of_other_version_is_rejected() {
+        let blocks = vec![Block::genesis(Network::Main)];
+        let bumped = BATCH_BLOCK_RESPONSE_VERSION + 1;
+        let bytes = to_versioned_bytes(bumped, &blocks).unwrap();
 
-    use crate::models::blockchain::block::block_selector::{
-        BlockSelector, BlockSelectorParseError,
-    };
+        let err = decode_batch_block_response(&bytes).unwrap_err();
+        assert!(matches!(
+            err,
+            VersionedResponseError::UnsupportedVersion { found, .. } if found == bumped
+        ));
+        assert!(err.to_string().contains("batch block"), "{err}");
+    }
 
-    use height_or_digest::HeightOrDigest;
+    #[test]
+    fn sender_randomness_valid_hex() {
+        let digest = Digest::default();
+        assert_eq!(digest, parse_sender_randomness(&digest.to_hex()).unwrap());
+    }
 
-    /// newtype for `BlockSelector` that provides ability to parse `height_or_digest/value`.
-    ///
-    /// This is useful for HTML form(s) that allow user to enter either height or
-    /// digest into the same text input field.
-    ///
-    /// In particular it is necessary to support javascript-free website with such
-    /// an html form.
-    #[derive(Debug, Clone, Copy)]
-    pub struct BlockSelectorExtended(BlockSelector);
+    #[test]
+    fn sender_randomness_too_short() {
+        let hex = &Digest::default().to_hex()[2..];
+        let msg = bad_request_message(parse_sender_randomness(hex));
+        assert!(msg.contains("must be 40 bytes"), "{msg}");
+        assert!(msg.contains("got 78 hex characters"), "{msg}");
+    }
 
-    impl std::fmt::Display for BlockSelectorExtended {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            write!(f, "{}", self.0)
-        }
+    #[test]
+    fn sender_randomness_too_long() {
+        let hex = Digest::default().to_hex() + "00";
+        let msg = bad_request_message(parse_sender_randomness(&hex));
+        assert!(msg.contains("must be 40 bytes"), "{msg}");
+        assert!(msg.contains("got 82 hex characters"), "{msg}");
     }
 
-    impl FromStr for BlockSelectorExtended {
-        type Err = BlockSelectorParseError;
+    #[test]
+    fn sender_randomness_not_hex() {
+        let hex = "z".repeat(80);
+        let msg = bad_request_message(parse_sender_randomness(&hex));
+        assert!(msg.contains("non-hex character 'z'"), "{msg}");
+    }
 
-        // note: this parses BlockSelector, plus height_or_digest/<value>
-        fn from_str(s: &str) -> Result<Self, Self::Err> {
-            match BlockSelector::from_str(s) {
-                Ok(bs) => Ok(Self::from(bs)),
-                Err(e) => {
-                    let parts: Vec<_> = s.split('/').collect();
-                    if parts.len() == 2 && parts[0] == "height_or_digest" {
-                        Ok(Self::from(HeightOrDigest::from_str(parts[1])?))
-                    } else {
-                        Err(e)
-                    }
-                }
-            }
-        }
+    /// A random receiving address of each key type.
+    fn random_address_of_each_key_type() -> Vec<ReceivingAddress> {
+        vec![
+            GenerationReceivingAddress::derive_from_seed(rand::random()).into(),
+            SymmetricKey::from_seed(rand::random()).into(),
+        ]
     }
 
-    // note: axum uses serde Deserialize for Path elements.
-    impl<'de> Deserialize<'de> for BlockSelectorExtended {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where
-            D: Deserializer<'de>,
-        {
-            let s = String::deserialize(deserializer)?;
-            Self::from_str(&s).map_err(D::Error::custom)
+    #[test]
+    fn utxo_commitment_matches_wallet_output_for_each_notify_method() {
+        for address in random_address_of_each_key_type() {
+            utxo_commitment_matches_wallet_output(address);
         }
     }
 
-    impl From<HeightOrDigest> for BlockSelectorExtended {
-        fn from(hd: HeightOrDigest) -> Self {
-            Self(hd.into())
+    fn utxo_commitment_matches_wallet_output(address: ReceivingAddress) {
+        let amount = NativeCurrencyAmount::coins(3);
+        let sender_randomness: Digest = rand::random();
+
+        let mut commitments = vec![];
+        for medium in [
+            UtxoNotificationMedium::OnChain,
+            UtxoNotificationMedium::OffChain,
+        ] {
+            let wallet_output =
+                TxOutput::native_currency(amount, sender_randomness, address.clone(), medium, true);
+            let expected = AdditionRecord::from(&wallet_output)
+                .canonical_commitment
+                .to_hex();
+            let commitment = calculate_utxo_commitment(
+                address.clone(),
+                amount,
+                sender_randomness,
+                medium,
+                false,
+            );
+            assert_eq!(expected, commitment, "medium: {medium:?}");
+            commitments.push(commitment);
         }
+
+        // the notification medium is not part of the commitment
+        assert_eq!(commitments[0], commitments[1]);
+
+        let other_randomness: Digest = rand::random();
+        assert_ne!(
+            commitments[0],
+            calculate_utxo_commitment(
+                address,
+                amount,
+                other_randomness,
+                UtxoNotificationMedium::OnChain,
+                false,
+            )
+        );
     }
 
-    impl From<BlockSelector> for BlockSelectorExtended {
-        fn from(v: BlockSelector) -> Self {
-            Self(v)
+    #[test]
+    fn symmetric_key_commitment_binds_lock_script_and_privacy_digest() {
+        let key = SymmetricKey::from_seed(rand::random());
+        let address = ReceivingAddress::from(key);
+        let amount = NativeCurrencyAmount::coins(2);
+        let sender_randomness: Digest = rand::random();
+
+        let utxo = Utxo::new_native_currency(key.lock_script(), amount);
+        let expected = crate::util_types::mutator_set::commit(
+            Tip5::hash(&utxo),
+            sender_randomness,
+            key.privacy_digest(),
+        );
+        for medium in [
+            UtxoNotificationMedium::OnChain,
+            UtxoNotificationMedium::OffChain,
+        ] {
+            for is_change in [false, true] {
+                assert_eq!(
+                    expected.canonical_commitment.to_hex(),
+                    calculate_utxo_commitment(
+                        address.clone(),
+                        amount,
+                        sender_randomness,
+                        medium,
+                        is_change,
+                    ),
+                    "medium: {medium:?}, change: {is_change}"
+                );
+            }
         }
     }
 
-    impl From<BlockSelectorExtended> for BlockSelector {
-        fn from(v: BlockSelectorExtended) -> Self {
-            v.0
+    #[tokio::test]
+    async fn scanned_commitments_match_single_commitments() {
+        let global_state_lock = mock_genesis_global_state(
+            Network::Main,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let rpcstate = test_rpcstate(global_state_lock).await;
+        for address in random_address_of_each_key_type() {
+            scanned_commitments_match_single_commitments_for(&rpcstate, address).await;
         }
     }
 
-    mod height_or_digest {
-        use crate::models::blockchain::block::block_height::BlockHeight;
-        use crate::models::blockchain::block::block_selector::BlockSelector;
-        use crate::models::blockchain::block::block_selector::BlockSelectorParseError;
-        use crate::prelude::tasm_lib::prelude::Digest;
-        use serde::{Deserialize, Serialize};
-        use std::str::FromStr;
+    async fn scanned_commitments_match_single_commitments_for(
+        rpcstate: &NeptuneRPCServer,
+        address: ReceivingAddress,
+    ) {
+        let address = address.to_bech32m(Network::Main).unwrap();
+        let sender_randomness = (0..3)
+            .map(|_| rand::random::<Digest>().to_hex())
+            .collect_vec();
 
-        /// represents either a block-height or a block digest
-        #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-        pub enum HeightOrDigest {
-            /// Identifies block by Digest (hash)
-            Digest(Digest),
-            /// Identifies block by Height (count from genesis)
-            Height(BlockHeight),
-        }
+        let request = ScanCommitmentsRequest {
+            address: address.clone(),
+            amount: "1.5".to_string(),
+            sender_randomness: sender_randomness.clone(),
+            notify_method: None,
+            is_change: false,
+        };
+        let scanned = scan_commitments(State(rpcstate.clone()), Json(request))
+            .await
+            .unwrap();
+        let scanned: Vec<String> = serde_json::from_value(json_value(scanned).await).unwrap();
 
-        impl std::fmt::Display for HeightOrDigest {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                match self {
-                    Self::Digest(d) => write!(f, "{}", d),
-                    Self::Height(h) => write!(f, "{}", h),
-                }
-            }
+        let mut single = vec![];
+        for sender_randomness in sender_randomness {
+            let request = UtxoIndexRequest {
+                address: address.clone(),
+                amount: "1.5".to_string(),
+                sender_randomness,
+                notify_method: None,
+                is_change: false,
+            };
+            let commitment = build_utxo_index(State(rpcstate.clone()), Json(request))
+                .await
+                .unwrap();
+            single.push(json_value(commitment).await.as_str().unwrap().to_string());
         }
+        assert_eq!(single, scanned);
+    }
 
-        impl FromStr for HeightOrDigest {
-            type Err = BlockSelectorParseError;
+    #[test]
+    fn change_flag_does_not_alter_commitment() {
+        for address in random_address_of_each_key_type() {
+            let amount = NativeCurrencyAmount::coins(4);
+            let sender_randomness: Digest = rand::random();
+            let change_outputs = [
+                TxOutput::onchain_native_currency_as_change(
+                    amount,
+                    sender_randomness,
+                    address.clone(),
+                ),
+                TxOutput::offchain_native_currency_as_change(
+                    amount,
+                    sender_randomness,
+                    address.clone(),
+                ),
+            ];
+            let non_change = calculate_utxo_commitment(
+                address.clone(),
+                amount,
+                sender_randomness,
+                UtxoNotificationMedium::OnChain,
+                false,
+            );
 
-            // note: this parses the output of impl Display for HeightOrDigest
-            // note: this is used by clap parser in neptune-cli for block-info command
-            //       and probably future commands as well.
-            fn from_str(s: &str) -> Result<Self, Self::Err> {
-                Ok(match s.parse::<u64>() {
-                    Ok(h) => Self::Height(h.into()),
-                    Err(_) => Self::Digest(Digest::try_from_hex(s)?),
-                })
+            for change_output in change_outputs {
+                let change_record = AdditionRecord::from(&change_output);
+                assert_eq!(non_change, change_record.canonical_commitment.to_hex());
+                assert!(contains_fee_output(
+                    &[change_record],
+                    &address,
+                    amount,
+                    sender_randomness
+                ));
             }
         }
+    }
 
-        impl From<HeightOrDigest> for BlockSelector {
-            fn from(hd: HeightOrDigest) -> Self {
-                match hd {
-                    HeightOrDigest::Height(h) => Self::Height(h),
-                    HeightOrDigest::Digest(d) => Self::Digest(d),
-                }
-            }
+    #[test]
+    fn offchain_fee_output_is_matched() {
+        for fee_address in random_address_of_each_key_type() {
+            offchain_fee_output_is_matched_for(fee_address);
         }
     }
+
+    fn offchain_fee_output_is_matched_for(fee_address: ReceivingAddress) {
+        let amount = NativeCurrencyAmount::coins(1);
+        let sender_randomness: Digest = rand::random();
+
+        let fee_output = TxOutput::offchain_native_currency(
+            amount,
+            sender_randomness,
+            fee_address.clone(),
+            false,
+        );
+        let other_output = TxOutput::offchain_native_currency(
+            NativeCurrencyAmount::coins(5),
+            rand::random(),
+            GenerationReceivingAddress::derive_from_seed(rand::random()).into(),
+            true,
+        );
+        let outputs = vec![
+            AdditionRecord::from(&other_output),
+            AdditionRecord::from(&fee_output),
+        ];
+
+        assert!(contains_fee_output(
+            &outputs,
+            &fee_address,
+            amount,
+            sender_randomness
+        ));
+        assert!(!contains_fee_output(
+            &outputs[..1],
+            &fee_address,
+            amount,
+            sender_randomness
+        ));
+        assert!(!contains_fee_output(
+            &outputs,
+            &fee_address,
+            NativeCurrencyAmount::coins(2),
+            sender_randomness
+        ));
+    }
 }
@@ -102,47 +102,12 @@ pub async fn initialize(cli_args: cli_args::Args) -> Result<MainLoopHandler> {
 
     info!("Starting client on {}.", cli_args.network);
 
-    // Get data directory (wallet, block database), create one if none exists
-    let data_directory = DataDirectory::get(cli_args.data_dir.clone(), cli_args.network)?;
-    DataDirectory::create_dir_if_not_exists(&data_directory.root_dir_path()).await?;
-    info!("Data directory is {}", data_directory);
-
-    // Get wallet object, create various wallet secret files
-    let wallet_dir = data_directory.wallet_directory_path();
-    DataDirectory::create_dir_if_not_exists(&wallet_dir).await?;
-    let wallet_file_context =
-        WalletFileContext::read_from_file_or_create(&data_directory.wallet_directory_path())?;
-    info!("Now getting wallet state. This may take a while if the database needs pruning.");
-    let wallet_state =
-        WalletState::new_from_context(&data_directory, wallet_file_context, &cli_args).await;
-    info!("Got wallet state.");
-
-    // Connect to or create databases for block index, peers, mutator set, block sync
-    let block_index_db = ArchivalState::initialize_block_index_database(&data_directory).await?;
-    info!("Got block index database");
-
-    let peer_databases = NetworkingState::initialize_peer_databases(&data_directory).await?;
-    info!("Got peer database");
-
-    let archival_mutator_set = ArchivalState::initialize_mutator_set(&data_directory).await?;
-    info!("Got archival mutator set");
-
-    let archival_block_mmr = ArchivalState::initialize_archival_block_mmr(&data_directory).await?;
-    info!("Got archival block MMR");
-
-    let archival_state = ArchivalState::new(
-        data_directory.clone(),
-        block_index_db,
-        archival_mutator_set,
-        archival_block_mmr,
-        cli_args.network,
-    )
-    .await;
-
-    // Get latest block. Use hardcoded genesis block if nothing is in database.
-    let latest_block: Block = archival_state.get_tip().await;
+    let (mut global_state_lock, data_directory, rpc_server_to_main_rx) =
+        initialize_global_state(cli_args).await?;
+    let rpc_server_to_main_tx = global_state_lock.rpc_server_to_main_tx();
 
     // Bind socket to port on this machine, to handle incoming connections from peers
+    let cli_args = global_state_lock.cli();
     let incoming_peer_listener = if let Some(incoming_peer_listener) = cli_args.own_listen_port() {
         let ret = TcpListener::bind((cli_args.listen_addr, incoming_peer_listener))
            .await
@@ -154,8 +119,6 @@ pub async fn initialize(cli_args: cli_args::Args) -> Result<MainLoopHandler> {
         TcpListener::bind("127.0.0.1:0").await?
     };
 
-    let peer_map: HashMap<SocketAddr, PeerInfo> = HashMap::new();
-
     // Construct the broadcast channel to communicate from the main task to peer tasks
     let (main_to_peer_broadcast_tx, _main_to_peer_broadcast_rx) =
         broadcast::channel::<MainToPeerTask>(PEER_CHANNEL_CAPACITY);
@@ -164,41 +127,6 @@ pub async fn initialize(cli_args: cli_args::Args) -> Result<MainLoopHandler> {
     let (peer_task_to_main_tx, peer_task_to_main_rx) =
         mpsc::channel::<PeerTaskToMain>(PEER_CHANNEL_CAPACITY);
 
-    let networking_state = NetworkingState::new(peer_map, peer_databases);
-
-    let light_state: LightState = LightState::from(latest_block);
-    let blockchain_archival_state = BlockchainArchivalState {
-        light_state,
-        archival_state,
-    };
-    let blockchain_state = BlockchainState::Archival(Box::new(blockchain_archival_state));
-    let mempool = Mempool::new(
-        cli_args.max_mempool_size,
-        cli_args.max_mempool_num_tx,
-        blockchain_state.light_state().hash(),
-    );
-
-    let (rpc_server_to_main_tx, rpc_server_to_main_rx) =
-        mpsc::channel::<RPCServerToMain>(RPC_CHANNEL_CAPACITY);
-
-    let mut global_state_lock = GlobalStateLock::new(
-        wallet_state,
-        blockchain_state,
-        networking_state,
-        cli_args,
-        mempool,
-        rpc_server_to_main_tx.clone(),
-    );
-
-    // Check if we need to restore the wallet database, and if so, do it.
-    info!("Checking if we need to restore UTXOs");
-    global_state_lock
-        .lock_guard_mut()
-        .await
-        .restore_monitored_utxos_from_recovery_data()
-        .await?;
-    info!("UTXO restoration check complete");
-
     // Connect to peers, and provide each peer task with a thread-safe copy of the state
     let own_handshake_data: HandshakeData =
         global_state_lock.lock_guard().await.get_own_handshakedata();
@@ -255,9 +183,35 @@ Automatic mining adds randomly generated blocks which makes the blockchain non-d
         info!("Started mining task");
     }
 
+    // The mempool is persisted whether or not the REST API is served, as
+    // both share the pool's store.
+    #[cfg(feature = "rest")]
+    let pool_state = {
+        let pool_state = crate::tx_pool::PoolState::new(
+            &data_directory,
+            global_state_lock.cli().tx_pool_busy_timeout,
+        )?
+        .with_min_fee(global_state_lock.cli().min_tx_fee);
+
+        let num_restored =
+            crate::tx_pool::mempool_snapshot::restore(&pool_state, &mut global_state_lock).await?;
+        info!("Restored {num_restored} transactions from mempool snapshot");
+
+        let snapshot_join_handle = tokio::spawn(crate::tx_pool::mempool_snapshot::run(
+            pool_state.clone(),
+            global_state_lock.clone(),
+        ));
+        task_join_handles.push(snapshot_join_handle);
+
+        pool_state
+    };
+
     #[cfg(feature = "rest")]
     if let Some(rest_port) = global_state_lock.cli().rest_port {
-        let rest_listener = TcpListener::bind(format!("0.0.0.0:{}", rest_port)).await?;
+        let rest_listener = jsonrpc_server::bind_rest_listener(
+            SocketAddr::from(([0, 0, 0, 0], rest_port)),
+            global_state_lock.cli().rest_listen_backlog,
+        )?;
 
         let rpc_state_lock = global_state_lock.clone();
 
@@ -270,6 +224,7 @@ Automatic mining adds randomly generated blocks which makes the blockchain non-d
                 .await?
                 .into()];
 
+        let pool_state = pool_state.clone();
         let rpc_join_handle = tokio::spawn(async move {
             let server = rpc_server::NeptuneRPCServer::new(
                 rpc_state_lock.clone(),
@@ -278,11 +233,7 @@ Automatic mining adds randomly generated blocks which makes the blockchain non-d
                 valid_tokens.clone(),
             );
 
-            let pool_state =
-                crate::tx_pool::PoolState::new(data_directory.root_dir_path().join("tx_pool"))
-                    .expect("Error creating pool state");
-
-            jsonrpc_server::run_rpc_server(rest_listener, server,pool_state)
+            jsonrpc_server::run_rpc_server(rest_listener, server, pool_state)
                 .await
                 .expect("Error in REST server task");
         });
@@ -333,7 +284,7 @@ Automatic mining adds randomly generated blocks which makes the blockchain non-d
     info!("Started RPC server");
 
     // Handle incoming connections, messages from peer tasks, and messages from the mining task
-    Ok(MainLoopHandler::new(
+    let main_loop_handler = MainLoopHandler::new(
         incoming_peer_listener,
         global_state_lock,
         main_to_peer_broadcast_tx,
@@ -343,7 +294,165 @@ Automatic mining adds randomly generated blocks which makes the blockchain non-d
         miner_to_main_rx,
         rpc_server_to_main_rx,
         task_join_handles,
-    ))
+    );
+    #[cfg(feature = "rest")]
+    let main_loop_handler = main_loop_handler.with_mempool_snapshot_store(pool_state);
+
+    Ok(main_loop_handler)
+}
+
+/// Open the wallet and the databases in the data directory, and assemble the
+/// node's state from them. Also returns the data directory, and the receiving
+/// end of the channel from the RPC servers to the main loop.
+async fn initialize_global_state(
+    cli_args: cli_args::Args,
+) -> Result<(
+    GlobalStateLock,
+    DataDirectory,
+    mpsc::Receiver<RPCServerToMain>,
+)> {
+    // Get data directory (wallet, block database), create one if none exists
+    let data_directory = DataDirectory::get(cli_args.data_dir.clone(), cli_args.network)?;
+    DataDirectory::create_dir_if_not_exists(&data_directory.root_dir_path()).await?;
+    info!("Data directory is {}", data_directory);
+
+    // Get wallet object, create various wallet secret files
+    let wallet_dir = data_directory.wallet_directory_path();
+    DataDirectory::create_dir_if_not_exists(&wallet_dir).await?;
+    let wallet_file_context =
+        WalletFileContext::read_from_file_or_create(&data_directory.wallet_directory_path())?;
+    info!("Now getting wallet state. This may take a while if the database needs pruning.");
+    let wallet_state =
+        WalletState::new_from_context(&data_directory, wallet_file_context, &cli_args).await;
+    info!("Got wallet state.");
+
+    // Connect to or create databases for block index, peers, mutator set, block sync
+    let block_index_db = ArchivalState::initialize_block_index_database(&data_directory).await?;
+    info!("Got block index database");
+
+    let peer_databases = NetworkingState::initialize_peer_databases(&data_directory).await?;
+    info!("Got peer database");
+
+    let archival_mutator_set = ArchivalState::initialize_mutator_set(&data_directory).await?;
+    info!("Got archival mutator set");
+
+    let archival_block_mmr = ArchivalState::initialize_archival_block_mmr(&data_directory).await?;
+    info!("Got archival block MMR");
+
+    let archival_state = ArchivalState::new(
+        data_directory.clone(),
+        block_index_db,
+        archival_mutator_set,
+        archival_block_mmr,
+        cli_args.network,
+    )
+    .await;
+
+    if cli_args.check_db {
+        info!("Checking consistency of archival state");
+        archival_state
+            .check_consistency()
+            .await
+            .context("Archival state check failed, refusing to start")?;
+        info!("Archival state is consistent");
+    }
+
+    // Get latest block. Use hardcoded genesis block if nothing is in database.
+    let latest_block: Block = archival_state.get_tip().await;
+
+    let peer_map: HashMap<SocketAddr, PeerInfo> = HashMap::new();
+
+    let networking_state =
+        NetworkingState::new(peer_map, peer_databases, cli_args.max_orphan_blocks);
+
+    let light_state: LightState = LightState::from(latest_block);
+    let blockchain_archival_state = BlockchainArchivalState {
+        light_state,
+        archival_state,
+    };
+    let blockchain_state = BlockchainState::Archival(Box::new(blockchain_archival_state));
+    let mempool = Mempool::new(
+        cli_args.max_mempool_size,
+        cli_args.max_mempool_num_tx,
+        blockchain_state.light_state().hash(),
+    )
+    .with_eviction_policy(cli_args.mempool_eviction_policy)
+    .with_replace_by_fee_margin(cli_args.replace_by_fee_margin);
+
+    let (rpc_server_to_main_tx, rpc_server_to_main_rx) =
+        mpsc::channel::<RPCServerToMain>(RPC_CHANNEL_CAPACITY);
+
+    let mut global_state_lock = GlobalStateLock::new(
+        wallet_state,
+        blockchain_state,
+        networking_state,
+        cli_args,
+        mempool,
+        rpc_server_to_main_tx,
+    );
+
+    // Check if we need to restore the wallet database, and if so, do it.
+    info!("Checking if we need to restore UTXOs");
+    global_state_lock
+        .lock_guard_mut()
+        .await
+        .restore_monitored_utxos_from_recovery_data()
+        .await?;
+    info!("UTXO restoration check complete");
+
+    Ok((global_state_lock, data_directory, rpc_server_to_main_rx))
+}
+
+/// Import and export blocks as requested with `--import-blocks-from-file` and
+/// `--export-blocks-to-file`, then flush the databases. Unlike [`initialize`],
+/// this neither connects to peers nor starts any tasks, so the caller can exit
+/// once it returns.
+pub async fn import_and_export_blocks(cli_args: cli_args::Args) -> Result<()> {
+    info!(
+        "Starting client on {} to import or export blocks.",
+        cli_args.network
+    );
+    let (mut global_state_lock, _, _) = initialize_global_state(cli_args).await?;
+
+    if let Some(path) = global_state_lock.cli().import_blocks_from_file.clone() {
+        info!("Importing blocks from {}", path.display());
+        let file = std::fs::File::open(&path)
+            .with_context(|| format!("Failed to open block file {}", path.display()))?;
+        let summary = global_state_lock
+            .lock_guard_mut()
+            .await
+            .import_blocks(std::io::BufReader::new(file))
+            .await?;
+        info!(
+            "Imported {} blocks, skipped {} known blocks",
+            summary.imported, summary.skipped
+        );
+    }
+
+    if let Some(path) = global_state_lock.cli().export_blocks_to_file.clone() {
+        let range = global_state_lock
+            .cli()
+            .export_blocks_range
+            .clone()
+            .unwrap_or(0..=u64::MAX);
+        info!("Exporting blocks to {}", path.display());
+        let file = std::fs::File::create(&path)
+            .with_context(|| format!("Failed to create block file {}", path.display()))?;
+        let mut writer = std::io::BufWriter::new(file);
+        let manifest = global_state_lock
+            .lock_guard()
+            .await
+            .chain
+            .archival_state()
+            .export_blocks((*range.start()).into(), (*range.end()).into(), &mut writer)
+            .await?;
+        info!(
+            "Exported {} blocks of heights {} to {}",
+            manifest.num_blocks, manifest.first_height, manifest.last_height
+        );
+    }
+
+    global_state_lock.flush_databases().await
 }
 
 /// Time a fn call.  Duration is returned as a float in seconds.
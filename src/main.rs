@@ -39,6 +39,11 @@ pub fn main() -> Result<()> {
             set_up_logger();
         }
 
+        if args.imports_or_exports_blocks() {
+            neptune_cash::import_and_export_blocks(args).await?;
+            return Ok(neptune_cash::SUCCESS_EXIT_CODE);
+        }
+
         let mut main_loop_handler = neptune_cash::initialize(args).await?;
         main_loop_handler.run().await
     });
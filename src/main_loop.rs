@@ -12,7 +12,7 @@ use proof_upgrader::get_upgrade_task_from_mempool;
 use proof_upgrader::UpdateMutatorSetDataJob;
 use proof_upgrader::UpgradeJob;
 use rand::prelude::IteratorRandom;
-use rand::seq::IndexedRandom;
+use tasm_lib::prelude::Digest;
 use tokio::net::TcpListener;
 use tokio::select;
 use tokio::signal;
@@ -50,11 +50,14 @@ use crate::models::channel::RPCServerToMain;
 use crate::models::peer::handshake_data::HandshakeData;
 use crate::models::peer::peer_info::PeerInfo;
 use crate::models::peer::transaction_notification::TransactionNotification;
+use crate::models::peer::transfer_transaction::TransactionProofQuality;
+use crate::models::peer::DisconnectReason;
 use crate::models::peer::PeerSynchronizationState;
 use crate::models::proof_abstractions::tasm::program::TritonVmProofJobOptions;
 use crate::models::state::block_proposal::BlockProposal;
 use crate::models::state::mempool::TransactionOrigin;
 use crate::models::state::networking_state::SyncAnchor;
+use crate::models::state::transaction_kernel_id::TransactionKernelId;
 use crate::models::state::tx_proving_capability::TxProvingCapability;
 use crate::models::state::GlobalState;
 use crate::models::state::GlobalStateLock;
@@ -86,6 +89,10 @@ const POTENTIAL_PEER_MAX_COUNT_AS_A_FACTOR_OF_MAX_PEERS: usize = 20;
 pub(crate) const MAX_NUM_DIGESTS_IN_BATCH_REQUEST: usize = 200;
 const TX_UPDATER_CHANNEL_CAPACITY: usize = 1;
 
+/// Time window within which a repeated transaction notification is not sent to
+/// peers again.
+const TX_NOTIFICATION_DEDUP_WINDOW: Duration = Duration::from_secs(60);
+
 /// Wraps a transmission channel.
 ///
 /// To be used for the transmission channel to the miner, because
@@ -131,10 +138,61 @@ pub struct MainLoopHandler {
     rpc_server_to_main_rx: mpsc::Receiver<RPCServerToMain>,
     task_handles: Vec<JoinHandle<()>>,
 
+    /// Transaction notifications recently sent to peers.
+    recent_tx_notifications: RecentTransactionNotifications,
+
+    /// Store to which the mempool is written on shutdown, if any.
+    #[cfg(feature = "rest")]
+    mempool_snapshot_store: Option<crate::tx_pool::PoolState>,
+
     #[cfg(test)]
     mock_now: Option<SystemTime>,
 }
 
+/// Remembers which transaction notifications were recently sent to peers, such
+/// that the same notification is not gossiped repeatedly.
+#[derive(Debug, Default)]
+struct RecentTransactionNotifications {
+    /// Mutator set hash, proof quality, and time of the last notification sent
+    /// for each transaction.
+    sent: HashMap<TransactionKernelId, (Digest, TransactionProofQuality, SystemTime)>,
+}
+
+impl RecentTransactionNotifications {
+    /// Returns true iff the notification should be sent to peers, and if so,
+    /// records it as sent.
+    ///
+    /// A notification is a duplicate if, within [`TX_NOTIFICATION_DEDUP_WINDOW`],
+    /// a notification for the same transaction was sent that is synced to the
+    /// same mutator set and has at least the same proof quality. Updated or
+    /// upgraded transactions are thus always announced.
+    fn should_send(&mut self, notification: &TransactionNotification, now: SystemTime) -> bool {
+        self.sent.retain(|_, (_, _, sent_at)| {
+            now.duration_since(*sent_at).unwrap_or_default() < TX_NOTIFICATION_DEDUP_WINDOW
+        });
+
+        let is_duplicate = self.sent.get(&notification.txid).is_some_and(
+            |(mutator_set_hash, proof_quality, _)| {
+                *mutator_set_hash == notification.mutator_set_hash
+                    && *proof_quality >= notification.proof_quality
+            },
+        );
+        if is_duplicate {
+            return false;
+        }
+
+        self.sent.insert(
+            notification.txid,
+            (
+                notification.mutator_set_hash,
+                notification.proof_quality,
+                now,
+            ),
+        );
+        true
+    }
+}
+
 /// The mutable part of the main loop function
 struct MutableMainLoopState {
     /// Information used to batch-download blocks.
@@ -199,6 +257,24 @@ impl SyncState {
             .collect()
     }
 
+    /// Return the peer best suited for a synchronization request: among the
+    /// peers that have reported a PoW above the threshold, the one with the
+    /// highest claimed block height. Ties are broken in favor of the peer with
+    /// the lowest discovery distance.
+    fn best_peer_for_sync_request(&self, threshold_pow: ProofOfWork) -> Option<SocketAddr> {
+        self.peer_sync_states
+            .iter()
+            .filter(|(_sa, sync_state)| sync_state.claimed_max_pow > threshold_pow)
+            .max_by_key(|(sa, sync_state)| {
+                (
+                    sync_state.claimed_max_height,
+                    std::cmp::Reverse(sync_state.distance),
+                    std::cmp::Reverse(**sa),
+                )
+            })
+            .map(|(sa, _)| *sa)
+    }
+
     /// Determine if a peer should be sanctioned for failing to respond to a
     /// synchronization request fast enough. Also determine if a new request
     /// should be made or the previous one should be allowed to run for longer.
@@ -256,15 +332,65 @@ impl PotentialPeerInfo {
 /// holds information about a set of potential peers in the process of peer discovery
 struct PotentialPeersState {
     potential_peers: HashMap<SocketAddr, PotentialPeerInfo>,
+
+    /// The potential peers learned in the current round of peer discovery,
+    /// and their distances.
+    added_this_round: Vec<(SocketAddr, u8)>,
 }
 
 impl PotentialPeersState {
     fn default() -> Self {
         Self {
             potential_peers: HashMap::new(),
+            added_this_round: vec![],
         }
     }
 
+    /// Start a new round of peer discovery, resetting the count of potential
+    /// peers learned per round.
+    fn start_round(&mut self) {
+        self.added_this_round.clear();
+    }
+
+    /// Add the potential peers from a peer discovery answer, learning at most
+    /// `max_new_per_round` new potential peers in the current round. Once that
+    /// limit is reached, a new potential peer only replaces one learned in this
+    /// round that is further away.
+    fn add_from_discovery_answer(
+        &mut self,
+        reported_by: SocketAddr,
+        potential_peers: Vec<(SocketAddr, u128)>,
+        distance: u8,
+        max_peers: usize,
+        max_new_per_round: usize,
+        now: SystemTime,
+    ) {
+        for potential_peer in potential_peers {
+            if self.potential_peers.contains_key(&potential_peer.0) {
+                continue;
+            }
+
+            if self.added_this_round.len() >= max_new_per_round {
+                let Some((position, _)) = self
+                    .added_this_round
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (_, added_distance))| *added_distance > distance)
+                    .max_by_key(|(_, (_, added_distance))| *added_distance)
+                else {
+                    continue;
+                };
+                let (further_away, _) = self.added_this_round.swap_remove(position);
+                self.potential_peers.remove(&further_away);
+            }
+
+            if self.add(reported_by, potential_peer, max_peers, distance, now) {
+                self.added_this_round.push((potential_peer.0, distance));
+            }
+        }
+    }
+
+    /// Returns true iff the potential peer was not already known.
     fn add(
         &mut self,
         reported_by: SocketAddr,
@@ -272,7 +398,7 @@ impl PotentialPeersState {
         max_peers: usize,
         distance: u8,
         now: SystemTime,
-    ) {
+    ) -> bool {
         let potential_peer_socket_address = potential_peer.0;
         let potential_peer_instance_id = potential_peer.1;
 
@@ -282,7 +408,7 @@ impl PotentialPeersState {
             .potential_peers
             .contains_key(&potential_peer_socket_address)
         {
-            return;
+            return false;
         }
 
         // If this data structure is full, remove a random entry. Then add this.
@@ -303,6 +429,8 @@ impl PotentialPeersState {
             PotentialPeerInfo::new(reported_by, potential_peer_instance_id, distance, now);
         self.potential_peers
             .insert(potential_peer_socket_address, insert_value);
+
+        true
     }
 
     /// Return a peer from the potential peer list that we aren't connected to
@@ -422,12 +550,27 @@ impl MainLoopHandler {
             miner_to_main_rx,
             rpc_server_to_main_rx,
             task_handles,
+            recent_tx_notifications: RecentTransactionNotifications::default(),
+
+            #[cfg(feature = "rest")]
+            mempool_snapshot_store: None,
 
             #[cfg(test)]
             mock_now: None,
         }
     }
 
+    /// Write the mempool to `pool_state` on shutdown, such that transactions
+    /// received since the last periodic snapshot are not lost.
+    #[cfg(feature = "rest")]
+    pub(crate) fn with_mempool_snapshot_store(
+        mut self,
+        pool_state: crate::tx_pool::PoolState,
+    ) -> Self {
+        self.mempool_snapshot_store = Some(pool_state);
+        self
+    }
+
     pub fn global_state_lock(&mut self) -> GlobalStateLock {
         self.global_state_lock.clone()
     }
@@ -500,8 +643,7 @@ impl MainLoopHandler {
 
         // Then notify all peers
         for updated in updated_txs {
-            let pmsg = MainToPeerTask::TransactionNotification((&updated).try_into().unwrap());
-            self.main_to_peer_broadcast(pmsg);
+            self.broadcast_transaction_notification((&updated).try_into().unwrap());
         }
 
         // Tell miner that it can now start composing next block.
@@ -624,7 +766,8 @@ impl MainLoopHandler {
                     return Ok(None);
                 }
 
-                if !self.global_state_lock.cli().secret_compositions {
+                let share_proposal = !self.global_state_lock.cli().secret_compositions;
+                if share_proposal {
                     let pmsg = MainToPeerTask::BlockProposalNotification((&block).into());
                     self.main_to_peer_broadcast(pmsg);
                 }
@@ -633,6 +776,12 @@ impl MainLoopHandler {
                     // Use block proposal and add expected UTXOs from this
                     // proposal.
                     let mut state = self.global_state_lock.lock_guard_mut().await;
+                    if share_proposal {
+                        state
+                            .mining_state
+                            .record_proposal_notification((&block).into());
+                        state.publish_block_proposal(&block);
+                    }
                     state.mining_state.block_proposal =
                         BlockProposal::own_proposal(block.clone(), expected_utxos.clone());
                     state.wallet_state.add_expected_utxos(expected_utxos).await;
@@ -764,11 +913,12 @@ impl MainLoopHandler {
                 claimed_height,
                 claimed_cumulative_pow,
                 claimed_block_mmra,
+                distance,
             } => {
                 log_slow_scope!(fn_name!() + "::PeerTaskToMain::AddPeerMaxBlockHeight");
 
                 let claimed_state =
-                    PeerSynchronizationState::new(claimed_height, claimed_cumulative_pow);
+                    PeerSynchronizationState::new(claimed_height, claimed_cumulative_pow, distance);
                 main_loop_state
                     .sync_state
                     .peer_sync_states
@@ -826,15 +976,15 @@ impl MainLoopHandler {
                 log_slow_scope!(fn_name!() + "::PeerTaskToMain::PeerDiscoveryAnswer");
 
                 let max_peers = self.global_state_lock.cli().max_num_peers;
-                for pot_peer in pot_peers {
-                    main_loop_state.potential_peers.add(
-                        reported_by,
-                        pot_peer,
-                        max_peers,
-                        distance,
-                        self.now(),
-                    );
-                }
+                let max_new_per_round = self.global_state_lock.cli().max_discovered_peers_per_round;
+                main_loop_state.potential_peers.add_from_discovery_answer(
+                    reported_by,
+                    pot_peers,
+                    distance,
+                    max_peers,
+                    max_new_per_round,
+                    self.now(),
+                );
             }
             PeerTaskToMain::Transaction(pt2m_transaction) => {
                 log_slow_scope!(fn_name!() + "::PeerTaskToMain::Transaction");
@@ -868,8 +1018,7 @@ impl MainLoopHandler {
                 let transaction_notification: TransactionNotification =
                     (&pt2m_transaction.transaction).try_into()?;
 
-                let pmsg = MainToPeerTask::TransactionNotification(transaction_notification);
-                self.main_to_peer_broadcast(pmsg);
+                self.broadcast_transaction_notification(transaction_notification);
             }
             PeerTaskToMain::BlockProposal(block) => {
                 log_slow_scope!(fn_name!() + "::PeerTaskToMain::BlockProposal");
@@ -898,6 +1047,10 @@ impl MainLoopHandler {
 
                     global_state_mut.mining_state.block_proposal =
                         BlockProposal::foreign_proposal(*block.clone());
+                    global_state_mut
+                        .mining_state
+                        .record_proposal_notification((&*block).into());
+                    global_state_mut.publish_block_proposal(&block);
                 }
 
                 // Notify all peers of the block proposal we just accepted
@@ -928,10 +1081,29 @@ impl MainLoopHandler {
 
                 // tell to disconnect
                 if let Some((peer_socket, _peer_info)) = longest_lived_peer {
-                    let pmsg = MainToPeerTask::Disconnect(peer_socket.to_owned());
+                    let pmsg = MainToPeerTask::Disconnect(
+                        peer_socket.to_owned(),
+                        DisconnectReason::BootstrapperMakingRoom,
+                    );
                     self.main_to_peer_broadcast(pmsg);
                 }
             }
+            PeerTaskToMain::BanPeer(peer_address) => {
+                let ban_duration = cli_args.peer_ban_duration;
+                warn!(
+                    "Banning peer {peer_address} for {} seconds: standing below ban threshold",
+                    ban_duration.as_secs()
+                );
+                let expiry = self.now() + ban_duration;
+                self.global_state_lock
+                    .lock_guard_mut()
+                    .await
+                    .net
+                    .ban_temporarily(peer_address.ip(), expiry);
+
+                let pmsg = MainToPeerTask::Disconnect(peer_address, DisconnectReason::Banned);
+                self.main_to_peer_broadcast(pmsg);
+            }
         }
 
         Ok(())
@@ -985,7 +1157,10 @@ impl MainLoopHandler {
             i => info!("Disconnecting from {i} peers."),
         }
         for peer in peers_to_disconnect {
-            let pmsg = MainToPeerTask::Disconnect(peer.connected_address());
+            let pmsg = MainToPeerTask::Disconnect(
+                peer.connected_address(),
+                DisconnectReason::MaxPeerNumberExceeded,
+            );
             self.main_to_peer_broadcast(pmsg);
         }
 
@@ -1037,6 +1212,16 @@ impl MainLoopHandler {
                 info!("Not reconnecting to peer in bad standing: {peer_with_lost_connection}");
                 continue;
             }
+            let is_banned = self
+                .global_state_lock
+                .lock_guard()
+                .await
+                .net
+                .is_temporarily_banned(peer_with_lost_connection.ip(), self.now());
+            if is_banned {
+                info!("Not reconnecting to banned peer: {peer_with_lost_connection}");
+                continue;
+            }
 
             info!("Attempting to reconnect to peer: {peer_with_lost_connection}");
             let global_state_lock = self.global_state_lock.clone();
@@ -1096,6 +1281,7 @@ impl MainLoopHandler {
 
         // Ask all peers for their peer lists. This will eventually – once the
         // responses have come in – update the list of potential peers.
+        main_loop_state.potential_peers.start_round();
         let pmsg = MainToPeerTask::MakePeerDiscoveryRequest;
         self.main_to_peer_broadcast(pmsg);
 
@@ -1242,11 +1428,10 @@ impl MainLoopHandler {
         // Create the next request from the reported
         info!("Creating new sync request");
 
-        // Pick a random peer that has reported to have relevant blocks
-        let candidate_peers = main_loop_state
+        // Pick the best peer that has reported to have relevant blocks
+        let chosen_peer = main_loop_state
             .sync_state
-            .get_potential_peers_for_sync_request(own_cumulative_pow);
-        let chosen_peer = candidate_peers.choose(&mut rand::rng());
+            .best_peer_for_sync_request(own_cumulative_pow);
         assert!(
             chosen_peer.is_some(),
             "A synchronization candidate must be available for a request. \
@@ -1281,7 +1466,7 @@ impl MainLoopHandler {
             chosen_peer, own_tip_hash, own_tip_height
         );
         let pmsg = MainToPeerTask::RequestBlockBatch(MainToPeerTaskBatchBlockRequest {
-            peer_addr_target: *chosen_peer,
+            peer_addr_target: chosen_peer,
             known_blocks: ordered_preferred_block_digests,
             anchor_mmr: anchor.block_mmr.clone(),
         });
@@ -1291,7 +1476,7 @@ impl MainLoopHandler {
         let requested_block_height = own_tip_height.next();
         main_loop_state
             .sync_state
-            .record_request(requested_block_height, *chosen_peer, self.now());
+            .record_request(requested_block_height, chosen_peer, self.now());
 
         Ok(())
     }
@@ -1707,8 +1892,7 @@ impl MainLoopHandler {
                 // Is this a transaction we can share with peers? If so, share
                 // it immediately.
                 if let Ok(notification) = transaction.as_ref().try_into() {
-                    let pmsg = MainToPeerTask::TransactionNotification(notification);
-                    self.main_to_peer_broadcast(pmsg);
+                    self.broadcast_transaction_notification(notification);
                 } else {
                     // Otherwise, upgrade its proof quality, and share it by
                     // spinning up the proof upgrader.
@@ -1776,8 +1960,7 @@ impl MainLoopHandler {
                 Ok(false)
             }
             RPCServerToMain::BroadcastNotification(n) => {
-                let pmsg = MainToPeerTask::TransactionNotification(n);
-                self.main_to_peer_broadcast(pmsg);
+                self.broadcast_transaction_notification(n);
                 Ok(false)
             }
             RPCServerToMain::ClearMempool => {
@@ -1835,7 +2018,10 @@ impl MainLoopHandler {
             RPCServerToMain::PeerDisconnected(socket_addr) => {
                 info!("Received RPC disconnect {}", socket_addr);
                 self.main_to_peer_broadcast_tx
-                    .send(MainToPeerTask::Disconnect(socket_addr))?;
+                    .send(MainToPeerTask::Disconnect(
+                        socket_addr,
+                        DisconnectReason::RequestedByOperator,
+                    ))?;
 
                 Ok(false)
             }
@@ -1853,6 +2039,16 @@ impl MainLoopHandler {
         self.main_to_peer_broadcast(pmsg);
         debug!("sent bye");
 
+        #[cfg(feature = "rest")]
+        if let Some(pool_state) = &self.mempool_snapshot_store {
+            let saved =
+                crate::tx_pool::mempool_snapshot::save(pool_state, &self.global_state_lock).await;
+            match saved {
+                Ok(num_saved) => info!("Saved {num_saved} mempool transactions to disk"),
+                Err(e) => warn!("Failed to save mempool snapshot: {e}"),
+            }
+        }
+
         // Flush all databases
         self.global_state_lock.flush_databases().await?;
 
@@ -1883,6 +2079,24 @@ impl MainLoopHandler {
             );
         }
     }
+
+    /// Broadcast a transaction notification to all peers, unless the same
+    /// notification was sent recently.
+    fn broadcast_transaction_notification(&mut self, notification: TransactionNotification) {
+        if !self
+            .recent_tx_notifications
+            .should_send(&notification, self.now())
+        {
+            debug!(
+                "Not rebroadcasting recent notification for transaction {}",
+                notification.txid
+            );
+            return;
+        }
+
+        let pmsg = MainToPeerTask::TransactionNotification(notification);
+        self.main_to_peer_broadcast(pmsg);
+    }
 }
 
 #[cfg(test)]
@@ -2029,6 +2243,48 @@ mod test {
         }
     }
 
+    #[cfg(feature = "rest")]
+    #[tokio::test]
+    async fn mempool_is_saved_on_shutdown() {
+        use crate::models::proof_abstractions::timestamp::Timestamp;
+        use crate::models::state::mempool::TransactionOrigin;
+        use crate::tests::shared::make_mock_transaction_with_mutator_set_hash_and_timestamp;
+        use crate::tests::shared::unit_test_data_directory;
+        use crate::tx_pool::PoolState;
+        use crate::tx_pool::DEFAULT_BUSY_TIMEOUT;
+
+        let TestSetup {
+            main_loop_handler, ..
+        } = setup(0, 0).await;
+        let network = main_loop_handler.global_state_lock.cli().network;
+        let data_directory = unit_test_data_directory(network).unwrap();
+        let pool_state = PoolState::new(&data_directory, DEFAULT_BUSY_TIMEOUT).unwrap();
+        let mut main_loop_handler =
+            main_loop_handler.with_mempool_snapshot_store(pool_state.clone());
+
+        {
+            let mut global_state = main_loop_handler.global_state_lock.lock_guard_mut().await;
+            let msa_hash = global_state
+                .chain
+                .light_state()
+                .mutator_set_accumulator_after()
+                .hash();
+            let tx = make_mock_transaction_with_mutator_set_hash_and_timestamp(
+                vec![],
+                vec![],
+                msa_hash,
+                Timestamp::now(),
+            );
+            global_state
+                .mempool_insert(tx, TransactionOrigin::Foreign)
+                .await;
+        }
+        assert!(pool_state.mempool_snapshot().unwrap().is_empty());
+
+        main_loop_handler.graceful_shutdown(vec![]).await.unwrap();
+        assert_eq!(1, pool_state.mempool_snapshot().unwrap().len());
+    }
+
     mod sync_mode {
         use tasm_lib::twenty_first::util_types::mmr::mmr_accumulator::MmrAccumulator;
         use test_strategy::proptest;
@@ -2067,6 +2323,36 @@ mod test {
             );
         }
 
+        #[test]
+        fn best_peer_for_sync_request_prefers_height_then_distance() {
+            let threshold_pow = ProofOfWork::new([50; 6]);
+            let enough_pow = ProofOfWork::new([100; 6]);
+            let too_little_pow = ProofOfWork::new([10; 6]);
+
+            let mut sync_state = SyncState::default();
+            assert!(sync_state
+                .best_peer_for_sync_request(threshold_pow)
+                .is_none());
+
+            let peers = [
+                (900u64, enough_pow, 1),
+                (1_000, enough_pow, 3),
+                (1_000, enough_pow, 2),
+                (2_000, too_little_pow, 1),
+            ];
+            for (i, (height, pow, distance)) in peers.into_iter().enumerate() {
+                sync_state.peer_sync_states.insert(
+                    get_dummy_socket_address(i as u8),
+                    PeerSynchronizationState::new(height.into(), pow, distance),
+                );
+            }
+
+            assert_eq!(
+                Some(get_dummy_socket_address(2)),
+                sync_state.best_peer_for_sync_request(threshold_pow)
+            );
+        }
+
         #[tokio::test]
         #[traced_test]
         async fn sync_mode_abandoned_on_global_timeout() {
@@ -2098,7 +2384,7 @@ mod test {
             ));
             mutable_main_loop_state.sync_state.peer_sync_states.insert(
                 get_dummy_socket_address(0),
-                PeerSynchronizationState::new(claimed_max_height, claimed_max_pow),
+                PeerSynchronizationState::new(claimed_max_height, claimed_max_pow, 1),
             );
 
             let sync_start_time = main_loop_handler
@@ -2334,8 +2620,155 @@ mod test {
         }
     }
 
+    mod transaction_notifications {
+        use super::*;
+        use crate::models::blockchain::type_scripts::native_currency_amount::NativeCurrencyAmount;
+
+        fn notification(proof_quality: TransactionProofQuality) -> TransactionNotification {
+            TransactionNotification {
+                txid: TransactionKernelId::new(Digest::default()),
+                mutator_set_hash: Digest::default(),
+                proof_quality,
+                fee: NativeCurrencyAmount::coins(1),
+                num_inputs: 1,
+                num_outputs: 2,
+            }
+        }
+
+        #[tokio::test]
+        #[traced_test]
+        async fn duplicate_transaction_notification_is_sent_once() {
+            let TestSetup {
+                mut main_loop_handler,
+                mut main_to_peer_rx,
+            } = setup(1, 0).await;
+            let mut mutable_main_loop_state = main_loop_handler.mutable();
+
+            let notification = notification(TransactionProofQuality::ProofCollection);
+            for _ in 0..2 {
+                main_loop_handler
+                    .handle_rpc_server_message(
+                        RPCServerToMain::BroadcastNotification(notification),
+                        &mut mutable_main_loop_state,
+                    )
+                    .await
+                    .unwrap();
+            }
+
+            let Ok(MainToPeerTask::TransactionNotification(sent)) = main_to_peer_rx.try_recv()
+            else {
+                panic!("first notification must be sent to peers");
+            };
+            assert_eq!(notification, sent);
+            assert!(
+                main_to_peer_rx.try_recv().is_err(),
+                "duplicate notification must not be sent to peers"
+            );
+        }
+
+        #[test]
+        fn updated_or_upgraded_transaction_is_announced_again() {
+            let mut recent = RecentTransactionNotifications::default();
+            let now = UNIX_EPOCH;
+            let proof_collection = notification(TransactionProofQuality::ProofCollection);
+            assert!(recent.should_send(&proof_collection, now));
+            assert!(!recent.should_send(&proof_collection, now));
+
+            let single_proof = notification(TransactionProofQuality::SingleProof);
+            assert!(recent.should_send(&single_proof, now));
+            assert!(!recent.should_send(&proof_collection, now));
+
+            let updated = TransactionNotification {
+                mutator_set_hash: Digest::new([1u64.into(); Digest::LEN]),
+                ..single_proof
+            };
+            assert!(recent.should_send(&updated, now));
+
+            let later = now + TX_NOTIFICATION_DEDUP_WINDOW;
+            assert!(recent.should_send(&updated, later));
+        }
+    }
+
     mod peer_discovery {
         use super::*;
+        use crate::tests::shared::get_dummy_socket_address;
+
+        #[test]
+        fn discovery_answer_is_clamped_to_max_new_peers_per_round() {
+            let max_peers = 10;
+            let max_new_per_round = 5;
+            let now = UNIX_EPOCH;
+            let far_reporter = get_dummy_socket_address(100);
+            let close_reporter = get_dummy_socket_address(101);
+            let answer = |addresses: std::ops::Range<u8>| {
+                addresses
+                    .map(|i| (get_dummy_socket_address(i), u128::from(i)))
+                    .collect_vec()
+            };
+
+            let mut state = PotentialPeersState::default();
+            state.start_round();
+            state.add_from_discovery_answer(
+                far_reporter,
+                answer(0..30),
+                3,
+                max_peers,
+                max_new_per_round,
+                now,
+            );
+            assert_eq!(max_new_per_round, state.potential_peers.len());
+
+            // closer peers displace further ones learned in the same round
+            state.add_from_discovery_answer(
+                close_reporter,
+                answer(30..33),
+                2,
+                max_peers,
+                max_new_per_round,
+                now,
+            );
+            assert_eq!(max_new_per_round, state.potential_peers.len());
+            for i in 30..33 {
+                assert_eq!(
+                    2,
+                    state.potential_peers[&get_dummy_socket_address(i)].distance
+                );
+            }
+            assert_eq!(
+                2,
+                state
+                    .potential_peers
+                    .values()
+                    .filter(|info| info.distance == 3)
+                    .count()
+            );
+
+            // peers at the same distance do not
+            state.add_from_discovery_answer(
+                close_reporter,
+                answer(33..40),
+                3,
+                max_peers,
+                max_new_per_round,
+                now,
+            );
+            assert_eq!(max_new_per_round, state.potential_peers.len());
+            assert!(!state
+                .potential_peers
+                .contains_key(&get_dummy_socket_address(33)));
+
+            // a new round allows learning new peers again
+            state.start_round();
+            state.add_from_discovery_answer(
+                far_reporter,
+                answer(40..50),
+                3,
+                max_peers,
+                max_new_per_round,
+                now,
+            );
+            assert_eq!(2 * max_new_per_round, state.potential_peers.len());
+        }
 
         #[tokio::test]
         #[traced_test]
@@ -2362,7 +2795,10 @@ mod test {
             assert_eq!(4, main_to_peer_rx.len());
             for _ in 0..4 {
                 let peer_msg = main_to_peer_rx.recv().await.unwrap();
-                assert!(matches!(peer_msg, MainToPeerTask::Disconnect(_)))
+                assert!(matches!(
+                    peer_msg,
+                    MainToPeerTask::Disconnect(_, DisconnectReason::MaxPeerNumberExceeded)
+                ))
             }
         }
 
@@ -2448,6 +2884,49 @@ mod test {
             assert!(peer_discovery_sent_messages_on_peer_channel);
             assert!(logs_contain("Performing peer discovery"));
         }
+
+        #[tokio::test]
+        #[traced_test]
+        async fn peer_below_ban_threshold_is_disconnected_and_temporarily_refused() {
+            let TestSetup {
+                main_loop_handler,
+                mut main_to_peer_rx,
+            } = setup(2, 0).await;
+            let now = UNIX_EPOCH + Duration::from_secs(1_000_000);
+            let mut main_loop_handler = main_loop_handler.with_mocked_time(now);
+            let mut mutable_state = main_loop_handler.mutable();
+
+            let peer_address = *main_loop_handler
+                .global_state_lock
+                .lock_guard()
+                .await
+                .net
+                .peer_map
+                .keys()
+                .next()
+                .unwrap();
+            let ban_peer = PeerTaskToMain::BanPeer(peer_address);
+            main_loop_handler
+                .handle_peer_task_message(ban_peer, &mut mutable_state)
+                .await
+                .unwrap();
+
+            let peer_msg = main_to_peer_rx.recv().await.unwrap();
+            assert!(matches!(
+                peer_msg,
+                MainToPeerTask::Disconnect(address, DisconnectReason::Banned)
+                    if address == peer_address
+            ));
+
+            let ban_duration = main_loop_handler.global_state_lock.cli().peer_ban_duration;
+            let global_state = main_loop_handler.global_state_lock.lock_guard().await;
+            assert!(global_state
+                .net
+                .is_temporarily_banned(peer_address.ip(), now));
+            assert!(!global_state
+                .net
+                .is_temporarily_banned(peer_address.ip(), now + ban_duration));
+        }
     }
 
     #[test]
@@ -2626,7 +3105,7 @@ mod test {
 
             // main loop should send a `Disconnect` message
             let main_to_peers_message = main_to_peer_rx.recv().await.unwrap();
-            let MainToPeerTask::Disconnect(observed_drop_peer_socket_address) =
+            let MainToPeerTask::Disconnect(observed_drop_peer_socket_address, _) =
                 main_to_peers_message
             else {
                 panic!("Expected disconnect, got {main_to_peers_message:?}");
@@ -505,7 +505,7 @@ pub(super) fn prepare_coinbase_transaction_stateless(
     let next_block_height: BlockHeight = latest_block.header().height.next();
     info!("Creating coinbase for block of height {next_block_height}.");
 
-    let coinbase_amount = Block::block_subsidy(next_block_height);
+    let coinbase_amount = max_coinbase_for_height(next_block_height);
     let [liquid_coinbase_output, timelocked_coinbase_output] =
         composer_outputs(coinbase_amount, composer_parameters, timestamp)?;
     let total_composer_fee = liquid_coinbase_output.utxo().get_native_currency_amount()
@@ -10,37 +10,74 @@ use twenty_first::math::digest::Digest;
 use super::difficulty_control::Difficulty;
 use super::difficulty_control::ProofOfWork;
 use crate::models::blockchain::block::block_height::BlockHeight;
+use crate::models::blockchain::block::max_coinbase_for_height;
 use crate::models::blockchain::block::Block;
+use crate::models::blockchain::type_scripts::native_currency_amount;
 use crate::models::blockchain::type_scripts::native_currency_amount::NativeCurrencyAmount;
 use crate::models::proof_abstractions::timestamp::Timestamp;
 use crate::models::state::transaction_kernel_id::TransactionKernelId;
 use crate::prelude::twenty_first;
 
 /// Provides summary information about a Block
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct BlockInfo {
+    #[serde(rename = "height")]
     pub height: BlockHeight,
 
-    /// Block size in number of [`BFieldElement`](twenty_first::math::b_field_element::BFieldElement)s
+    /// Block size in number of [`BFieldElement`](twenty_first::math::b_field_element::BFieldElement)s,
+    /// the unit of the block size limit.
+    #[serde(rename = "size")]
     pub size: usize,
+
+    /// Block size in bytes, as serialized for storage and the network.
+    #[serde(rename = "size_bytes", default)]
+    pub size_bytes: usize,
+
+    /// How full the block is: its [`size`](Self::size) as a fraction of the
+    /// maximum block size at its height. See [`weight_fraction`].
+    #[serde(rename = "weight_fraction", default)]
+    pub weight_fraction: f64,
+    #[serde(rename = "digest")]
     pub digest: Digest,
+    #[serde(rename = "nonce")]
     pub nonce: Digest,
+    #[serde(rename = "prev_block_digest")]
     pub prev_block_digest: Digest,
+    #[serde(rename = "timestamp")]
     pub timestamp: Timestamp,
+    #[serde(rename = "cumulative_proof_of_work")]
     pub cumulative_proof_of_work: ProofOfWork,
+    #[serde(rename = "difficulty")]
     pub difficulty: Difficulty,
+    /// Number of transactions merged into the block, a lower bound; see
+    /// [`Block::num_transactions`].
+    #[serde(rename = "num_transactions", default)]
+    pub num_transactions: usize,
+    #[serde(rename = "num_inputs")]
     pub num_inputs: usize,
+    #[serde(rename = "inputs")]
     pub inputs: Vec<String>,
+    #[serde(rename = "num_outputs")]
     pub num_outputs: usize,
+    #[serde(rename = "outputs")]
     pub outputs: Vec<String>,
+    #[serde(rename = "num_public_announcements")]
     pub num_public_announcements: usize,
+    #[serde(rename = "coinbase_amount", with = "native_currency_amount::api_json")]
     pub coinbase_amount: NativeCurrencyAmount,
+    #[serde(rename = "fee", with = "native_currency_amount::api_json")]
     pub fee: NativeCurrencyAmount,
+    #[serde(rename = "is_genesis")]
     pub is_genesis: bool,
+    #[serde(rename = "is_tip")]
     pub is_tip: bool,
+    #[serde(rename = "is_canonical")]
     pub is_canonical: bool,
+    #[serde(rename = "sibling_blocks")]
     pub sibling_blocks: Vec<Digest>,
+    #[serde(rename = "txid")]
     pub txid: TransactionKernelId,
+    #[serde(rename = "guesser_digest")]
     pub guesser_digest: Digest,
 }
 
@@ -51,6 +88,8 @@ impl std::fmt::Display for BlockInfo {
             + &format!("height: {}\n", self.height)
             + &format!("digest: {}\n", self.digest.to_hex())
             + &format!("size: {}\n", self.size)
+            + &format!("size_bytes: {}\n", self.size_bytes)
+            + &format!("weight_fraction: {}\n", self.weight_fraction)
             + &format!("nonce: {}\n", self.nonce.to_hex())
             + &format!("prev_block_digest: {}\n", self.prev_block_digest.to_hex())
             + &format!("timestamp: {}\n", self.timestamp.standard_format())
@@ -59,6 +98,7 @@ impl std::fmt::Display for BlockInfo {
                 self.cumulative_proof_of_work
             )
             + &format!("difficulty: {}\n", self.difficulty)
+            + &format!("num_transactions: {}\n", self.num_transactions)
             + &format!("num_inputs: {}\n", self.num_inputs)
             + &format!("inputs: {:#?}\n", self.inputs)
             + &format!("num_outputs: {}\n", self.num_outputs)
@@ -106,9 +146,12 @@ impl BlockInfo {
             prev_block_digest: header.prev_block_digest,
             height: header.height,
             size: block.size(),
+            size_bytes: block.size_in_bytes(),
+            weight_fraction: weight_fraction(block.size(), Block::max_size(header.height)),
             timestamp: header.timestamp,
             difficulty: header.difficulty,
             cumulative_proof_of_work: header.cumulative_proof_of_work,
+            num_transactions: block.num_transactions(),
             num_inputs: body.transaction_kernel.inputs.len(),
             inputs: body
                 .transaction_kernel
@@ -141,6 +184,120 @@ impl BlockInfo {
     /// field because a miner may choose to reward themself less than the
     /// calculated reward amount.
     pub fn expected_coinbase_amount(&self) -> NativeCurrencyAmount {
-        Block::block_subsidy(self.height)
+        max_coinbase_for_height(self.height)
+    }
+}
+
+/// `size` as a fraction of `max_size`. A `max_size` of zero yields zero rather
+/// than an infinite or undefined fraction, which JSON cannot represent.
+fn weight_fraction(size: usize, max_size: usize) -> f64 {
+    match max_size {
+        0 => 0.0,
+        max_size => size as f64 / max_size as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_models::network::Network;
+    use crate::tests::shared::fake_valid_block_proposal_successor_for_test;
+    use crate::tests::shared::invalid_empty_block;
+
+    #[test]
+    fn size_bytes_is_length_of_serialized_block() {
+        let genesis = Block::genesis(Network::Main);
+        let block = invalid_empty_block(&genesis);
+        for block in [&genesis, &block] {
+            let info = BlockInfo::new(block, genesis.hash(), block.hash(), vec![], true);
+            assert_eq!(bincode::serialize(block).unwrap().len(), info.size_bytes);
+            assert_eq!(block.size(), info.size);
+        }
+    }
+
+    #[test]
+    fn weight_fraction_is_size_relative_to_max_size() {
+        assert_eq!(0.25, weight_fraction(250, 1_000));
+        assert_eq!(1.0, weight_fraction(1_000, 1_000));
+        assert_eq!(0.0, weight_fraction(0, 1_000));
+        assert_eq!(0.0, weight_fraction(1_000, 0));
+
+        let genesis = Block::genesis(Network::Main);
+        let info = BlockInfo::new(&genesis, genesis.hash(), genesis.hash(), vec![], true);
+        let max_size = Block::max_size(genesis.header().height);
+        assert_eq!(
+            genesis.size() as f64 / max_size as f64,
+            info.weight_fraction
+        );
+        assert!(info.weight_fraction > 0.0 && info.weight_fraction < 1.0);
+    }
+
+    #[test]
+    fn fields_missing_from_older_json_default_to_zero() {
+        let genesis = Block::genesis(Network::Main);
+        let info = BlockInfo::new(&genesis, genesis.hash(), genesis.hash(), vec![], true);
+        let mut json = serde_json::to_value(&info).unwrap();
+        let map = json.as_object_mut().unwrap();
+        for key in ["size_bytes", "weight_fraction", "num_transactions"] {
+            map.remove(key);
+        }
+
+        let decoded: BlockInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(0, decoded.size_bytes);
+        assert_eq!(0.0, decoded.weight_fraction);
+        assert_eq!(0, decoded.num_transactions);
+    }
+
+    #[tokio::test]
+    async fn num_transactions_counts_merged_transactions() {
+        let network = Network::Main;
+        let genesis = Block::genesis(network);
+        let info = BlockInfo::new(&genesis, genesis.hash(), genesis.hash(), vec![], true);
+        assert_eq!(1, info.num_transactions);
+
+        // the composer's coinbase transaction merged with a nop transaction
+        let timestamp = genesis.header().timestamp + Timestamp::hours(1);
+        let block =
+            fake_valid_block_proposal_successor_for_test(&genesis, timestamp, [7; 32], network)
+                .await;
+        let info = BlockInfo::new(&block, genesis.hash(), block.hash(), vec![], true);
+        assert_eq!(2, info.num_transactions);
+    }
+
+    #[test]
+    fn json_keys_are_stable() {
+        let genesis = Block::genesis(Network::Main);
+        let info = BlockInfo::new(&genesis, genesis.hash(), genesis.hash(), vec![], true);
+        let serde_json::Value::Object(map) = serde_json::to_value(&info).unwrap() else {
+            panic!("expected a JSON object");
+        };
+
+        let expected = [
+            "coinbase_amount",
+            "cumulative_proof_of_work",
+            "difficulty",
+            "digest",
+            "fee",
+            "guesser_digest",
+            "height",
+            "inputs",
+            "is_canonical",
+            "is_genesis",
+            "is_tip",
+            "nonce",
+            "num_inputs",
+            "num_outputs",
+            "num_public_announcements",
+            "num_transactions",
+            "outputs",
+            "prev_block_digest",
+            "sibling_blocks",
+            "size",
+            "size_bytes",
+            "timestamp",
+            "txid",
+            "weight_fraction",
+        ];
+        assert_eq!(expected.to_vec(), map.keys().sorted().collect_vec());
     }
 }
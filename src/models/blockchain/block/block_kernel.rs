@@ -1,15 +1,27 @@
 use get_size2::GetSize;
+use itertools::Itertools;
 use serde::Deserialize;
 use serde::Serialize;
 use strum::EnumCount;
+use tasm_lib::prelude::Digest;
+use tasm_lib::prelude::Tip5;
 use tasm_lib::twenty_first::math::b_field_element::BFieldElement;
 use tasm_lib::twenty_first::math::bfield_codec::BFieldCodec;
 
 use super::block_appendix::BlockAppendix;
 use super::block_body::BlockBody;
 use super::block_header::BlockHeader;
+use super::mutator_set_update::MutatorSetUpdate;
+use crate::models::blockchain::transaction::utxo::Coin;
+use crate::models::blockchain::transaction::utxo::Utxo;
+use crate::models::blockchain::type_scripts::time_lock::TimeLock;
 use crate::models::proof_abstractions::mast_hash::HasDiscriminant;
 use crate::models::proof_abstractions::mast_hash::MastHash;
+use crate::models::proof_abstractions::timestamp::Timestamp;
+use crate::models::state::wallet::address::hash_lock_key::HashLockKey;
+use crate::util_types::mutator_set::addition_record::AdditionRecord;
+use crate::util_types::mutator_set::commit;
+use crate::util_types::mutator_set::mutator_set_accumulator::MutatorSetAccumulator;
 
 /// The kernel of a block contains all data that is not proof data
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, BFieldCodec, GetSize)]
@@ -28,6 +40,78 @@ impl BlockKernel {
             appendix,
         }
     }
+
+    /// See [`Block::guesser_fee_utxos`](super::Block::guesser_fee_utxos).
+    pub(crate) fn guesser_fee_utxos(&self) -> Vec<Utxo> {
+        const MINER_REWARD_TIME_LOCK_PERIOD: Timestamp = Timestamp::years(3);
+
+        if self.header.height.is_genesis() {
+            return vec![];
+        }
+
+        let lock = self.header.guesser_digest;
+        let lock_script = HashLockKey::lock_script_from_after_image(lock);
+
+        let total_guesser_reward = self.body.transaction_kernel.fee;
+        let mut value_locked = total_guesser_reward;
+        value_locked.div_two();
+        let value_unlocked = total_guesser_reward.checked_sub(&value_locked).unwrap();
+
+        let coins = vec![
+            Coin::new_native_currency(value_locked),
+            TimeLock::until(self.header.timestamp + MINER_REWARD_TIME_LOCK_PERIOD),
+        ];
+        let locked_utxo = Utxo::new(lock_script.clone(), coins);
+        let unlocked_utxo = Utxo::new_native_currency(lock_script, value_unlocked);
+
+        vec![locked_utxo, unlocked_utxo]
+    }
+
+    /// See [`Block::guesser_fee_addition_records`](super::Block::guesser_fee_addition_records).
+    /// The `block_hash` must be the hash of this kernel, which the caller may
+    /// have cached.
+    pub(crate) fn guesser_fee_addition_records(&self, block_hash: Digest) -> Vec<AdditionRecord> {
+        self.guesser_fee_utxos()
+            .into_iter()
+            .map(|utxo| {
+                let item = Tip5::hash(&utxo);
+
+                // Adding the block hash to the mutator set here means that no
+                // composer can start proving before solving the PoW-race;
+                // production of future proofs is impossible as they depend on
+                // inputs hidden behind the veil of future PoW.
+                let sender_randomness = block_hash;
+                let receiver_digest = self.header.guesser_digest;
+
+                commit(item, sender_randomness, receiver_digest)
+            })
+            .collect_vec()
+    }
+
+    /// See [`Block::mutator_set_update`](super::Block::mutator_set_update).
+    pub(crate) fn mutator_set_update(&self, block_hash: Digest) -> MutatorSetUpdate {
+        let mut mutator_set_update = MutatorSetUpdate::new(
+            self.body.transaction_kernel.inputs.clone(),
+            self.body.transaction_kernel.outputs.clone(),
+        );
+
+        let extra_addition_records = self.guesser_fee_addition_records(block_hash);
+        mutator_set_update.additions.extend(extra_addition_records);
+        mutator_set_update
+    }
+
+    /// See [`Block::mutator_set_accumulator_after`](super::Block::mutator_set_accumulator_after).
+    pub(crate) fn mutator_set_accumulator_after(
+        &self,
+        block_hash: Digest,
+    ) -> MutatorSetAccumulator {
+        let mut msa = self.body.mutator_set_accumulator.clone();
+        let mutator_set_update =
+            MutatorSetUpdate::new(vec![], self.guesser_fee_addition_records(block_hash));
+        mutator_set_update.apply_to_accumulator(&mut msa)
+            .expect("mutator set update derived from guesser fees should be applicable to mutator set accumulator contained in body");
+        msa
+    }
 }
 
 #[derive(Debug, Copy, Clone, EnumCount)]
@@ -6,6 +6,7 @@
 //!  * A BlockHeight
 //!  * Genesis
 //!  * Tip
+//!  * A cumulative proof-of-work threshold
 //!
 //! Then call BlockSelector::to_digest() to obtain the block's Digest, if it
 //! exists.
@@ -21,6 +22,7 @@ use serde::Serialize;
 use thiserror::Error;
 
 use super::block_height::BlockHeight;
+use super::difficulty_control::ProofOfWork;
 use crate::models::state::GlobalState;
 use crate::twenty_first::error::TryFromHexDigestError;
 use crate::twenty_first::math::digest::Digest;
@@ -32,6 +34,10 @@ pub enum BlockSelector {
     Height(BlockHeight), // Identifies block by Height (count from genesis)
     Genesis,             // Indicates the genesis block
     Tip,                 // Indicates the latest canonical block
+
+    /// The lowest canonical block whose cumulative proof-of-work is at least
+    /// the given amount.
+    CumulativeWorkAtLeast(ProofOfWork),
 }
 
 /// BlockSelector can be written out as any of:
@@ -40,6 +46,7 @@ pub enum BlockSelector {
 ///  tip
 ///  height/<N>
 ///  digest/<hex>
+///  cumulative_work/<N>
 /// ```
 ///
 /// This is intended to be easy for humans to read and also input, ie suitable
@@ -51,6 +58,7 @@ impl std::fmt::Display for BlockSelector {
             Self::Height(h) => write!(f, "height/{}", h),
             Self::Genesis => write!(f, "genesis"),
             Self::Tip => write!(f, "tip"),
+            Self::CumulativeWorkAtLeast(pow) => write!(f, "cumulative_work/{}", pow),
         }
     }
 }
@@ -60,7 +68,7 @@ pub enum BlockSelectorParseError {
     #[error("Invalid selector {0}.  Try genesis or tip")]
     InvalidSelector(String),
 
-    #[error("Invalid pair selector {0}.  Try height/<N> or digest/<hex>")]
+    #[error("Invalid pair selector {0}.  Try height/<N>, digest/<hex> or cumulative_work/<N>")]
     InvalidPairSelector(String),
 
     #[error("Wrong selector length {0}.  (too many or too few '/')")]
@@ -71,6 +79,9 @@ pub enum BlockSelectorParseError {
 
     #[error("Bad Height")]
     BadHeight(#[from] ParseIntError),
+
+    #[error("Bad cumulative proof-of-work: {0}")]
+    BadProofOfWork(String),
 }
 
 impl FromStr for BlockSelector {
@@ -91,6 +102,12 @@ impl FromStr for BlockSelector {
             match parts[0] {
                 "digest" => Ok(Self::Digest(Digest::try_from_hex(parts[1])?)),
                 "height" => Ok(Self::Height(parts[1].parse::<u64>()?.into())),
+                "cumulative_work" => {
+                    let threshold = parts[1]
+                        .parse::<ProofOfWork>()
+                        .map_err(|e| BlockSelectorParseError::BadProofOfWork(e.to_string()))?;
+                    Ok(Self::CumulativeWorkAtLeast(threshold))
+                }
                 other => Err(BlockSelectorParseError::InvalidPairSelector(
                     other.to_string(),
                 )),
@@ -120,6 +137,97 @@ impl BlockSelector {
             }
             BlockSelector::Tip => Some(state.chain.light_state().hash()),
             BlockSelector::Genesis => Some(state.chain.archival_state().genesis_block().hash()),
+            BlockSelector::CumulativeWorkAtLeast(threshold) => {
+                Self::lowest_canonical_block_with_work(state, *threshold).await
+            }
+        }
+    }
+
+    /// Binary search over the canonical chain, whose cumulative proof-of-work
+    /// grows with height.
+    async fn lowest_canonical_block_with_work(
+        state: &GlobalState,
+        threshold: ProofOfWork,
+    ) -> Option<Digest> {
+        let tip_header = state.chain.light_state().header();
+        if tip_header.cumulative_proof_of_work < threshold {
+            return None;
+        }
+
+        let archival_state = state.chain.archival_state();
+        let block_mmr = archival_state.archival_block_mmr.ammr();
+        let mut low = 0;
+        let mut high: u64 = tip_header.height.into();
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let digest = block_mmr.try_get_leaf(middle).await?;
+            let header = archival_state.get_block_header(digest).await?;
+            if header.cumulative_proof_of_work >= threshold {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+
+        block_mmr.try_get_leaf(low).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_models::cli_args;
+    use crate::config_models::network::Network;
+    use crate::models::blockchain::block::Block;
+    use crate::models::state::wallet::wallet_entropy::WalletEntropy;
+    use crate::tests::shared::invalid_empty_block;
+    use crate::tests::shared::mock_genesis_global_state;
+
+    #[test]
+    fn cumulative_work_selector_round_trips() {
+        let selector = BlockSelector::CumulativeWorkAtLeast(ProofOfWork::new([7, 1, 0, 0, 0, 0]));
+        assert_eq!("cumulative_work/4294967303", selector.to_string());
+        assert_eq!(selector, selector.to_string().parse().unwrap());
+        assert!(matches!(
+            "cumulative_work/abc".parse::<BlockSelector>(),
+            Err(BlockSelectorParseError::BadProofOfWork(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn cumulative_work_selects_lowest_block_meeting_threshold() {
+        let network = Network::Main;
+        let mut global_state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let mut blocks = vec![Block::genesis(network)];
+        for _ in 0..5 {
+            let block = invalid_empty_block(blocks.last().unwrap());
+            global_state_lock.set_new_tip(block.clone()).await.unwrap();
+            blocks.push(block);
+        }
+
+        let state = global_state_lock.lock_guard().await;
+        let state = &*state;
+        let select = |threshold| async move {
+            BlockSelector::CumulativeWorkAtLeast(threshold)
+                .as_digest(state)
+                .await
+        };
+        for block in &blocks {
+            let work = block.header().cumulative_proof_of_work;
+            assert_eq!(Some(block.hash()), select(work).await);
+
+            // just above this block's work selects its successor, if any
+            let successor = blocks
+                .iter()
+                .find(|b| b.header().cumulative_proof_of_work > work)
+                .map(|b| b.hash());
+            assert_eq!(successor, select(work + [1]).await);
         }
     }
 }
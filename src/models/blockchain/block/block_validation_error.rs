@@ -24,7 +24,8 @@ pub(super) enum BlockValidationError {
     /// 0.f) Cumulative PoW was updated correctly
     #[error("block cumulative proof-of-work must be updated correctly")]
     CumulativeProofOfWork,
-    ///   0.g) Block timestamp is less than host-time (utc) + 5 minutes
+    ///   0.g) Block timestamp is less than host-time (utc) plus the allowed
+    ///      future drift, by default 5 minutes
     #[error("block must not be from the future")]
     FutureDating,
 
@@ -3,6 +3,7 @@ use std::fmt::Display;
 use std::ops::Add;
 use std::ops::Shr;
 use std::ops::ShrAssign;
+use std::str::FromStr;
 
 use anyhow::ensure;
 #[cfg(any(test, feature = "arbitrary-impls"))]
@@ -301,6 +302,24 @@ impl TryFrom<f64> for ProofOfWork {
     }
 }
 
+/// Parses the decimal representation produced by [`Display`].
+impl FromStr for ProofOfWork {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: BigUint = s.parse()?;
+        let digits = value.to_u32_digits();
+        ensure!(
+            digits.len() <= POW_NUM_LIMBS,
+            "proof-of-work value {s} exceeds the maximum"
+        );
+
+        let mut limbs = [0u32; POW_NUM_LIMBS];
+        limbs[..digits.len()].copy_from_slice(&digits);
+        Ok(Self(limbs))
+    }
+}
+
 impl PartialOrd for ProofOfWork {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
@@ -925,6 +944,18 @@ mod test {
         prop_assert!(calculated < ProofOfWork::MAXIMUM);
     }
 
+    #[proptest]
+    fn proof_of_work_display_round_trips(#[strategy(arb())] pow: ProofOfWork) {
+        prop_assert_eq!(pow, pow.to_string().parse::<ProofOfWork>().unwrap());
+    }
+
+    #[test]
+    fn too_large_proof_of_work_is_not_parsed() {
+        let too_large = BigUint::from(ProofOfWork::MAXIMUM) + 1u32;
+        assert!(too_large.to_string().parse::<ProofOfWork>().is_err());
+        assert!("-1".parse::<ProofOfWork>().is_err());
+    }
+
     #[test]
     fn test_sanity_max_pow_after_unit() {
         let init_cumpow = 100u64;
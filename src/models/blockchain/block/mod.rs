@@ -61,7 +61,6 @@ use crate::models::proof_abstractions::tasm::program::TritonVmProofJobOptions;
 use crate::models::proof_abstractions::timestamp::Timestamp;
 use crate::models::proof_abstractions::verifier::verify;
 use crate::models::proof_abstractions::SecretWitness;
-use crate::models::state::wallet::address::hash_lock_key::HashLockKey;
 use crate::models::state::wallet::address::ReceivingAddress;
 use crate::models::state::wallet::wallet_entropy::WalletEntropy;
 use crate::prelude::twenty_first;
@@ -102,6 +101,16 @@ pub(crate) const MINING_REWARD_TIME_LOCK_PERIOD: Timestamp = Timestamp::years(3)
 
 pub(crate) const INITIAL_BLOCK_SUBSIDY: NativeCurrencyAmount = NativeCurrencyAmount::coins(128);
 
+/// How far into the future, relative to the local clock, a block's timestamp
+/// may be by default.
+pub(crate) const FUTUREDATING_LIMIT: Timestamp = Timestamp::minutes(5);
+
+/// The largest coinbase a block at the given height may claim. Unlike
+/// [`Block::coinbase_amount`], this needs only the height, not the block.
+pub fn max_coinbase_for_height(height: BlockHeight) -> NativeCurrencyAmount {
+    Block::block_subsidy(height)
+}
+
 /// All blocks have proofs except the genesis block
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, BFieldCodec, GetSize, Default)]
 pub enum BlockProof {
@@ -374,11 +383,7 @@ impl Block {
     /// Includes the guesser-fee UTXOs which are not included by the
     /// `mutator_set_accumulator` field on the block body.
     pub fn mutator_set_accumulator_after(&self) -> MutatorSetAccumulator {
-        let mut msa = self.kernel.body.mutator_set_accumulator.clone();
-        let mutator_set_update = MutatorSetUpdate::new(vec![], self.guesser_fee_addition_records());
-        mutator_set_update.apply_to_accumulator(&mut msa)
-            .expect("mutator set update derived from guesser fees should be applicable to mutator set accumulator contained in body");
-        msa
+        self.kernel.mutator_set_accumulator_after(self.hash())
     }
 
     #[inline]
@@ -629,7 +634,13 @@ impl Block {
         }
     }
 
-    pub fn block_with_invalid_proof(&self) -> Self {
+    /// A copy of this block without its proof, for notifications and transfers
+    /// where the receiver needs only the header and body.
+    ///
+    /// The copy has the same hash as the original, since the proof is not part
+    /// of it, but it is unverifiable: [`Self::is_valid`] rejects it. It must not
+    /// be stored or relayed as if it were the original block.
+    pub fn without_proof(&self) -> Self {
         Self {
             digest: self.digest.clone(),
             kernel: self.kernel.clone(),
@@ -716,6 +727,20 @@ impl Block {
         previous_block: &Block,
         now: Timestamp,
         network: Network,
+    ) -> bool {
+        self.is_valid_with_future_drift(previous_block, now, network, FUTUREDATING_LIMIT)
+            .await
+    }
+
+    /// Like [`Self::is_valid`], but accepts blocks whose timestamp is up to
+    /// `allowed_future_drift` ahead of `now`, instead of
+    /// [`FUTUREDATING_LIMIT`].
+    pub(crate) async fn is_valid_with_future_drift(
+        &self,
+        previous_block: &Block,
+        now: Timestamp,
+        network: Network,
+        allowed_future_drift: Timestamp,
     ) -> bool {
         match self
             .is_valid_internal(
@@ -723,6 +748,7 @@ impl Block {
                 now,
                 Some(network.target_block_interval()),
                 Some(network.minimum_block_time()),
+                Some(allowed_future_drift),
             )
             .await
         {
@@ -737,8 +763,9 @@ impl Block {
     /// Verify a block.
     ///
     /// Like `is_valid` but also allows specifying a custom
-    /// `target_block_interval` and `minimum_block_time`. If `None` is passed,
-    /// these variables take the default values.
+    /// `target_block_interval`, `minimum_block_time`, and
+    /// `allowed_future_drift`. If `None` is passed, these variables take the
+    /// default values.
     ///
     /// Also, unlike `is_valid`, this function returns a `Result` type whose
     /// associated error type is a [`BlockValidationError`]. This error type gives
@@ -749,9 +776,8 @@ impl Block {
         now: Timestamp,
         target_block_interval: Option<Timestamp>,
         minimum_block_time: Option<Timestamp>,
+        allowed_future_drift: Option<Timestamp>,
     ) -> Result<(), BlockValidationError> {
-        const FUTUREDATING_LIMIT: Timestamp = Timestamp::minutes(5);
-
         // Note that there is a correspondence between the logic here and the
         // error types in `BlockValidationError`.
 
@@ -802,7 +828,7 @@ impl Block {
         }
 
         // 0.g)
-        let future_limit = now + FUTUREDATING_LIMIT;
+        let future_limit = now + allowed_future_drift.unwrap_or(FUTUREDATING_LIMIT);
         if self.kernel.header.timestamp >= future_limit {
             return Err(BlockValidationError::FutureDating);
         }
@@ -830,11 +856,7 @@ impl Block {
         }
 
         // 1.e)
-        if self.header().height < BLOCK_HEIGHT_HF_1 && self.size() > MAX_BLOCK_SIZE_BEFORE_HF_1 {
-            return Err(BlockValidationError::MaxSize);
-        }
-
-        if self.header().height >= BLOCK_HEIGHT_HF_1 && self.size() > MAX_BLOCK_SIZE_AFTER_HF_1 {
+        if !Self::size_is_allowed(self.size(), self.header().height) {
             return Err(BlockValidationError::MaxSize);
         }
 
@@ -885,11 +907,11 @@ impl Block {
             return Err(BlockValidationError::TransactionTimestamp);
         }
 
-        let block_subsidy = Self::block_subsidy(self.kernel.header.height);
+        let max_coinbase = max_coinbase_for_height(self.kernel.header.height);
         let coinbase = self.kernel.body.transaction_kernel.coinbase;
         if let Some(coinbase) = coinbase {
             // 2.f)
-            if coinbase > block_subsidy {
+            if coinbase > max_coinbase {
                 return Err(BlockValidationError::CoinbaseTooBig);
             }
 
@@ -1013,8 +1035,53 @@ impl Block {
         self.encode().len()
     }
 
+    /// The maximum size, in number of BFieldElements, of a block at the given
+    /// height.
+    pub(crate) fn max_size(height: BlockHeight) -> usize {
+        if height < BLOCK_HEIGHT_HF_1 {
+            MAX_BLOCK_SIZE_BEFORE_HF_1
+        } else {
+            MAX_BLOCK_SIZE_AFTER_HF_1
+        }
+    }
+
+    /// True iff a block of `size` BFieldElements, as returned by
+    /// [`Self::size`], respects the size limit at the given height. Blocks at
+    /// exactly the limit are allowed.
+    fn size_is_allowed(size: usize, height: BlockHeight) -> bool {
+        size <= Self::max_size(height)
+    }
+
+    /// Size in bytes of the block's bincode serialization, as stored and sent
+    /// over the network. Not to be compared against the block size limit,
+    /// which is in number of BFieldElements; see [`Self::size`].
+    pub(crate) fn size_in_bytes(&self) -> usize {
+        bincode::serialized_size(self).expect("block must be serializable") as usize
+    }
+
+    /// Number of transactions merged into this block's single transaction
+    /// kernel, as far as the block records it.
+    ///
+    /// A kernel records only whether it is the result of a merger, not of how
+    /// many transactions, so this is a lower bound: 1 for an unmerged kernel,
+    /// and 2 for a merged one, i.e. the composer's coinbase transaction and at
+    /// least one other.
+    pub(crate) fn num_transactions(&self) -> usize {
+        if self.body().transaction_kernel.merge_bit {
+            2
+        } else {
+            1
+        }
+    }
+
     /// The amount rewarded to the guesser who finds a valid nonce for this
     /// block.
+    ///
+    /// The block subsidy is split by the composer: the composer's share goes to
+    /// the coinbase outputs, while the guesser's share is left as transaction
+    /// fee. So the whole transaction fee, including the fees of the merged
+    /// transactions, is the guesser's. It is paid out by
+    /// [`Self::guesser_fee_utxos`], except for the genesis block.
     pub(crate) fn total_guesser_reward(&self) -> NativeCurrencyAmount {
         self.body().transaction_kernel.fee
     }
@@ -1025,28 +1092,7 @@ impl Block {
     ///
     /// The genesis block does not have a guesser reward.
     pub(crate) fn guesser_fee_utxos(&self) -> Vec<Utxo> {
-        const MINER_REWARD_TIME_LOCK_PERIOD: Timestamp = Timestamp::years(3);
-
-        if self.header().height.is_genesis() {
-            return vec![];
-        }
-
-        let lock = self.header().guesser_digest;
-        let lock_script = HashLockKey::lock_script_from_after_image(lock);
-
-        let total_guesser_reward = self.total_guesser_reward();
-        let mut value_locked = total_guesser_reward;
-        value_locked.div_two();
-        let value_unlocked = total_guesser_reward.checked_sub(&value_locked).unwrap();
-
-        let coins = vec![
-            Coin::new_native_currency(value_locked),
-            TimeLock::until(self.header().timestamp + MINER_REWARD_TIME_LOCK_PERIOD),
-        ];
-        let locked_utxo = Utxo::new(lock_script.clone(), coins);
-        let unlocked_utxo = Utxo::new_native_currency(lock_script, value_unlocked);
-
-        vec![locked_utxo, unlocked_utxo]
+        self.kernel.guesser_fee_utxos()
     }
 
     /// Compute the addition records that correspond to the UTXOs generated for
@@ -1054,35 +1100,14 @@ impl Block {
     ///
     /// The genesis block does not have this addition record.
     pub(crate) fn guesser_fee_addition_records(&self) -> Vec<AdditionRecord> {
-        self.guesser_fee_utxos()
-            .into_iter()
-            .map(|utxo| {
-                let item = Tip5::hash(&utxo);
-
-                // Adding the block hash to the mutator set here means that no
-                // composer can start proving before solving the PoW-race;
-                // production of future proofs is impossible as they depend on
-                // inputs hidden behind the veil of future PoW.
-                let sender_randomness = self.hash();
-                let receiver_digest = self.header().guesser_digest;
-
-                commit(item, sender_randomness, receiver_digest)
-            })
-            .collect_vec()
+        self.kernel.guesser_fee_addition_records(self.hash())
     }
 
     /// Return the mutator set update corresponding to this block, which sends
     /// the mutator set accumulator after the predecessor to the mutator set
     /// accumulator after self.
     pub(crate) fn mutator_set_update(&self) -> MutatorSetUpdate {
-        let mut mutator_set_update = MutatorSetUpdate::new(
-            self.body().transaction_kernel.inputs.clone(),
-            self.body().transaction_kernel.outputs.clone(),
-        );
-
-        let extra_addition_records = self.guesser_fee_addition_records();
-        mutator_set_update.additions.extend(extra_addition_records);
-        mutator_set_update
+        self.kernel.mutator_set_update(self.hash())
     }
 }
 
@@ -1107,6 +1132,7 @@ pub(crate) mod block_tests {
     use crate::mine_loop::composer_parameters::ComposerParameters;
     use crate::mine_loop::mine_loop_tests::make_coinbase_transaction_from_state;
     use crate::mine_loop::prepare_coinbase_transaction_stateless;
+    use crate::models::blockchain::block::block_height::BLOCKS_PER_GENERATION;
     use crate::models::blockchain::transaction::primitive_witness::PrimitiveWitness;
     use crate::models::blockchain::transaction::TransactionProof;
     use crate::models::blockchain::type_scripts::native_currency::NativeCurrency;
@@ -1114,11 +1140,13 @@ pub(crate) mod block_tests {
     use crate::models::state::mempool::TransactionOrigin;
     use crate::models::state::tx_creation_config::TxCreationConfig;
     use crate::models::state::tx_proving_capability::TxProvingCapability;
+    use crate::models::state::wallet::address::hash_lock_key::HashLockKey;
     use crate::models::state::wallet::address::KeyType;
     use crate::models::state::wallet::transaction_output::TxOutput;
     use crate::models::state::wallet::wallet_entropy::WalletEntropy;
     use crate::tests::shared::fake_valid_successor_for_tests;
     use crate::tests::shared::invalid_block_with_transaction;
+    use crate::tests::shared::invalid_empty_block_with_timestamp;
     use crate::tests::shared::make_mock_block;
     use crate::tests::shared::make_mock_transaction;
     use crate::tests::shared::mock_genesis_global_state;
@@ -1126,6 +1154,49 @@ pub(crate) mod block_tests {
 
     pub(crate) const PREMINE_MAX_SIZE: NativeCurrencyAmount = NativeCurrencyAmount::coins(831488);
 
+    #[test]
+    fn block_at_max_size_is_allowed_and_one_over_is_not() {
+        let before_hf_1 = BlockHeight::from(1u64);
+        let at_hf_1 = BLOCK_HEIGHT_HF_1;
+        for (height, max_size) in [
+            (before_hf_1, MAX_BLOCK_SIZE_BEFORE_HF_1),
+            (at_hf_1, MAX_BLOCK_SIZE_AFTER_HF_1),
+        ] {
+            assert_eq!(max_size, Block::max_size(height));
+            assert!(Block::size_is_allowed(max_size, height));
+            assert!(!Block::size_is_allowed(max_size + 1, height));
+        }
+
+        let genesis = Block::genesis(Network::Main);
+        assert!(Block::size_is_allowed(
+            genesis.size(),
+            genesis.header().height
+        ));
+    }
+
+    #[test]
+    fn block_without_proof_round_trips_header_and_body() {
+        let genesis = Block::genesis(Network::Main);
+        let block = invalid_empty_block_with_timestamp(&genesis, genesis.header().timestamp);
+        let block = Block::new(
+            *block.header(),
+            block.body().clone(),
+            block.appendix().clone(),
+            BlockProof::SingleProof(Proof(bfe_vec![7; 100])),
+        );
+
+        let stripped = block.without_proof();
+        assert_eq!(BlockProof::Invalid, stripped.proof);
+        assert_eq!(block.hash(), stripped.hash());
+
+        let decoded: Block = bincode::deserialize(&bincode::serialize(&stripped).unwrap()).unwrap();
+        assert_eq!(block.header(), decoded.header());
+        assert_eq!(block.body(), decoded.body());
+        assert_eq!(block.kernel, decoded.kernel);
+        assert_eq!(block.hash(), decoded.hash());
+        assert!(decoded.size() < block.size());
+    }
+
     #[test]
     fn all_genesis_blocks_have_unique_mutator_set_hashes() {
         let mutator_set_hash = |network| {
@@ -1174,6 +1245,30 @@ pub(crate) mod block_tests {
         Block::block_subsidy(random_height.into());
     }
 
+    #[test]
+    fn max_coinbase_halves_every_generation() {
+        let last_of_generation_0 = BlockHeight::from(BLOCKS_PER_GENERATION - 1);
+        let first_of_generation_1 = BlockHeight::from(BLOCKS_PER_GENERATION);
+        let first_of_generation_2 = BlockHeight::from(2 * BLOCKS_PER_GENERATION);
+        for (height, max_coinbase) in [
+            (BlockHeight::genesis(), NativeCurrencyAmount::coins(128)),
+            (1u64.into(), NativeCurrencyAmount::coins(128)),
+            (last_of_generation_0, NativeCurrencyAmount::coins(128)),
+            (first_of_generation_1, NativeCurrencyAmount::coins(64)),
+            (first_of_generation_2, NativeCurrencyAmount::coins(32)),
+        ] {
+            assert_eq!(
+                max_coinbase,
+                max_coinbase_for_height(height),
+                "height {height}"
+            );
+            assert_eq!(
+                Block::block_subsidy(height),
+                max_coinbase_for_height(height)
+            );
+        }
+    }
+
     #[test]
     fn block_subsidy_generation_0() {
         let block_height_generation_0 = 199u64.into();
@@ -1452,6 +1547,28 @@ pub(crate) mod block_tests {
         use crate::models::state::wallet::address::KeyType;
         use crate::tests::shared::fake_valid_successor_for_tests;
 
+        #[tokio::test]
+        async fn larger_future_drift_accepts_block_default_rejects() {
+            let network = Network::Main;
+            let genesis_block = Block::genesis(network);
+            let block_time = genesis_block.header().timestamp + Timestamp::hours(1);
+            let mut rng: StdRng = SeedableRng::seed_from_u64(2225550002);
+            let block1 =
+                fake_valid_successor_for_tests(&genesis_block, block_time, rng.random(), network)
+                    .await;
+            assert!(block1.is_valid(&genesis_block, block_time, network).await);
+
+            // the local clock lags 10 minutes behind the block's
+            let now = block_time - Timestamp::minutes(10);
+            assert!(!block1.is_valid(&genesis_block, now, network).await);
+            let allowed_future_drift = Timestamp::minutes(15);
+            assert!(
+                block1
+                    .is_valid_with_future_drift(&genesis_block, now, network, allowed_future_drift)
+                    .await
+            );
+        }
+
         #[traced_test]
         #[tokio::test]
         async fn blocks_with_0_to_10_inputs_and_successors_are_valid() {
@@ -2029,7 +2146,7 @@ pub(crate) mod block_tests {
             .unwrap();
 
             let block_is_valid = block
-                .is_valid_internal(blocks.last().unwrap(), now, None, None)
+                .is_valid_internal(blocks.last().unwrap(), now, None, None, None)
                 .await;
             println!("block is valid? {:?}", block_is_valid.map(|_| "yes"));
             println!();
@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use crate::job_queue::triton_vm::TritonVmJobQueue;
 use crate::models::blockchain::block::mutator_set_update::MutatorSetUpdate;
+use crate::models::blockchain::type_scripts::native_currency_amount::NativeCurrencyAmount;
 use crate::models::proof_abstractions::mast_hash::MastHash;
 use crate::models::proof_abstractions::tasm::program::ConsensusProgram;
 use crate::models::proof_abstractions::tasm::program::TritonVmProofJobOptions;
@@ -265,6 +266,13 @@ impl Transaction {
         BigRational::new_raw(transaction_fee.into(), transaction_size)
     }
 
+    /// The fee paid per byte of serialized transaction, rounded towards zero.
+    /// Ranks like [`Self::fee_density`].
+    pub fn fee_per_byte(&self) -> NativeCurrencyAmount {
+        let nau_per_byte = self.fee_density().to_integer();
+        NativeCurrencyAmount::from_nau(nau_per_byte.try_into().unwrap_or(i128::MAX))
+    }
+
     /// Determine if the transaction can be validly confirmed if the block has
     /// the given mutator set accumulator. Specifically, test whether the
     /// removal records determine indices absent in the mutator set sliding
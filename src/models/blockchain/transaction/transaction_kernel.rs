@@ -110,6 +110,15 @@ pub(crate) enum TransactionConfirmabilityError {
 }
 
 impl TransactionKernel {
+    /// Check whether any public announcement starts with the given prefix,
+    /// e.g. a wallet's notification tag. An empty prefix matches any kernel
+    /// with at least one public announcement.
+    pub fn has_public_announcement_with_prefix(&self, prefix: &[BFieldElement]) -> bool {
+        self.public_announcements
+            .iter()
+            .any(|announcement| announcement.message.starts_with(prefix))
+    }
+
     pub(crate) fn is_confirmable_relative_to(
         &self,
         mutator_set_accumulator: &MutatorSetAccumulator,
@@ -421,6 +430,7 @@ pub mod transaction_kernel_tests {
     use rand::Rng;
     use rand::RngCore;
     use rand::SeedableRng;
+    use tasm_lib::triton_vm::prelude::bfe_vec;
 
     use super::*;
     use crate::models::blockchain::block::mutator_set_update::MutatorSetUpdate;
@@ -542,6 +552,26 @@ pub mod transaction_kernel_tests {
         );
     }
 
+    #[test]
+    fn public_announcement_prefix_filter() {
+        let tag = bfe_vec![7, 8];
+        let matching = PublicAnnouncement::new([tag.clone(), bfe_vec![1, 2, 3]].concat());
+        let other = PublicAnnouncement::new(bfe_vec![7, 9, 1]);
+        let kernel = pseudorandom_transaction_kernel(random(), 0, 0, 0);
+
+        let without_match = TransactionKernelModifier::default()
+            .public_announcements(vec![other.clone()])
+            .clone_modify(&kernel);
+        let with_match = TransactionKernelModifier::default()
+            .public_announcements(vec![other, matching])
+            .clone_modify(&kernel);
+
+        assert!(with_match.has_public_announcement_with_prefix(&tag));
+        assert!(!without_match.has_public_announcement_with_prefix(&tag));
+        assert!(without_match.has_public_announcement_with_prefix(&[]));
+        assert!(!kernel.has_public_announcement_with_prefix(&[]));
+    }
+
     #[test]
     pub fn decode_public_announcement() {
         let pubscript = random_public_announcement();
@@ -23,6 +23,7 @@ use serde::Serialize;
 use tasm_lib::structure::tasm_object::TasmObject;
 use tasm_lib::triton_vm::prelude::LabelledInstruction;
 use tasm_lib::twenty_first::math::bfield_codec::BFieldCodec;
+use thiserror::Error;
 
 use super::native_currency::NativeCurrency;
 use crate::models::blockchain::transaction::utxo::Coin;
@@ -451,7 +452,67 @@ impl TryFrom<f64> for NativeCurrencyAmount {
     }
 }
 
+/// Reasons for rejecting a user-supplied decimal amount of coins, see
+/// [`NativeCurrencyAmount::coins_from_str_checked`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum AmountParseError {
+    #[error("empty amount")]
+    Empty,
+
+    #[error("negative amount not allowed")]
+    Negative,
+
+    #[error("invalid character '{0}' in amount")]
+    InvalidCharacter(char),
+
+    #[error("more than one decimal point in amount")]
+    MultipleDecimalPoints,
+
+    #[error("too many decimal places: {found} (at most {max} allowed)")]
+    TooManyDecimalPlaces { found: usize, max: usize },
+
+    #[error("amount too large: {0}")]
+    TooLarge(String),
+}
+
 impl NativeCurrencyAmount {
+    /// The number of decimal places of a coin amount that can be represented
+    /// exactly. One nau is 2.5 * 10^-31 coins, which takes 32 decimal places.
+    pub const MAX_DECIMAL_PLACES: usize = 32;
+
+    /// Like [`Self::coins_from_str`], but intended for untrusted input such as
+    /// API requests: rejects negative amounts and amounts that cannot be
+    /// represented exactly, and reports precisely what is wrong with the input.
+    pub fn coins_from_str_checked(s: &str) -> Result<Self, AmountParseError> {
+        if s.is_empty() {
+            return Err(AmountParseError::Empty);
+        }
+        if s.starts_with('-') {
+            return Err(AmountParseError::Negative);
+        }
+        if let Some(c) = s.chars().find(|c| !c.is_ascii_digit() && *c != '.') {
+            return Err(AmountParseError::InvalidCharacter(c));
+        }
+        if !s.chars().any(|c| c.is_ascii_digit()) {
+            return Err(AmountParseError::Empty);
+        }
+
+        let mut parts = s.split('.');
+        let _integer_part = parts.next();
+        let fractional_part = parts.next().unwrap_or_default();
+        if parts.next().is_some() {
+            return Err(AmountParseError::MultipleDecimalPoints);
+        }
+        if fractional_part.len() > Self::MAX_DECIMAL_PLACES {
+            return Err(AmountParseError::TooManyDecimalPlaces {
+                found: fractional_part.len(),
+                max: Self::MAX_DECIMAL_PLACES,
+            });
+        }
+
+        Self::coins_from_str(s).map_err(|e| AmountParseError::TooLarge(e.to_string()))
+    }
+
     /// Convert a decimal string representation of a not necessarily integral
     /// amount of native currency into a `NativeCurrencyAmount` object.
     pub fn coins_from_str(s: &str) -> Result<Self, anyhow::Error> {
@@ -510,6 +571,86 @@ impl Display for NativeCurrencyAmount {
     }
 }
 
+/// Serde representation of a [`NativeCurrencyAmount`] for API responses:
+///
+/// ```text
+/// { "display": "0.1", "nau": "400000000000000000000000000000" }
+/// ```
+///
+/// `display` is the lossless decimal amount of coins without trailing zeros.
+/// `nau` is the exact amount in nau, written as a string because it does not
+/// fit in a JSON number. Only `nau` is read when deserializing.
+///
+/// Use as `#[serde(with = "native_currency_amount::api_json")]`.
+pub mod api_json {
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serialize;
+    use serde::Serializer;
+
+    use super::NativeCurrencyAmount;
+
+    #[derive(Serialize, Deserialize)]
+    struct ApiAmount {
+        display: String,
+        nau: String,
+    }
+
+    pub fn serialize<S: Serializer>(
+        amount: &NativeCurrencyAmount,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let lossless = amount.display_lossless();
+        let display = lossless.trim_end_matches('0').trim_end_matches('.');
+        ApiAmount {
+            display: display.to_string(),
+            nau: amount.to_nau().to_string(),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<NativeCurrencyAmount, D::Error> {
+        let api_amount = ApiAmount::deserialize(deserializer)?;
+        let nau = api_amount
+            .nau
+            .parse::<i128>()
+            .map_err(serde::de::Error::custom)?;
+        Ok(NativeCurrencyAmount::from_nau(nau))
+    }
+
+    /// Like [`api_json`](super::api_json), for optional amounts. `None` is
+    /// written as `null`.
+    ///
+    /// Use as `#[serde(with = "native_currency_amount::api_json::option")]`.
+    pub mod option {
+        use serde::Deserialize;
+        use serde::Deserializer;
+        use serde::Serialize;
+        use serde::Serializer;
+
+        use super::NativeCurrencyAmount;
+
+        #[derive(Serialize, Deserialize)]
+        struct Wrapper(#[serde(with = "super")] NativeCurrencyAmount);
+
+        pub fn serialize<S: Serializer>(
+            amount: &Option<NativeCurrencyAmount>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            amount.map(Wrapper).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<NativeCurrencyAmount>, D::Error> {
+            let wrapper = Option::<Wrapper>::deserialize(deserializer)?;
+            Ok(wrapper.map(|Wrapper(amount)| amount))
+        }
+    }
+}
+
 #[cfg(any(test, feature = "arbitrary-impls"))]
 pub mod neptune_arbitrary {
     use arbitrary::Arbitrary;
@@ -561,6 +702,7 @@ pub mod neptune_arbitrary {
 #[cfg(test)]
 pub(crate) mod test {
     use std::cmp::max;
+    use std::collections::HashSet;
 
     use arbitrary::Arbitrary;
     use arbitrary::Unstructured;
@@ -1035,4 +1177,104 @@ pub(crate) mod test {
             prop_assert!(difference < difference_threshold);
         }
     }
+
+    #[test]
+    fn api_json_shape() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Wrapper {
+            #[serde(with = "api_json")]
+            amount: NativeCurrencyAmount,
+        }
+
+        let amount = NativeCurrencyAmount::coins_from_str("0.1").unwrap();
+        let json = serde_json::to_value(Wrapper { amount }).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "amount": {
+                    "display": "0.1",
+                    "nau": "400000000000000000000000000000",
+                }
+            }),
+            json
+        );
+
+        let wrapper: Wrapper = serde_json::from_value(json).unwrap();
+        assert_eq!(amount, wrapper.amount);
+
+        let json = serde_json::to_value(Wrapper {
+            amount: NativeCurrencyAmount::coins(2),
+        })
+        .unwrap();
+        assert_eq!("2", json["amount"]["display"]);
+        assert_eq!("8000000000000000000000000000000", json["amount"]["nau"]);
+
+        let json = serde_json::to_value(Wrapper {
+            amount: NativeCurrencyAmount::zero(),
+        })
+        .unwrap();
+        assert_eq!("0", json["amount"]["display"]);
+        assert_eq!("0", json["amount"]["nau"]);
+    }
+
+    #[test]
+    fn coins_from_str_checked_accepts_valid_amounts() {
+        for (input, expected) in [
+            ("1", NativeCurrencyAmount::coins(1)),
+            ("0.1", NativeCurrencyAmount::coins_from_str("0.1").unwrap()),
+            (".5", NativeCurrencyAmount::coins_from_str("0.5").unwrap()),
+            ("42000000", NativeCurrencyAmount::coins(42_000_000)),
+        ] {
+            assert_eq!(
+                expected,
+                NativeCurrencyAmount::coins_from_str_checked(input).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn coins_from_str_checked_accepts_one_nau() {
+        let one_nau = format!("0.{}25", "0".repeat(30));
+        assert_eq!(
+            NativeCurrencyAmount::from_nau(1),
+            NativeCurrencyAmount::coins_from_str_checked(&one_nau).unwrap()
+        );
+
+        let three_nau = format!("0.{}75", "0".repeat(30));
+        assert_eq!(
+            NativeCurrencyAmount::from_nau(3),
+            NativeCurrencyAmount::coins_from_str_checked(&three_nau).unwrap()
+        );
+    }
+
+    #[test]
+    fn coins_from_str_checked_reports_precise_errors() {
+        let too_many_decimals = format!("0.{}", "1".repeat(33));
+        let cases = [
+            ("", AmountParseError::Empty),
+            (".", AmountParseError::Empty),
+            ("-1", AmountParseError::Negative),
+            ("1,5", AmountParseError::InvalidCharacter(',')),
+            (" 1", AmountParseError::InvalidCharacter(' ')),
+            ("1e5", AmountParseError::InvalidCharacter('e')),
+            ("1.2.3", AmountParseError::MultipleDecimalPoints),
+            (
+                too_many_decimals.as_str(),
+                AmountParseError::TooManyDecimalPlaces { found: 33, max: 32 },
+            ),
+        ];
+
+        let mut messages = HashSet::new();
+        for (input, expected) in cases {
+            let error = NativeCurrencyAmount::coins_from_str_checked(input).unwrap_err();
+            assert_eq!(expected, error, "input: {input:?}");
+            messages.insert(error.to_string());
+        }
+
+        let too_large = NativeCurrencyAmount::coins_from_str_checked("42000001").unwrap_err();
+        assert!(matches!(too_large, AmountParseError::TooLarge(_)));
+        messages.insert(too_large.to_string());
+
+        // the 8 distinct errors above yield 8 distinct messages
+        assert_eq!(8, messages.len());
+    }
 }
@@ -1,6 +1,8 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use num_traits::CheckedSub;
+use num_traits::Zero;
 use serde::Deserialize;
 use serde::Serialize;
 use tasm_lib::triton_vm::prelude::Digest;
@@ -12,6 +14,7 @@ use super::blockchain::block::Block;
 use super::blockchain::transaction::Transaction;
 use super::blockchain::type_scripts::native_currency_amount::NativeCurrencyAmount;
 use super::peer::transaction_notification::TransactionNotification;
+use super::peer::DisconnectReason;
 use super::proof_abstractions::mast_hash::MastHash;
 use super::state::wallet::expected_utxo::ExpectedUtxo;
 use super::state::wallet::monitored_utxo::MonitoredUtxo;
@@ -91,20 +94,76 @@ pub struct MainToPeerTaskBatchBlockRequest {
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub(crate) struct BlockProposalNotification {
     pub(crate) body_mast_hash: Digest,
+
+    /// The guesser's portion of the block reward: the whole transaction fee.
     pub(crate) guesser_fee: NativeCurrencyAmount,
+
+    /// The composer's portion of the block reward: the part of the coinbase
+    /// not left as fee. As the fees of merged transactions also go to the
+    /// guesser, this is a lower bound if the composer merged transactions
+    /// into the block.
+    pub(crate) composer_fee: NativeCurrencyAmount,
     pub(crate) height: BlockHeight,
 }
 
 impl From<&Block> for BlockProposalNotification {
     fn from(value: &Block) -> Self {
+        let guesser_fee = value.body().transaction_kernel.fee;
+        let composer_fee = value
+            .coinbase_amount()
+            .checked_sub(&guesser_fee)
+            .unwrap_or_else(NativeCurrencyAmount::zero);
         Self {
             body_mast_hash: value.body().mast_hash(),
-            guesser_fee: value.body().transaction_kernel.fee,
+            guesser_fee,
+            composer_fee,
             height: value.header().height,
         }
     }
 }
 
+/// The first version of [`BlockProposalNotification`], without the composer's
+/// portion of the block reward. Still accepted from peers that predate the
+/// split.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub(crate) struct BlockProposalNotificationV1 {
+    pub(crate) body_mast_hash: Digest,
+    pub(crate) guesser_fee: NativeCurrencyAmount,
+    pub(crate) height: BlockHeight,
+}
+
+impl From<BlockProposalNotification> for BlockProposalNotificationV1 {
+    fn from(value: BlockProposalNotification) -> Self {
+        let BlockProposalNotification {
+            body_mast_hash,
+            guesser_fee,
+            height,
+            ..
+        } = value;
+        Self {
+            body_mast_hash,
+            guesser_fee,
+            height,
+        }
+    }
+}
+
+impl From<&Block> for BlockProposalNotificationV1 {
+    fn from(value: &Block) -> Self {
+        BlockProposalNotification::from(value).into()
+    }
+}
+
+impl BlockProposalNotification {
+    /// Returns true iff `block` is the proposal this notification announced.
+    /// Used to reject a proposal from a peer that does not match the
+    /// notification the same peer sent earlier.
+    #[cfg_attr(not(test), expect(dead_code))]
+    pub(crate) fn matches(&self, block: &Block) -> bool {
+        *self == Self::from(block)
+    }
+}
+
 #[derive(Clone, Debug, strum::Display)]
 pub(crate) enum MainToPeerTask {
     Block(Box<Block>),
@@ -123,8 +182,8 @@ pub(crate) enum MainToPeerTask {
     /// Publish knowledge of a transaction
     TransactionNotification(TransactionNotification),
 
-    /// Disconnect from a specific peer
-    Disconnect(SocketAddr),
+    /// Disconnect from a specific peer, for the given reason
+    Disconnect(SocketAddr, DisconnectReason),
 
     /// Disconnect from all peers
     DisconnectAll(),
@@ -141,7 +200,7 @@ impl MainToPeerTask {
                 "make specific peer discovery req"
             }
             MainToPeerTask::TransactionNotification(_) => "transaction notification",
-            MainToPeerTask::Disconnect(_) => "disconnect",
+            MainToPeerTask::Disconnect(_, reason) => return format!("disconnect ({reason})"),
             MainToPeerTask::DisconnectAll() => "disconnect all",
             MainToPeerTask::BlockProposalNotification(_) => "block proposal notification",
         }
@@ -160,6 +219,9 @@ pub(crate) enum PeerTaskToMain {
         /// The MMR *after* adding the tip hash, so not the one contained in the
         /// tip, but in its child.
         claimed_block_mmra: MmrAccumulator,
+
+        /// Discovery distance of the peer.
+        distance: u8,
     },
     RemovePeerMaxBlockHeight(SocketAddr),
 
@@ -169,6 +231,9 @@ pub(crate) enum PeerTaskToMain {
     Transaction(Box<PeerTaskToMainTransaction>),
     BlockProposal(Box<Block>),
     DisconnectFromLongestLivedPeer,
+
+    /// The peer's standing fell below the ban threshold.
+    BanPeer(SocketAddr),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -187,6 +252,7 @@ impl PeerTaskToMain {
             PeerTaskToMain::Transaction(_) => "transaction",
             PeerTaskToMain::BlockProposal(_) => "block proposal",
             PeerTaskToMain::DisconnectFromLongestLivedPeer => "disconnect from longest lived peer",
+            PeerTaskToMain::BanPeer(_) => "ban peer",
         }
         .to_string()
     }
@@ -217,3 +283,76 @@ pub enum RPCServerToMain {
     PauseMiner,
     RestartMiner,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_models::network::Network;
+    use crate::models::state::wallet::wallet_entropy::WalletEntropy;
+    use crate::tests::shared::invalid_empty_block;
+    use crate::tests::shared::make_mock_block_guesser_preimage_and_guesser_fraction;
+
+    #[test]
+    fn block_proposal_notification_matches_announced_block() {
+        let genesis = Block::genesis(Network::Main);
+        let block1 = invalid_empty_block(&genesis);
+        let notification = BlockProposalNotification::from(&block1);
+        assert!(notification.matches(&block1));
+    }
+
+    #[test]
+    fn block_proposal_notification_does_not_match_other_block() {
+        let genesis = Block::genesis(Network::Main);
+        let block1 = invalid_empty_block(&genesis);
+        let block2 = invalid_empty_block(&block1);
+        let notification = BlockProposalNotification::from(&block1);
+        assert!(!notification.matches(&block2));
+        assert!(!notification.matches(&genesis));
+
+        // a single differing field suffices for a mismatch
+        let wrong_fee = BlockProposalNotification {
+            guesser_fee: NativeCurrencyAmount::coins(1),
+            ..notification
+        };
+        assert!(!wrong_fee.matches(&block1));
+        let wrong_composer_fee = BlockProposalNotification {
+            composer_fee: notification.composer_fee + NativeCurrencyAmount::coins(1),
+            ..notification
+        };
+        assert!(!wrong_composer_fee.matches(&block1));
+        let wrong_height = BlockProposalNotification {
+            height: notification.height.next(),
+            ..notification
+        };
+        assert!(!wrong_height.matches(&block1));
+    }
+
+    #[tokio::test]
+    async fn guesser_and_composer_fee_sum_to_block_reward() {
+        let network = Network::Main;
+        let genesis = Block::genesis(network);
+        let composer_key = WalletEntropy::new_random().nth_generation_spending_key_for_tests(0);
+        let (block, composer_utxos) = make_mock_block_guesser_preimage_and_guesser_fraction(
+            &genesis,
+            None,
+            composer_key,
+            [7; 32],
+            0.25,
+            Digest::default(),
+            network,
+        )
+        .await;
+
+        let notification = BlockProposalNotification::from(&block);
+        let composer_share: NativeCurrencyAmount = composer_utxos
+            .iter()
+            .map(|expected_utxo| expected_utxo.utxo.get_native_currency_amount())
+            .sum();
+        assert_eq!(block.total_guesser_reward(), notification.guesser_fee);
+        assert_eq!(composer_share, notification.composer_fee);
+        assert_eq!(
+            block.coinbase_amount(),
+            notification.guesser_fee + notification.composer_fee
+        );
+    }
+}
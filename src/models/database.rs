@@ -108,6 +108,8 @@ pub enum BlockIndexKey {
     // Tip-hash could also be fetched from archival block MMR instead. Maybe
     // this key is superfluous?
     BlockTipDigest, // points to block digest of most canonical block known
+
+    PrunedBelow, // points to height below which block bodies are pruned
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -117,6 +119,7 @@ pub enum BlockIndexValue {
     Height(Vec<Digest>),
     LastFile(LastFileRecord),
     BlockTipDigest(Digest),
+    PrunedBelow(BlockHeight),
 }
 
 impl BlockIndexValue {
@@ -154,6 +157,13 @@ impl BlockIndexValue {
             _ => panic!("Requested BlockTipDigest, found {:?}", self),
         }
     }
+
+    pub fn as_pruned_below(&self) -> BlockHeight {
+        match self {
+            BlockIndexValue::PrunedBelow(height) => height.to_owned(),
+            _ => panic!("Requested PrunedBelow, found {:?}", self),
+        }
+    }
 }
 
 #[derive(Clone)]
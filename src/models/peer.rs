@@ -38,6 +38,7 @@ use super::blockchain::block::difficulty_control::Difficulty;
 use super::blockchain::block::difficulty_control::ProofOfWork;
 use super::blockchain::block::Block;
 use super::channel::BlockProposalNotification;
+use super::channel::BlockProposalNotificationV1;
 use super::proof_abstractions::timestamp::Timestamp;
 use super::state::transaction_kernel_id::TransactionKernelId;
 use crate::config_models::network::Network;
@@ -186,15 +187,23 @@ impl Display for PositivePeerSanction {
 pub struct PeerSynchronizationState {
     pub claimed_max_height: BlockHeight,
     pub(crate) claimed_max_pow: ProofOfWork,
+
+    /// Discovery distance of the peer; 1 for peers we connected to directly.
+    pub(crate) distance: u8,
     pub synchronization_start: SystemTime,
     pub last_request_received: Option<SystemTime>,
 }
 
 impl PeerSynchronizationState {
-    pub(crate) fn new(claimed_max_height: BlockHeight, claimed_max_pow: ProofOfWork) -> Self {
+    pub(crate) fn new(
+        claimed_max_height: BlockHeight,
+        claimed_max_pow: ProofOfWork,
+        distance: u8,
+    ) -> Self {
         Self {
             claimed_max_height,
             claimed_max_pow,
+            distance,
             synchronization_start: SystemTime::now(),
             last_request_received: None,
         }
@@ -385,6 +394,25 @@ pub enum ConnectionRefusedReason {
     SelfConnect,
 }
 
+/// Why a node closes its connection to a peer. Sent to the peer before
+/// disconnecting.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, strum::Display)]
+pub enum DisconnectReason {
+    /// The node is connected to more peers than it allows.
+    MaxPeerNumberExceeded,
+
+    /// The node is a bootstrapper and makes room for new peers by dropping the
+    /// longest-lived connection.
+    BootstrapperMakingRoom,
+
+    /// The node's operator requested the disconnect.
+    RequestedByOperator,
+
+    /// The peer's standing fell below the ban threshold. Connections are
+    /// refused until the ban expires.
+    Banned,
+}
+
 impl From<InternalConnectionStatus> for TransferConnectionStatus {
     fn from(value: InternalConnectionStatus) -> Self {
         match value {
@@ -446,7 +474,9 @@ pub(crate) enum PeerMessage {
     SyncChallenge(SyncChallenge),
     SyncChallengeResponse(Box<SyncChallengeResponse>),
 
-    BlockProposalNotification(BlockProposalNotification),
+    /// Announces a block proposal without the composer's portion of the block
+    /// reward. Sent by older peers; see [`Self::BlockProposalNotificationV2`].
+    BlockProposalNotification(BlockProposalNotificationV1),
 
     BlockProposalRequest(BlockProposalRequest),
 
@@ -467,6 +497,13 @@ pub(crate) enum PeerMessage {
     /// Inform peer that we are disconnecting them.
     Bye,
     ConnectionStatus(TransferConnectionStatus),
+
+    /// Inform peer that we are disconnecting them, and why.
+    Disconnect(DisconnectReason),
+
+    /// Announces a block proposal, with both the guesser's and the composer's
+    /// portion of the block reward.
+    BlockProposalNotificationV2(BlockProposalNotification),
 }
 
 impl PeerMessage {
@@ -487,7 +524,9 @@ impl PeerMessage {
             PeerMessage::PeerListResponse(_) => "peer list resp",
             PeerMessage::Bye => "bye",
             PeerMessage::ConnectionStatus(_) => "connection status",
+            PeerMessage::Disconnect(_) => "disconnect",
             PeerMessage::BlockProposalNotification(_) => "block proposal notification",
+            PeerMessage::BlockProposalNotificationV2(_) => "block proposal notification v2",
             PeerMessage::BlockProposalRequest(_) => "block proposal request",
             PeerMessage::BlockProposal(_) => "block proposal",
             PeerMessage::UnableToSatisfyBatchRequest => "unable to satisfy batch request",
@@ -514,7 +553,9 @@ impl PeerMessage {
             PeerMessage::PeerListResponse(_) => false,
             PeerMessage::Bye => false,
             PeerMessage::ConnectionStatus(_) => false,
+            PeerMessage::Disconnect(_) => false,
             PeerMessage::BlockProposalNotification(_) => false,
+            PeerMessage::BlockProposalNotificationV2(_) => false,
             PeerMessage::BlockProposalRequest(_) => false,
             PeerMessage::BlockProposal(_) => false,
             PeerMessage::UnableToSatisfyBatchRequest => true,
@@ -541,7 +582,9 @@ impl PeerMessage {
             PeerMessage::PeerListResponse(_) => false,
             PeerMessage::Bye => false,
             PeerMessage::ConnectionStatus(_) => false,
+            PeerMessage::Disconnect(_) => false,
             PeerMessage::BlockProposalNotification(_) => true,
+            PeerMessage::BlockProposalNotificationV2(_) => true,
             PeerMessage::BlockProposalRequest(_) => true,
             PeerMessage::BlockProposal(_) => true,
             PeerMessage::UnableToSatisfyBatchRequest => false,
@@ -732,15 +775,23 @@ impl SyncChallengeResponse {
     }
 
     /// Determine whether the proofs in `SyncChallengeResponse` are valid. Also
-    /// checks proof-of-work.
-    pub(crate) async fn is_valid(&self, now: Timestamp, network: Network) -> bool {
+    /// checks proof-of-work. Blocks may be timestamped up to
+    /// `future_drift_limit` ahead of `now`.
+    pub(crate) async fn is_valid(
+        &self,
+        now: Timestamp,
+        network: Network,
+        future_drift_limit: Timestamp,
+    ) -> bool {
         let Ok(tip_predecessor) = Block::try_from(self.tip_parent.clone()) else {
             return false;
         };
         let Ok(tip) = Block::try_from(self.tip.clone()) else {
             return false;
         };
-        if !tip.is_valid(&tip_predecessor, now, network).await
+        if !tip
+            .is_valid_with_future_drift(&tip_predecessor, now, network, future_drift_limit)
+            .await
             || !tip.has_proof_of_work(tip_predecessor.header())
         {
             return false;
@@ -767,7 +818,9 @@ impl SyncChallengeResponse {
                 return false;
             };
 
-            if !child.is_valid(&parent, now, network).await
+            if !child
+                .is_valid_with_future_drift(&parent, now, network, future_drift_limit)
+                .await
                 || !child.has_proof_of_work(parent.header())
             {
                 return false;
@@ -10,6 +10,15 @@ use crate::models::blockchain::block::block_header::BlockHeader;
 
 pub(crate) type VersionString = ArrayString<U30>;
 
+/// The first version that understands the second version of the block
+/// proposal notification. See
+/// [`HandshakeData::supports_block_proposal_notification_v2`].
+const BLOCK_PROPOSAL_NOTIFICATION_V2_MIN_VERSION: semver::Version = semver::Version::new(0, 3, 0);
+
+/// The first version that understands the disconnect message. See
+/// [`HandshakeData::supports_disconnect_reason`].
+const DISCONNECT_REASON_MIN_VERSION: semver::Version = semver::Version::new(0, 3, 0);
+
 /// Datastruct defining the handshake peers exchange when establishing a new
 /// connection.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -25,3 +34,22 @@ pub(crate) struct HandshakeData {
     /// compare own timestamp to peer's or to a list of peers.
     pub timestamp: SystemTime,
 }
+
+impl HandshakeData {
+    /// Returns true iff the peer's version understands
+    /// [`BlockProposalNotificationV2`](super::PeerMessage::BlockProposalNotificationV2).
+    /// Older peers, and peers with an unparsable version, must be sent the
+    /// first version of the block proposal notification instead.
+    pub(crate) fn supports_block_proposal_notification_v2(&self) -> bool {
+        semver::Version::parse(&self.version)
+            .is_ok_and(|version| version >= BLOCK_PROPOSAL_NOTIFICATION_V2_MIN_VERSION)
+    }
+
+    /// Returns true iff the peer's version understands
+    /// [`Disconnect`](super::PeerMessage::Disconnect). Older peers, and peers
+    /// with an unparsable version, are disconnected without being told why.
+    pub(crate) fn supports_disconnect_reason(&self) -> bool {
+        semver::Version::parse(&self.version)
+            .is_ok_and(|version| version >= DISCONNECT_REASON_MIN_VERSION)
+    }
+}
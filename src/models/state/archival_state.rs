@@ -1,3 +1,4 @@
+use std::io::Write;
 use std::ops::DerefMut;
 use std::path::PathBuf;
 
@@ -5,6 +6,7 @@ use anyhow::bail;
 use anyhow::Result;
 use memmap2::MmapOptions;
 use num_traits::Zero;
+use serde::de::DeserializeOwned;
 use tasm_lib::twenty_first::prelude::Mmr;
 use tokio::io::AsyncSeekExt;
 use tokio::io::AsyncWriteExt;
@@ -13,6 +15,8 @@ use tracing::debug;
 use tracing::warn;
 use twenty_first::math::digest::Digest;
 
+use super::block_import::BlockFileManifest;
+use super::block_import::BlockFileWriter;
 use super::shared::new_block_file_is_needed;
 use super::StorageVecBase;
 use crate::config_models::data_directory::DataDirectory;
@@ -25,6 +29,7 @@ use crate::models::blockchain::block::block_header::BlockHeader;
 use crate::models::blockchain::block::block_header::BlockHeaderWithBlockHashWitness;
 use crate::models::blockchain::block::block_header::HeaderToBlockHashWitness;
 use crate::models::blockchain::block::block_height::BlockHeight;
+use crate::models::blockchain::block::block_kernel::BlockKernel;
 use crate::models::blockchain::block::mutator_set_update::MutatorSetUpdate;
 use crate::models::blockchain::block::Block;
 use crate::models::blockchain::transaction::transaction_kernel::TransactionKernelProxy;
@@ -63,9 +68,10 @@ pub(crate) struct ArchivalState {
     ///   Height(BlockHeight)  -> Height(Vec<Digest>)
     ///   LastFile             -> LastFile(LastFileRecord)
     ///   BlockTipDigest       -> BlockTipDigest(Digest)
+    ///   PrunedBelow          -> PrunedBelow(BlockHeight)
     /// ```
     ///
-    /// So this is effectively 5 logical indexes.
+    /// So this is effectively 6 logical indexes.
     pub(crate) block_index_db: NeptuneLevelDb<BlockIndexKey, BlockIndexValue>,
 
     // The genesis block is stored on the heap, as we would otherwise get stack overflows whenever we instantiate
@@ -80,6 +86,25 @@ pub(crate) struct ArchivalState {
     pub(crate) archival_block_mmr: RustyArchivalBlockMmr,
 }
 
+/// Returned by [`ArchivalState::get_block`] for a block whose body was
+/// discarded by [`ArchivalState::prune_block_bodies`]. The block's header is
+/// still available through [`ArchivalState::get_block_header`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("body of block {digest} of height {height} has been pruned")]
+pub(crate) struct BlockBodyPruned {
+    pub(crate) digest: Digest,
+    pub(crate) height: BlockHeight,
+}
+
+/// The lowest height at which the stored chain is inconsistent, as found by
+/// [`ArchivalState::check_consistency`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("archival state is inconsistent at height {height}: {reason}")]
+pub(crate) struct ArchivalInconsistency {
+    pub(crate) height: BlockHeight,
+    pub(crate) reason: String,
+}
+
 // The only reason we have this `Debug` implementation is that it's required
 // for some tracing/logging functionalities.
 impl core::fmt::Debug for ArchivalState {
@@ -504,6 +529,25 @@ impl ArchivalState {
     }
 
     async fn get_block_from_block_record(&self, block_record: BlockRecord) -> Result<Block> {
+        self.read_block_file(block_record).await
+    }
+
+    /// Read only the kernel of the block in the record. The proof, which makes
+    /// up most of the block, is not read.
+    async fn get_block_kernel_from_block_record(
+        &self,
+        block_record: BlockRecord,
+    ) -> Result<BlockKernel> {
+        // The kernel is the first field of a serialized block, and trailing
+        // bytes are ignored when deserializing.
+        self.read_block_file(block_record).await
+    }
+
+    /// Deserialize `T` from the start of the block in the record.
+    async fn read_block_file<T>(&self, block_record: BlockRecord) -> Result<T>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
         // Get path of file for block
         let block_file_path: PathBuf = self
             .data_dir
@@ -535,12 +579,12 @@ impl ArchivalState {
                     .len(block_record.file_location.block_length)
                     .map(&block_file)?
             };
-            let block: Block = match bincode::deserialize(&mmap) {
+            let block: T = match bincode::deserialize(&mmap) {
                 Ok(b) => b,
                 Err(e) => {
-                    panic!("Could not deserialize block file into `Block`.\n\
+                    panic!("Could not deserialize block file into `{}`.\n\
                             Block files may be corrupt, out of date, or incompatible with current version of neptune-core.\n\
-                            Error was: {e}");
+                            Error was: {e}", std::any::type_name::<T>());
                 }
             };
             Ok(block)
@@ -737,6 +781,172 @@ impl ArchivalState {
         }
     }
 
+    /// True iff the archival mutator set is synced to the tip, i.e., it is not
+    /// in the middle of an update and its AOCL and commitment match those the
+    /// tip block commits to.
+    pub(crate) async fn mutator_set_consistent(&self) -> bool {
+        let tip = self.get_tip().await;
+        let expected = tip.mutator_set_accumulator_after();
+        let ams = self.archival_mutator_set.ams();
+
+        self.archival_mutator_set.get_sync_label() == tip.hash()
+            && ams.aocl.num_leafs().await == expected.aocl.num_leafs()
+            && ams.hash().await == expected.hash()
+    }
+
+    /// Check that the canonical chain is stored consistently, such that a
+    /// crash in the middle of a write can be detected at startup. For every
+    /// height up to the tip, the archival block MMR must hold a digest whose
+    /// block is indexed, extends the previous one, is listed under its height,
+    /// has its body on disk (unless pruned), and whose AOCL indices follow
+    /// those of its parent. The archival mutator set must be synced to the tip
+    /// and hold exactly the AOCL leafs of the canonical chain.
+    ///
+    /// Reads block records only, not blocks, so it is safe to run before the
+    /// tip is loaded.
+    pub(crate) async fn check_consistency(&self) -> Result<(), ArchivalInconsistency> {
+        let inconsistent = |height: u64, reason: String| {
+            Err(ArchivalInconsistency {
+                height: height.into(),
+                reason,
+            })
+        };
+
+        let genesis_digest = self.genesis_block.hash();
+        let tip_digest = self
+            .block_index_db
+            .get(BlockIndexKey::BlockTipDigest)
+            .await
+            .map(|x| x.as_tip_digest())
+            .unwrap_or(genesis_digest);
+        let Some(tip_header) = self.get_block_header(tip_digest).await else {
+            return inconsistent(0, format!("tip {tip_digest} is not indexed"));
+        };
+        let tip_height = u64::from(tip_header.height);
+
+        let ammr = self.archival_block_mmr.ammr();
+        if ammr.try_get_leaf(0).await != Some(genesis_digest) {
+            return inconsistent(
+                0,
+                "archival block MMR does not start at genesis".to_string(),
+            );
+        }
+
+        // number of AOCL leafs after each canonical block, by height
+        let mut num_aocl_leafs = vec![self
+            .genesis_block
+            .mutator_set_accumulator_after()
+            .aocl
+            .num_leafs()];
+        let pruned_below = self.pruned_below().await;
+        let mut parent_digest = genesis_digest;
+        for height in 1..=tip_height {
+            let Some(digest) = ammr.try_get_leaf(height).await else {
+                return inconsistent(height, "missing from archival block MMR".to_string());
+            };
+            let Some(record) = self
+                .block_index_db
+                .get(BlockIndexKey::Block(digest))
+                .await
+                .map(|x| x.as_block_record())
+            else {
+                return inconsistent(height, format!("block {digest} is not indexed"));
+            };
+            if record.block_header.height != height.into()
+                || record.block_header.prev_block_digest != parent_digest
+            {
+                return inconsistent(
+                    height,
+                    format!("block {digest} does not extend canonical block {parent_digest}"),
+                );
+            }
+            if !self
+                .block_height_to_block_digests(height.into())
+                .await
+                .contains(&digest)
+            {
+                return inconsistent(height, format!("block {digest} is not indexed by height"));
+            }
+
+            let expected = *num_aocl_leafs.last().unwrap();
+            if record.min_aocl_index != expected {
+                return inconsistent(
+                    height,
+                    format!(
+                        "block {digest} starts at AOCL index {}, expected {expected}",
+                        record.min_aocl_index
+                    ),
+                );
+            }
+            if record.block_header.height >= pruned_below
+                && !self.block_body_is_on_disk(&record).await
+            {
+                return inconsistent(
+                    height,
+                    format!(
+                        "body of block {digest} is missing from block file {}",
+                        record.file_location.file_index
+                    ),
+                );
+            }
+
+            num_aocl_leafs.push(expected + record.num_additions);
+            parent_digest = digest;
+        }
+
+        if parent_digest != tip_digest {
+            return inconsistent(
+                tip_height,
+                format!("tip {tip_digest} is not the canonical block {parent_digest}"),
+            );
+        }
+        let num_block_mmr_leafs = ammr.num_leafs().await;
+        if num_block_mmr_leafs != tip_height + 1 {
+            return inconsistent(
+                tip_height + 1,
+                format!("archival block MMR has {num_block_mmr_leafs} leafs beyond the tip"),
+            );
+        }
+
+        let sync_label = self.archival_mutator_set.get_sync_label();
+        let ams_num_aocl_leafs = self.archival_mutator_set.ams().aocl.num_leafs().await;
+        if let Some(height) = num_aocl_leafs
+            .iter()
+            .position(|&num_leafs| num_leafs > ams_num_aocl_leafs)
+        {
+            return inconsistent(
+                height as u64,
+                format!("archival mutator set has only {ams_num_aocl_leafs} AOCL leafs"),
+            );
+        }
+        if ams_num_aocl_leafs > *num_aocl_leafs.last().unwrap() {
+            return inconsistent(
+                tip_height + 1,
+                "archival mutator set has AOCL leafs beyond the tip".to_string(),
+            );
+        }
+        if sync_label != tip_digest {
+            return inconsistent(
+                tip_height,
+                format!("archival mutator set is synced to {sync_label}, not to the tip"),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// True iff the block file indicated by `record` is long enough to hold
+    /// the block.
+    async fn block_body_is_on_disk(&self, record: &BlockRecord) -> bool {
+        let location = record.file_location;
+        let block_file_path = self.data_dir.block_file_path(location.file_index);
+        tokio::fs::metadata(&block_file_path)
+            .await
+            .is_ok_and(|metadata| {
+                metadata.len() >= location.offset + u64::try_from(location.block_length).unwrap()
+            })
+    }
+
     /// Return parent of tip block. Returns `None` iff tip is genesis block.
     pub(crate) async fn get_tip_parent(&self) -> Option<Block> {
         let tip_digest = self
@@ -793,7 +1003,21 @@ impl ArchivalState {
             })
     }
 
+    /// True iff the block with the given digest is stored. Cheaper than
+    /// [`Self::get_block`], as the block itself is not read from disk.
+    pub(crate) async fn has_block(&self, block_digest: Digest) -> bool {
+        block_digest == self.genesis_block.hash()
+            || self
+                .block_index_db
+                .get(BlockIndexKey::Block(block_digest))
+                .await
+                .is_some()
+    }
+
     // Return the block with a given block digest, iff it's available in state somewhere.
+    //
+    // Fails with [`BlockBodyPruned`] if the block is known but its body has been
+    // pruned.
     pub(crate) async fn get_block(&self, block_digest: Digest) -> Result<Option<Block>> {
         let maybe_record: Option<BlockRecord> = self
             .block_index_db
@@ -806,12 +1030,134 @@ impl ArchivalState {
             return Ok(maybe_genesis_block);
         };
 
+        if record.block_header.height < self.pruned_below().await {
+            return Err(BlockBodyPruned {
+                digest: block_digest,
+                height: record.block_header.height,
+            }
+            .into());
+        }
+
         // Fetch block from disk
         let block = self.get_block_from_block_record(record).await?;
 
         Ok(Some(block))
     }
 
+    /// Like [`Self::get_block`] but only returns the block's kernel, which is
+    /// much cheaper to read as the proof is skipped.
+    pub(crate) async fn get_block_kernel(
+        &self,
+        block_digest: Digest,
+    ) -> Result<Option<BlockKernel>> {
+        let maybe_record: Option<BlockRecord> = self
+            .block_index_db
+            .get(BlockIndexKey::Block(block_digest))
+            .await
+            .map(|x| x.as_block_record());
+        let Some(record) = maybe_record else {
+            let maybe_genesis_kernel = (self.genesis_block.hash() == block_digest)
+                .then(|| self.genesis_block.kernel.clone());
+            return Ok(maybe_genesis_kernel);
+        };
+
+        if record.block_header.height < self.pruned_below().await {
+            return Err(BlockBodyPruned {
+                digest: block_digest,
+                height: record.block_header.height,
+            }
+            .into());
+        }
+
+        let kernel = self.get_block_kernel_from_block_record(record).await?;
+
+        Ok(Some(kernel))
+    }
+
+    /// The height below which block bodies have been discarded. Genesis, i.e.,
+    /// nothing, if no bodies have been pruned.
+    pub(crate) async fn pruned_below(&self) -> BlockHeight {
+        self.block_index_db
+            .get(BlockIndexKey::PrunedBelow)
+            .await
+            .map(|x| x.as_pruned_below())
+            .unwrap_or_else(BlockHeight::genesis)
+    }
+
+    /// True iff the block with the given digest is stored but its body has
+    /// been pruned. The genesis block is never pruned.
+    pub(crate) async fn body_is_pruned(&self, block_digest: Digest) -> bool {
+        let Some(header) = self
+            .block_index_db
+            .get(BlockIndexKey::Block(block_digest))
+            .await
+            .map(|x| x.as_block_record().block_header)
+        else {
+            return false;
+        };
+
+        header.height < self.pruned_below().await
+    }
+
+    /// Discard the bodies of all blocks more than `depth` blocks below
+    /// `tip_height`, canonical or not. Headers are kept, so blocks can still
+    /// be validated against and fork choice is unaffected.
+    ///
+    /// Bodies are reported as pruned right away, but disk space is reclaimed
+    /// per block file: a file is deleted once all blocks in it are pruned. The
+    /// file currently written to is never deleted.
+    pub(crate) async fn prune_block_bodies(
+        &mut self,
+        tip_height: BlockHeight,
+        depth: u64,
+    ) -> Result<()> {
+        let pruned_below = BlockHeight::from(u64::from(tip_height).saturating_sub(depth));
+        if pruned_below <= self.pruned_below().await {
+            return Ok(());
+        }
+
+        // Record the new height before deleting anything, such that no block
+        // record points into a deleted file without being marked as pruned.
+        let mut batch = WriteBatchAsync::new();
+        batch.op_write(
+            BlockIndexKey::PrunedBelow,
+            BlockIndexValue::PrunedBelow(pruned_below),
+        );
+        self.block_index_db.batch_write(batch).await;
+
+        let last_file = self
+            .block_index_db
+            .get(BlockIndexKey::LastFile)
+            .await
+            .map(|x| x.as_last_file_record().last_file)
+            .unwrap_or_default();
+        for file_index in 0..last_file {
+            let Some(file_record) = self
+                .block_index_db
+                .get(BlockIndexKey::File(file_index))
+                .await
+                .map(|x| x.as_file_record())
+            else {
+                continue;
+            };
+            if file_record.max_block_height >= pruned_below {
+                continue;
+            }
+
+            let block_file_path = self.data_dir.block_file_path(file_index);
+            match tokio::fs::remove_file(&block_file_path).await {
+                Ok(()) => debug!("Deleted pruned block file {}", block_file_path.display()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => bail!(
+                    "Could not delete pruned block file {}: {e}",
+                    block_file_path.display()
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
     /// Return the digests of the known blocks at a specific height
     pub(crate) async fn block_height_to_block_digests(
         &self,
@@ -848,6 +1194,67 @@ impl ArchivalState {
             })
     }
 
+    /// Return the heights in `start..=end` of the canonical blocks whose
+    /// timestamp is not later than their parent's.
+    ///
+    /// Block validity requires a timestamp at least the minimum block time
+    /// after the parent's, see [`Block::is_valid`], so any height returned
+    /// points to a block that was stored without validation or to a corrupt
+    /// database.
+    pub(crate) async fn check_timestamp_monotonic(
+        &self,
+        start: BlockHeight,
+        end: BlockHeight,
+    ) -> Vec<BlockHeight> {
+        let canonical_header = |height: u64| async move {
+            let digest = self.archival_block_mmr.ammr().try_get_leaf(height).await?;
+            self.get_block_header(digest).await
+        };
+
+        // genesis has no parent to compare against
+        let start = std::cmp::max(u64::from(start), 1);
+        let Some(mut parent) = canonical_header(start - 1).await else {
+            return vec![];
+        };
+
+        let mut anomalies = vec![];
+        for height in start..=u64::from(end) {
+            let Some(header) = canonical_header(height).await else {
+                break;
+            };
+            if header.timestamp.to_millis() <= parent.timestamp.to_millis() {
+                anomalies.push(header.height);
+            }
+            parent = header;
+        }
+
+        anomalies
+    }
+
+    /// Write the canonical blocks of heights `start..=end` to `writer`, in the
+    /// format read by [`GlobalState::import_blocks`](super::GlobalState::import_blocks).
+    /// Stops at the tip if `end` lies beyond it. Returns the manifest written
+    /// after the blocks.
+    pub(crate) async fn export_blocks<W: Write>(
+        &self,
+        start: BlockHeight,
+        end: BlockHeight,
+        writer: &mut W,
+    ) -> Result<BlockFileManifest> {
+        let mut block_file = BlockFileWriter::new(writer);
+        for height in u64::from(start)..=u64::from(end) {
+            let Some(digest) = self.archival_block_mmr.ammr().try_get_leaf(height).await else {
+                break;
+            };
+            let Some(block) = self.get_block(digest).await? else {
+                bail!("canonical block of height {height} is missing");
+            };
+            block_file.append(&block)?;
+        }
+
+        block_file.finish()
+    }
+
     /// Return a list of digests of the ancestors to the requested digest. Does not include the input
     /// digest. If no ancestors can be found, returns the empty list. The count is the maximum length
     /// of the returned list. E.g. if the input digest corresponds to height 2 and count is 5, the
@@ -1133,6 +1540,8 @@ impl ArchivalState {
 #[cfg(test)]
 mod archival_state_tests {
 
+    use std::io::Cursor;
+
     use itertools::Itertools;
     use rand::random;
     use rand::rngs::StdRng;
@@ -1156,6 +1565,7 @@ mod archival_state_tests {
     use crate::models::blockchain::type_scripts::native_currency_amount::NativeCurrencyAmount;
     use crate::models::proof_abstractions::timestamp::Timestamp;
     use crate::models::state::archival_state::ArchivalState;
+    use crate::models::state::block_import::read_block_frame;
     use crate::models::state::tx_creation_config::TxCreationConfig;
     use crate::models::state::tx_proving_capability::TxProvingCapability;
     use crate::models::state::wallet::address::KeyType;
@@ -1164,7 +1574,10 @@ mod archival_state_tests {
     use crate::models::state::wallet::transaction_output::TxOutputList;
     use crate::models::state::wallet::wallet_entropy::WalletEntropy;
     use crate::tests::shared::add_block_to_archival_state;
+    use crate::tests::shared::fake_valid_sequence_of_blocks_for_tests;
     use crate::tests::shared::invalid_block_with_transaction;
+    use crate::tests::shared::invalid_empty_block;
+    use crate::tests::shared::invalid_empty_block_with_timestamp;
     use crate::tests::shared::make_mock_block;
     use crate::tests::shared::mock_genesis_archival_state;
     use crate::tests::shared::mock_genesis_global_state;
@@ -2381,6 +2794,13 @@ mod archival_state_tests {
                 block,
                 archival_state.get_block(block.hash()).await?.unwrap()
             );
+            assert_eq!(
+                block.kernel,
+                archival_state
+                    .get_block_kernel(block.hash())
+                    .await?
+                    .unwrap()
+            );
         }
 
         Ok(())
@@ -3581,7 +4001,6 @@ mod archival_state_tests {
 
     mod block_hash_witness {
         use super::*;
-        use crate::tests::shared::fake_valid_sequence_of_blocks_for_tests;
 
         #[traced_test]
         #[tokio::test]
@@ -3633,4 +4052,236 @@ mod archival_state_tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn out_of_order_timestamp_is_flagged() {
+        let network = Network::Main;
+        let mut archival_state = make_test_archival_state(network).await;
+
+        // block 3 claims to be older than block 2
+        let mut blocks = vec![Block::genesis(network)];
+        for offset in [
+            Timestamp::hours(1),
+            Timestamp::hours(2),
+            Timestamp::minutes(90),
+        ] {
+            let timestamp = blocks[0].header().timestamp + offset;
+            let block = invalid_empty_block_with_timestamp(blocks.last().unwrap(), timestamp);
+            add_block_to_archival_state(&mut archival_state, block.clone())
+                .await
+                .unwrap();
+            blocks.push(block);
+        }
+
+        assert_eq!(
+            vec![BlockHeight::from(3u64)],
+            archival_state
+                .check_timestamp_monotonic(0u64.into(), 10u64.into())
+                .await
+        );
+        assert!(archival_state
+            .check_timestamp_monotonic(0u64.into(), 2u64.into())
+            .await
+            .is_empty());
+        assert_eq!(
+            vec![BlockHeight::from(3u64)],
+            archival_state
+                .check_timestamp_monotonic(3u64.into(), 3u64.into())
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn pruned_bodies_are_reported_while_headers_remain() {
+        let network = Network::Main;
+        let mut archival_state = make_test_archival_state(network).await;
+        let mut blocks = vec![Block::genesis(network)];
+        for _ in 0..5 {
+            let block = invalid_empty_block(blocks.last().unwrap());
+            add_block_to_archival_state(&mut archival_state, block.clone())
+                .await
+                .unwrap();
+            blocks.push(block);
+        }
+        assert_eq!(BlockHeight::genesis(), archival_state.pruned_below().await);
+
+        archival_state
+            .prune_block_bodies(blocks[5].header().height, 2)
+            .await
+            .unwrap();
+        assert_eq!(BlockHeight::from(3u64), archival_state.pruned_below().await);
+
+        for block in &blocks[1..3] {
+            let error = archival_state.get_block(block.hash()).await.unwrap_err();
+            assert_eq!(
+                Some(&BlockBodyPruned {
+                    digest: block.hash(),
+                    height: block.header().height,
+                }),
+                error.downcast_ref::<BlockBodyPruned>()
+            );
+            assert!(archival_state.body_is_pruned(block.hash()).await);
+            assert_eq!(
+                Some(*block.header()),
+                archival_state.get_block_header(block.hash()).await
+            );
+        }
+
+        let unpruned = [&blocks[0], &blocks[3], &blocks[4], &blocks[5]];
+        for block in unpruned {
+            assert!(!archival_state.body_is_pruned(block.hash()).await);
+            assert_eq!(
+                block.hash(),
+                archival_state
+                    .get_block(block.hash())
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .hash()
+            );
+        }
+        assert_eq!(blocks[5].hash(), archival_state.get_tip().await.hash());
+        assert_eq!(
+            blocks[4].hash(),
+            archival_state.get_tip_parent().await.unwrap().hash()
+        );
+
+        // the file being written to is kept, and pruning never un-prunes
+        assert!(archival_state.data_dir.block_file_path(0).exists());
+        archival_state
+            .prune_block_bodies(blocks[5].header().height, 4)
+            .await
+            .unwrap();
+        assert_eq!(BlockHeight::from(3u64), archival_state.pruned_below().await);
+    }
+
+    #[tokio::test]
+    async fn consistency_check_finds_lowest_truncated_block() {
+        let network = Network::Main;
+        let mut archival_state = make_test_archival_state(network).await;
+        assert_eq!(Ok(()), archival_state.check_consistency().await);
+
+        let mut blocks = vec![Block::genesis(network)];
+        for _ in 0..3 {
+            let block = invalid_empty_block(blocks.last().unwrap());
+            add_block_to_archival_state(&mut archival_state, block.clone())
+                .await
+                .unwrap();
+            blocks.push(block);
+        }
+        assert_eq!(Ok(()), archival_state.check_consistency().await);
+
+        // simulate a crash while writing block 2, after its index was stored
+        let location = archival_state
+            .block_index_db
+            .get(BlockIndexKey::Block(blocks[2].hash()))
+            .await
+            .unwrap()
+            .as_block_record()
+            .file_location;
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(archival_state.data_dir.block_file_path(location.file_index))
+            .unwrap()
+            .set_len(location.offset + 1)
+            .unwrap();
+
+        let inconsistency = archival_state.check_consistency().await.unwrap_err();
+        assert_eq!(BlockHeight::from(2u64), inconsistency.height);
+        assert!(
+            inconsistency.reason.contains("missing from block file"),
+            "{inconsistency}"
+        );
+    }
+
+    #[tokio::test]
+    async fn consistency_check_finds_mutator_set_behind_tip() {
+        let network = Network::Main;
+        let mut archival_state = make_test_archival_state(network).await;
+        let mut blocks = vec![Block::genesis(network)];
+        for _ in 0..2 {
+            let block = invalid_empty_block(blocks.last().unwrap());
+            add_block_to_archival_state(&mut archival_state, block.clone())
+                .await
+                .unwrap();
+            blocks.push(block);
+        }
+
+        // simulate a crash after block 3 was stored, but before the mutator set
+        // was updated
+        let block_3 = invalid_empty_block(&blocks[2]);
+        archival_state.write_block_as_tip(&block_3).await.unwrap();
+        archival_state.append_to_archival_block_mmr(&block_3).await;
+
+        let inconsistency = archival_state.check_consistency().await.unwrap_err();
+        assert_eq!(BlockHeight::from(3u64), inconsistency.height);
+    }
+
+    #[tokio::test]
+    async fn exported_blocks_reimport_to_same_chain() {
+        let network = Network::Main;
+        let new_state = || {
+            mock_genesis_global_state(
+                network,
+                0,
+                WalletEntropy::new_random(),
+                cli_args::Args::default(),
+            )
+        };
+        let mut source = new_state().await;
+        let genesis = Block::genesis(network);
+        let blocks: [Block; 3] = fake_valid_sequence_of_blocks_for_tests(
+            &genesis,
+            Timestamp::hours(1),
+            [2; 32],
+            network,
+        )
+        .await;
+        for block in &blocks {
+            source.set_new_tip(block.clone()).await.unwrap();
+        }
+
+        // the range extends beyond the tip
+        let source = source.lock_guard().await;
+        let mut file = vec![];
+        let manifest = source
+            .chain
+            .archival_state()
+            .export_blocks(1u64.into(), 100u64.into(), &mut file)
+            .await
+            .unwrap();
+        assert_eq!(3, manifest.num_blocks);
+        assert_eq!(blocks[2].header().height, manifest.last_height);
+
+        let mut destination = new_state().await;
+        let mut destination = destination.lock_guard_mut().await;
+        let summary = destination.import_blocks(Cursor::new(file)).await.unwrap();
+        assert_eq!(3, summary.imported);
+
+        let source_mmr = source.chain.archival_state().archival_block_mmr.ammr();
+        let destination_mmr = destination.chain.archival_state().archival_block_mmr.ammr();
+        for height in 0..=4 {
+            assert_eq!(
+                source_mmr.try_get_leaf(height).await,
+                destination_mmr.try_get_leaf(height).await
+            );
+        }
+        assert_eq!(
+            source.chain.light_state().hash(),
+            destination.chain.light_state().hash()
+        );
+
+        let mut partial = vec![];
+        let manifest = source
+            .chain
+            .archival_state()
+            .export_blocks(2u64.into(), 2u64.into(), &mut partial)
+            .await
+            .unwrap();
+        assert_eq!(1, manifest.num_blocks);
+        let mut partial = partial.as_slice();
+        let block_2 = read_block_frame(&mut partial).unwrap().unwrap();
+        assert_eq!(blocks[1].hash(), block_2.hash());
+        assert!(read_block_frame(&mut partial).unwrap().is_none());
+    }
 }
@@ -0,0 +1,390 @@
+//! Bulk import of blocks from a file, for bootstrapping a node without
+//! downloading the chain from peers block by block.
+//!
+//! A block file is a sequence of frames. Each frame is the length of a
+//! bincode-encoded [`Block`], as a little-endian `u64`, followed by the
+//! encoding itself. The file ends with a [`BlockFileManifest`], announced by
+//! [`MANIFEST_MARKER`] in place of a length, which lets a reader detect a
+//! truncated or corrupted file before importing any of its blocks.
+
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Seek;
+use std::io::Write;
+
+use anyhow::bail;
+use anyhow::ensure;
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+use sha3::Digest as _;
+use sha3::Sha3_256;
+use tracing::debug;
+use tracing::info;
+
+use super::GlobalState;
+use crate::models::blockchain::block::block_height::BlockHeight;
+use crate::models::blockchain::block::Block;
+use crate::models::proof_abstractions::timestamp::Timestamp;
+
+/// Frames announcing a longer encoding are rejected before anything is
+/// allocated for them.
+pub const MAX_BLOCK_FRAME_LEN: u64 = 256 * 1024 * 1024;
+
+/// Written in place of a frame length to announce the manifest.
+pub const MANIFEST_MARKER: u64 = u64::MAX;
+
+/// Summary of the blocks in a block file, written after the last block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockFileManifest {
+    pub num_blocks: u64,
+
+    /// Height of the first block, or zero if there are no blocks.
+    pub first_height: BlockHeight,
+
+    /// Height of the last block, or zero if there are no blocks.
+    pub last_height: BlockHeight,
+
+    /// SHA3-256 chain over the block encodings: each link hashes the previous
+    /// link followed by the next encoding, starting from all zeros.
+    pub chain_digest: [u8; 32],
+}
+
+impl BlockFileManifest {
+    fn include(&mut self, height: BlockHeight, encoding: &[u8]) {
+        if self.num_blocks == 0 {
+            self.first_height = height;
+        }
+        self.last_height = height;
+        self.chain_digest = Sha3_256::new()
+            .chain_update(self.chain_digest)
+            .chain_update(encoding)
+            .finalize()
+            .into();
+        self.num_blocks += 1;
+    }
+}
+
+/// Writes a block file. The file is only complete after [`Self::finish`].
+#[derive(Debug)]
+pub struct BlockFileWriter<W: Write> {
+    writer: W,
+    manifest: BlockFileManifest,
+}
+
+impl<W: Write> BlockFileWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            manifest: BlockFileManifest::default(),
+        }
+    }
+
+    /// Append `block` to the block file.
+    pub fn append(&mut self, block: &Block) -> Result<()> {
+        let encoding = bincode::serialize(block)?;
+        self.writer
+            .write_all(&(encoding.len() as u64).to_le_bytes())?;
+        self.writer.write_all(&encoding)?;
+        self.manifest.include(block.header().height, &encoding);
+
+        Ok(())
+    }
+
+    /// Write the manifest and flush. Returns the manifest.
+    pub fn finish(mut self) -> Result<BlockFileManifest> {
+        self.writer.write_all(&MANIFEST_MARKER.to_le_bytes())?;
+        bincode::serialize_into(&mut self.writer, &self.manifest)?;
+        self.writer.flush()?;
+
+        Ok(self.manifest)
+    }
+}
+
+enum Frame {
+    Block(Vec<u8>),
+    Manifest(BlockFileManifest),
+}
+
+fn read_frame<R: Read>(reader: &mut R) -> Result<Frame> {
+    let mut len = [0u8; 8];
+    match reader.read_exact(&mut len) {
+        Ok(()) => (),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+            bail!("block file ends without a manifest; it may be truncated")
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    let len = u64::from_le_bytes(len);
+    if len == MANIFEST_MARKER {
+        let manifest = bincode::deserialize_from(reader).context("block file manifest")?;
+        return Ok(Frame::Manifest(manifest));
+    }
+    if len > MAX_BLOCK_FRAME_LEN {
+        bail!("block frame of {len} bytes exceeds maximum of {MAX_BLOCK_FRAME_LEN}");
+    }
+    let mut encoding = vec![0u8; len as usize];
+    reader
+        .read_exact(&mut encoding)
+        .context("block file ends in the middle of a block")?;
+
+    Ok(Frame::Block(encoding))
+}
+
+/// Read the next block of a block file. Returns `None` once the manifest is
+/// reached. Does not verify the manifest; see [`verify_block_file`].
+pub fn read_block_frame<R: Read>(reader: &mut R) -> Result<Option<Block>> {
+    match read_frame(reader)? {
+        Frame::Block(encoding) => Ok(Some(bincode::deserialize(&encoding)?)),
+        Frame::Manifest(_) => Ok(None),
+    }
+}
+
+/// Read a whole block file and check it against its manifest. Returns the
+/// manifest.
+pub fn verify_block_file<R: Read>(reader: &mut R) -> Result<BlockFileManifest> {
+    let mut computed = BlockFileManifest::default();
+    let claimed = loop {
+        match read_frame(reader)? {
+            Frame::Block(encoding) => {
+                let block: Block = bincode::deserialize(&encoding)
+                    .with_context(|| format!("block {} is corrupt", computed.num_blocks))?;
+                computed.include(block.header().height, &encoding);
+            }
+            Frame::Manifest(manifest) => break manifest,
+        }
+    };
+
+    ensure!(
+        reader.read(&mut [0u8])? == 0,
+        "block file has data after its manifest"
+    );
+    ensure!(
+        claimed == computed,
+        "block file does not match its manifest; claimed {claimed:?}, found {computed:?}"
+    );
+
+    Ok(claimed)
+}
+
+/// What an import of a block file did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Blocks that were validated and stored.
+    pub imported: usize,
+
+    /// Blocks that were already known, for instance from an earlier, interrupted
+    /// import of the same file.
+    pub skipped: usize,
+}
+
+impl GlobalState {
+    /// Import the blocks of a block file, in order.
+    ///
+    /// The file is checked against its manifest before any block is stored.
+    /// Every block must extend a known block and is validated as if received
+    /// from a peer. A block with more cumulative proof-of-work than the current
+    /// tip becomes the new tip. Known blocks are skipped, so an interrupted
+    /// import can be resumed by importing the same file again. The import stops
+    /// at the first invalid block; the blocks before it stay imported.
+    pub(crate) async fn import_blocks<R: Read + Seek>(
+        &mut self,
+        mut reader: R,
+    ) -> Result<ImportSummary> {
+        let manifest = verify_block_file(&mut reader)?;
+        debug!(
+            "Block file holds {} blocks of heights {} to {}",
+            manifest.num_blocks, manifest.first_height, manifest.last_height
+        );
+        reader.rewind()?;
+
+        let network = self.cli().network;
+        let future_drift_limit = self.cli().future_drift_limit();
+
+        let mut summary = ImportSummary::default();
+        while let Some(block) = read_block_frame(&mut reader)? {
+            let height = block.header().height;
+            let archival_state = self.chain.archival_state();
+            if archival_state
+                .get_block_header(block.hash())
+                .await
+                .is_some()
+            {
+                debug!("Skipping known block of height {height}");
+                summary.skipped += 1;
+                continue;
+            }
+
+            let parent_digest = block.header().prev_block_digest;
+            let Some(parent) = archival_state.get_block(parent_digest).await? else {
+                bail!("block of height {height} does not extend a known block");
+            };
+            let is_valid = block.has_proof_of_work(parent.header())
+                && block
+                    .is_valid_with_future_drift(
+                        &parent,
+                        Timestamp::now(),
+                        network,
+                        future_drift_limit,
+                    )
+                    .await;
+            if !is_valid {
+                bail!("block of height {height} is invalid");
+            }
+
+            let tip_work = self.chain.light_state().header().cumulative_proof_of_work;
+            if block.header().cumulative_proof_of_work > tip_work {
+                self.set_new_tip(block).await?;
+            } else {
+                self.store_block_not_tip(block).await?;
+            }
+            summary.imported += 1;
+
+            if summary.imported % 1000 == 0 {
+                info!(
+                    "Imported {} blocks, up to height {height}",
+                    summary.imported
+                );
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::config_models::cli_args;
+    use crate::config_models::network::Network;
+    use crate::models::state::wallet::wallet_entropy::WalletEntropy;
+    use crate::tests::shared::fake_valid_sequence_of_blocks_for_tests;
+    use crate::tests::shared::mock_genesis_global_state;
+
+    fn block_file<'a>(blocks: impl IntoIterator<Item = &'a Block>) -> Vec<u8> {
+        let mut file = vec![];
+        let mut writer = BlockFileWriter::new(&mut file);
+        for block in blocks {
+            writer.append(block).unwrap();
+        }
+        writer.finish().unwrap();
+        file
+    }
+
+    #[tokio::test]
+    async fn exported_chain_is_imported_and_resumed() {
+        let network = Network::Main;
+        let genesis = Block::genesis(network);
+        let blocks: [Block; 4] = fake_valid_sequence_of_blocks_for_tests(
+            &genesis,
+            Timestamp::hours(1),
+            [0; 32],
+            network,
+        )
+        .await;
+
+        let mut state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let mut state = state_lock.lock_guard_mut().await;
+
+        // an import interrupted after the first two blocks
+        let interrupted = block_file(&blocks[..2]);
+        let summary = state.import_blocks(Cursor::new(interrupted)).await.unwrap();
+        assert_eq!(2, summary.imported);
+        assert_eq!(blocks[1].hash(), state.chain.light_state().hash());
+
+        let summary = state
+            .import_blocks(Cursor::new(block_file(&blocks)))
+            .await
+            .unwrap();
+        assert_eq!(
+            ImportSummary {
+                imported: 2,
+                skipped: 2
+            },
+            summary
+        );
+        assert_eq!(blocks[3].hash(), state.chain.light_state().hash());
+        assert_eq!(
+            blocks[3].hash(),
+            state.chain.archival_state().get_tip().await.hash()
+        );
+    }
+
+    #[tokio::test]
+    async fn import_stops_at_block_without_known_parent() {
+        let network = Network::Main;
+        let genesis = Block::genesis(network);
+        let blocks: [Block; 2] = fake_valid_sequence_of_blocks_for_tests(
+            &genesis,
+            Timestamp::hours(1),
+            [1; 32],
+            network,
+        )
+        .await;
+        let file = block_file([&blocks[1]]);
+
+        let mut state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let mut state = state_lock.lock_guard_mut().await;
+        let err = state.import_blocks(Cursor::new(file)).await.unwrap_err();
+        assert!(err.to_string().contains("does not extend"), "{err}");
+        assert_eq!(genesis.hash(), state.chain.light_state().hash());
+    }
+
+    #[tokio::test]
+    async fn corrupted_block_file_is_rejected_before_import() {
+        let network = Network::Main;
+        let genesis = Block::genesis(network);
+        let blocks: [Block; 3] = fake_valid_sequence_of_blocks_for_tests(
+            &genesis,
+            Timestamp::hours(1),
+            [2; 32],
+            network,
+        )
+        .await;
+        let file = block_file(&blocks);
+        let manifest = verify_block_file(&mut file.as_slice()).unwrap();
+        assert_eq!(3, manifest.num_blocks);
+        assert_eq!(blocks[0].header().height, manifest.first_height);
+        assert_eq!(blocks[2].header().height, manifest.last_height);
+
+        let first_frame_len = 8 + bincode::serialized_size(&blocks[0]).unwrap() as usize;
+        let truncated = file[..file.len() - 1].to_vec();
+        let without_manifest = file[..first_frame_len].to_vec();
+        let mut flipped = file.clone();
+        flipped[first_frame_len - 1] ^= 1;
+        let mut trailing_data = file.clone();
+        trailing_data.push(0);
+
+        let mut state_lock = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let mut state = state_lock.lock_guard_mut().await;
+        for corrupted in [truncated, without_manifest, flipped, trailing_data] {
+            assert!(state.import_blocks(Cursor::new(corrupted)).await.is_err());
+            assert_eq!(genesis.hash(), state.chain.light_state().hash());
+        }
+
+        state.import_blocks(Cursor::new(file)).await.unwrap();
+        assert_eq!(blocks[2].hash(), state.chain.light_state().hash());
+    }
+}
@@ -13,6 +13,7 @@
 use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::iter::Rev;
 
 use bytesize::ByteSize;
@@ -68,6 +69,14 @@ pub const MEMPOOL_IGNORE_TRANSACTIONS_THIS_MANY_SECS_AHEAD: u64 = 5 * 60;
 
 pub const TRANSACTION_NOTIFICATION_AGE_LIMIT_IN_SECS: u64 = 60 * 60 * 24;
 
+/// Number of additions and removals the mempool remembers for clients that
+/// sync incrementally.
+pub const MEMPOOL_CHANGE_LOG_CAPACITY: usize = 10_000;
+
+/// By how many percent, by default, a transaction's fee density must exceed
+/// that of the transactions it conflicts with in order to replace them.
+pub const DEFAULT_REPLACE_BY_FEE_MARGIN: u32 = 10;
+
 type LookupItem<'a> = (TransactionKernelId, &'a Transaction);
 
 /// Represents a mempool state change.
@@ -87,6 +96,32 @@ pub enum MempoolEvent {
     UpdateTxMutatorSet(TransactionKernelId, Transaction),
 }
 
+/// An addition to or a removal from the set of transactions in the mempool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MempoolChange {
+    Added(TransactionKernelId),
+    Removed(TransactionKernelId),
+}
+
+/// The net effect of all mempool changes after some sequence number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MempoolChanges {
+    /// Transactions added since the cursor and still in the mempool.
+    pub added: Vec<TransactionKernelId>,
+
+    /// Transactions removed since the cursor and no longer in the mempool.
+    pub removed: Vec<TransactionKernelId>,
+
+    /// The sequence number of the latest change. Pass this as the cursor of the
+    /// next request.
+    pub sequence: u64,
+
+    /// False if changes after the cursor have been forgotten, or if the cursor
+    /// is unknown to this mempool. The client must then fetch the entire
+    /// mempool again.
+    pub complete: bool,
+}
+
 /// Used to mark origin of transaction. To determine if transaction was
 /// initiated locally or not.
 #[derive(Debug, GetSize, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -113,10 +148,54 @@ impl std::fmt::Display for TransactionOrigin {
     }
 }
 
+/// The path through which a transaction entered the mempool.
+#[derive(Debug, GetSize, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "arbitrary-impls"), derive(arbitrary::Arbitrary))]
+pub enum TransactionIngress {
+    /// Created, merged, or upgraded by this node.
+    Local,
+
+    /// Gossiped by a peer.
+    Peer,
+
+    /// Submitted through the REST API's broadcast endpoint.
+    RpcBroadcast,
+
+    /// Submitted through the REST API's fee-paid send endpoint.
+    FeePaidSend,
+}
+
+impl From<TransactionOrigin> for TransactionIngress {
+    fn from(origin: TransactionOrigin) -> Self {
+        match origin {
+            TransactionOrigin::Own => Self::Local,
+            TransactionOrigin::Foreign => Self::Peer,
+        }
+    }
+}
+
+/// Decides which transaction is dropped when the mempool exceeds its maximum
+/// size or its maximum number of transactions.
+#[derive(
+    Debug, GetSize, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum,
+)]
+pub enum MempoolEvictionPolicy {
+    /// Drop the transaction with the lowest [`FeeDensity`].
+    #[default]
+    LowestFee,
+
+    /// Drop the transaction with the oldest timestamp.
+    Oldest,
+
+    /// Drop the transaction taking up the most memory.
+    LargestSize,
+}
+
 #[derive(Debug, GetSize, Clone, Serialize, Deserialize)]
 pub(crate) struct MempoolTransaction {
     pub(crate) transaction: Transaction,
     pub(crate) origin: TransactionOrigin,
+    pub(crate) ingress: TransactionIngress,
 }
 
 /// Unpersisted view of valid transactions that have not been confirmed yet.
@@ -143,6 +222,12 @@ pub(crate) struct MempoolTransaction {
 /// guarantee that no conflicting transactions can be contained in the mempool.
 /// This means that two transactions that spend the same input will never be
 /// allowed into the mempool simultaneously.
+///
+/// Mempool transactions never depend on one another, so the mempool tracks no
+/// ancestors or descendants. An input's removal record commits to the AOCL
+/// leaf index of the spent UTXO, which only exists once the UTXO is confirmed.
+/// Moreover, a removal record cannot be linked to the addition record of the
+/// UTXO it spends without the owner's secrets.
 #[derive(Debug, GetSize)]
 pub struct Mempool {
     /// Maximum size this data structure may take up in memory.
@@ -164,6 +249,24 @@ pub struct Mempool {
     /// Records the digest of the block that the transactions were synced to.
     /// Used to discover reorganizations.
     tip_digest: Digest,
+
+    /// Which transaction to drop when the mempool is full.
+    eviction_policy: MempoolEvictionPolicy,
+
+    /// By how many percent the fee density of a transaction must exceed that
+    /// of the transactions it conflicts with in order to replace them.
+    replace_by_fee_margin: u32,
+
+    /// Number of transactions dropped because the mempool was full.
+    num_evicted: u64,
+
+    /// Number of additions and removals since the mempool was created.
+    sequence: u64,
+
+    /// The latest additions and removals, oldest first. The last entry has
+    /// sequence number `sequence`.
+    #[get_size(ignore)] // Bounded by `MEMPOOL_CHANGE_LOG_CAPACITY`
+    change_log: VecDeque<MempoolChange>,
 }
 
 /// note that all methods that modify state and result in a MempoolEvent
@@ -188,9 +291,101 @@ impl Mempool {
             tx_dictionary: table,
             queue,
             tip_digest,
+            eviction_policy: MempoolEvictionPolicy::default(),
+            replace_by_fee_margin: DEFAULT_REPLACE_BY_FEE_MARGIN,
+            num_evicted: 0,
+            sequence: 0,
+            change_log: VecDeque::new(),
         }
     }
 
+    /// Set the policy deciding which transaction to drop when the mempool is
+    /// full.
+    pub fn with_eviction_policy(mut self, eviction_policy: MempoolEvictionPolicy) -> Self {
+        self.eviction_policy = eviction_policy;
+        self
+    }
+
+    /// The policy deciding which transaction to drop when the mempool is full.
+    pub fn eviction_policy(&self) -> MempoolEvictionPolicy {
+        self.eviction_policy
+    }
+
+    /// Set by how many percent the fee density of a transaction must exceed
+    /// that of the transactions it conflicts with in order to replace them.
+    pub fn with_replace_by_fee_margin(mut self, replace_by_fee_margin: u32) -> Self {
+        self.replace_by_fee_margin = replace_by_fee_margin;
+        self
+    }
+
+    /// True iff a transaction with fee density `new` may replace conflicting
+    /// transactions, the lowest fee density of which is `old`.
+    fn pays_replacement_fee(&self, new: &FeeDensity, old: &FeeDensity) -> bool {
+        let margin = FeeDensity::new(
+            (100 + u64::from(self.replace_by_fee_margin)).into(),
+            100.into(),
+        );
+        new > old && *new >= old * margin
+    }
+
+    /// Return the number of transactions that were dropped because the
+    /// mempool was full, since the mempool was created.
+    pub fn num_evicted(&self) -> u64 {
+        self.num_evicted
+    }
+
+    /// The sequence number of the latest addition or removal.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Return the net additions and removals after sequence number `since`.
+    ///
+    /// A transaction that was both added and removed after the cursor is only
+    /// reported in the list matching its current status.
+    ///
+    /// Computes in O(k), where k is the number of changes after the cursor.
+    pub fn changes_since(&self, since: u64) -> MempoolChanges {
+        // sequence number of the change preceding the oldest remembered one
+        let forgotten = self.sequence - self.change_log.len() as u64;
+        let complete = forgotten <= since && since <= self.sequence;
+        let num_skipped = since
+            .saturating_sub(forgotten)
+            .try_into()
+            .unwrap_or(usize::MAX);
+
+        let mut added = vec![];
+        let mut removed = vec![];
+        let mut seen = HashSet::new();
+        for change in self.change_log.iter().skip(num_skipped) {
+            let (MempoolChange::Added(txid) | MempoolChange::Removed(txid)) = *change;
+            if !seen.insert(txid) {
+                continue;
+            }
+            if self.contains(txid) {
+                added.push(txid);
+            } else {
+                removed.push(txid);
+            }
+        }
+
+        MempoolChanges {
+            added,
+            removed,
+            sequence: self.sequence,
+            complete,
+        }
+    }
+
+    /// Record an addition or removal in the change log.
+    fn record_change(&mut self, change: MempoolChange) {
+        self.sequence += 1;
+        if self.change_log.len() == MEMPOOL_CHANGE_LOG_CAPACITY {
+            self.change_log.pop_front();
+        }
+        self.change_log.push_back(change);
+    }
+
     /// Update the block digest to which all transactions are synced.
     pub(super) fn set_tip_digest_sync_label(&mut self, tip_digest: Digest) {
         self.tip_digest = tip_digest;
@@ -294,6 +489,23 @@ impl Mempool {
             .map(|x| &x.transaction)
     }
 
+    /// Whether a transaction was initiated by this node or by a third party.
+    ///
+    /// Computes in O(1) from HashMap
+    pub(crate) fn origin(&self, transaction_id: TransactionKernelId) -> Option<TransactionOrigin> {
+        self.tx_dictionary.get(&transaction_id).map(|x| x.origin)
+    }
+
+    /// The path through which a transaction entered the mempool.
+    ///
+    /// Computes in O(1) from HashMap
+    pub(crate) fn ingress(
+        &self,
+        transaction_id: TransactionKernelId,
+    ) -> Option<TransactionIngress> {
+        self.tx_dictionary.get(&transaction_id).map(|x| x.ingress)
+    }
+
     /// get mutable reference to a transaction from mempool
     ///
     /// Computes in O(1) from HashMap
@@ -346,7 +558,8 @@ impl Mempool {
     /// this method may return:
     ///   n events: RemoveTx,AddTx.  tx replaces a list of older txs with lower fee.
     ///   1 event:  AddTx. tx does not replace an older one.
-    ///   0 events: tx not added because an older matching tx has a higher fee.
+    ///   0 events: tx not added because it does not outbid an older matching tx
+    ///             by the replace-by-fee margin.
     ///
     /// # Panics
     ///
@@ -355,6 +568,17 @@ impl Mempool {
         &mut self,
         new_tx: Transaction,
         origin: TransactionOrigin,
+    ) -> Vec<MempoolEvent> {
+        self.insert_with_ingress(new_tx, origin, origin.into())
+    }
+
+    /// Like [`Mempool::insert`], but records the path through which the
+    /// transaction arrived instead of deriving it from `origin`.
+    pub(crate) fn insert_with_ingress(
+        &mut self,
+        new_tx: Transaction,
+        origin: TransactionOrigin,
+        ingress: TransactionIngress,
     ) -> Vec<MempoolEvent> {
         fn new_tx_has_higher_proof_quality(
             new_tx: &Transaction,
@@ -403,7 +627,8 @@ impl Mempool {
         let min_fee_of_conflicts = conflicts.iter().map(|x| x.1.fee_density()).min();
         let conflicts = conflicts.into_iter().map(|x| x.0).collect_vec();
         if let Some(min_fee_of_conflicting_tx) = min_fee_of_conflicts {
-            let better_fee_density = min_fee_of_conflicting_tx < new_tx.fee_density();
+            let better_fee_density =
+                self.pays_replacement_fee(&new_tx.fee_density(), &min_fee_of_conflicting_tx);
             if new_tx_has_higher_proof_quality || better_fee_density {
                 for conflicting_txid in conflicts {
                     if let Some(e) = self.remove(conflicting_txid) {
@@ -411,11 +636,13 @@ impl Mempool {
                     }
                 }
             } else {
-                // If new transaction has a lower fee density than the one previous seen,
-                // ignore it. Stop execution here.
+                // If new transaction does not outbid the ones previously seen
+                // by the replace-by-fee margin, ignore it. Stop execution here.
                 debug!(
-                    "Attempted to insert transaction into mempool but it's \
-                     fee density was eclipsed by another transaction."
+                    "Attempted to insert transaction into mempool but its \
+                     fee density does not exceed that of the conflicting \
+                     transactions by {}%.",
+                    self.replace_by_fee_margin
                 );
                 return events;
             }
@@ -428,8 +655,10 @@ impl Mempool {
         let as_mempool_transaction = MempoolTransaction {
             transaction: new_tx.clone(),
             origin,
+            ingress,
         };
         self.tx_dictionary.insert(txid, as_mempool_transaction);
+        self.record_change(MempoolChange::Added(txid));
         events.push(MempoolEvent::AddTx(new_tx));
 
         assert_eq!(
@@ -453,6 +682,7 @@ impl Mempool {
         self.tx_dictionary.remove(&transaction_id).map(|tx| {
             self.queue.remove(&transaction_id);
             debug_assert_eq!(self.tx_dictionary.len(), self.queue.len());
+            self.record_change(MempoolChange::Removed(transaction_id));
             MempoolEvent::RemoveTx(tx.transaction)
         })
     }
@@ -477,6 +707,16 @@ impl Mempool {
         self.tx_dictionary.len()
     }
 
+    /// Iterate over all transactions in the mempool together with their
+    /// origin, in no particular order.
+    pub(crate) fn iter_with_origin(
+        &self,
+    ) -> impl Iterator<Item = (&Transaction, TransactionOrigin)> + '_ {
+        self.tx_dictionary
+            .values()
+            .map(|tx| (&tx.transaction, tx.origin))
+    }
+
     /// Return the number of transactions currently stored in the mempool that
     /// were initiated locally.
     ///
@@ -558,6 +798,7 @@ impl Mempool {
         if let Some((transaction_digest, fee_density)) = self.queue.pop_max() {
             if let Some(tx) = self.tx_dictionary.remove(&transaction_digest) {
                 debug_assert_eq!(self.tx_dictionary.len(), self.queue.len());
+                self.record_change(MempoolChange::Removed(transaction_digest));
 
                 let event = MempoolEvent::RemoveTx(tx.transaction);
 
@@ -571,10 +812,12 @@ impl Mempool {
     /// Returns the removed value.
     ///
     /// Computes in θ(lg N)
+    #[expect(dead_code)]
     fn pop_min(&mut self) -> Option<(MempoolEvent, FeeDensity)> {
         if let Some((transaction_digest, fee_density)) = self.queue.pop_min() {
             if let Some(tx) = self.tx_dictionary.remove(&transaction_digest) {
                 debug_assert_eq!(self.tx_dictionary.len(), self.queue.len());
+                self.record_change(MempoolChange::Removed(transaction_digest));
 
                 let event = MempoolEvent::RemoveTx(tx.transaction);
 
@@ -791,11 +1034,38 @@ impl Mempool {
         (events, update_jobs)
     }
 
+    /// Removes a single transaction, selected by the mempool's
+    /// [`MempoolEvictionPolicy`], and counts it as evicted. Returns the
+    /// removal event, or `None` if the mempool is empty.
+    ///
+    /// Computes in θ(lg N) for [`MempoolEvictionPolicy::LowestFee`], and in
+    /// O(N) otherwise.
+    fn evict_one(&mut self) -> Option<MempoolEvent> {
+        let victim = match self.eviction_policy {
+            MempoolEvictionPolicy::LowestFee => self.queue.peek_min().map(|(txid, _)| *txid),
+            MempoolEvictionPolicy::Oldest => self
+                .tx_dictionary
+                .iter()
+                .min_by_key(|(_, tx)| tx.transaction.kernel.timestamp)
+                .map(|(txid, _)| *txid),
+            MempoolEvictionPolicy::LargestSize => self
+                .tx_dictionary
+                .iter()
+                .max_by_key(|(_, tx)| tx.transaction.get_size())
+                .map(|(txid, _)| *txid),
+        }?;
+
+        let event = self.remove(victim)?;
+        self.num_evicted += 1;
+
+        Some(event)
+    }
+
     /// Shrink the memory pool to the value of its `max_size` field.
     /// Likely computes in O(n).
     fn shrink_to_max_size(&mut self) {
-        // Repeately remove the least valuable transaction
-        while self.get_size() > self.max_total_size && self.pop_min().is_some() {}
+        // Repeately remove a transaction selected by the eviction policy
+        while self.get_size() > self.max_total_size && self.evict_one().is_some() {}
 
         self.shrink_to_fit();
     }
@@ -804,7 +1074,7 @@ impl Mempool {
     /// if that field is set.
     fn shrink_to_max_length(&mut self) {
         if let Some(max_length) = self.max_length {
-            while self.len() > max_length && self.pop_min().is_some() {}
+            while self.len() > max_length && self.evict_one().is_some() {}
         }
 
         self.shrink_to_fit()
@@ -847,6 +1117,47 @@ impl Mempool {
         let dpq_clone = self.queue.clone();
         dpq_clone.into_sorted_iter().rev()
     }
+
+    /// Yields the transactions in order of descending fee per byte of
+    /// serialized transaction, along with their fee per byte. This is the
+    /// order in which they are most valuable to a miner with limited space.
+    ///
+    /// Computes in O(N lg N)
+    pub fn get_sorted_iter_by_fee_rate(
+        &self,
+    ) -> impl Iterator<Item = (&Transaction, NativeCurrencyAmount)> + '_ {
+        self.get_sorted_iter().filter_map(|(txid, _fee_density)| {
+            let transaction = self.get(txid)?;
+            Some((transaction, transaction.fee_per_byte()))
+        })
+    }
+
+    /// Yields the transaction IDs in order of descending fee, regardless of
+    /// the size of the transactions, along with their fee.
+    ///
+    /// Computes in O(N lg N)
+    pub fn get_sorted_iter_by_fee(
+        &self,
+    ) -> impl Iterator<Item = (TransactionKernelId, NativeCurrencyAmount)> + '_ {
+        self.tx_dictionary
+            .iter()
+            .map(|(txid, tx)| (*txid, tx.transaction.kernel.fee))
+            .sorted_by(|(_, fee_a), (_, fee_b)| fee_b.cmp(fee_a))
+    }
+
+    /// Yields the transaction IDs oldest first, as measured by their
+    /// timestamp, along with their timestamp. This is the order in which the
+    /// [`MempoolEvictionPolicy::Oldest`] policy drops them.
+    ///
+    /// Computes in O(N lg N)
+    pub fn get_sorted_iter_by_age(
+        &self,
+    ) -> impl Iterator<Item = (TransactionKernelId, Timestamp)> + '_ {
+        self.tx_dictionary
+            .iter()
+            .map(|(txid, tx)| (*txid, tx.transaction.kernel.timestamp))
+            .sorted_by_key(|(_, timestamp)| *timestamp)
+    }
 }
 
 #[cfg(test)]
@@ -888,10 +1199,12 @@ mod tests {
     use crate::models::state::wallet::wallet_entropy::WalletEntropy;
     use crate::models::state::GlobalStateLock;
     use crate::tests::shared::make_mock_block;
+    use crate::tests::shared::make_mock_transaction_with_mutator_set_hash_and_timestamp;
     use crate::tests::shared::make_mock_txs_with_primitive_witness_with_timestamp;
     use crate::tests::shared::make_plenty_mock_transaction_supported_by_invalid_single_proofs;
     use crate::tests::shared::make_plenty_mock_transaction_supported_by_primitive_witness;
     use crate::tests::shared::mock_genesis_global_state;
+    use crate::util_types::mutator_set::addition_record::AdditionRecord;
 
     #[tokio::test]
     pub async fn insert_then_get_then_remove_then_get() {
@@ -1768,6 +2081,84 @@ mod tests {
         );
     }
 
+    mod replace_by_fee {
+        use super::*;
+
+        fn with_fee(tx: &Transaction, coins: u32) -> Transaction {
+            Transaction {
+                kernel: TransactionKernelModifier::default()
+                    .fee(NativeCurrencyAmount::coins(coins))
+                    .modify(tx.kernel.clone()),
+                proof: tx.proof.clone(),
+            }
+        }
+
+        fn mempool_with(tx: &Transaction) -> Mempool {
+            let mut mempool = Mempool::new(ByteSize::gb(1), None, Digest::default())
+                .with_replace_by_fee_margin(10);
+            mempool.insert(tx.clone(), TransactionOrigin::Foreign);
+            mempool
+        }
+
+        #[test]
+        fn sufficient_bump_replaces_conflicting_transaction() {
+            let tx = make_plenty_mock_transaction_supported_by_invalid_single_proofs(1)
+                .pop()
+                .unwrap();
+            let original = with_fee(&tx, 100);
+            let replacement = with_fee(&tx, 110);
+            let mut mempool = mempool_with(&original);
+
+            let events = mempool.insert(replacement.clone(), TransactionOrigin::Foreign);
+            assert_eq!(
+                vec![
+                    MempoolEvent::RemoveTx(original.clone()),
+                    MempoolEvent::AddTx(replacement.clone())
+                ],
+                events
+            );
+            assert_eq!(1, mempool.len());
+            assert!(mempool.contains(replacement.kernel.txid()));
+            assert!(!mempool.contains(original.kernel.txid()));
+        }
+
+        #[test]
+        fn insufficient_bump_is_rejected() {
+            let tx = make_plenty_mock_transaction_supported_by_invalid_single_proofs(1)
+                .pop()
+                .unwrap();
+            let original = with_fee(&tx, 100);
+            let replacement = with_fee(&tx, 109);
+            let mut mempool = mempool_with(&original);
+
+            assert!(mempool
+                .insert(replacement.clone(), TransactionOrigin::Foreign)
+                .is_empty());
+            assert_eq!(1, mempool.len());
+            assert!(mempool.contains(original.kernel.txid()));
+            assert!(!mempool.contains(replacement.kernel.txid()));
+        }
+
+        #[test]
+        fn non_conflicting_transaction_is_inserted_regardless_of_fee() {
+            let [tx_a, tx_b]: [Transaction; 2] =
+                make_plenty_mock_transaction_supported_by_invalid_single_proofs(2)
+                    .try_into()
+                    .unwrap();
+            let expensive = with_fee(&tx_a, 100);
+            let cheap = with_fee(&tx_b, 1);
+            let mut mempool = mempool_with(&expensive);
+
+            assert_eq!(
+                vec![MempoolEvent::AddTx(cheap.clone())],
+                mempool.insert(cheap.clone(), TransactionOrigin::Foreign)
+            );
+            assert_eq!(2, mempool.len());
+            assert!(mempool.contains(expensive.kernel.txid()));
+            assert!(mempool.contains(cheap.kernel.txid()));
+        }
+    }
+
     #[traced_test]
     #[tokio::test]
     async fn conflicting_txs_preserve_highest_fee() {
@@ -1940,6 +2331,151 @@ mod tests {
         }
     }
 
+    #[test]
+    fn lower_fee_with_higher_fee_rate_is_ranked_first() {
+        let genesis_block = Block::genesis(Network::Main);
+        let make_tx = |fee: u32, num_outputs: usize| {
+            let outputs = (0..num_outputs)
+                .map(|_| AdditionRecord::new(random()))
+                .collect_vec();
+            let mut tx = make_mock_transaction_with_mutator_set_hash_and_timestamp(
+                vec![],
+                outputs,
+                Digest::default(),
+                Timestamp::now(),
+            );
+            tx.kernel = TransactionKernelModifier::default()
+                .fee(NativeCurrencyAmount::coins(fee))
+                .modify(tx.kernel);
+            tx
+        };
+        let small = make_tx(10, 0);
+        let large = make_tx(20, 100);
+        assert!(small.kernel.fee < large.kernel.fee);
+        assert!(small.fee_per_byte() > large.fee_per_byte());
+
+        let mut mempool = Mempool::new(ByteSize::gb(1), None, genesis_block.hash());
+        mempool.insert(large.clone(), TransactionOrigin::Foreign);
+        mempool.insert(small.clone(), TransactionOrigin::Foreign);
+
+        let ranked = mempool.get_sorted_iter_by_fee_rate().collect_vec();
+        assert_eq!(
+            vec![
+                (&small, small.fee_per_byte()),
+                (&large, large.fee_per_byte())
+            ],
+            ranked
+        );
+    }
+
+    #[test]
+    fn sorted_iters_rank_by_fee_fee_rate_and_age() {
+        let genesis_block = Block::genesis(Network::Main);
+        let now = Timestamp::now();
+        let make_tx = |fee: u32, timestamp: Timestamp, num_outputs: usize| {
+            let outputs = (0..num_outputs)
+                .map(|_| AdditionRecord::new(random()))
+                .collect_vec();
+            let mut tx = make_mock_transaction_with_mutator_set_hash_and_timestamp(
+                vec![],
+                outputs,
+                Digest::default(),
+                timestamp,
+            );
+            tx.kernel = TransactionKernelModifier::default()
+                .fee(NativeCurrencyAmount::coins(fee))
+                .modify(tx.kernel);
+            tx
+        };
+
+        // each order ranks the three transactions differently
+        let dense = make_tx(10, now - Timestamp::hours(1), 0);
+        let rich = make_tx(30, now, 100);
+        let old = make_tx(20, now - Timestamp::hours(2), 50);
+        assert!(dense.fee_density() > old.fee_density());
+        assert!(old.fee_density() > rich.fee_density());
+
+        let mut mempool = Mempool::new(ByteSize::gb(1), None, genesis_block.hash());
+        for tx in [&rich, &dense, &old] {
+            mempool.insert(tx.clone(), TransactionOrigin::Foreign);
+        }
+
+        let txids = |txs: [&Transaction; 3]| txs.map(|tx| tx.kernel.txid()).to_vec();
+        let by_fee_rate = mempool
+            .get_sorted_iter()
+            .map(|(txid, _)| txid)
+            .collect_vec();
+        assert_eq!(txids([&dense, &old, &rich]), by_fee_rate);
+
+        let by_fee = mempool.get_sorted_iter_by_fee().collect_vec();
+        assert_eq!(
+            txids([&rich, &old, &dense]),
+            by_fee.iter().map(|(id, _)| *id).collect_vec()
+        );
+        assert_eq!(rich.kernel.fee, by_fee[0].1);
+
+        let by_age = mempool.get_sorted_iter_by_age().collect_vec();
+        assert_eq!(
+            txids([&old, &dense, &rich]),
+            by_age.iter().map(|(id, _)| *id).collect_vec()
+        );
+        assert_eq!(old.kernel.timestamp, by_age[0].1);
+    }
+
+    #[test]
+    fn eviction_policy_selects_victim() {
+        let network = Network::Main;
+        let genesis_block = Block::genesis(network);
+        let now = Timestamp::now();
+
+        // Three transactions that each stand out on exactly one criterion.
+        let make_tx = |fee: u32, timestamp: Timestamp, num_outputs: usize| {
+            let outputs = (0..num_outputs)
+                .map(|_| AdditionRecord::new(random()))
+                .collect_vec();
+            let mut tx = make_mock_transaction_with_mutator_set_hash_and_timestamp(
+                vec![],
+                outputs,
+                Digest::default(),
+                timestamp,
+            );
+            tx.kernel = TransactionKernelModifier::default()
+                .fee(NativeCurrencyAmount::coins(fee))
+                .modify(tx.kernel);
+            tx
+        };
+        let lowest_fee = make_tx(1, now, 0);
+        let oldest = make_tx(10, now - Timestamp::hours(1), 0);
+        let largest = make_tx(1000, now, 20);
+        assert!(lowest_fee.fee_density() < oldest.fee_density());
+        assert!(oldest.fee_density() < largest.fee_density());
+        assert!(largest.get_size() > lowest_fee.get_size());
+
+        for (policy, expected_victim) in [
+            (MempoolEvictionPolicy::LowestFee, &lowest_fee),
+            (MempoolEvictionPolicy::Oldest, &oldest),
+            (MempoolEvictionPolicy::LargestSize, &largest),
+        ] {
+            let mut mempool = Mempool::new(ByteSize::gb(1), Some(2), genesis_block.hash())
+                .with_eviction_policy(policy);
+            assert_eq!(policy, mempool.eviction_policy());
+
+            for tx in [&lowest_fee, &oldest, &largest] {
+                mempool.insert(tx.clone(), TransactionOrigin::Foreign);
+            }
+
+            assert_eq!(2, mempool.len(), "policy: {policy:?}");
+            assert_eq!(1, mempool.num_evicted(), "policy: {policy:?}");
+            for tx in [&lowest_fee, &oldest, &largest] {
+                assert_eq!(
+                    tx != expected_victim,
+                    mempool.contains(tx.kernel.txid()),
+                    "policy: {policy:?}"
+                );
+            }
+        }
+    }
+
     #[traced_test]
     #[tokio::test]
     async fn get_mempool_size() {
@@ -1,8 +1,10 @@
 use std::collections::HashMap;
+use std::collections::VecDeque;
 
 use tasm_lib::prelude::Digest;
 
 use super::mining_status::MiningStatus;
+use crate::models::channel::BlockProposalNotification;
 use crate::models::state::BlockProposal;
 use crate::Block;
 
@@ -10,6 +12,13 @@ use crate::Block;
 /// be reached unless node is under some form of attack.
 pub const MAX_NUM_EXPORTED_BLOCK_PROPOSAL_STORED: usize = 10_000;
 
+/// Number of block proposal notifications remembered for inspection.
+pub const MAX_NUM_RECENT_PROPOSAL_NOTIFICATIONS: usize = 32;
+
+/// Number of block proposals a slow subscriber may fall behind before it
+/// misses some.
+pub const PROPOSAL_CHANNEL_CAPACITY: usize = 8;
+
 #[derive(Debug, Default)]
 pub(crate) struct MiningState {
     /// The block proposal to which guessers contribute proof-of-work. Can only be updated by
@@ -24,4 +33,63 @@ pub(crate) struct MiningState {
     /// since when.
     // Only the mining task should write to this, anyone can read.
     pub(crate) mining_status: MiningStatus,
+
+    /// Whether the miner was last asked, over RPC, to pause rather than to
+    /// resume. Mining may be inactive for other reasons, e.g. syncing.
+    pub(crate) paused: bool,
+
+    /// The most recent block proposal notifications sent to peers, oldest
+    /// first. Not persisted.
+    recent_proposal_notifications: VecDeque<BlockProposalNotification>,
+}
+
+impl MiningState {
+    /// Remember a block proposal notification, forgetting the oldest one if
+    /// [`MAX_NUM_RECENT_PROPOSAL_NOTIFICATIONS`] are already remembered.
+    pub(crate) fn record_proposal_notification(&mut self, notification: BlockProposalNotification) {
+        if self.recent_proposal_notifications.len() >= MAX_NUM_RECENT_PROPOSAL_NOTIFICATIONS {
+            self.recent_proposal_notifications.pop_front();
+        }
+        self.recent_proposal_notifications.push_back(notification);
+    }
+
+    /// The remembered block proposal notifications, most recent first.
+    pub(crate) fn recent_proposal_notifications(
+        &self,
+    ) -> impl Iterator<Item = &BlockProposalNotification> {
+        self.recent_proposal_notifications.iter().rev()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::models::blockchain::type_scripts::native_currency_amount::NativeCurrencyAmount;
+
+    fn notification(height: u64) -> BlockProposalNotification {
+        BlockProposalNotification {
+            body_mast_hash: Digest::default(),
+            guesser_fee: NativeCurrencyAmount::coins(1),
+            composer_fee: NativeCurrencyAmount::coins(3),
+            height: height.into(),
+        }
+    }
+
+    #[test]
+    fn only_most_recent_proposal_notifications_are_remembered() {
+        let mut mining_state = MiningState::default();
+        let num_notifications = MAX_NUM_RECENT_PROPOSAL_NOTIFICATIONS as u64 + 5;
+        for height in 0..num_notifications {
+            mining_state.record_proposal_notification(notification(height));
+        }
+
+        let expected = (5..num_notifications).rev().map(notification).collect_vec();
+        let remembered = mining_state
+            .recent_proposal_notifications()
+            .cloned()
+            .collect_vec();
+        assert_eq!(expected, remembered);
+    }
 }
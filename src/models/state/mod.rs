@@ -1,4 +1,5 @@
 pub mod archival_state;
+pub mod block_import;
 pub mod block_proposal;
 pub mod blockchain_state;
 pub mod light_state;
@@ -6,6 +7,8 @@ pub mod mempool;
 pub mod mining_state;
 pub mod mining_status;
 pub mod networking_state;
+pub(crate) mod orphan_blocks;
+pub mod reorganization;
 pub mod shared;
 pub mod transaction_details;
 pub mod transaction_kernel_id;
@@ -31,14 +34,19 @@ use anyhow::Result;
 use block_proposal::BlockProposal;
 use blockchain_state::BlockchainState;
 use mempool::Mempool;
+use mempool::TransactionIngress;
 use mempool::TransactionOrigin;
 use mining_state::MiningState;
+use mining_state::PROPOSAL_CHANNEL_CAPACITY;
 use mining_status::ComposingWorkInfo;
 use mining_status::GuessingWorkInfo;
 use mining_status::MiningStatus;
 use networking_state::NetworkingState;
 use num_traits::Zero;
+use reorganization::ReorgNotification;
+use reorganization::REORG_CHANNEL_CAPACITY;
 use tasm_lib::triton_vm::prelude::*;
+use tokio::sync::broadcast;
 use tracing::debug;
 use tracing::info;
 use tracing::warn;
@@ -581,6 +589,12 @@ pub struct GlobalState {
 
     /// The `mining_state` can be updated by main task, mining task, or RPC server.
     pub(crate) mining_state: MiningState,
+
+    /// Notifies subscribers, e.g. the RPC server, of reorganizations.
+    reorg_tx: broadcast::Sender<ReorgNotification>,
+
+    /// Notifies subscribers, e.g. the RPC server, of shared block proposals.
+    proposal_tx: broadcast::Sender<Arc<Block>>,
 }
 
 impl Drop for GlobalState {
@@ -614,6 +628,27 @@ impl GlobalState {
             cli,
             mempool,
             mining_state: MiningState::default(),
+            reorg_tx: broadcast::channel(REORG_CHANNEL_CAPACITY).0,
+            proposal_tx: broadcast::channel(PROPOSAL_CHANNEL_CAPACITY).0,
+        }
+    }
+
+    /// Receive a notification for every future reorganization, i.e., every new
+    /// tip that does not descend from the previous tip.
+    pub(crate) fn subscribe_to_reorgs(&self) -> broadcast::Receiver<ReorgNotification> {
+        self.reorg_tx.subscribe()
+    }
+
+    /// Receive every future block proposal that is shared with peers.
+    pub(crate) fn subscribe_to_block_proposals(&self) -> broadcast::Receiver<Arc<Block>> {
+        self.proposal_tx.subscribe()
+    }
+
+    /// Pass a block proposal that is shared with peers on to the subscribers,
+    /// if any.
+    pub(crate) fn publish_block_proposal(&self, proposal: &Block) {
+        if self.proposal_tx.receiver_count() > 0 {
+            let _ = self.proposal_tx.send(Arc::new(proposal.clone()));
         }
     }
 
@@ -1453,6 +1488,13 @@ impl GlobalState {
     ) -> Result<Vec<UpdateMutatorSetDataJob>> {
         crate::macros::log_scope_duration!();
 
+        let old_tip = self.chain.light_state().hash();
+        let reorg = if new_block.header().prev_block_digest == old_tip {
+            None
+        } else {
+            self.reorg_to(old_tip, &new_block).await
+        };
+
         // Apply the updates
         self.chain
             .archival_state_mut()
@@ -1490,6 +1532,13 @@ impl GlobalState {
         );
         let previous_ms_accumulator = tip_parent.mutator_set_accumulator_after().clone();
 
+        if let Some(prune_depth) = self.cli().prune_depth {
+            self.chain
+                .archival_state_mut()
+                .prune_block_bodies(new_block.header().height, prune_depth)
+                .await?;
+        }
+
         // Update mempool with UTXOs from this block. This is done by
         // removing all transaction that became invalid/was mined by this
         // block. Also returns the list of update-jobs that should be
@@ -1519,9 +1568,37 @@ impl GlobalState {
         // Flush databases
         self.flush_databases().await?;
 
+        if let Some(reorg) = reorg {
+            info!(
+                "Reorganized from {} to {}, above height {}",
+                reorg.old_tip, reorg.new_tip, reorg.common_ancestor_height
+            );
+            // having no subscribers is fine
+            let _ = self.reorg_tx.send(reorg);
+        }
+
         Ok(update_jobs)
     }
 
+    /// The reorganization caused by setting `new_tip` as tip instead of
+    /// `old_tip`, if any. The predecessor of `new_tip` must be stored.
+    async fn reorg_to(&self, old_tip: Digest, new_tip: &Block) -> Option<ReorgNotification> {
+        let archival_state = self.chain.archival_state();
+        let (_, common_ancestor, _) = archival_state
+            .find_path(old_tip, new_tip.header().prev_block_digest)
+            .await;
+        if common_ancestor == old_tip {
+            return None;
+        }
+
+        let common_ancestor_header = archival_state.get_block_header(common_ancestor).await?;
+        Some(ReorgNotification {
+            common_ancestor_height: common_ancestor_header.height,
+            old_tip,
+            new_tip: new_tip.hash(),
+        })
+    }
+
     /// resync membership proofs
     pub async fn resync_membership_proofs(&mut self) -> Result<()> {
         // Do not fix memberhip proofs if node is in sync mode, as we would otherwise
@@ -1551,6 +1628,11 @@ impl GlobalState {
         // Ok(())
     }
 
+    /// Respond to a sync challenge.
+    ///
+    /// Fails with [`BlockBodyPruned`](archival_state::BlockBodyPruned) if a
+    /// requested block's body has been pruned, in which case the challenge
+    /// cannot be answered by this node, but is not invalid.
     pub(crate) async fn response_to_sync_challenge(
         &self,
         sync_challenge: SyncChallenge,
@@ -1558,22 +1640,17 @@ impl GlobalState {
         async fn fetch_block_pair(
             state: &GlobalState,
             child_digest: Digest,
-        ) -> Option<(Block, Block)> {
-            let child = state
-                .chain
-                .archival_state()
-                .get_block(child_digest)
-                .await
-                .expect("fetching block from archival state should work.");
+        ) -> Result<Option<(Block, Block)>> {
+            let child = state.chain.archival_state().get_block(child_digest).await?;
             let Some(child) = child else {
                 warn!("Got sync challenge for unknown tip");
 
-                return None;
+                return Ok(None);
             };
             if child.header().height < 2.into() {
                 warn!("Got sync challenge for tip of too low height; cannot send genesis block");
 
-                return None;
+                return Ok(None);
             }
 
             let parent_digest = child.header().prev_block_digest;
@@ -1581,16 +1658,15 @@ impl GlobalState {
                 .chain
                 .archival_state()
                 .get_block(parent_digest)
-                .await
-                .expect("fetching block from archival state should work.")
+                .await?
                 .expect(
                     "parent of known block from archival state must exist, if height exceeds 1.",
                 );
 
-            Some((parent, child))
+            Ok(Some((parent, child)))
         }
 
-        let Some((tip_parent, tip)) = fetch_block_pair(self, sync_challenge.tip_digest).await
+        let Some((tip_parent, tip)) = fetch_block_pair(self, sync_challenge.tip_digest).await?
         else {
             bail!("could not fetch tip and tip predecessor");
         };
@@ -1623,7 +1699,7 @@ impl GlobalState {
             else {
                 bail!("could not get leaf from archival block mmr");
             };
-            let Some((p, c)) = fetch_block_pair(self, child_digest).await else {
+            let Some((p, c)) = fetch_block_pair(self, child_digest).await? else {
                 bail!("could not fetch indicated block pair");
             };
 
@@ -1729,6 +1805,20 @@ impl GlobalState {
         self.wallet_state.handle_mempool_events(events).await
     }
 
+    /// Like [`Self::mempool_insert`], but records the path through which the
+    /// transaction arrived.
+    pub(crate) async fn mempool_insert_with_ingress(
+        &mut self,
+        transaction: Transaction,
+        origin: TransactionOrigin,
+        ingress: TransactionIngress,
+    ) {
+        let events = self
+            .mempool
+            .insert_with_ingress(transaction, origin, ingress);
+        self.wallet_state.handle_mempool_events(events).await
+    }
+
     /// prunes stale tx in mempool and notifies wallet of changes.
     pub async fn mempool_prune_stale_transactions(&mut self) {
         let events = self.mempool.prune_stale_transactions();
@@ -3143,6 +3233,53 @@ mod global_state_tests {
         use twenty_first::prelude::Mmr;
 
         use super::*;
+        use crate::tests::shared::invalid_empty_block_with_timestamp;
+
+        #[tokio::test]
+        async fn reorganizations_are_notified_with_common_ancestor() {
+            let network = Network::Main;
+            let mut global_state_lock = mock_genesis_global_state(
+                network,
+                0,
+                WalletEntropy::new_random(),
+                cli_args::Args::default(),
+            )
+            .await;
+            let mut global_state = global_state_lock.lock_guard_mut().await;
+            let mut reorgs = global_state.subscribe_to_reorgs();
+
+            let genesis = Block::genesis(network);
+            let block_1 = invalid_empty_block(&genesis);
+            let block_2a = invalid_empty_block(&block_1);
+            let block_3a = invalid_empty_block(&block_2a);
+            let later = block_2a.header().timestamp + Timestamp::hours(1);
+            let block_2b = invalid_empty_block_with_timestamp(&block_1, later);
+
+            // extending the tip is no reorganization
+            for block in [&block_1, &block_2a, &block_3a] {
+                global_state.set_new_tip(block.clone()).await.unwrap();
+            }
+            assert!(reorgs.try_recv().is_err());
+
+            global_state.set_new_tip(block_2b.clone()).await.unwrap();
+            let expected = ReorgNotification {
+                common_ancestor_height: block_1.header().height,
+                old_tip: block_3a.hash(),
+                new_tip: block_2b.hash(),
+            };
+            assert_eq!(expected, reorgs.try_recv().unwrap());
+
+            // back to the first branch
+            let block_4a = invalid_empty_block(&block_3a);
+            global_state.set_new_tip(block_4a.clone()).await.unwrap();
+            let expected = ReorgNotification {
+                common_ancestor_height: block_1.header().height,
+                old_tip: block_2b.hash(),
+                new_tip: block_4a.hash(),
+            };
+            assert_eq!(expected, reorgs.try_recv().unwrap());
+            assert!(reorgs.try_recv().is_err());
+        }
 
         async fn assert_correct_global_state(
             global_state: &GlobalState,
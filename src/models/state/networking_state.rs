@@ -17,6 +17,7 @@ use crate::models::database::PeerDatabases;
 use crate::models::peer::peer_info::PeerInfo;
 use crate::models::peer::InstanceId;
 use crate::models::peer::PeerStanding;
+use crate::models::state::orphan_blocks::OrphanBlocks;
 
 pub const BANNED_IPS_DB_NAME: &str = "banned_ips";
 
@@ -106,10 +107,24 @@ pub struct NetworkingState {
     ///
     /// Only the peer tasks may update this map.
     disconnection_times: HashMap<InstanceId, SystemTime>,
+
+    /// Expiry times of temporary bans of peers whose standing fell below the
+    /// ban threshold.
+    ///
+    /// Only the main task may update this map.
+    ban_expiry_times: HashMap<IpAddr, SystemTime>,
+
+    /// Blocks received from peers whose parent is not known yet. Peer tasks
+    /// add to this buffer and take orphans out once their parent arrives.
+    pub(crate) orphan_blocks: OrphanBlocks,
 }
 
 impl NetworkingState {
-    pub(crate) fn new(peer_map: PeerMap, peer_databases: PeerDatabases) -> Self {
+    pub(crate) fn new(
+        peer_map: PeerMap,
+        peer_databases: PeerDatabases,
+        max_orphan_blocks: usize,
+    ) -> Self {
         Self {
             peer_map,
             peer_databases,
@@ -120,6 +135,8 @@ impl NetworkingState {
             // after startup of the client.
             last_tx_proof_upgrade_attempt: SystemTime::now(),
             disconnection_times: HashMap::new(),
+            ban_expiry_times: HashMap::new(),
+            orphan_blocks: OrphanBlocks::new(max_orphan_blocks),
         }
     }
 
@@ -139,16 +156,16 @@ impl NetworkingState {
 
     /// Return a list of peer sanctions stored in the database.
     pub fn all_peer_sanctions_in_database(&self) -> HashMap<IpAddr, PeerStanding> {
-        let mut sanctions = HashMap::default();
-
-        let mut dbiterator = self.peer_databases.peer_standings.iter();
-        for (ip, standing) in dbiterator.by_ref() {
-            if standing.is_negative() {
-                sanctions.insert(ip, standing);
-            }
-        }
+        self.all_peer_standings_in_database()
+            .into_iter()
+            .filter(|(_, standing)| standing.is_negative())
+            .collect()
+    }
 
-        sanctions
+    /// Return the standings of all peers stored in the database, whatever
+    /// their sign.
+    pub(crate) fn all_peer_standings_in_database(&self) -> HashMap<IpAddr, PeerStanding> {
+        self.peer_databases.peer_standings.iter().collect()
     }
 
     pub async fn get_peer_standing_from_database(&self, ip: IpAddr) -> Option<PeerStanding> {
@@ -218,4 +235,18 @@ impl NetworkingState {
     pub(crate) fn last_disconnection_time_of_peer(&self, id: InstanceId) -> Option<SystemTime> {
         self.disconnection_times.get(&id).copied()
     }
+
+    /// Refuse connections to and from `ip` until `expiry`.
+    ///
+    /// Only the main task may call this method.
+    pub(crate) fn ban_temporarily(&mut self, ip: IpAddr, expiry: SystemTime) {
+        self.ban_expiry_times.insert(ip, expiry);
+    }
+
+    /// Whether `ip` is under a temporary ban at time `now`.
+    pub(crate) fn is_temporarily_banned(&self, ip: IpAddr, now: SystemTime) -> bool {
+        self.ban_expiry_times
+            .get(&ip)
+            .is_some_and(|&expiry| now < expiry)
+    }
 }
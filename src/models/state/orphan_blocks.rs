@@ -0,0 +1,135 @@
+//! A bounded buffer for blocks whose parent is not known yet.
+//!
+//! A peer may relay a block before the node has its parent, for instance
+//! because the parent was announced by a different peer. Rather than dropping
+//! such a block, it is held here until its parent arrives, at which point it
+//! can be validated and applied.
+
+use std::collections::VecDeque;
+
+use tasm_lib::prelude::Digest;
+
+use crate::models::blockchain::block::Block;
+
+/// Blocks whose parent is unknown, oldest first. None of these blocks have
+/// been validated.
+#[derive(Debug, Clone)]
+pub(crate) struct OrphanBlocks {
+    capacity: usize,
+    blocks: VecDeque<Block>,
+}
+
+impl OrphanBlocks {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            blocks: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Hold on to `block` until its parent arrives. If the buffer is full, the
+    /// oldest orphan is dropped and returned. Blocks that are already held are
+    /// ignored.
+    pub(crate) fn insert(&mut self, block: Block) -> Option<Block> {
+        if self.capacity == 0 || self.contains(block.hash()) {
+            return None;
+        }
+
+        let evicted = if self.blocks.len() >= self.capacity {
+            self.blocks.pop_front()
+        } else {
+            None
+        };
+        self.blocks.push_back(block);
+
+        evicted
+    }
+
+    pub(crate) fn contains(&self, block_digest: Digest) -> bool {
+        self.blocks.iter().any(|block| block.hash() == block_digest)
+    }
+
+    /// Drop the orphan with the given digest, if held.
+    pub(crate) fn remove(&mut self, block_digest: Digest) {
+        self.blocks.retain(|block| block.hash() != block_digest);
+    }
+
+    /// Remove and return the orphans whose parent is `parent_digest`, oldest
+    /// first.
+    pub(crate) fn take_children(&mut self, parent_digest: Digest) -> Vec<Block> {
+        let (children, others): (Vec<_>, VecDeque<_>) = std::mem::take(&mut self.blocks)
+            .into_iter()
+            .partition(|block| block.header().prev_block_digest == parent_digest);
+        self.blocks = others;
+
+        children
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_models::network::Network;
+    use crate::models::proof_abstractions::timestamp::Timestamp;
+    use crate::tests::shared::invalid_empty_block;
+    use crate::tests::shared::invalid_empty_block_with_timestamp;
+
+    #[test]
+    fn full_buffer_evicts_oldest_orphan() {
+        let genesis = Block::genesis(Network::Main);
+        let block_1 = invalid_empty_block(&genesis);
+        let block_2 = invalid_empty_block(&block_1);
+        let block_3 = invalid_empty_block(&block_2);
+
+        let mut orphans = OrphanBlocks::new(2);
+        assert!(orphans.insert(block_1.clone()).is_none());
+        assert!(orphans.insert(block_2.clone()).is_none());
+        assert!(orphans.insert(block_2.clone()).is_none());
+        assert_eq!(2, orphans.len());
+
+        let evicted = orphans.insert(block_3.clone()).unwrap();
+        assert_eq!(block_1.hash(), evicted.hash());
+        assert_eq!(2, orphans.len());
+        assert!(!orphans.contains(block_1.hash()));
+        assert!(orphans.contains(block_3.hash()));
+    }
+
+    #[test]
+    fn take_children_removes_only_children() {
+        let genesis = Block::genesis(Network::Main);
+        let block_1 = invalid_empty_block(&genesis);
+        let timestamp = block_1.header().timestamp + Timestamp::hours(1);
+        let child_a = invalid_empty_block_with_timestamp(&block_1, timestamp);
+        let child_b = invalid_empty_block_with_timestamp(&block_1, timestamp + Timestamp::hours(1));
+        let grandchild = invalid_empty_block(&child_a);
+
+        let mut orphans = OrphanBlocks::new(10);
+        orphans.insert(child_a.clone());
+        orphans.insert(grandchild.clone());
+        orphans.insert(child_b.clone());
+
+        let children = orphans.take_children(block_1.hash());
+        assert_eq!(
+            vec![child_a.hash(), child_b.hash()],
+            children
+                .iter()
+                .map(|block| block.hash())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(1, orphans.len());
+        assert!(orphans.contains(grandchild.hash()));
+        assert!(orphans.take_children(block_1.hash()).is_empty());
+    }
+
+    #[test]
+    fn zero_capacity_holds_nothing() {
+        let genesis = Block::genesis(Network::Main);
+        let mut orphans = OrphanBlocks::new(0);
+        assert!(orphans.insert(invalid_empty_block(&genesis)).is_none());
+        assert_eq!(0, orphans.len());
+    }
+}
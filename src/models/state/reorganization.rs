@@ -0,0 +1,20 @@
+use serde::Deserialize;
+use serde::Serialize;
+use tasm_lib::prelude::Digest;
+
+use crate::models::blockchain::block::block_height::BlockHeight;
+
+/// Number of reorganization notifications a slow subscriber may fall behind
+/// before it misses some.
+pub const REORG_CHANNEL_CAPACITY: usize = 32;
+
+/// Sent when a new tip does not descend from the previous tip, so that the
+/// blocks above the common ancestor are no longer canonical.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReorgNotification {
+    /// Height of the last block that the old and the new canonical chain have
+    /// in common.
+    pub common_ancestor_height: BlockHeight,
+    pub old_tip: Digest,
+    pub new_tip: Digest,
+}
@@ -1,4 +1,5 @@
 use std::fmt::Display;
+use std::str::FromStr;
 
 use get_size2::GetSize;
 use itertools::Itertools;
@@ -9,6 +10,7 @@ use tasm_lib::triton_vm::prelude::Tip5;
 use tasm_lib::twenty_first::prelude::MerkleTree;
 
 use crate::models::blockchain::transaction::transaction_kernel::TransactionKernel;
+use crate::twenty_first::error::TryFromHexDigestError;
 
 /// A unique identifier of a transaction whose value is unaffected by a
 /// transaction update.
@@ -27,6 +29,15 @@ impl Display for TransactionKernelId {
     }
 }
 
+// note: this parses the output of impl Display for TransactionKernelId
+impl FromStr for TransactionKernelId {
+    type Err = TryFromHexDigestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Digest::try_from_hex(s).map(Self)
+    }
+}
+
 impl TransactionKernel {
     // Return a digest that is unchanged by transaction updates.
     ///
@@ -100,6 +100,36 @@ mod test {
         worker::test_bech32m_conversion(GenerationReceivingAddress::derive_from_seed(seed).into());
     }
 
+    /// tests that the round-trip check rejects encodings which decode to an
+    /// address but are not its canonical encoding
+    #[test]
+    fn bech32m_round_trip_check_rejects_non_canonical_encodings() {
+        use bech32::ToBase32;
+
+        let network = Network::Testnet;
+        let generation_address = GenerationReceivingAddress::derive_from_seed(Digest::default());
+        let address = ReceivingAddress::from(&generation_address);
+        let encoded = address.to_bech32m(network).unwrap();
+
+        let upper_case = encoded.to_uppercase();
+        assert!(ReceivingAddress::from_bech32m(&upper_case, network).is_ok());
+        assert!(ReceivingAddress::from_bech32m_checked(&upper_case, network).is_err());
+
+        let mut payload = bincode::serialize(&generation_address).unwrap();
+        payload.push(0);
+        let hrp = address.get_hrp(network);
+        let surplus_data =
+            bech32::encode(&hrp, payload.to_base32(), bech32::Variant::Bech32m).unwrap();
+        assert!(ReceivingAddress::from_bech32m(&surplus_data, network).is_ok());
+        assert!(ReceivingAddress::from_bech32m_checked(&surplus_data, network).is_err());
+
+        let mut flipped = encoded.clone().into_bytes();
+        let last = flipped.last_mut().unwrap();
+        *last = if *last == b'q' { b'p' } else { b'q' };
+        let flipped = String::from_utf8(flipped).unwrap();
+        assert!(ReceivingAddress::from_bech32m_checked(&flipped, network).is_err());
+    }
+
     mod worker {
         use super::*;
         use crate::models::blockchain::transaction::transaction_kernel::TransactionKernelModifier;
@@ -226,6 +256,11 @@ mod test {
 
             // 3. verify both addresses match
             assert_eq!(receiving_address, receiving_address_again);
+
+            // 4. verify that the encoding passes the round-trip check
+            let receiving_address_checked =
+                ReceivingAddress::from_bech32m_checked(&encoded, Network::Testnet).unwrap();
+            assert_eq!(receiving_address, receiving_address_checked);
         }
     }
 }
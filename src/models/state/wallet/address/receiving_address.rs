@@ -251,6 +251,21 @@ impl ReceivingAddress {
         // turn.
     }
 
+    /// parses an address from its bech32m encoding, and checks that encoding
+    /// the parsed address again yields the input.
+    ///
+    /// Unlike [Self::from_bech32m()], this rejects encodings that decode to an
+    /// address without being its canonical encoding, for instance upper-case
+    /// strings or strings carrying surplus data.
+    pub fn from_bech32m_checked(encoded: &str, network: Network) -> Result<Self> {
+        let address = Self::from_bech32m(encoded, network)?;
+        if address.to_bech32m(network)? != encoded {
+            bail!("bech32m string is not the canonical encoding of the address it decodes to");
+        }
+
+        Ok(address)
+    }
+
     /// returns human-readable-prefix (hrp) for a given network
     pub fn get_hrp(&self, network: Network) -> String {
         match self {
@@ -31,6 +31,7 @@ use super::address::symmetric_key;
 use super::address::BaseKeyType;
 use super::address::BaseSpendingKey;
 use super::address::KeyType;
+use super::address::ReceivingAddress;
 use super::address::SpendingKey;
 use super::coin_with_possible_timelock::CoinWithPossibleTimeLock;
 use super::expected_utxo::ExpectedUtxo;
@@ -1119,6 +1120,17 @@ impl WalletState {
         }
     }
 
+    /// Find the derivation index of the spending key that `address` belongs
+    /// to, scanning indices `0..max_index` of keys of the address's type.
+    ///
+    /// Returns `None` if no key in that range matches, for instance because
+    /// the address belongs to another wallet or was derived at a higher index.
+    pub fn derivation_index_of(&self, address: &ReceivingAddress, max_index: u64) -> Option<u64> {
+        let key_type = KeyType::from(address);
+        (0..max_index)
+            .find(|&index| self.nth_spending_key(key_type, index).to_address() == *address)
+    }
+
     /// Get the next unused generation spending key.
     ///
     /// returns key at present counter, and increments the counter.
@@ -3542,6 +3554,16 @@ pub(crate) mod tests {
             Ok(())
         }
 
+        /// tests that the derivation index of derived addresses is recovered,
+        /// for all key types.
+        #[traced_test]
+        #[tokio::test]
+        async fn derivation_index_of_derived_addresses_is_found() {
+            for key_type in KeyType::all_types() {
+                worker::derivation_index_of_derived_addresses_is_found(key_type).await
+            }
+        }
+
         mod worker {
             use super::*;
             use crate::database::storage::storage_schema::traits::StorageWriter;
@@ -3654,6 +3676,47 @@ pub(crate) mod tests {
 
                 Ok(())
             }
+
+            /// tests that the derivation index of an address is recovered for
+            /// a given key type.
+            ///
+            /// 1. Generate a mock WalletState and derive 5 addresses
+            /// 2. Verify each address is found at the index it was derived at
+            /// 3. Verify an address beyond the scanned range is not found
+            /// 4. Verify another wallet's address is not found
+            pub(super) async fn derivation_index_of_derived_addresses_is_found(key_type: KeyType) {
+                info!("key_type: {}", key_type);
+
+                // 1. Generate a mock WalletState and derive 5 addresses
+                let cli_args = cli_args::Args::default();
+                let wallet = mock_genesis_wallet_state(
+                    WalletEntropy::new_random(),
+                    Network::RegTest,
+                    &cli_args,
+                )
+                .await;
+                let addresses = (0..5)
+                    .map(|index| wallet.nth_spending_key(key_type, index).to_address())
+                    .collect_vec();
+
+                // 2. Verify each address is found at the index it was derived at
+                for (index, address) in (0..).zip(&addresses) {
+                    assert_eq!(Some(index), wallet.derivation_index_of(address, 10));
+                }
+
+                // 3. Verify an address beyond the scanned range is not found
+                assert_eq!(None, wallet.derivation_index_of(&addresses[4], 4));
+
+                // 4. Verify another wallet's address is not found
+                let other_wallet = mock_genesis_wallet_state(
+                    WalletEntropy::new_random(),
+                    Network::RegTest,
+                    &cli_args,
+                )
+                .await;
+                let other_address = other_wallet.nth_spending_key(key_type, 0).to_address();
+                assert_eq!(None, wallet.derivation_index_of(&other_address, 10));
+            }
         }
     }
 
@@ -36,6 +36,7 @@ use crate::models::blockchain::block::mutator_set_update::MutatorSetUpdate;
 use crate::models::blockchain::block::Block;
 use crate::models::blockchain::transaction::transaction_kernel::TransactionConfirmabilityError;
 use crate::models::blockchain::transaction::Transaction;
+use crate::models::blockchain::type_scripts::native_currency_amount::NativeCurrencyAmount;
 use crate::models::channel::MainToPeerTask;
 use crate::models::channel::PeerTaskToMain;
 use crate::models::channel::PeerTaskToMainTransaction;
@@ -55,6 +56,7 @@ use crate::models::peer::PositivePeerSanction;
 use crate::models::peer::SyncChallenge;
 use crate::models::proof_abstractions::mast_hash::MastHash;
 use crate::models::proof_abstractions::timestamp::Timestamp;
+use crate::models::state::archival_state::BlockBodyPruned;
 use crate::models::state::block_proposal::BlockProposalRejectError;
 use crate::models::state::mempool::MEMPOOL_IGNORE_TRANSACTIONS_THIS_MANY_SECS_AHEAD;
 use crate::models::state::mempool::MEMPOOL_TX_THRESHOLD_AGE_IN_SECS;
@@ -154,7 +156,9 @@ impl PeerLoopHandler {
 
     /// Punish a peer for bad behavior.
     ///
-    /// Return `Err` if the peer in question is (now) banned.
+    /// Return `Err` if the peer in question is (now) banned. If its standing
+    /// falls below the ban threshold but it is not banned yet, ask the main
+    /// task to ban it temporarily.
     ///
     /// # Locking:
     ///   * acquires `global_state_lock` for write
@@ -179,6 +183,15 @@ impl PeerLoopHandler {
             warn!("Banning peer: {err}");
         }
 
+        let ban_threshold = -i32::from(self.global_state_lock.cli().peer_ban_threshold);
+        let is_below_ban_threshold = peer_info.standing.standing < ban_threshold;
+        drop(global_state_mut);
+        if sanction_result.is_ok() && is_below_ban_threshold {
+            self.to_main_tx
+                .send(PeerTaskToMain::BanPeer(self.peer_address))
+                .await?;
+        }
+
         sanction_result.map_err(|err| anyhow::anyhow!("Banning peer: {err}"))
     }
 
@@ -279,7 +292,7 @@ impl PeerLoopHandler {
     ///  - Panics if called with the empty list.
     async fn handle_blocks(
         &mut self,
-        received_blocks: Vec<Block>,
+        mut received_blocks: Vec<Block>,
         parent_of_first_block: Block,
     ) -> Result<Option<BlockHeight>> {
         debug!(
@@ -298,7 +311,12 @@ impl PeerLoopHandler {
             let new_block_has_proof_of_work = new_block.has_proof_of_work(previous_block.header());
             debug!("new block has proof of work? {new_block_has_proof_of_work}");
             let new_block_is_valid = new_block
-                .is_valid(previous_block, now, self.global_state_lock.cli().network)
+                .is_valid_with_future_drift(
+                    previous_block,
+                    now,
+                    self.global_state_lock.cli().network,
+                    self.global_state_lock.cli().future_drift_limit(),
+                )
                 .await;
             debug!("new block is valid? {new_block_is_valid}");
             if !new_block_has_proof_of_work {
@@ -341,6 +359,12 @@ impl PeerLoopHandler {
             previous_block = new_block;
         }
 
+        // Orphans that were waiting for the received blocks can be validated
+        // now. They are sent along with the received blocks.
+        let number_of_received_blocks = received_blocks.len();
+        let orphans = self.connect_orphans(&received_blocks).await;
+        received_blocks.extend(orphans);
+
         // evaluate the fork choice rule
         debug!("Checking last block's canonicity ...");
         let last_block = received_blocks.last().unwrap();
@@ -372,7 +396,6 @@ impl PeerLoopHandler {
 
         // Send the new blocks to the main task which handles the state update
         // and storage to the database.
-        let number_of_received_blocks = received_blocks.len();
         self.to_main_tx
             .send(PeerTaskToMain::NewBlocks(received_blocks))
             .await?;
@@ -388,6 +411,63 @@ impl PeerLoopHandler {
         Ok(Some(last_block_height))
     }
 
+    /// Take the buffered orphans that descend from the last of the validated
+    /// `received_blocks` out of the orphan buffer, and return the longest
+    /// chain of them that is valid. Orphans that fail validation are dropped
+    /// without sanctioning this peer, as they may have been relayed by another
+    /// one. Only the oldest valid child of each block is kept; its siblings
+    /// are dropped.
+    ///
+    /// Locking:
+    ///   * Acquires `global_state_lock` for write.
+    async fn connect_orphans(&mut self, received_blocks: &[Block]) -> Vec<Block> {
+        let now = self.now();
+        let network = self.global_state_lock.cli().network;
+        let future_drift_limit = self.global_state_lock.cli().future_drift_limit();
+
+        let mut connected: Vec<Block> = vec![];
+        loop {
+            let parent = connected
+                .last()
+                .unwrap_or_else(|| received_blocks.last().unwrap());
+            let children = {
+                let mut global_state_mut = self.global_state_lock.lock_guard_mut().await;
+                let orphan_blocks = &mut global_state_mut.net.orphan_blocks;
+                for received_block in received_blocks {
+                    orphan_blocks.remove(received_block.hash());
+                }
+                orphan_blocks.take_children(parent.hash())
+            };
+
+            let mut valid_child = None;
+            for child in children {
+                let is_valid = child.has_proof_of_work(parent.header())
+                    && child
+                        .is_valid_with_future_drift(parent, now, network, future_drift_limit)
+                        .await;
+                if is_valid {
+                    info!(
+                        "Orphan block with height {} connects to received blocks",
+                        child.header().height
+                    );
+                    valid_child = Some(child);
+                    break;
+                } else {
+                    warn!(
+                        "Dropping invalid orphan block of height {}",
+                        child.header().height
+                    );
+                }
+            }
+
+            let Some(child) = valid_child else {
+                return connected;
+            };
+
+            connected.push(child);
+        }
+    }
+
     /// Take a single block received from a peer and (attempt to) find a path
     /// between the received block and a common ancestor stored in the blocks
     /// database.
@@ -397,7 +477,9 @@ impl PeerLoopHandler {
     ///  - If the parent is not stored, it is requested from the peer and the
     ///    received block is pushed to the fork reconciliation list for later
     ///    handling by this function. The fork reconciliation list starts out
-    ///    empty, but grows as more parents are requested and transmitted.
+    ///    empty, but grows as more parents are requested and transmitted. The
+    ///    received block is also put in the orphan buffer, so it can be
+    ///    applied when its parent arrives from any peer.
     ///  - If the parent is found in the database, a) block handling continues:
     ///    the entire list of fork reconciliation blocks are passed down the
     ///    pipeline, potentially leading to a state update; and b) the fork
@@ -417,24 +499,21 @@ impl PeerLoopHandler {
         <S as Sink<PeerMessage>>::Error: std::error::Error + Sync + Send + 'static,
         <S as TryStream>::Error: std::error::Error,
     {
-        // Does the received block match the fork reconciliation list?
+        // Does the received block match the fork reconciliation list? Only the
+        // link is checked here. The blocks are validated by `handle_blocks`
+        // once they connect to a stored block, so no effort is spent on
+        // blocks that may never do so.
         let received_block_matches_fork_reconciliation_list = if let Some(successor) =
             peer_state.fork_reconciliation_blocks.last()
         {
-            let valid = successor
-                .is_valid(
-                    received_block.as_ref(),
-                    self.now(),
-                    self.global_state_lock.cli().network,
-                )
-                .await;
-            if !valid {
+            let connects = successor.header().prev_block_digest == received_block.hash();
+            if !connects {
                 warn!(
-                        "Fork reconciliation failed after receiving {} blocks: successor of received block is invalid",
+                        "Fork reconciliation failed after receiving {} blocks: received block is not the parent of its successor",
                         peer_state.fork_reconciliation_blocks.len() + 1
                     );
             }
-            valid
+            connects
         } else {
             true
         };
@@ -474,26 +553,25 @@ impl PeerLoopHandler {
         let parent_digest = received_block_header.prev_block_digest;
         let parent_height = received_block_header.height.previous()
             .expect("transferred block must have previous height because genesis block cannot be transferred");
-        debug!("Try ensure path: fetching parent block");
-        let parent_block = self
+        let parent_is_known = self
             .global_state_lock
             .lock_guard()
             .await
             .chain
             .archival_state()
-            .get_block(parent_digest)
-            .await?;
+            .has_block(parent_digest)
+            .await;
         debug!(
-            "Completed parent block fetching from DB: {}",
-            if parent_block.is_some() {
-                "found".to_string()
+            "Try ensure path: parent block {}",
+            if parent_is_known {
+                "found"
             } else {
-                "not found".to_string()
+                "not found"
             }
         );
 
         // If parent is not known (but not genesis) request it.
-        let Some(parent_block) = parent_block else {
+        if !parent_is_known {
             if parent_height.is_genesis() {
                 peer_state.fork_reconciliation_blocks.clear();
                 self.punish(NegativePeerSanction::DifferentGenesis).await?;
@@ -504,10 +582,41 @@ impl PeerLoopHandler {
                 parent_height
             );
 
+            // Hold on to the block in case its parent arrives through another
+            // peer.
+            let orphan = peer_state
+                .fork_reconciliation_blocks
+                .last()
+                .unwrap()
+                .clone();
+            let evicted = self
+                .global_state_lock
+                .lock_guard_mut()
+                .await
+                .net
+                .orphan_blocks
+                .insert(orphan);
+            if let Some(evicted) = evicted {
+                debug!("Orphan block buffer full: dropped block {}", evicted.hash());
+            }
+
             peer.send(PeerMessage::BlockRequestByHash(parent_digest))
                 .await?;
 
             return Ok(());
+        }
+
+        debug!("Try ensure path: fetching parent block");
+        let Some(parent_block) = self
+            .global_state_lock
+            .lock_guard()
+            .await
+            .chain
+            .archival_state()
+            .get_block(parent_digest)
+            .await?
+        else {
+            bail!("Parent block {parent_digest} is known but could not be read");
         };
 
         // We want to treat the received fork reconciliation blocks (plus the
@@ -538,6 +647,47 @@ impl PeerLoopHandler {
         Ok(())
     }
 
+    /// Request the announced block proposal from the peer if it would be
+    /// favored over the current one. The composer's portion of the block
+    /// reward plays no role, so both versions of the notification are handled
+    /// alike.
+    async fn handle_block_proposal_notification<S>(
+        &self,
+        peer: &mut S,
+        height: BlockHeight,
+        guesser_fee: NativeCurrencyAmount,
+        body_mast_hash: Digest,
+    ) -> Result<()>
+    where
+        S: Sink<PeerMessage> + TryStream<Ok = PeerMessage> + Unpin,
+        <S as Sink<PeerMessage>>::Error: std::error::Error + Sync + Send + 'static,
+        <S as TryStream>::Error: std::error::Error,
+    {
+        let verdict = self
+            .global_state_lock
+            .lock_guard()
+            .await
+            .favor_incoming_block_proposal(height, guesser_fee);
+        match verdict {
+            Ok(_) => {
+                peer.send(PeerMessage::BlockProposalRequest(
+                    BlockProposalRequest::new(body_mast_hash),
+                ))
+                .await?
+            }
+            Err(reject_reason) => {
+                info!(
+                    "Rejecting notification of block proposal with guesser fee {} from peer \
+                    {}. Reason:\n{reject_reason}",
+                    guesser_fee.display_n_decimals(5),
+                    self.peer_address
+                )
+            }
+        }
+
+        Ok(())
+    }
+
     /// Handle peer messages and returns Ok(true) if connection should be closed.
     /// Connection should also be closed if an error is returned.
     /// Otherwise, returns OK(false).
@@ -569,6 +719,10 @@ impl PeerLoopHandler {
                 info!("Got bye. Closing connection to peer");
                 Ok(DISCONNECT_CONNECTION)
             }
+            PeerMessage::Disconnect(reason) => {
+                info!("Peer is disconnecting. Reason: {reason}. Closing connection to peer");
+                Ok(DISCONNECT_CONNECTION)
+            }
             PeerMessage::PeerListRequest => {
                 let peer_info = {
                     log_slow_scope!(fn_name!() + "::PeerMessage::PeerListRequest");
@@ -734,6 +888,11 @@ impl PeerLoopHandler {
 
                     match response {
                         Ok(resp) => resp,
+                        Err(e) if e.is::<BlockBodyPruned>() => {
+                            // Our own pruning, not the challenge, is at fault.
+                            info!("Cannot respond to sync challenge: {e}");
+                            return Ok(KEEP_CONNECTION_ALIVE);
+                        }
                         Err(e) => {
                             warn!("could not generate sync challenge response:\n{e}");
                             self.punish(NegativePeerSanction::InvalidSyncChallenge)
@@ -798,7 +957,11 @@ impl PeerLoopHandler {
                 let claimed_tip_height = challenge_response.tip.header.height;
                 let now = self.now();
                 if !challenge_response
-                    .is_valid(now, self.global_state_lock.cli().network)
+                    .is_valid(
+                        now,
+                        self.global_state_lock.cli().network,
+                        self.global_state_lock.cli().future_drift_limit(),
+                    )
                     .await
                 {
                     self.punish(NegativePeerSanction::InvalidSyncChallengeResponse)
@@ -849,6 +1012,7 @@ impl PeerLoopHandler {
                         claimed_height: claimed_tip_height,
                         claimed_cumulative_pow: issued_challenge.accumulated_pow,
                         claimed_block_mmra: sync_mmra_anchor,
+                        distance: self.distance,
                     })
                     .await?;
 
@@ -862,7 +1026,14 @@ impl PeerLoopHandler {
                     .chain
                     .archival_state()
                     .get_block(block_digest)
-                    .await?;
+                    .await;
+                let block = match block {
+                    Err(e) if e.is::<BlockBodyPruned>() => {
+                        debug!("Peer requested block {block_digest} whose body is pruned");
+                        return Ok(KEEP_CONNECTION_ALIVE);
+                    }
+                    block => block?,
+                };
 
                 match block {
                     None => {
@@ -913,15 +1084,21 @@ impl PeerLoopHandler {
                         Some(digest) => digest,
                     };
 
-                    let canonical_chain_block: Block = self
+                    let canonical_chain_block = self
                         .global_state_lock
                         .lock_guard()
                         .await
                         .chain
                         .archival_state()
                         .get_block(canonical_block_digest)
-                        .await?
-                        .unwrap();
+                        .await;
+                    let canonical_chain_block: Block = match canonical_chain_block {
+                        Err(e) if e.is::<BlockBodyPruned>() => {
+                            debug!("Got block request by height ({block_height}) for pruned block");
+                            return Ok(KEEP_CONNECTION_ALIVE);
+                        }
+                        block => block?.unwrap(),
+                    };
 
                     PeerMessage::Block(Box::new(canonical_chain_block.try_into().unwrap()))
                 };
@@ -1082,12 +1259,13 @@ impl PeerLoopHandler {
                 let mut returned_blocks: Vec<Block> =
                     Vec::with_capacity(digests_of_returned_blocks.len());
                 for block_digest in digests_of_returned_blocks {
-                    let block = state
-                        .chain
-                        .archival_state()
-                        .get_block(block_digest)
-                        .await?
-                        .unwrap();
+                    let block = match state.chain.archival_state().get_block(block_digest).await {
+                        Err(e) if e.is::<BlockBodyPruned>() => {
+                            debug!("Unable to satisfy batch-block request: bodies are pruned");
+                            return Ok(KEEP_CONNECTION_ALIVE);
+                        }
+                        block => block?.unwrap(),
+                    };
                     returned_blocks.push(block);
                 }
 
@@ -1148,15 +1326,23 @@ impl PeerLoopHandler {
                 // only valid for archival nodes.
                 let (first_block, _) = &authenticated_blocks[0];
                 let first_blocks_parent_digest: Digest = first_block.header.prev_block_digest;
-                let most_canonical_own_block_match: Option<Block> = self
+                let most_canonical_own_block_match = self
                     .global_state_lock
                     .lock_guard()
                     .await
                     .chain
                     .archival_state()
                     .get_block(first_blocks_parent_digest)
-                    .await
-                    .expect("Block lookup must succeed");
+                    .await;
+                let most_canonical_own_block_match: Option<Block> =
+                    match most_canonical_own_block_match {
+                        Err(e) if e.is::<BlockBodyPruned>() => {
+                            // Our own pruning, not the response, is at fault.
+                            info!("Cannot validate batch response: {e}");
+                            return Ok(KEEP_CONNECTION_ALIVE);
+                        }
+                        block => block?,
+                    };
                 let most_canonical_own_block_match: Block = match most_canonical_own_block_match {
                     Some(block) => block,
                     None => {
@@ -1454,31 +1640,25 @@ impl PeerLoopHandler {
 
                 Ok(KEEP_CONNECTION_ALIVE)
             }
-            PeerMessage::BlockProposalNotification(block_proposal_notification) => {
-                let verdict = self
-                    .global_state_lock
-                    .lock_guard()
-                    .await
-                    .favor_incoming_block_proposal(
-                        block_proposal_notification.height,
-                        block_proposal_notification.guesser_fee,
-                    );
-                match verdict {
-                    Ok(_) => {
-                        peer.send(PeerMessage::BlockProposalRequest(
-                            BlockProposalRequest::new(block_proposal_notification.body_mast_hash),
-                        ))
-                        .await?
-                    }
-                    Err(reject_reason) => {
-                        info!(
-                        "Rejecting notification of block proposal with guesser fee {} from peer \
-                        {}. Reason:\n{reject_reason}",
-                        block_proposal_notification.guesser_fee.display_n_decimals(5),
-                        self.peer_address
-                    )
-                    }
-                }
+            PeerMessage::BlockProposalNotification(notification) => {
+                self.handle_block_proposal_notification(
+                    peer,
+                    notification.height,
+                    notification.guesser_fee,
+                    notification.body_mast_hash,
+                )
+                .await?;
+
+                Ok(KEEP_CONNECTION_ALIVE)
+            }
+            PeerMessage::BlockProposalNotificationV2(notification) => {
+                self.handle_block_proposal_notification(
+                    peer,
+                    notification.height,
+                    notification.guesser_fee,
+                    notification.body_mast_hash,
+                )
+                .await?;
 
                 Ok(KEEP_CONNECTION_ALIVE)
             }
@@ -1535,7 +1715,12 @@ impl PeerLoopHandler {
                     } else {
                         // Verify validity and that proposal is child of current tip
                         if block
-                            .is_valid(&tip, self.now(), self.global_state_lock.cli().network)
+                            .is_valid_with_future_drift(
+                                &tip,
+                                self.now(),
+                                self.global_state_lock.cli().network,
+                                self.global_state_lock.cli().future_drift_limit(),
+                            )
                             .await
                         {
                             None // all is well, no punishment.
@@ -1645,13 +1830,20 @@ impl PeerLoopHandler {
                 peer.send(PeerMessage::PeerListRequest).await?;
                 Ok(KEEP_CONNECTION_ALIVE)
             }
-            MainToPeerTask::Disconnect(peer_address) => {
+            MainToPeerTask::Disconnect(peer_address, reason) => {
                 log_slow_scope!(fn_name!() + "::MainToPeerTask::Disconnect");
 
                 // Only disconnect from the peer the main task requested a disconnect for.
                 if peer_address != self.peer_address {
                     return Ok(KEEP_CONNECTION_ALIVE);
                 }
+
+                info!("Disconnecting from peer {peer_address}. Reason: {reason}");
+                if self.peer_handshake_data.supports_disconnect_reason() {
+                    if let Err(e) = peer.send(PeerMessage::Disconnect(reason)).await {
+                        debug!("Failed to inform peer {peer_address} of disconnect: {e}");
+                    }
+                }
                 self.register_peer_disconnection().await;
 
                 Ok(DISCONNECT_CONNECTION)
@@ -1677,12 +1869,18 @@ impl PeerLoopHandler {
                 Ok(KEEP_CONNECTION_ALIVE)
             }
             MainToPeerTask::BlockProposalNotification(block_proposal_notification) => {
-                debug!("Sending PeerMessage::BlockProposalNotification");
-                peer.send(PeerMessage::BlockProposalNotification(
-                    block_proposal_notification,
-                ))
-                .await?;
-                debug!("Sent PeerMessage::BlockProposalNotification");
+                let message = if self
+                    .peer_handshake_data
+                    .supports_block_proposal_notification_v2()
+                {
+                    PeerMessage::BlockProposalNotificationV2(block_proposal_notification)
+                } else {
+                    PeerMessage::BlockProposalNotification(block_proposal_notification.into())
+                };
+                let message_type = message.get_type();
+                debug!("Sending {message_type}");
+                peer.send(message).await?;
+                debug!("Sent {message_type}");
                 Ok(KEEP_CONNECTION_ALIVE)
             }
         }
@@ -1942,8 +2140,10 @@ mod peer_loop_tests {
     use crate::config_models::network::Network;
     use crate::models::blockchain::block::block_header::TARGET_BLOCK_INTERVAL;
     use crate::models::blockchain::type_scripts::native_currency_amount::NativeCurrencyAmount;
+    use crate::models::peer::handshake_data::VersionString;
     use crate::models::peer::peer_block_notifications::PeerBlockNotification;
     use crate::models::peer::transaction_notification::TransactionNotification;
+    use crate::models::peer::DisconnectReason;
     use crate::models::state::mempool::TransactionOrigin;
     use crate::models::state::tx_creation_config::TxCreationConfig;
     use crate::models::state::tx_proving_capability::TxProvingCapability;
@@ -1983,6 +2183,72 @@ mod peer_loop_tests {
         Ok(())
     }
 
+    #[traced_test]
+    #[tokio::test]
+    async fn disconnect_reason_is_logged_and_sent_to_peer() {
+        let reason = DisconnectReason::MaxPeerNumberExceeded;
+        let mut mock = Mock::new(vec![
+            Action::Write(PeerMessage::Disconnect(reason)),
+            Action::Read(PeerMessage::Bye),
+        ]);
+
+        let (_peer_broadcast_tx, _from_main_rx, to_main_tx, _to_main_rx, state_lock, hsd) =
+            get_test_genesis_setup(Network::Main, 1, cli_args::Args::default())
+                .await
+                .unwrap();
+        let peer_address = get_dummy_socket_address(0);
+        let mut peer_state = MutablePeerState::new(hsd.tip_header.height);
+        let mut peer_loop_handler =
+            PeerLoopHandler::new(to_main_tx, state_lock, peer_address, hsd, true, 1);
+
+        let keep_alive = peer_loop_handler
+            .handle_main_task_message(
+                MainToPeerTask::Disconnect(peer_address, reason),
+                &mut mock,
+                &mut peer_state,
+            )
+            .await
+            .unwrap();
+        assert_eq!(DISCONNECT_CONNECTION, keep_alive);
+        assert!(logs_contain("Reason: MaxPeerNumberExceeded"));
+
+        // The reason was written to the peer, so the next action is a read.
+        assert_eq!(Ok(Some(PeerMessage::Bye)), mock.try_next().await);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn disconnect_reason_is_not_sent_to_old_peer() {
+        // Peers that predate the disconnect message cannot decode it, so they
+        // are disconnected without being told why.
+        let reason = DisconnectReason::MaxPeerNumberExceeded;
+        let mut mock = Mock::new(vec![Action::Read(PeerMessage::Bye)]);
+
+        let (_peer_broadcast_tx, _from_main_rx, to_main_tx, _to_main_rx, state_lock, mut hsd) =
+            get_test_genesis_setup(Network::Main, 1, cli_args::Args::default())
+                .await
+                .unwrap();
+        hsd.version = VersionString::try_from_str("0.2.2").unwrap();
+        let peer_address = get_dummy_socket_address(0);
+        let mut peer_state = MutablePeerState::new(hsd.tip_header.height);
+        let mut peer_loop_handler =
+            PeerLoopHandler::new(to_main_tx, state_lock, peer_address, hsd, true, 1);
+
+        let keep_alive = peer_loop_handler
+            .handle_main_task_message(
+                MainToPeerTask::Disconnect(peer_address, reason),
+                &mut mock,
+                &mut peer_state,
+            )
+            .await
+            .unwrap();
+        assert_eq!(DISCONNECT_CONNECTION, keep_alive);
+        assert!(logs_contain("Reason: MaxPeerNumberExceeded"));
+
+        // Nothing was written to the peer, so the next action is a read.
+        assert_eq!(Ok(Some(PeerMessage::Bye)), mock.try_next().await);
+    }
+
     #[traced_test]
     #[tokio::test]
     async fn test_peer_loop_peer_list() {
@@ -2785,6 +3051,43 @@ mod peer_loop_tests {
         drop(to_main_rx1);
     }
 
+    #[traced_test]
+    #[tokio::test]
+    async fn peer_below_ban_threshold_is_reported_to_main() -> Result<()> {
+        let network = Network::Main;
+        let cli = cli_args::Args {
+            peer_ban_threshold: 1,
+            ..Default::default()
+        };
+        let (_peer_broadcast_tx, from_main_rx_clone, to_main_tx, mut to_main_rx1, state_lock, hsd) =
+            get_test_genesis_setup(network, 0, cli).await?;
+        let peer_address = get_dummy_socket_address(0);
+
+        // Each request for an unknown height lowers the standing by 1, so the
+        // second request brings it below the threshold.
+        let mock = Mock::new(vec![
+            Action::Read(PeerMessage::BlockRequestByHeight(5u64.into())),
+            Action::Read(PeerMessage::BlockRequestByHeight(5u64.into())),
+            Action::Read(PeerMessage::Bye),
+        ]);
+        let mut peer_loop_handler =
+            PeerLoopHandler::new(to_main_tx, state_lock, peer_address, hsd, false, 1);
+        peer_loop_handler
+            .run_wrapper(mock, from_main_rx_clone)
+            .await?;
+
+        match to_main_rx1.recv().await {
+            Some(PeerTaskToMain::BanPeer(address)) => assert_eq!(peer_address, address),
+            _ => bail!("Must ask main task to ban peer"),
+        }
+        match to_main_rx1.recv().await {
+            Some(PeerTaskToMain::RemovePeerMaxBlockHeight(_)) => (),
+            _ => bail!("Must receive remove of peer block max height"),
+        }
+
+        Ok(())
+    }
+
     #[traced_test]
     #[tokio::test]
     async fn test_peer_loop_receival_of_first_block() -> Result<()> {
@@ -2907,6 +3210,152 @@ mod peer_loop_tests {
         Ok(())
     }
 
+    #[traced_test]
+    #[tokio::test]
+    async fn fork_reconciliation_blocks_are_not_validated_before_they_connect() -> Result<()> {
+        // The client only knows the genesis block and receives block 3, which
+        // lies too far in the future to be valid. Its parent, block 2, is
+        // requested and received. As block 2 does not connect to a stored
+        // block either, block 3 must not be validated yet; instead, block 1 is
+        // requested and the peer is not punished.
+        let network = Network::Main;
+        let (_peer_broadcast_tx, from_main_rx_clone, to_main_tx, mut to_main_rx1, state_lock, hsd) =
+            get_test_genesis_setup(network, 0, cli_args::Args::default()).await?;
+        let peer_address = get_dummy_socket_address(0);
+        let genesis_block = Block::genesis(network);
+        let [block_1, block_2, block_3] = fake_valid_sequence_of_blocks_for_tests(
+            &genesis_block,
+            Timestamp::hours(1),
+            StdRng::seed_from_u64(5550002).random(),
+            network,
+        )
+        .await;
+
+        let mock = Mock::new(vec![
+            Action::Read(PeerMessage::Block(Box::new(
+                block_3.clone().try_into().unwrap(),
+            ))),
+            Action::Write(PeerMessage::BlockRequestByHash(block_2.hash())),
+            Action::Read(PeerMessage::Block(Box::new(
+                block_2.clone().try_into().unwrap(),
+            ))),
+            Action::Write(PeerMessage::BlockRequestByHash(block_1.hash())),
+            Action::Read(PeerMessage::Bye),
+        ]);
+
+        let mut peer_loop_handler = PeerLoopHandler::with_mocked_time(
+            to_main_tx.clone(),
+            state_lock.clone(),
+            peer_address,
+            hsd,
+            true,
+            1,
+            block_2.header().timestamp,
+        );
+        peer_loop_handler
+            .run_wrapper(mock, from_main_rx_clone)
+            .await?;
+
+        match to_main_rx1.recv().await {
+            Some(PeerTaskToMain::RemovePeerMaxBlockHeight(_)) => (),
+            _ => bail!("Must receive remove of peer block max height"),
+        }
+        assert_eq!(Err(TryRecvError::Empty), to_main_rx1.try_recv().map(|_| ()));
+
+        let standing = state_lock
+            .lock_guard()
+            .await
+            .net
+            .get_peer_standing_from_database(peer_address.ip())
+            .await;
+        assert!(standing.is_none_or(|standing| !standing.standing.is_negative()));
+
+        Ok(())
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn orphan_block_is_applied_once_parent_arrives_from_other_peer() -> Result<()> {
+        // One peer sends block 2 before the client knows block 1, and then
+        // disconnects. When another peer later sends block 1, the buffered
+        // block 2 must be applied along with it.
+        let network = Network::Main;
+        let (_, from_main_rx_clone, to_main_tx, mut to_main_rx1, state_lock, _hsd) =
+            get_test_genesis_setup(network, 0, cli_args::Args::default()).await?;
+        let genesis_block = Block::genesis(network);
+        let [block_1, block_2] = fake_valid_sequence_of_blocks_for_tests(
+            &genesis_block,
+            Timestamp::hours(1),
+            StdRng::seed_from_u64(5550003).random(),
+            network,
+        )
+        .await;
+
+        let (hsd_a, peer_address_a) = get_dummy_peer_connection_data_genesis(network, 1);
+        let mock = Mock::new(vec![
+            Action::Read(PeerMessage::Block(Box::new(
+                block_2.clone().try_into().unwrap(),
+            ))),
+            Action::Write(PeerMessage::BlockRequestByHash(block_1.hash())),
+            Action::Read(PeerMessage::Bye),
+        ]);
+        let mut peer_loop_handler = PeerLoopHandler::with_mocked_time(
+            to_main_tx.clone(),
+            state_lock.clone(),
+            peer_address_a,
+            hsd_a,
+            true,
+            1,
+            block_2.header().timestamp,
+        );
+        peer_loop_handler
+            .run_wrapper(mock, from_main_rx_clone.resubscribe())
+            .await?;
+
+        match to_main_rx1.recv().await {
+            Some(PeerTaskToMain::RemovePeerMaxBlockHeight(_)) => (),
+            _ => bail!("Must receive remove of peer block max height"),
+        }
+        assert_eq!(1, state_lock.lock_guard().await.net.orphan_blocks.len());
+
+        let (hsd_b, peer_address_b) = get_dummy_peer_connection_data_genesis(network, 2);
+        let mock = Mock::new(vec![
+            Action::Read(PeerMessage::Block(Box::new(
+                block_1.clone().try_into().unwrap(),
+            ))),
+            Action::Read(PeerMessage::Bye),
+        ]);
+        let mut peer_loop_handler = PeerLoopHandler::with_mocked_time(
+            to_main_tx.clone(),
+            state_lock.clone(),
+            peer_address_b,
+            hsd_b,
+            true,
+            1,
+            block_2.header().timestamp,
+        );
+        peer_loop_handler
+            .run_wrapper(mock, from_main_rx_clone)
+            .await?;
+
+        match to_main_rx1.recv().await {
+            Some(PeerTaskToMain::NewBlocks(blocks)) => {
+                assert_eq!(
+                    vec![block_1.hash(), block_2.hash()],
+                    blocks.iter().map(|block| block.hash()).collect_vec()
+                );
+            }
+            _ => bail!("Did not find msg sent to main task"),
+        };
+        match to_main_rx1.recv().await {
+            Some(PeerTaskToMain::RemovePeerMaxBlockHeight(_)) => (),
+            _ => bail!("Must receive remove of peer block max height"),
+        }
+        assert_eq!(0, state_lock.lock_guard().await.net.orphan_blocks.len());
+
+        Ok(())
+    }
+
     #[traced_test]
     #[tokio::test]
     async fn prevent_ram_exhaustion_test() -> Result<()> {
@@ -3410,7 +3859,7 @@ mod peer_loop_tests {
         // a peer of a transaction it doesn't know; the client must then request it.
 
         let network = Network::Main;
-        let (_peer_broadcast_tx, from_main_rx_clone, to_main_tx, mut to_main_rx1, state_lock, _hsd) =
+        let (_, from_main_rx_clone, to_main_tx, mut to_main_rx1, state_lock, _hsd) =
             get_test_genesis_setup(network, 1, cli_args::Args::default())
                 .await
                 .unwrap();
@@ -3574,7 +4023,10 @@ mod peer_loop_tests {
 
     mod block_proposals {
         use super::*;
+        use crate::models::channel::BlockProposalNotification;
+        use crate::models::peer::handshake_data::VersionString;
         use crate::tests::shared::get_dummy_handshake_data_for_genesis;
+        use crate::tests::shared::get_dummy_version;
 
         struct TestSetup {
             peer_loop_handler: PeerLoopHandler,
@@ -3660,7 +4112,8 @@ mod peer_loop_tests {
         async fn accept_block_proposal_notification_height_one() {
             // Node knows genesis block, receives a block proposal notification
             // for block 1 and must accept this by requesting the block
-            // proposal from peer.
+            // proposal from peer. Both versions of the notification are
+            // accepted.
             let TestSetup {
                 peer_broadcast_tx,
                 mut peer_loop_handler,
@@ -3681,6 +4134,10 @@ mod peer_loop_tests {
                 Action::Write(PeerMessage::BlockProposalRequest(
                     BlockProposalRequest::new(block1.body().mast_hash()),
                 )),
+                Action::Read(PeerMessage::BlockProposalNotificationV2((&block1).into())),
+                Action::Write(PeerMessage::BlockProposalRequest(
+                    BlockProposalRequest::new(block1.body().mast_hash()),
+                )),
                 Action::Read(PeerMessage::Bye),
             ]);
             peer_loop_handler
@@ -3691,6 +4148,46 @@ mod peer_loop_tests {
             drop(to_main_tx);
             drop(peer_broadcast_tx);
         }
+
+        #[traced_test]
+        #[tokio::test]
+        async fn block_proposal_notification_version_depends_on_peer_version() {
+            // Peers that predate the composer fee cannot decode the second
+            // version of the notification, so they must be sent the first.
+            // Peers running this version get the second.
+            let TestSetup {
+                mut peer_loop_handler,
+                mut peer_state,
+                ..
+            } = genesis_setup(Network::Main).await;
+            let block1 = fake_valid_block_for_tests(
+                &peer_loop_handler.global_state_lock,
+                StdRng::seed_from_u64(5550001).random(),
+            )
+            .await;
+            let notification = BlockProposalNotification::from(&block1);
+            let v1 = PeerMessage::BlockProposalNotification(notification.clone().into());
+            let v2 = PeerMessage::BlockProposalNotificationV2(notification.clone());
+
+            let version = |version: &str| VersionString::try_from_str(version).unwrap();
+            for (version, expected) in [
+                (version("0.2.2"), &v1),
+                (version("not-a-version"), &v1),
+                (get_dummy_version(), &v2),
+            ] {
+                peer_loop_handler.peer_handshake_data.version = version;
+                let mut mock = Mock::new(vec![Action::Write(expected.clone())]);
+                let keep_alive = peer_loop_handler
+                    .handle_main_task_message(
+                        MainToPeerTask::BlockProposalNotification(notification.clone()),
+                        &mut mock,
+                        &mut peer_state,
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(KEEP_CONNECTION_ALIVE, keep_alive);
+            }
+        }
     }
 
     mod proof_qualities {
@@ -3914,6 +4411,80 @@ mod peer_loop_tests {
             );
         }
 
+        #[traced_test]
+        #[tokio::test]
+        async fn sync_challenge_for_pruned_blocks_is_ignored() {
+            // Criterium: A node that has pruned the challenged blocks cannot
+            // respond, but the challenger must not be punished for that.
+
+            let network = Network::Main;
+            let (
+                _alice_main_to_peer_tx,
+                alice_main_to_peer_rx,
+                alice_peer_to_main_tx,
+                alice_peer_to_main_rx,
+                mut alice,
+                alice_hsd,
+            ) = get_test_genesis_setup(network, 0, cli_args::Args::default())
+                .await
+                .unwrap();
+            let genesis_block: Block = Block::genesis(network);
+            let blocks: [Block; 11] = fake_valid_sequence_of_blocks_for_tests(
+                &genesis_block,
+                Timestamp::hours(1),
+                [0u8; 32],
+                network,
+            )
+            .await;
+            for block in &blocks {
+                alice.set_new_tip(block.clone()).await.unwrap();
+            }
+            let tip_height = blocks.last().unwrap().header().height;
+            alice
+                .lock_guard_mut()
+                .await
+                .chain
+                .archival_state_mut()
+                .prune_block_bodies(tip_height, 5)
+                .await
+                .unwrap();
+
+            let sync_challenge = SyncChallenge {
+                tip_digest: blocks[10].hash(),
+                challenges: [blocks[2].header().height; 10],
+            };
+
+            // Alice sends nothing in response, so the mock holds no write.
+            let alice_p2p_messages = Mock::new(vec![
+                Action::Read(PeerMessage::SyncChallenge(sync_challenge)),
+                Action::Read(PeerMessage::Bye),
+            ]);
+
+            let peer_address = get_dummy_socket_address(0);
+            let mut alice_peer_loop_handler = PeerLoopHandler::new(
+                alice_peer_to_main_tx.clone(),
+                alice.clone(),
+                peer_address,
+                alice_hsd,
+                false,
+                1,
+            );
+            alice_peer_loop_handler
+                .run_wrapper(alice_p2p_messages, alice_main_to_peer_rx)
+                .await
+                .unwrap();
+
+            drop(alice_peer_to_main_rx);
+
+            let standing = alice
+                .lock_guard()
+                .await
+                .net
+                .get_peer_standing_from_database(peer_address.ip())
+                .await;
+            assert!(standing.is_none_or(|standing| standing.latest_punishment.is_none()));
+        }
+
         #[traced_test]
         #[tokio::test]
         async fn bad_sync_challenge_genesis_block_doesnt_crash_client() {
@@ -4097,6 +4668,7 @@ mod peer_loop_tests {
                 claimed_height: bob_tip.header().height,
                 claimed_cumulative_pow: bob_tip.header().cumulative_proof_of_work,
                 claimed_block_mmra: expected_anchor_mmra,
+                distance: 1,
             };
             let observed_message_from_alice_peer_loop = alice_peer_to_main_rx.recv().await.unwrap();
             assert_eq!(
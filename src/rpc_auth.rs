@@ -220,6 +220,20 @@ impl Cookie {
         }
     }
 
+    /// parse a cookie from its hex encoding, in either case
+    pub fn try_from_hex(hex: &str) -> Result<Self, error::AuthError> {
+        if hex.len() != 2 * 32 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(error::AuthError::InvalidCookie);
+        }
+
+        let mut bytes: CookieBytes = [0; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16)
+                .map_err(|_| error::AuthError::InvalidCookie)?;
+        }
+        Ok(Self(bytes))
+    }
+
     fn gen_secret() -> CookieBytes {
         rand::random()
     }
@@ -372,6 +386,26 @@ mod test {
             Ok(())
         }
 
+        /// tests hex decoding of cookies.
+        ///
+        /// tests:
+        ///  1. Cookie::try_from_hex() inverts Cookie::as_hex(), in either case
+        ///  2. Cookie::try_from_hex() rejects malformed input
+        #[test]
+        pub fn try_from_hex() {
+            let cookie = Cookie::new_in_mem();
+            let hex = cookie.as_hex();
+
+            assert_eq!(cookie, Cookie::try_from_hex(&hex).unwrap());
+            assert_eq!(cookie, Cookie::try_from_hex(&hex.to_lowercase()).unwrap());
+
+            let malformed: [&str; 3] = [&hex[1..], &format!("{hex}00"), &format!("+{}", &hex[1..])];
+            for malformed in malformed {
+                let result = Cookie::try_from_hex(malformed);
+                assert!(matches!(result, Err(error::AuthError::InvalidCookie)));
+            }
+        }
+
         // tests concurrent access to .cookie file.
         //
         // this test exists because previously some other tests would randomly
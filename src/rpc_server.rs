@@ -43,6 +43,8 @@
 //!
 //! Every RPC method returns an [RpcResult] which is wrapped inside a
 //! [tarpc::Response] by the rpc server.
+pub(crate) mod block_cache;
+
 use std::collections::HashMap;
 use std::net::IpAddr;
 use std::net::SocketAddr;
@@ -50,6 +52,7 @@ use std::sync::Arc;
 
 use anyhow::anyhow;
 use anyhow::Result;
+use block_cache::BlockCache;
 use get_size2::GetSize;
 use itertools::Itertools;
 use num_traits::Zero;
@@ -83,6 +86,7 @@ use crate::models::blockchain::transaction::transaction_proof::TransactionProofT
 use crate::models::blockchain::transaction::PublicAnnouncement;
 use crate::models::blockchain::transaction::Transaction;
 use crate::models::blockchain::transaction::TransactionProof;
+use crate::models::blockchain::type_scripts::native_currency_amount;
 use crate::models::blockchain::type_scripts::native_currency_amount::NativeCurrencyAmount;
 use crate::models::channel::ClaimUtxoData;
 use crate::models::channel::RPCServerToMain;
@@ -91,6 +95,8 @@ use crate::models::peer::InstanceId;
 use crate::models::peer::PeerStanding;
 use crate::models::proof_abstractions::mast_hash::MastHash;
 use crate::models::proof_abstractions::timestamp::Timestamp;
+use crate::models::state::archival_state::BlockBodyPruned;
+use crate::models::state::mempool::TransactionIngress;
 use crate::models::state::mining_state::MAX_NUM_EXPORTED_BLOCK_PROPOSAL_STORED;
 use crate::models::state::mining_status::MiningStatus;
 use crate::models::state::transaction_details::TransactionDetails;
@@ -154,7 +160,7 @@ pub struct DashBoardOverviewDataFromClient {
     pub cpu_temp: Option<f32>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MempoolTransactionInfo {
     pub id: TransactionKernelId,
     pub proof_type: TransactionProofType,
@@ -162,10 +168,22 @@ pub struct MempoolTransactionInfo {
     pub inputs: Vec<String>,
     pub num_outputs: usize,
     pub outputs: Vec<String>,
+    #[serde(with = "native_currency_amount::api_json")]
     pub positive_balance_effect: NativeCurrencyAmount,
+    #[serde(with = "native_currency_amount::api_json")]
     pub negative_balance_effect: NativeCurrencyAmount,
+    #[serde(with = "native_currency_amount::api_json")]
     pub fee: NativeCurrencyAmount,
+
+    /// The fee paid per byte of serialized transaction.
+    #[serde(default, with = "native_currency_amount::api_json")]
+    pub fee_per_byte: NativeCurrencyAmount,
     pub synced: bool,
+
+    /// How the transaction entered the mempool. `None` for information
+    /// produced by nodes that did not record this.
+    #[serde(default)]
+    pub ingress: Option<TransactionIngress>,
 }
 
 impl From<&Transaction> for MempoolTransactionInfo {
@@ -194,7 +212,9 @@ impl From<&Transaction> for MempoolTransactionInfo {
             positive_balance_effect: NativeCurrencyAmount::zero(),
             negative_balance_effect: NativeCurrencyAmount::zero(),
             fee: mptx.kernel.fee,
+            fee_per_byte: mptx.fee_per_byte(),
             synced: false,
+            ingress: None,
         }
     }
 }
@@ -220,6 +240,11 @@ impl MempoolTransactionInfo {
         self.synced = true;
         self
     }
+
+    pub(crate) fn with_ingress(mut self, ingress: TransactionIngress) -> Self {
+        self.ingress = Some(ingress);
+        self
+    }
 }
 
 /// Data required to attempt to solve the proof-of-work puzzle that allows the
@@ -251,7 +276,7 @@ pub struct ProofOfWorkPuzzle {
 impl ProofOfWorkPuzzle {
     /// Return a PoW puzzle assuming that the caller has already set the correct
     /// guesser digest.
-    fn new(block_proposal: Block, latest_block_header: BlockHeader) -> Self {
+    pub(crate) fn new(block_proposal: Block, latest_block_header: BlockHeader) -> Self {
         let guesser_reward = block_proposal.total_guesser_reward();
         let (kernel_auth_path, header_auth_path) = precalculate_block_auth_paths(&block_proposal);
         let threshold = latest_block_header.difficulty.target();
@@ -1212,6 +1237,10 @@ pub trait RPC {
 
     /// Determine whether the user-supplied string is a valid address
     ///
+    /// Only the canonical bech32m encoding of an address is accepted, so
+    /// strings that decode to an address but do not re-encode to the same
+    /// string, such as upper-case ones, are rejected.
+    ///
     /// ```no_run
     /// # use anyhow::Result;
     /// use neptune_cash::config_models::network::Network;
@@ -1973,6 +2002,9 @@ pub(crate) struct NeptuneRPCServer {
     // matches one of these.  there should only be one of each `Token` variant
     // in the list (dups ignored).
     valid_tokens: Vec<rpc_auth::Token>,
+
+    /// Recently fetched blocks, for the REST API.
+    pub(crate) block_cache: BlockCache,
 }
 
 impl NeptuneRPCServer {
@@ -1983,14 +2015,21 @@ impl NeptuneRPCServer {
         data_directory: DataDirectory,
         valid_tokens: Vec<rpc_auth::Token>,
     ) -> Self {
+        let block_cache = BlockCache::new(state.cli().rest_block_cache_size);
         Self {
             state,
             valid_tokens,
             rpc_server_to_main_tx,
             data_directory,
+            block_cache,
         }
     }
 
+    /// authenticate a token presented by a client of any RPC interface
+    pub(crate) fn auth(&self, token: &rpc_auth::Token) -> Result<(), rpc_auth::error::AuthError> {
+        token.auth(&self.valid_tokens)
+    }
+
     async fn confirmations_internal(&self, state: &GlobalState) -> Option<BlockHeight> {
         match state.get_latest_balance_height().await {
             Some(latest_balance_height) => {
@@ -2353,7 +2392,11 @@ impl RPC for NeptuneRPCServer {
         let tip_digest = state.chain.light_state().hash();
         let archival_state = state.chain.archival_state();
 
-        let Some(block) = archival_state.get_block(digest).await.unwrap() else {
+        let block = match archival_state.get_block(digest).await {
+            Err(e) if e.is::<BlockBodyPruned>() => return Ok(None),
+            block => block.unwrap(),
+        };
+        let Some(block) = block else {
             return Ok(None);
         };
         let is_canonical = archival_state
@@ -2392,7 +2435,11 @@ impl RPC for NeptuneRPCServer {
             return Ok(None);
         };
         let archival_state = state.chain.archival_state();
-        let Some(block) = archival_state.get_block(digest).await.unwrap() else {
+        let block = match archival_state.get_block(digest).await {
+            Err(e) if e.is::<BlockBodyPruned>() => return Ok(None),
+            block => block.unwrap(),
+        };
+        let Some(block) = block else {
             return Ok(None);
         };
 
@@ -2507,7 +2554,7 @@ impl RPC for NeptuneRPCServer {
         log_slow_scope!(fn_name!());
         token.auth(&self.valid_tokens)?;
 
-        let ret = ReceivingAddress::from_bech32m(&address_string, network).ok();
+        let ret = ReceivingAddress::from_bech32m_checked(&address_string, network).ok();
         tracing::debug!(
             "Responding to address validation request of {address_string}: {}",
             ret.is_some()
@@ -3153,7 +3200,7 @@ impl RPC for NeptuneRPCServer {
     }
 
     async fn pause_miner(
-        self,
+        mut self,
         _context: tarpc::context::Context,
         token: rpc_auth::Token,
     ) -> RpcResult<()> {
@@ -3165,6 +3212,7 @@ impl RPC for NeptuneRPCServer {
                 .rpc_server_to_main_tx
                 .send(RPCServerToMain::PauseMiner)
                 .await;
+            self.state.lock_guard_mut().await.mining_state.paused = true;
         } else {
             info!("Cannot pause miner since it was never started");
         }
@@ -3173,7 +3221,7 @@ impl RPC for NeptuneRPCServer {
 
     // documented in trait. do not add doc-comment.
     async fn restart_miner(
-        self,
+        mut self,
         _context: tarpc::context::Context,
         token: rpc_auth::Token,
     ) -> RpcResult<()> {
@@ -3185,6 +3233,7 @@ impl RPC for NeptuneRPCServer {
                 .rpc_server_to_main_tx
                 .send(RPCServerToMain::RestartMiner)
                 .await;
+            self.state.lock_guard_mut().await.mining_state.paused = false;
         } else {
             info!("Cannot restart miner since it was never started");
         }
@@ -3566,6 +3615,9 @@ impl RPC for NeptuneRPCServer {
                     if let Some(neg_effect) = outgoing.get(id) {
                         mptxi = Some(mptxi.unwrap().with_negative_effect_on_balance(*neg_effect));
                     }
+                    if let Some(ingress) = global_state.mempool.ingress(*id) {
+                        mptxi = Some(mptxi.unwrap().with_ingress(ingress));
+                    }
                 }
 
                 mptxi
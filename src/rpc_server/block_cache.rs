@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+
+use anyhow::Result;
+use tasm_lib::prelude::Digest;
+use tokio::sync::OnceCell;
+
+use crate::models::blockchain::block::Block;
+
+/// The most recently fetched blocks, keyed by digest, in front of the archival
+/// state.
+///
+/// The block stored under a digest never changes, so entries never become
+/// stale; a reorganization merely lets the blocks of the abandoned branch age
+/// out. Concurrent requests for the same uncached block share one read. Cheap
+/// to clone; clones share the cache.
+#[derive(Debug, Clone)]
+pub(crate) struct BlockCache {
+    capacity: usize,
+    inner: Arc<Mutex<BlockCacheInner>>,
+}
+
+#[derive(Debug, Default)]
+struct BlockCacheInner {
+    blocks: HashMap<Digest, Arc<Block>>,
+
+    /// Digests of the cached blocks, least recently used first.
+    recency: VecDeque<Digest>,
+
+    /// Reads in progress. Requests for a block that is being read wait for
+    /// that read instead of starting their own.
+    in_flight: HashMap<Digest, Arc<OnceCell<Option<Arc<Block>>>>>,
+}
+
+impl BlockCacheInner {
+    fn touch(&mut self, digest: Digest) {
+        if let Some(position) = self.recency.iter().position(|d| *d == digest) {
+            self.recency.remove(position);
+        }
+        self.recency.push_back(digest);
+    }
+}
+
+impl BlockCache {
+    /// A cache holding at most `capacity` blocks. A capacity of zero disables
+    /// caching.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Arc::default(),
+        }
+    }
+
+    /// The block with the given digest, from the cache if present, or else
+    /// from `load`, which is expected to read the archival state. If another
+    /// request is already reading the block, waits for that read instead of
+    /// calling `load`. A failed read is not shared; a waiting request then
+    /// tries its own.
+    pub(crate) async fn get_or_load<F, Fut>(
+        &self,
+        digest: Digest,
+        load: F,
+    ) -> Result<Option<Arc<Block>>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Option<Block>>>,
+    {
+        let read = {
+            let mut inner = self.lock();
+            if let Some(block) = inner.blocks.get(&digest).cloned() {
+                inner.touch(digest);
+                return Ok(Some(block));
+            }
+            inner.in_flight.entry(digest).or_default().clone()
+        };
+
+        let result = read
+            .get_or_try_init(|| async { Ok(load().await?.map(Arc::new)) })
+            .await
+            .cloned();
+
+        // Remove the read whether or not it failed, such that a failed read
+        // does not linger, unless a later request has replaced it already.
+        let mut inner = self.lock();
+        if inner
+            .in_flight
+            .get(&digest)
+            .is_some_and(|in_flight| Arc::ptr_eq(in_flight, &read))
+        {
+            inner.in_flight.remove(&digest);
+        }
+        if let Ok(Some(block)) = &result {
+            self.insert(&mut inner, digest, block.clone());
+        }
+
+        result
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BlockCacheInner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn insert(&self, inner: &mut BlockCacheInner, digest: Digest, block: Arc<Block>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        inner.blocks.insert(digest, block);
+        inner.touch(digest);
+        while inner.recency.len() > self.capacity {
+            let Some(evicted) = inner.recency.pop_front() else {
+                break;
+            };
+            inner.blocks.remove(&evicted);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    use futures::future::join_all;
+
+    use super::*;
+    use crate::config_models::network::Network;
+    use crate::tests::shared::invalid_empty_block;
+
+    /// Stands in for the archival state, counting the reads.
+    struct CountingBlockStore {
+        blocks: Vec<Block>,
+        num_reads: AtomicUsize,
+    }
+
+    impl CountingBlockStore {
+        async fn get_block(&self, digest: Digest) -> Result<Option<Block>> {
+            self.num_reads.fetch_add(1, Ordering::SeqCst);
+            Ok(self.blocks.iter().find(|b| b.hash() == digest).cloned())
+        }
+
+        fn num_reads(&self) -> usize {
+            self.num_reads.load(Ordering::SeqCst)
+        }
+    }
+
+    #[tokio::test]
+    async fn cached_blocks_are_not_read_again_until_evicted() {
+        let genesis = Block::genesis(Network::Main);
+        let block_1 = invalid_empty_block(&genesis);
+        let block_2 = invalid_empty_block(&block_1);
+        let store = CountingBlockStore {
+            blocks: vec![genesis.clone(), block_1.clone(), block_2.clone()],
+            num_reads: AtomicUsize::new(0),
+        };
+        let cache = BlockCache::new(2);
+        let (store_ref, cache_ref) = (&store, &cache);
+        let fetch = move |block: &Block| {
+            let digest = block.hash();
+            let (store, cache) = (store_ref, cache_ref);
+            async move {
+                cache
+                    .get_or_load(digest, || store.get_block(digest))
+                    .await
+                    .unwrap()
+                    .unwrap()
+            }
+        };
+
+        assert_eq!(genesis.hash(), fetch(&genesis).await.hash());
+        assert_eq!(1, store.num_reads());
+        assert_eq!(genesis.hash(), fetch(&genesis).await.hash());
+        assert_eq!(1, store.num_reads());
+
+        // the genesis block is used more recently than block 1, so block 1 is
+        // evicted when block 2 is cached
+        fetch(&block_1).await;
+        fetch(&genesis).await;
+        fetch(&block_2).await;
+        assert_eq!(3, store.num_reads());
+        fetch(&genesis).await;
+        fetch(&block_2).await;
+        assert_eq!(3, store.num_reads());
+        fetch(&block_1).await;
+        assert_eq!(4, store.num_reads());
+
+        // unknown blocks are not cached
+        let unknown = invalid_empty_block(&block_2).hash();
+        for _ in 0..2 {
+            let block = cache
+                .get_or_load(unknown, || store.get_block(unknown))
+                .await;
+            assert!(block.unwrap().is_none());
+        }
+        assert_eq!(6, store.num_reads());
+    }
+
+    #[tokio::test]
+    async fn cache_of_capacity_zero_always_reads() {
+        let genesis = Block::genesis(Network::Main);
+        let store = CountingBlockStore {
+            blocks: vec![genesis.clone()],
+            num_reads: AtomicUsize::new(0),
+        };
+        let cache = BlockCache::new(0);
+        for _ in 0..2 {
+            let digest = genesis.hash();
+            let block = cache.get_or_load(digest, || store.get_block(digest)).await;
+            assert!(block.unwrap().is_some());
+        }
+        assert_eq!(2, store.num_reads());
+    }
+
+    #[tokio::test]
+    async fn concurrent_requests_for_one_block_share_one_read() {
+        let genesis = Block::genesis(Network::Main);
+        let store = CountingBlockStore {
+            blocks: vec![genesis.clone()],
+            num_reads: AtomicUsize::new(0),
+        };
+
+        // coalescing does not rely on the block being cached afterwards
+        for capacity in [0, 2] {
+            let cache = BlockCache::new(capacity);
+            let digest = genesis.hash();
+            let store = &store;
+            let slow_read = move || async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                store.get_block(digest).await
+            };
+            let requests = (0..10).map(|_| cache.get_or_load(digest, slow_read));
+            for block in join_all(requests).await {
+                assert_eq!(digest, block.unwrap().unwrap().hash());
+            }
+            assert_eq!(1, store.num_reads());
+            store.num_reads.store(0, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_read_is_not_kept_in_flight() {
+        let digest = Block::genesis(Network::Main).hash();
+        let cache = BlockCache::new(2);
+        let failing_read = || async { Err(anyhow::anyhow!("read failed")) };
+        assert!(cache.get_or_load(digest, failing_read).await.is_err());
+        assert!(cache.lock().in_flight.is_empty());
+        assert!(cache.lock().blocks.is_empty());
+    }
+}
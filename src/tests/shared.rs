@@ -223,7 +223,7 @@ pub(crate) async fn mock_genesis_global_state(
             std::net::SocketAddr::from_str(&format!("123.123.123.{}:8080", i)).unwrap();
         peer_map.insert(peer_address, get_dummy_peer_outgoing(peer_address));
     }
-    let networking_state = NetworkingState::new(peer_map, peer_db);
+    let networking_state = NetworkingState::new(peer_map, peer_db, cli.max_orphan_blocks);
     let genesis_block = archival_state.get_tip().await;
 
     // Sanity check
@@ -242,7 +242,9 @@ pub(crate) async fn mock_genesis_global_state(
         cli.max_mempool_size,
         cli.max_mempool_num_tx,
         genesis_block.hash(),
-    );
+    )
+    .with_eviction_policy(cli.mempool_eviction_policy)
+    .with_replace_by_fee_margin(cli.replace_by_fee_margin);
 
     let wallet_state = mock_genesis_wallet_state(wallet, network, &cli).await;
 
@@ -0,0 +1,212 @@
+//! Persists the mempool to the pool's SQLite store, so that its transactions
+//! survive a restart of the node instead of having to be rebroadcast.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use itertools::Itertools;
+use tokio::time::MissedTickBehavior;
+use tracing::debug;
+use tracing::warn;
+
+use super::PoolState;
+use crate::models::blockchain::transaction::Transaction;
+use crate::models::proof_abstractions::timestamp::Timestamp;
+use crate::models::state::mempool::TransactionOrigin;
+use crate::models::state::mempool::MEMPOOL_TX_THRESHOLD_AGE_IN_SECS;
+use crate::models::state::GlobalStateLock;
+
+/// How often the mempool is written to disk.
+pub const MEMPOOL_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Write all mempool transactions to the snapshot table, replacing the previous
+/// snapshot. Returns the number of transactions written.
+pub async fn save(pool_state: &PoolState, global_state_lock: &GlobalStateLock) -> Result<usize> {
+    let transactions = global_state_lock
+        .lock_guard()
+        .await
+        .mempool
+        .iter_with_origin()
+        .map(|(tx, origin)| (tx.clone(), origin))
+        .collect_vec();
+
+    let entries = transactions
+        .iter()
+        .map(|(tx, origin)| {
+            Ok((
+                tx.kernel.txid().to_string(),
+                bincode::serialize(tx)?,
+                *origin == TransactionOrigin::Own,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    pool_state.replace_mempool_snapshot(&entries)?;
+
+    Ok(entries.len())
+}
+
+/// Insert the transactions of the last snapshot into the mempool. Returns the
+/// number of restored transactions.
+///
+/// Transactions older than [`MEMPOOL_TX_THRESHOLD_AGE_IN_SECS`] are skipped, as
+/// are transactions not synced to the mutator set of the current tip: those
+/// were either confirmed while the node was down, or cannot be confirmed
+/// without first being updated.
+pub async fn restore(
+    pool_state: &PoolState,
+    global_state_lock: &mut GlobalStateLock,
+) -> Result<usize> {
+    let snapshot = pool_state.mempool_snapshot()?;
+    let cutoff = Timestamp::now() - Timestamp::seconds(MEMPOOL_TX_THRESHOLD_AGE_IN_SECS);
+
+    let mut state = global_state_lock.lock_guard_mut().await;
+    let tip_mutator_set_hash = state
+        .chain
+        .light_state()
+        .mutator_set_accumulator_after()
+        .hash();
+
+    let mut num_restored = 0;
+    for (raw_tx, is_own) in snapshot {
+        let transaction: Transaction = match bincode::deserialize(&raw_tx) {
+            Ok(tx) => tx,
+            Err(e) => {
+                warn!("Skipping undecodable transaction in mempool snapshot: {e}");
+                continue;
+            }
+        };
+        let txid = transaction.kernel.txid();
+
+        if transaction.kernel.timestamp <= cutoff {
+            debug!("Not restoring stale transaction {txid} from mempool snapshot");
+            continue;
+        }
+        if transaction.kernel.mutator_set_hash != tip_mutator_set_hash {
+            debug!("Not restoring unsynced transaction {txid} from mempool snapshot");
+            continue;
+        }
+
+        let origin = if is_own {
+            TransactionOrigin::Own
+        } else {
+            TransactionOrigin::Foreign
+        };
+        state.mempool_insert(transaction, origin).await;
+        num_restored += 1;
+    }
+
+    Ok(num_restored)
+}
+
+/// Snapshot the mempool every [`MEMPOOL_SNAPSHOT_INTERVAL`], forever.
+pub async fn run(pool_state: PoolState, global_state_lock: GlobalStateLock) {
+    let mut interval = tokio::time::interval(MEMPOOL_SNAPSHOT_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    // the first tick completes immediately, right after the mempool was
+    // restored. There is nothing new to save yet.
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        match save(&pool_state, &global_state_lock).await {
+            Ok(num_saved) => debug!("Saved {num_saved} mempool transactions to disk"),
+            Err(e) => warn!("Failed to save mempool snapshot: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tasm_lib::prelude::Digest;
+
+    use super::*;
+    use crate::config_models::cli_args;
+    use crate::config_models::network::Network;
+    use crate::models::state::wallet::wallet_entropy::WalletEntropy;
+    use crate::tests::shared::make_mock_transaction_with_mutator_set_hash_and_timestamp;
+    use crate::tests::shared::mock_genesis_global_state;
+    use crate::tests::shared::unit_test_data_directory;
+    use crate::tx_pool::DEFAULT_BUSY_TIMEOUT;
+
+    #[tokio::test]
+    async fn mempool_is_restored_after_restart() {
+        let network = Network::Main;
+        let mut state = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        let msa_hash = state
+            .lock_guard()
+            .await
+            .chain
+            .light_state()
+            .mutator_set_accumulator_after()
+            .hash();
+
+        let now = Timestamp::now();
+        let own_tx = make_mock_transaction_with_mutator_set_hash_and_timestamp(
+            vec![],
+            vec![],
+            msa_hash,
+            now,
+        );
+        let foreign_tx = make_mock_transaction_with_mutator_set_hash_and_timestamp(
+            vec![],
+            vec![],
+            msa_hash,
+            now - Timestamp::seconds(1),
+        );
+        let stale_tx = make_mock_transaction_with_mutator_set_hash_and_timestamp(
+            vec![],
+            vec![],
+            msa_hash,
+            now - Timestamp::seconds(MEMPOOL_TX_THRESHOLD_AGE_IN_SECS + 60),
+        );
+        let unsynced_tx = make_mock_transaction_with_mutator_set_hash_and_timestamp(
+            vec![],
+            vec![],
+            Digest::default(),
+            now - Timestamp::seconds(2),
+        );
+
+        {
+            let mut global_state = state.lock_guard_mut().await;
+            global_state
+                .mempool_insert(own_tx.clone(), TransactionOrigin::Own)
+                .await;
+            for tx in [&foreign_tx, &stale_tx, &unsynced_tx] {
+                global_state
+                    .mempool_insert(tx.clone(), TransactionOrigin::Foreign)
+                    .await;
+            }
+            assert_eq!(4, global_state.mempool.len());
+        }
+
+        let data_directory = unit_test_data_directory(network).unwrap();
+        let pool_state = PoolState::new(&data_directory, DEFAULT_BUSY_TIMEOUT).unwrap();
+        assert_eq!(4, save(&pool_state, &state).await.unwrap());
+
+        // simulate a restart: a fresh node with an empty mempool
+        let mut restarted = mock_genesis_global_state(
+            network,
+            0,
+            WalletEntropy::new_random(),
+            cli_args::Args::default(),
+        )
+        .await;
+        assert!(restarted.lock_guard().await.mempool.is_empty());
+
+        assert_eq!(2, restore(&pool_state, &mut restarted).await.unwrap());
+        let global_state = restarted.lock_guard().await;
+        assert_eq!(2, global_state.mempool.len());
+        assert!(global_state.mempool.contains(own_tx.kernel.txid()));
+        assert!(global_state.mempool.contains(foreign_tx.kernel.txid()));
+        assert!(!global_state.mempool.contains(stale_tx.kernel.txid()));
+        assert!(!global_state.mempool.contains(unsynced_tx.kernel.txid()));
+        assert_eq!(1, global_state.mempool.num_own_txs());
+    }
+}
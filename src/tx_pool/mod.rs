@@ -1,38 +1,206 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    fmt::Display,
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use anyhow::{bail, Result};
 use rand::Rng;
+use serde::Serialize;
 use sqlite::State;
 use tasm_lib::twenty_first;
 
+use crate::config_models::data_directory::DataDirectory;
+use crate::models::blockchain::type_scripts::native_currency_amount::NativeCurrencyAmount;
+use crate::models::state::transaction_kernel_id::TransactionKernelId;
+
+pub mod mempool_snapshot;
 pub mod router;
 
+/// How long a statement waits for a lock held by another connection before
+/// failing with "database is locked", unless configured otherwise with
+/// `--tx-pool-busy-timeout`.
+pub const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default minimum fee, 0.1 coins, in nau.
+const DEFAULT_MIN_FEE_NAU: i128 = 400000000000000000000000000000;
+
+/// A transaction pays less than the minimum fee.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("fee {fee} is below the minimum fee of {min_fee}")]
+pub struct FeeBelowMinimum {
+    pub fee: NativeCurrencyAmount,
+    pub min_fee: NativeCurrencyAmount,
+}
+
+/// Why a pool operation failed.
+#[derive(Debug, thiserror::Error)]
+pub enum PoolError {
+    #[error("tx pool database error: {0}")]
+    Db(#[from] sqlite::Error),
+
+    #[error("failed to create tx pool database: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("tx pool database failed integrity check: {0}")]
+    Corrupt(String),
+
+    #[error(transparent)]
+    FeeTooLow(#[from] FeeBelowMinimum),
+
+    #[error("transaction {0} is already in the pool")]
+    Duplicate(PoolTxId),
+
+    #[error("transaction {0} is not being executed")]
+    NotFound(PoolTxId),
+
+    #[error("failed to encode transaction: {0}")]
+    Serialize(#[from] bincode::Error),
+}
+
+/// Check that `fee` is at least `min_fee`. Applies both to transactions
+/// submitted to the pool and to transactions broadcast into the mempool.
+pub fn check_min_fee(
+    fee: NativeCurrencyAmount,
+    min_fee: NativeCurrencyAmount,
+) -> Result<(), FeeBelowMinimum> {
+    if fee < min_fee {
+        return Err(FeeBelowMinimum { fee, min_fee });
+    }
+
+    Ok(())
+}
+
+/// The ID of a transaction in the pool.
+///
+/// Stored as the hex string produced by [`TransactionKernelId`]'s `Display`
+/// impl. Parsing validates that format, so malformed IDs are rejected before
+/// they reach the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PoolTxId(TransactionKernelId);
+
+impl From<TransactionKernelId> for PoolTxId {
+    fn from(txid: TransactionKernelId) -> Self {
+        Self(txid)
+    }
+}
+
+impl Display for PoolTxId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A string is not a valid transaction ID.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid transaction id {id:?}: {reason}")]
+pub struct InvalidPoolTxId {
+    pub id: String,
+    pub reason: String,
+}
+
+impl FromStr for PoolTxId {
+    type Err = InvalidPoolTxId;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TransactionKernelId::from_str(s)
+            .map(Self)
+            .map_err(|e| InvalidPoolTxId {
+                id: s.to_string(),
+                reason: e.to_string(),
+            })
+    }
+}
+
+/// Pending transactions chosen to fill a block.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockTemplate {
+    /// The chosen transactions, as `(id, rawtx)` pairs.
+    pub transactions: Vec<(String, Vec<u8>)>,
+
+    /// Sum of the chosen transactions' fees, in units of 10^-10 coins as
+    /// stored by the pool.
+    pub total_fee: i64,
+
+    /// Sum of the chosen transactions' sizes in bytes.
+    pub total_size: usize,
+}
+
+/// What a run of [`PoolState::run_maintenance`] did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MaintenanceSummary {
+    /// Finished rows that were deleted.
+    pub purged: usize,
+
+    /// Unfinished rows that were handed back to the queue.
+    pub requeued: usize,
+
+    /// Size of the database before and after vacuuming, in bytes.
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
 #[derive(Clone)]
 pub struct PoolState {
     db: Arc<sqlite::ConnectionThreadSafe>,
+
+    /// Serializes handing out transactions, so that no two provers are given
+    /// the same one.
+    pull_lock: Arc<Mutex<()>>,
+
+    /// Transactions paying less are rejected.
+    min_fee: NativeCurrencyAmount,
 }
 
 impl PoolState {
-    pub fn new(path: PathBuf) -> anyhow::Result<Self> {
-        #[cfg(debug_assertions)]
-        let path = ":memory:";
-
-        #[cfg(not(debug_assertions))]
-        let path = {
-            if !path.exists() {
-                std::fs::create_dir_all(&path)?;
-            }
-            path.join("transactions.db")
-        };
+    /// Open the pool's database at
+    /// [`DataDirectory::tx_pool_database_file_path`]. Debug builds keep the
+    /// database in memory instead.
+    pub fn new(data_directory: &DataDirectory, busy_timeout: Duration) -> Result<Self, PoolError> {
+        if cfg!(debug_assertions) {
+            Self::open(Path::new(":memory:"), busy_timeout)
+        } else {
+            Self::open(&data_directory.tx_pool_database_file_path(), busy_timeout)
+        }
+    }
+
+    /// Open the database file at `path`, creating it and its parent directory
+    /// if they do not exist.
+    fn open(path: &Path, busy_timeout: Duration) -> Result<Self, PoolError> {
+        if let Some(parent_dir) = path.parent() {
+            std::fs::create_dir_all(parent_dir)?;
+        }
 
         let db = sqlite::Connection::open_thread_safe(path)?;
         let db = Arc::new(db);
-        let s = Self { db };
-        s.create_db()?;
+        let s = Self {
+            db,
+            pull_lock: Arc::new(Mutex::new(())),
+            min_fee: NativeCurrencyAmount::from_nau(DEFAULT_MIN_FEE_NAU),
+        };
+        s.create_db(busy_timeout)?;
         Ok(s)
     }
 
-    fn create_db(&self) -> Result<(), sqlite::Error> {
+    /// Set the minimum fee a transaction must pay to enter the pool.
+    pub fn with_min_fee(mut self, min_fee: NativeCurrencyAmount) -> Self {
+        self.min_fee = min_fee;
+        self
+    }
+
+    pub fn min_fee(&self) -> NativeCurrencyAmount {
+        self.min_fee
+    }
+
+    fn create_db(&self, busy_timeout: Duration) -> Result<(), sqlite::Error> {
+        // WAL lets readers proceed while a submit is being written.
+        self.db.execute("PRAGMA journal_mode=WAL")?;
+        self.db.execute(format!(
+            "PRAGMA busy_timeout = {}",
+            busy_timeout.as_millis()
+        ))?;
+
         self.db.execute(
             "CREATE TABLE IF NOT EXISTS transactions (
                 id TEXT PRIMARY KEY,
@@ -41,16 +209,129 @@ impl PoolState {
                 height INTEGER DEFAULT 0,
                 queue_time INTEGER DEFAULT 0,
                 finished_at INTEGER DEFAULT 0,
-                revoke_key TEXT NOT NULL
+                revoke_key TEXT NOT NULL,
+                is_priority INTEGER DEFAULT 0,
+                size INTEGER DEFAULT 0
             )",
         )?;
+        self.add_column_if_missing("transactions", "is_priority", "INTEGER DEFAULT 0")?;
+        if self.add_column_if_missing("transactions", "size", "INTEGER DEFAULT 0")? {
+            self.db
+                .execute("UPDATE transactions SET size = length(rawtx)")?;
+        }
         self.db
             .execute("CREATE INDEX IF NOT EXISTS idx_transactions_fee ON transactions (fee)")?;
+        self.db.execute(
+            "CREATE TABLE IF NOT EXISTS executing (
+                id TEXT PRIMARY KEY,
+                rawtx BLOB NOT NULL,
+                fee BIGINT NOT NULL,
+                created_at INTEGER DEFAULT (strftime('%s', 'now')),
+                finished_at INTEGER DEFAULT 0
+            )",
+        )?;
+        self.db.execute(
+            "CREATE TABLE IF NOT EXISTS mempool_snapshot (
+                id TEXT PRIMARY KEY,
+                rawtx BLOB NOT NULL,
+                is_own INTEGER NOT NULL
+            )",
+        )?;
 
         Ok(())
     }
 
-    pub fn add_transaction(&self, id: &str, transaction: &[u8], fee: i128) -> Result<String> {
+    /// Check that the database is reachable and not corrupt.
+    pub fn ping(&self) -> Result<(), PoolError> {
+        let mut stmt = self.db.prepare("PRAGMA quick_check")?;
+        let mut problems = vec![];
+        while let State::Row = stmt.next()? {
+            let line = stmt.read::<String, _>(0)?;
+            if line != "ok" {
+                problems.push(line);
+            }
+        }
+
+        if !problems.is_empty() {
+            return Err(PoolError::Corrupt(problems.join("; ")));
+        }
+        Ok(())
+    }
+
+    /// Add a column to a table created by an older version of this code.
+    /// Returns true if the column was added.
+    fn add_column_if_missing(
+        &self,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> Result<bool, sqlite::Error> {
+        let mut stmt = self.db.prepare(format!("PRAGMA table_info({table})"))?;
+        while let Ok(State::Row) = stmt.next() {
+            if stmt.read::<String, _>("name")? == column {
+                return Ok(false);
+            }
+        }
+
+        self.db.execute(format!(
+            "ALTER TABLE {table} ADD COLUMN {column} {definition}"
+        ))?;
+        Ok(true)
+    }
+
+    /// Replace the stored mempool snapshot with `entries`, given as
+    /// `(id, rawtx, is_own)`.
+    pub fn replace_mempool_snapshot(
+        &self,
+        entries: &[(String, Vec<u8>, bool)],
+    ) -> Result<(), PoolError> {
+        self.db.execute("BEGIN TRANSACTION")?;
+        let result = (|| -> Result<(), sqlite::Error> {
+            self.db.execute("DELETE FROM mempool_snapshot")?;
+            for (id, rawtx, is_own) in entries {
+                let mut stmt = self
+                    .db
+                    .prepare("INSERT INTO mempool_snapshot (id,rawtx,is_own) VALUES (?,?,?)")?;
+                stmt.bind((1, id.as_str()))?;
+                stmt.bind((2, rawtx.as_slice()))?;
+                stmt.bind((3, i64::from(*is_own)))?;
+                stmt.next()?;
+            }
+            Ok(())
+        })();
+
+        match result {
+            Ok(()) => self.db.execute("COMMIT")?,
+            Err(e) => {
+                self.db.execute("ROLLBACK")?;
+                return Err(e.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the stored mempool snapshot as `(rawtx, is_own)` pairs.
+    pub fn mempool_snapshot(&self) -> Result<Vec<(Vec<u8>, bool)>, PoolError> {
+        let mut stmt = self.db.prepare("SELECT * FROM mempool_snapshot")?;
+        let mut entries = vec![];
+        while let Ok(State::Row) = stmt.next() {
+            let raw_tx = stmt.read::<Vec<u8>, _>("rawtx")?;
+            let is_own = stmt.read::<i64, _>("is_own")? != 0;
+            entries.push((raw_tx, is_own));
+        }
+
+        Ok(entries)
+    }
+
+    /// Queue a transaction for proving. Priority transactions, i.e. those that
+    /// paid the priority fee, are handed out before all others.
+    pub fn add_transaction(
+        &self,
+        id: &PoolTxId,
+        transaction: &[u8],
+        fee: i128,
+        is_priority: bool,
+    ) -> Result<String, PoolError> {
         // generate a random key
         let mut rng = rand::rng();
         let mut revoke_key = vec![];
@@ -59,26 +340,39 @@ impl PoolState {
         }
         let revoke_key = String::from_iter(revoke_key);
 
-        let mut stmt = self
-            .db
-            .prepare("INSERT INTO transactions (id,rawtx,fee) VALUES (?,?,?)")?;
-        stmt.bind((1, id))?;
+        let mut stmt = self.db.prepare(
+            "INSERT INTO transactions (id,rawtx,fee,is_priority,size,revoke_key)
+                VALUES (?,?,?,?,?,?)
+                ON CONFLICT (id) DO NOTHING RETURNING id",
+        )?;
+        stmt.bind((1, id.to_string().as_str()))?;
         stmt.bind((2, transaction))?;
+        stmt.bind((4, i64::from(is_priority)))?;
+        stmt.bind((5, transaction.len() as i64))?;
+        stmt.bind((6, revoke_key.as_str()))?;
 
-        if fee < 400000000000000000000000000000 {
-            bail!("fee is too low")
-        }
+        check_min_fee(NativeCurrencyAmount::from_nau(fee), self.min_fee)?;
 
         let fee = fee_to_i64(fee);
         stmt.bind((3, fee))?;
-        stmt.next()?;
-        Ok(revoke_key)
+
+        // Only a conflicting id skips the insert, and then no row is returned.
+        // Every other constraint violation is a database error.
+        match stmt.next()? {
+            State::Row => Ok(revoke_key),
+            State::Done => Err(PoolError::Duplicate(*id)),
+        }
     }
 
-    pub fn get_most_worth_transaction(&self) -> Result<Option<Vec<u8>>, sqlite::Error> {
+    pub fn get_most_worth_transaction(&self) -> Result<Option<Vec<u8>>, PoolError> {
+        let _pull_guard = self
+            .pull_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
         let mut stmt = self
             .db
-            .prepare("SELECT * FROM transactions ORDER BY FEE DESC LIMIT 1")?;
+            .prepare("SELECT * FROM transactions ORDER BY is_priority DESC, fee DESC LIMIT 1")?;
 
         while let Ok(State::Row) = stmt.next() {
             let raw_tx = stmt.read::<Vec<u8>, _>("rawtx").unwrap();
@@ -102,9 +396,120 @@ impl PoolState {
         Ok(None)
     }
 
-    pub fn get_executing_transaction(&self, id: &str) -> Result<Option<(Vec<u8>, u64, u64)>> {
+    /// Select pending transactions for a block of at most `max_size` bytes,
+    /// as `(id, rawtx)` pairs. Transactions are considered in order of
+    /// descending fee, and each one that still fits the remaining budget is
+    /// taken. The selected transactions stay in the pool.
+    pub fn select_for_block(&self, max_size: usize) -> Result<Vec<(String, Vec<u8>)>, PoolError> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT id, rawtx, size FROM transactions ORDER BY fee DESC")?;
+
+        let mut remaining = max_size;
+        let mut selected = vec![];
+        while let State::Row = stmt.next()? {
+            let size = read_size(&stmt)?;
+            if size > remaining {
+                continue;
+            }
+
+            remaining -= size;
+            let id = stmt.read::<String, _>("id")?;
+            let raw_tx = stmt.read::<Vec<u8>, _>("rawtx")?;
+            selected.push((id, raw_tx));
+            if remaining == 0 {
+                break;
+            }
+        }
+
+        Ok(selected)
+    }
+
+    /// Choose pending transactions for a block of at most `max_size` bytes
+    /// and `max_count` transactions, maximizing the total fee. The selected
+    /// transactions stay in the pool.
+    ///
+    /// Finding the optimum is a knapsack problem, which is NP-hard. Instead,
+    /// transactions are taken greedily in order of descending fee per byte,
+    /// skipping those that no longer fit. If the single highest-fee
+    /// transaction that fits on its own pays more than the greedy selection,
+    /// it is returned alone. Without a count limit, this guarantees at least
+    /// half the optimal total fee.
+    pub fn build_block_template(
+        &self,
+        max_size: usize,
+        max_count: usize,
+    ) -> Result<BlockTemplate, PoolError> {
+        struct Candidate {
+            id: String,
+            raw_tx: Vec<u8>,
+            fee: i64,
+            size: usize,
+        }
+
+        let mut stmt = self
+            .db
+            .prepare("SELECT id, rawtx, fee, size FROM transactions")?;
+        let mut candidates = vec![];
+        while let State::Row = stmt.next()? {
+            candidates.push(Candidate {
+                id: stmt.read::<String, _>("id")?,
+                raw_tx: stmt.read::<Vec<u8>, _>("rawtx")?,
+                fee: stmt.read::<i64, _>("fee")?,
+                size: read_size(&stmt)?,
+            });
+        }
+
+        // descending fee per byte, compared by cross-multiplication
+        candidates.sort_by(|a, b| {
+            let a_density = i128::from(a.fee) * b.size.max(1) as i128;
+            let b_density = i128::from(b.fee) * a.size.max(1) as i128;
+            b_density.cmp(&a_density)
+        });
+
+        let mut greedy = vec![];
+        let mut greedy_size = 0;
+        let mut greedy_fee = 0;
+        for (index, candidate) in candidates.iter().enumerate() {
+            if greedy.len() == max_count {
+                break;
+            }
+            if greedy_size + candidate.size <= max_size {
+                greedy_size += candidate.size;
+                greedy_fee += candidate.fee;
+                greedy.push(index);
+            }
+        }
+
+        let best_single = candidates
+            .iter()
+            .enumerate()
+            .filter(|(_, candidate)| max_count > 0 && candidate.size <= max_size)
+            .max_by_key(|(_, candidate)| candidate.fee);
+        let chosen = match best_single {
+            Some((index, candidate)) if candidate.fee > greedy_fee => vec![index],
+            _ => greedy,
+        };
+
+        let mut template = BlockTemplate::default();
+        for index in chosen {
+            let candidate = &candidates[index];
+            template.total_fee += candidate.fee;
+            template.total_size += candidate.size;
+            template
+                .transactions
+                .push((candidate.id.clone(), candidate.raw_tx.clone()));
+        }
+
+        Ok(template)
+    }
+
+    pub fn get_executing_transaction(
+        &self,
+        id: &PoolTxId,
+    ) -> Result<Option<(Vec<u8>, u64, u64)>, PoolError> {
         let mut stmt = self.db.prepare("SELECT * FROM executing WHERE id=?")?;
-        stmt.bind((1, id))?;
+        stmt.bind((1, id.to_string().as_str()))?;
         while let Ok(State::Row) = stmt.next() {
             let raw_tx = stmt.read::<Vec<u8>, _>("rawtx").unwrap();
             let created_at = stmt.read::<i64, _>("created_at").unwrap();
@@ -115,9 +520,9 @@ impl PoolState {
         Ok(None)
     }
 
-    pub fn get_pending_transaction(&self, id: &str) -> Result<Option<String>> {
+    pub fn get_pending_transaction(&self, id: &PoolTxId) -> Result<Option<String>, PoolError> {
         let mut stmt = self.db.prepare("SELECT * FROM transactions WHERE id=?")?;
-        stmt.bind((1, id))?;
+        stmt.bind((1, id.to_string().as_str()))?;
         while let Ok(State::Row) = stmt.next() {
             let txid = stmt.read::<String, _>("id").unwrap();
             return Ok(Some(txid));
@@ -126,22 +531,174 @@ impl PoolState {
         Ok(None)
     }
 
-    pub fn drop_old_transaction(&self) -> Result<()> {
+    /// Number of transactions waiting to be picked up by a prover.
+    pub fn pending_count(&self) -> Result<u64, PoolError> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT COUNT(*) AS count FROM transactions")?;
+        stmt.next()?;
+        Ok(stmt.read::<i64, _>("count")? as u64)
+    }
+
+    /// Number of transactions picked up by a prover that have not finished yet.
+    pub fn executing_count(&self) -> Result<u64, PoolError> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT COUNT(*) AS count FROM executing WHERE finished_at = 0")?;
+        stmt.next()?;
+        Ok(stmt.read::<i64, _>("count")? as u64)
+    }
+
+    /// Average time from a prover picking up a transaction until it finished
+    /// proving it. `None` if no transaction has finished yet.
+    pub fn avg_proving_duration(&self) -> Result<Option<Duration>, PoolError> {
+        let mut stmt = self.db.prepare(
+            "SELECT AVG(finished_at - created_at) AS avg FROM executing WHERE finished_at > 0",
+        )?;
+        stmt.next()?;
+        let avg_secs = stmt.read::<Option<f64>, _>("avg")?;
+        Ok(avg_secs.map(|secs| Duration::from_secs_f64(secs.max(0.0))))
+    }
+
+    /// Estimated time until all pending transactions are proven by `workers`
+    /// provers working in parallel, each taking the average proving duration
+    /// per transaction. Zero if no transaction has finished yet, since there
+    /// is nothing to base the estimate on.
+    pub fn estimated_queue_drain(&self, workers: usize) -> Result<Duration, PoolError> {
+        let Some(avg_proving_duration) = self.avg_proving_duration()? else {
+            return Ok(Duration::ZERO);
+        };
+        let rounds = self.pending_count()?.div_ceil(workers.max(1) as u64);
+        Ok(avg_proving_duration.saturating_mul(u32::try_from(rounds).unwrap_or(u32::MAX)))
+    }
+
+    /// Delete executing rows that finished more than `older_than` ago. Returns
+    /// the number of deleted rows.
+    pub fn purge_finished(&self, older_than: Duration) -> Result<usize, PoolError> {
+        let cutoff = cutoff_secs(older_than);
+
+        let mut stmt = self
+            .db
+            .prepare("DELETE FROM executing WHERE finished_at > 0 AND finished_at < ?")?;
+        stmt.bind((1, cutoff))?;
+        stmt.next()?;
+
+        Ok(self.db.change_count())
+    }
+
+    /// Return executing rows that were picked up more than `older_than` ago and
+    /// never finished to the queue, so that another prover can pick them up.
+    /// Returns the number of requeued rows.
+    ///
+    /// Requeued transactions lose their priority and get a fresh revoke key.
+    pub fn requeue_stale(&self, older_than: Duration) -> Result<usize, PoolError> {
+        let cutoff = cutoff_secs(older_than);
+
+        let _pull_guard = self
+            .pull_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        self.db.execute("BEGIN TRANSACTION")?;
+        let result = (|| -> Result<usize, sqlite::Error> {
+            let mut stmt = self.db.prepare(
+                "INSERT OR IGNORE INTO transactions (id,rawtx,fee,size,revoke_key)
+                    SELECT id, rawtx, fee, length(rawtx), lower(hex(randomblob(16)))
+                    FROM executing WHERE finished_at = 0 AND created_at < ?",
+            )?;
+            stmt.bind((1, cutoff))?;
+            stmt.next()?;
+
+            let mut stmt = self
+                .db
+                .prepare("DELETE FROM executing WHERE finished_at = 0 AND created_at < ?")?;
+            stmt.bind((1, cutoff))?;
+            stmt.next()?;
+
+            Ok(self.db.change_count())
+        })();
+
+        match result {
+            Ok(requeued) => {
+                self.db.execute("COMMIT")?;
+                Ok(requeued)
+            }
+            Err(e) => {
+                self.db.execute("ROLLBACK")?;
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Size of the database, in bytes.
+    fn size(&self) -> Result<u64, PoolError> {
+        let mut stmt = self.db.prepare(
+            "SELECT page_count * page_size AS size
+                FROM pragma_page_count(), pragma_page_size()",
+        )?;
+        stmt.next()?;
+        Ok(stmt.read::<i64, _>("size")? as u64)
+    }
+
+    /// Purge rows that finished more than `purge_older_than` ago, requeue rows
+    /// that were picked up more than `requeue_older_than` ago but never
+    /// finished, and then compact the database file.
+    pub fn run_maintenance(
+        &self,
+        purge_older_than: Duration,
+        requeue_older_than: Duration,
+    ) -> Result<MaintenanceSummary, PoolError> {
+        let purged = self.purge_finished(purge_older_than)?;
+        let requeued = self.requeue_stale(requeue_older_than)?;
+
+        let size_before = self.size()?;
+        self.db.execute("VACUUM")?;
+        let size_after = self.size()?;
+
+        Ok(MaintenanceSummary {
+            purged,
+            requeued,
+            size_before,
+            size_after,
+        })
+    }
+
+    pub fn drop_old_transaction(&self) -> Result<(), PoolError> {
         let mut stmt = self.db.prepare("DELETE transactions")?;
         stmt.next()?;
         Ok(())
     }
 
-    pub fn finish_transaction(&self, id: &str) -> anyhow::Result<()> {
+    /// Mark an executing transaction as finished. Fails with
+    /// [`PoolError::NotFound`] if no prover picked it up.
+    pub fn finish_transaction(&self, id: &PoolTxId) -> Result<(), PoolError> {
         let mut stmt = self
             .db
             .prepare("UPDATE executing SET finished_at=strftime('%s', 'now') WHERE id=?")?;
-        stmt.bind((1, id))?;
+        stmt.bind((1, id.to_string().as_str()))?;
         stmt.next()?;
+        if self.db.change_count() == 0 {
+            return Err(PoolError::NotFound(*id));
+        }
+
         Ok(())
     }
 }
 
+/// Seconds since the Unix epoch, `ago` before now.
+fn cutoff_secs(ago: Duration) -> i64 {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    i64::try_from(now.saturating_sub(ago).as_secs()).unwrap_or(i64::MAX)
+}
+
+/// Read the `size` column of the current row.
+fn read_size(stmt: &sqlite::Statement) -> Result<usize, PoolError> {
+    let size = stmt.read::<i64, _>("size")?;
+    usize::try_from(size).map_err(|_| PoolError::Corrupt(format!("negative size {size}")))
+}
+
 // 转为小数点10位
 fn fee_to_i64(fee: i128) -> i64 {
     let fee = fee >> 2;
@@ -152,9 +709,106 @@ fn fee_to_i64(fee: i128) -> i64 {
 #[cfg(test)]
 mod tests {
 
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+    use tasm_lib::prelude::Digest;
+    use tasm_lib::triton_vm::prelude::BFieldElement;
+
+    use crate::config_models::network::Network;
+    use crate::jsonrpc_server::RestError;
     use crate::models::blockchain::type_scripts::native_currency_amount::NativeCurrencyAmount;
+    use crate::tests::shared::unit_test_data_directory;
 
     use super::*;
+
+    /// A valid transaction ID, distinct for distinct `n`.
+    fn txid(n: u64) -> PoolTxId {
+        TransactionKernelId::new(Digest::new([BFieldElement::new(n); 5])).into()
+    }
+
+    fn pool_state() -> PoolState {
+        let data_directory = unit_test_data_directory(Network::Main).unwrap();
+        PoolState::new(&data_directory, DEFAULT_BUSY_TIMEOUT).unwrap()
+    }
+
+    #[test]
+    fn database_is_placed_in_custom_data_directory() {
+        let data_directory = unit_test_data_directory(Network::Main).unwrap();
+        let db_path = data_directory.tx_pool_database_file_path();
+        assert_eq!(
+            data_directory
+                .root_dir_path()
+                .join("tx_pool")
+                .join("transactions.db"),
+            db_path
+        );
+
+        assert!(!db_path.exists());
+        PoolState::open(&db_path, DEFAULT_BUSY_TIMEOUT).unwrap();
+        assert!(db_path.is_file());
+    }
+
+    #[test]
+    fn valid_txid_is_parsed() {
+        let id = txid(7);
+        assert_eq!(id, id.to_string().parse::<PoolTxId>().unwrap());
+    }
+
+    #[test]
+    fn malformed_txid_is_rejected() {
+        for malformed in ["", "1", "rich", &txid(7).to_string()[2..], &"zz".repeat(40)] {
+            let err = malformed.parse::<PoolTxId>().unwrap_err();
+            assert_eq!(malformed, err.id);
+        }
+    }
+
+    #[tokio::test]
+    async fn transaction_status_rejects_malformed_id() {
+        let state = pool_state();
+        let status = |id: String| {
+            router::get_transaction_status(
+                axum::extract::State(state.clone()),
+                axum::extract::Path(id),
+            )
+        };
+
+        assert!(status(txid(1).to_string()).await.is_ok());
+        assert!(matches!(
+            status("rich".to_string()).await,
+            Err(RestError::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn executing_transaction_raw_bytes_can_be_fetched() {
+        let state = pool_state();
+        let raw_tx = |id: PoolTxId| {
+            router::get_executing_transaction_raw(
+                axum::extract::State(state.clone()),
+                axum::extract::Path(id.to_string()),
+            )
+        };
+
+        let tx = vec![4, 2, 1];
+        state
+            .add_transaction(&txid(1), &tx, 100000000000000000000000000000 << 2, false)
+            .unwrap();
+        assert!(matches!(raw_tx(txid(1)).await, Err(RestError::NotFound(_))));
+
+        assert_eq!(tx, state.get_most_worth_transaction().unwrap().unwrap());
+        let response = raw_tx(txid(1)).await.unwrap();
+        assert_eq!(
+            "application/octet-stream",
+            response.headers()["Content-Type"]
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(tx, body.to_vec());
+
+        assert!(matches!(raw_tx(txid(2)).await, Err(RestError::NotFound(_))));
+    }
+
     #[test]
     fn test_fee_to_i64() {
         let fee = NativeCurrencyAmount::coins_from_str("0.1").unwrap();
@@ -166,22 +820,27 @@ mod tests {
 
     #[test]
     fn test_tx_insert() {
-        let state = PoolState::new(PathBuf::new()).unwrap();
+        let state = pool_state();
         let tx = vec![1, 2, 3];
         state
-            .add_transaction("1", &tx, 100000000000000000000000000000 << 2)
+            .add_transaction(&txid(1), &tx, 100000000000000000000000000000 << 2, false)
             .unwrap();
         state
-            .add_transaction("2", &tx, 200000000000000000000000000000 << 2)
+            .add_transaction(&txid(2), &tx, 200000000000000000000000000000 << 2, false)
             .unwrap();
         state
-            .add_transaction("3", &tx, 300000000000000000000000000000 << 2)
+            .add_transaction(&txid(3), &tx, 300000000000000000000000000000 << 2, false)
             .unwrap();
         state
-            .add_transaction("4", &tx, 400000000000000000000000000000 << 2)
+            .add_transaction(&txid(4), &tx, 400000000000000000000000000000 << 2, false)
             .unwrap();
         state
-            .add_transaction("5", &vec![3, 2, 3], 500000000000000000000000000000 << 2)
+            .add_transaction(
+                &txid(5),
+                &vec![3, 2, 3],
+                500000000000000000000000000000 << 2,
+                false,
+            )
             .unwrap();
 
         let time = std::time::SystemTime::now()
@@ -191,8 +850,459 @@ mod tests {
         let tx = state.get_most_worth_transaction().unwrap().unwrap();
         assert_eq!(tx, vec![3, 2, 3]);
 
-        let status = state.get_executing_transaction("5").unwrap().unwrap();
+        let status = state.get_executing_transaction(&txid(5)).unwrap().unwrap();
         assert!(status.1 >= time);
         assert!(status.1 < time + 10);
     }
+
+    #[test]
+    fn priority_transaction_is_popped_first() {
+        let state = pool_state();
+        state
+            .add_transaction(&txid(1), &[1], 900000000000000000000000000000 << 2, false)
+            .unwrap();
+        state
+            .add_transaction(&txid(2), &[2], 100000000000000000000000000000 << 2, true)
+            .unwrap();
+        state
+            .add_transaction(&txid(3), &[3], 100000000000000000000000000000 << 2, false)
+            .unwrap();
+
+        assert_eq!(
+            vec![2],
+            state.get_most_worth_transaction().unwrap().unwrap()
+        );
+        assert_eq!(
+            vec![1],
+            state.get_most_worth_transaction().unwrap().unwrap()
+        );
+        assert_eq!(
+            vec![3],
+            state.get_most_worth_transaction().unwrap().unwrap()
+        );
+        assert_eq!(None, state.get_most_worth_transaction().unwrap());
+    }
+
+    #[test]
+    fn pool_methods_return_expected_error_variants() {
+        let state = pool_state();
+        let fee = state.min_fee().to_nau();
+
+        let too_low = state.add_transaction(&txid(1), &[1], fee - 1, false);
+        assert!(matches!(too_low, Err(PoolError::FeeTooLow(_))));
+
+        state.add_transaction(&txid(1), &[1], fee, false).unwrap();
+        let duplicate = state.add_transaction(&txid(1), &[1], fee, false);
+        assert!(matches!(duplicate, Err(PoolError::Duplicate(id)) if id == txid(1)));
+
+        let not_found = state.finish_transaction(&txid(2));
+        assert!(matches!(not_found, Err(PoolError::NotFound(id)) if id == txid(2)));
+        state.get_most_worth_transaction().unwrap();
+        state.finish_transaction(&txid(1)).unwrap();
+
+        state.add_transaction(&txid(3), &[3], fee, false).unwrap();
+        state
+            .db
+            .execute("UPDATE transactions SET size = -1")
+            .unwrap();
+        let corrupt = state.select_for_block(100);
+        assert!(matches!(corrupt, Err(PoolError::Corrupt(_))));
+
+        state.db.execute("DROP TABLE executing").unwrap();
+        assert!(matches!(state.executing_count(), Err(PoolError::Db(_))));
+    }
+
+    #[test]
+    fn pool_errors_map_to_http_statuses() {
+        let status = |err: PoolError| RestError::from(err).into_response().status();
+        let fee_too_low = FeeBelowMinimum {
+            fee: NativeCurrencyAmount::from_nau(0),
+            min_fee: NativeCurrencyAmount::coins(1),
+        };
+        let serialize = bincode::deserialize::<u64>(&[]).unwrap_err();
+
+        assert_eq!(StatusCode::BAD_REQUEST, status(fee_too_low.into()));
+        assert_eq!(StatusCode::CONFLICT, status(PoolError::Duplicate(txid(1))));
+        assert_eq!(StatusCode::NOT_FOUND, status(PoolError::NotFound(txid(1))));
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, status(serialize.into()));
+        let corrupt = PoolError::Corrupt("page 1 is bad".to_string());
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, status(corrupt));
+    }
+
+    #[test]
+    fn purge_finished_only_removes_expired_rows() {
+        let state = pool_state();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let day = 24 * 60 * 60;
+        state
+            .db
+            .execute(format!(
+                "INSERT INTO executing (id,rawtx,fee,created_at,finished_at) VALUES
+                    ('{expired_1}', x'00', 1, {c1}, {f1}),
+                    ('{expired_2}', x'00', 1, {c2}, {f2}),
+                    ('{recent}', x'00', 1, {c3}, {f3}),
+                    ('{unfinished}', x'00', 1, {c2}, 0)",
+                expired_1 = txid(1),
+                expired_2 = txid(2),
+                recent = txid(3),
+                unfinished = txid(4),
+                c1 = now - 10 * day,
+                f1 = now - 9 * day,
+                c2 = now - 3 * day,
+                f2 = now - 2 * day,
+                c3 = now - 2 * 60,
+                f3 = now - 60,
+            ))
+            .unwrap();
+
+        assert_eq!(2, state.purge_finished(Duration::from_secs(day)).unwrap());
+        assert!(state.get_executing_transaction(&txid(1)).unwrap().is_none());
+        assert!(state.get_executing_transaction(&txid(2)).unwrap().is_none());
+        assert!(state.get_executing_transaction(&txid(3)).unwrap().is_some());
+        assert!(state.get_executing_transaction(&txid(4)).unwrap().is_some());
+
+        assert_eq!(0, state.purge_finished(Duration::from_secs(day)).unwrap());
+    }
+
+    #[tokio::test]
+    async fn purge_finished_requires_authentication() {
+        let network = Network::Main;
+        let global_state_lock = crate::tests::shared::mock_genesis_global_state(
+            network,
+            0,
+            crate::models::state::wallet::wallet_entropy::WalletEntropy::new_random(),
+            crate::config_models::cli_args::Args::default(),
+        )
+        .await;
+        let cookie = crate::rpc_auth::Cookie::new_in_mem();
+        let rpcstate = crate::rpc_server::NeptuneRPCServer::new(
+            global_state_lock.clone(),
+            global_state_lock.rpc_server_to_main_tx(),
+            unit_test_data_directory(network).unwrap(),
+            vec![cookie.into()],
+        );
+        let purge = |headers: axum::http::HeaderMap| {
+            router::purge_finished(
+                axum::extract::State((rpcstate.clone(), pool_state())),
+                axum::extract::Query(serde_json::from_str("{}").unwrap()),
+                headers,
+            )
+        };
+
+        let unauthenticated = purge(axum::http::HeaderMap::new()).await;
+        assert!(matches!(unauthenticated, Err(RestError::Unauthorized(_))));
+
+        let mut headers = axum::http::HeaderMap::new();
+        let bearer = format!("Bearer {}", cookie.as_hex()).parse().unwrap();
+        headers.insert(axum::http::header::AUTHORIZATION, bearer);
+        assert!(purge(headers).await.is_ok());
+    }
+
+    #[test]
+    fn maintenance_summary_reflects_purge_and_requeue() {
+        let state = pool_state();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let hour = 60 * 60;
+        state
+            .db
+            .execute(format!(
+                "INSERT INTO executing (id,rawtx,fee,created_at,finished_at) VALUES
+                    ('{expired}', x'00', 1, {c1}, {f1}),
+                    ('{recent}', x'00', 1, {c2}, {f2}),
+                    ('{stale}', x'0102', 7, {c1}, 0),
+                    ('{in_progress}', x'00', 1, {c2}, 0)",
+                expired = txid(1),
+                recent = txid(2),
+                stale = txid(3),
+                in_progress = txid(4),
+                c1 = now - 30 * hour,
+                f1 = now - 29 * hour,
+                c2 = now - 60,
+                f2 = now - 30,
+            ))
+            .unwrap();
+
+        let purge_age = Duration::from_secs(24 * hour);
+        let requeue_age = Duration::from_secs(6 * hour);
+        let summary = state.run_maintenance(purge_age, requeue_age).unwrap();
+        assert_eq!(1, summary.purged);
+        assert_eq!(1, summary.requeued);
+        assert!(summary.size_after <= summary.size_before);
+
+        assert!(state.get_executing_transaction(&txid(1)).unwrap().is_none());
+        assert!(state.get_executing_transaction(&txid(2)).unwrap().is_some());
+        assert!(state.get_executing_transaction(&txid(3)).unwrap().is_none());
+        assert!(state.get_executing_transaction(&txid(4)).unwrap().is_some());
+        assert_eq!(1, state.pending_count().unwrap());
+        assert_eq!(
+            vec![1, 2],
+            state.get_most_worth_transaction().unwrap().unwrap()
+        );
+
+        let summary = state.run_maintenance(purge_age, requeue_age).unwrap();
+        assert_eq!((0, 0), (summary.purged, summary.requeued));
+    }
+
+    #[test]
+    fn ping_healthy_db() {
+        let state = pool_state();
+        state.ping().unwrap();
+
+        state
+            .add_transaction(&txid(1), &[1], 100000000000000000000000000000 << 2, false)
+            .unwrap();
+        state.ping().unwrap();
+    }
+
+    #[test]
+    fn second_connection_waits_for_busy_timeout() {
+        let data_directory = unit_test_data_directory(Network::Main).unwrap();
+        let db_path = data_directory.tx_pool_database_file_path();
+        let holder = PoolState::open(&db_path, DEFAULT_BUSY_TIMEOUT).unwrap();
+        let impatient = PoolState::open(&db_path, Duration::ZERO).unwrap();
+        let patient = PoolState::open(&db_path, Duration::from_secs(10)).unwrap();
+        let fee = 100000000000000000000000000000 << 2;
+
+        holder.db.execute("BEGIN IMMEDIATE").unwrap();
+        let err = impatient
+            .add_transaction(&txid(1), &[1], fee, false)
+            .unwrap_err();
+        assert!(err.to_string().contains("locked"), "{err}");
+
+        let release = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            holder.db.execute("COMMIT").unwrap();
+        });
+        patient.add_transaction(&txid(1), &[1], fee, false).unwrap();
+        release.join().unwrap();
+
+        assert_eq!(1, impatient.pending_count().unwrap());
+    }
+
+    #[test]
+    fn concurrent_submit_and_pull() {
+        const NUM_SUBMITTERS: usize = 4;
+        const NUM_PULLERS: usize = 4;
+        const TXS_PER_SUBMITTER: usize = 25;
+        let num_txs = NUM_SUBMITTERS * TXS_PER_SUBMITTER;
+
+        let state = pool_state();
+        let num_pulled = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let submitters = (0..NUM_SUBMITTERS).map(|submitter| {
+            let state = state.clone();
+            std::thread::spawn(move || {
+                for i in 0..TXS_PER_SUBMITTER {
+                    let id = txid((submitter * TXS_PER_SUBMITTER + i) as u64);
+                    let fee = 100000000000000000000000000000 << 2;
+                    state
+                        .add_transaction(&id, id.to_string().as_bytes(), fee, false)
+                        .unwrap();
+                }
+            })
+        });
+        let pullers = (0..NUM_PULLERS).map(|_| {
+            let state = state.clone();
+            let num_pulled = num_pulled.clone();
+            std::thread::spawn(move || {
+                let mut pulled = vec![];
+                while num_pulled.load(std::sync::atomic::Ordering::SeqCst) < num_txs {
+                    if let Some(tx) = state.get_most_worth_transaction().unwrap() {
+                        num_pulled.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                        pulled.push(tx);
+                    }
+                }
+                pulled
+            })
+        });
+
+        let pullers = pullers.collect::<Vec<_>>();
+        for submitter in submitters.collect::<Vec<_>>() {
+            submitter.join().unwrap();
+        }
+        let mut all_pulled = pullers
+            .into_iter()
+            .flat_map(|puller| puller.join().unwrap())
+            .collect::<Vec<_>>();
+
+        all_pulled.sort();
+        all_pulled.dedup();
+        assert_eq!(
+            num_txs,
+            all_pulled.len(),
+            "every transaction pulled exactly once"
+        );
+        assert_eq!(0, state.pending_count().unwrap());
+    }
+
+    #[test]
+    fn select_for_block_respects_budget_and_prefers_fee() {
+        let state = pool_state();
+        let unit_fee = 100000000000000000000000000000 << 2;
+        let [a, b, c, d] = [1, 2, 3, 4].map(txid);
+        for (id, fee_multiple, size) in [(a, 9, 60), (b, 5, 50), (c, 4, 40), (d, 1, 10)] {
+            state
+                .add_transaction(&id, &vec![0; size], fee_multiple * unit_fee, false)
+                .unwrap();
+        }
+
+        let ids = |selection: Vec<(String, Vec<u8>)>| {
+            selection
+                .into_iter()
+                .map(|(id, _)| id.parse::<PoolTxId>().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        // "b" is the second-highest fee but no longer fits after "a"
+        assert_eq!(vec![a, c], ids(state.select_for_block(100).unwrap()));
+        assert_eq!(vec![a, d], ids(state.select_for_block(75).unwrap()));
+        assert_eq!(vec![d], ids(state.select_for_block(39).unwrap()));
+        assert!(state.select_for_block(9).unwrap().is_empty());
+        assert_eq!(
+            vec![a, b, c, d],
+            ids(state.select_for_block(usize::MAX).unwrap())
+        );
+
+        // selection does not consume the transactions
+        assert_eq!(4, state.pending_count().unwrap());
+    }
+
+    #[test]
+    fn block_template_is_close_to_brute_force_optimum() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let unit_fee = 100000000000000000000000000000 << 2;
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..20 {
+            let state = pool_state();
+            let num_txs = rng.random_range(1..=8);
+            let txs = (0..num_txs)
+                .map(|_| (rng.random_range(1..=20), rng.random_range(1..=50)))
+                .collect::<Vec<(i128, usize)>>();
+            for (i, (fee_multiple, size)) in txs.iter().enumerate() {
+                let fee = fee_multiple * unit_fee;
+                state
+                    .add_transaction(&txid(i as u64), &vec![0; *size], fee, false)
+                    .unwrap();
+            }
+            let max_size = rng.random_range(0..=100);
+
+            let optimum = (0u32..1 << num_txs)
+                .map(|subset| {
+                    let chosen = txs
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| subset & (1 << i) != 0)
+                        .map(|(_, tx)| *tx)
+                        .collect::<Vec<_>>();
+                    let size = chosen.iter().map(|(_, size)| size).sum::<usize>();
+                    let fee = chosen.iter().map(|(fee, _)| fee).sum::<i128>();
+                    (size, fee)
+                })
+                .filter(|(size, _)| *size <= max_size)
+                .map(|(_, fee)| fee)
+                .max()
+                .unwrap();
+
+            let template = state.build_block_template(max_size, usize::MAX).unwrap();
+            let template_fee = template
+                .transactions
+                .iter()
+                .map(|(id, _)| {
+                    let index = (0..num_txs)
+                        .position(|i| txid(i as u64).to_string() == *id)
+                        .unwrap();
+                    txs[index].0
+                })
+                .sum::<i128>();
+            let stored_fee_per_unit = fee_to_i64(unit_fee);
+
+            assert!(template.total_size <= max_size);
+            assert_eq!(
+                template_fee * i128::from(stored_fee_per_unit),
+                i128::from(template.total_fee)
+            );
+            assert!(template_fee <= optimum);
+            assert!(
+                2 * template_fee >= optimum,
+                "{template_fee} vs optimum {optimum}"
+            );
+        }
+    }
+
+    #[test]
+    fn block_template_respects_count_limit() {
+        let state = pool_state();
+        let unit_fee = 100000000000000000000000000000 << 2;
+        let [a, b, c] = [1, 2, 3].map(txid);
+        for (id, fee_multiple) in [(a, 3), (b, 2), (c, 1)] {
+            state
+                .add_transaction(&id, &[0; 10], fee_multiple * unit_fee, false)
+                .unwrap();
+        }
+
+        let template = state.build_block_template(usize::MAX, 2).unwrap();
+        let ids = template
+            .transactions
+            .iter()
+            .map(|(id, _)| id.parse::<PoolTxId>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![a, b], ids);
+        assert_eq!(20, template.total_size);
+        assert!(state
+            .build_block_template(usize::MAX, 0)
+            .unwrap()
+            .transactions
+            .is_empty());
+    }
+
+    #[test]
+    fn test_queue_eta() {
+        let state = pool_state();
+        assert_eq!(None, state.avg_proving_duration().unwrap());
+        assert_eq!(Duration::ZERO, state.estimated_queue_drain(1).unwrap());
+
+        // two finished rows taking 60s and 120s, one still executing
+        state
+            .db
+            .execute(
+                "INSERT INTO executing (id,rawtx,fee,created_at,finished_at) VALUES
+                    ('a', x'00', 1, 1000, 1060),
+                    ('b', x'00', 1, 2000, 2120),
+                    ('c', x'00', 1, 3000, 0)",
+            )
+            .unwrap();
+        assert_eq!(
+            Some(Duration::from_secs(90)),
+            state.avg_proving_duration().unwrap()
+        );
+
+        let tx = vec![1, 2, 3];
+        for id in [1, 2, 3].map(txid) {
+            state
+                .add_transaction(&id, &tx, 100000000000000000000000000000 << 2, false)
+                .unwrap();
+        }
+        assert_eq!(3, state.pending_count().unwrap());
+        assert_eq!(
+            Duration::from_secs(270),
+            state.estimated_queue_drain(1).unwrap()
+        );
+        assert_eq!(
+            Duration::from_secs(180),
+            state.estimated_queue_drain(2).unwrap()
+        );
+        assert_eq!(
+            Duration::from_secs(270),
+            state.estimated_queue_drain(0).unwrap(),
+            "zero workers count as one"
+        );
+    }
 }
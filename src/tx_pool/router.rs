@@ -1,17 +1,17 @@
-use anyhow::Context;
+use std::time::Duration;
+
 use axum::{
     body::Body,
-    extract::{Request, State},
-    http::StatusCode,
+    extract::{Query, Request, State},
+    http::{HeaderMap, StatusCode},
     response::Response,
 };
 use axum_extra::response::ErasedJson;
-use bytes::Buf;
 use serde::Deserialize;
 use serde_json::json;
 
 use crate::{
-    jsonrpc_server::RestError,
+    jsonrpc_server::{authenticate, deserialize_body, json_response, pays_priority_fee, RestError},
     models::{
         blockchain::transaction::Transaction,
         peer::{
@@ -20,21 +20,28 @@ use crate::{
         },
         state::mempool::TransactionOrigin,
     },
+    rpc_server::NeptuneRPCServer,
 };
 
+use super::check_min_fee;
+use super::PoolError;
 use super::PoolState;
+use super::PoolTxId;
 
-pub async fn get_transaction(State(state): State<PoolState>, req: Request) -> Response {
-    let transaction = state.get_most_worth_transaction().unwrap();
-    let body = bincode::serialize(&transaction).unwrap();
+pub async fn get_transaction(
+    State(state): State<PoolState>,
+    req: Request,
+) -> Result<Response, RestError> {
+    let transaction = state.get_most_worth_transaction()?;
+    let body = bincode::serialize(&transaction).map_err(PoolError::from)?;
 
     let body = Body::from(body);
 
-    Response::builder()
+    Ok(Response::builder()
         .status(StatusCode::OK)
         .header("Content-Type", "application/octet-stream")
         .body(body)
-        .unwrap()
+        .unwrap())
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -45,18 +52,43 @@ struct BroadcastTx {
     notification: TransactionNotification,
 }
 
+/// Optional proof that a submitted transaction paid the priority fee. Both
+/// fields are needed to recompute the fee output's commitment.
+#[derive(Debug, Deserialize, Default)]
+pub struct PriorityFeeParams {
+    /// The priority fee, as a decimal amount of coins.
+    fee_amount: Option<String>,
+
+    /// Hex-encoded sender randomness of the fee output.
+    sender_randomness: Option<String>,
+}
+
 pub async fn submit_transaction(
     State(state): State<PoolState>,
+    Query(priority_fee): Query<PriorityFeeParams>,
     body: axum::body::Bytes,
 ) -> Result<ErasedJson, RestError> {
-    let tx: BroadcastTx = bincode::deserialize_from(body.reader()).context("deserialize error")?;
-    let id = tx.transaction.kernel.txid().to_string();
-    let transaction = bincode::serialize(&tx.transaction).unwrap();
+    let tx: BroadcastTx = deserialize_body(&body)?;
+    check_min_fee(tx.transaction.kernel.fee, state.min_fee())?;
+    let id = PoolTxId::from(tx.transaction.kernel.txid());
+    let is_priority = match (priority_fee.fee_amount, priority_fee.sender_randomness) {
+        (Some(amount), Some(sender_randomness)) => {
+            pays_priority_fee(&tx.transaction.kernel.outputs, &amount, &sender_randomness)?
+        }
+        (None, None) => false,
+        _ => {
+            return Err(RestError::BadRequest(
+                "fee_amount and sender_randomness must be given together".to_string(),
+            ))
+        }
+    };
+    let transaction = bincode::serialize(&tx.transaction).map_err(PoolError::from)?;
     let fee = tx.transaction.kernel.fee.to_nau();
-    state.add_transaction(&id, &transaction, fee)?;
+    state.add_transaction(&id, &transaction, fee, is_priority)?;
 
-    Ok(ErasedJson::pretty(json!({
-        "id": id,
+    Ok(json_response(json!({
+        "id": id.to_string(),
+        "is_priority": is_priority,
     })))
 }
 
@@ -64,17 +96,19 @@ pub async fn submit_single_proof_transaction(
     State(state): State<PoolState>,
     body: axum::body::Bytes,
 ) -> Result<ErasedJson, RestError> {
-    let tx: BroadcastTx = bincode::deserialize_from(body.reader()).context("deserialize error")?;
-    let id = tx.transaction.kernel.txid().to_string();
+    let tx: BroadcastTx = deserialize_body(&body)?;
+    let id = PoolTxId::from(tx.transaction.kernel.txid());
     if tx.transaction.proof.proof_quality()? != TransactionProofQuality::SingleProof {
-        return Err(RestError("proof quality is not single proof".to_string()));
+        return Err(RestError::Internal(
+            "proof quality is not single proof".to_string(),
+        ));
     }
 
     //TODO: broadcast transaction
 
     state.finish_transaction(&id)?;
 
-    Ok(ErasedJson::pretty(json!({
+    Ok(json_response(json!({
         "status": "broadcasted"
     })))
 }
@@ -83,27 +117,133 @@ pub async fn get_transaction_status(
     State(state): State<PoolState>,
     axum::extract::Path(id): axum::extract::Path<String>,
 ) -> Result<ErasedJson, RestError> {
+    let id: PoolTxId = id.parse()?;
     if let Some((_transaction, created, finished)) = state.get_executing_transaction(&id)? {
         if finished > 0 {
-            return Ok(ErasedJson::pretty(json!({
+            return Ok(json_response(json!({
                 "status": "success",
                 "created_at": created,
                 "finished_at": finished,
             })));
         }
-        return Ok(ErasedJson::pretty(json!({
+        return Ok(json_response(json!({
             "status": "executing",
             "created_at": created,
         })));
     };
 
     if let Some(_transaction) = state.get_pending_transaction(&id)? {
-        return Ok(ErasedJson::pretty(json!({
+        return Ok(json_response(json!({
             "status": "pending"
         })));
     }
 
-    Ok(ErasedJson::pretty(json!({
+    Ok(json_response(json!({
         "status": "outdated"
     })))
 }
+
+/// The raw bytes of a transaction a prover is working on, such that it can be
+/// re-proven after the prover failed.
+pub async fn get_executing_transaction_raw(
+    State(state): State<PoolState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> Result<Response, RestError> {
+    let id: PoolTxId = id.parse()?;
+    let Some((raw_tx, _created, _finished)) = state.get_executing_transaction(&id)? else {
+        return Err(RestError::NotFound(format!(
+            "transaction {id} is not executing"
+        )));
+    };
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/octet-stream")
+        .body(Body::from(raw_tx))
+        .unwrap())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QueueEtaParams {
+    /// Number of provers working through the queue in parallel.
+    #[serde(default = "default_workers")]
+    workers: usize,
+}
+
+fn default_workers() -> usize {
+    1
+}
+
+pub async fn get_queue_eta(
+    State(state): State<PoolState>,
+    Query(params): Query<QueueEtaParams>,
+) -> Result<ErasedJson, RestError> {
+    let avg_proving_duration = state.avg_proving_duration()?;
+    let eta = state.estimated_queue_drain(params.workers)?;
+
+    Ok(json_response(json!({
+        "pending": state.pending_count()?,
+        "workers": params.workers.max(1),
+        "avg_proving_secs": avg_proving_duration.map(|d| d.as_secs_f64()),
+        "eta_secs": eta.as_secs_f64(),
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PurgeFinishedParams {
+    /// Only purge rows that finished at least this many seconds ago.
+    #[serde(default = "default_purge_age_secs")]
+    older_than_secs: u64,
+}
+
+fn default_purge_age_secs() -> u64 {
+    24 * 60 * 60
+}
+
+/// Purge old finished rows. Requires authentication.
+pub async fn purge_finished(
+    State((rpcstate, state)): State<(NeptuneRPCServer, PoolState)>,
+    Query(params): Query<PurgeFinishedParams>,
+    headers: HeaderMap,
+) -> Result<ErasedJson, RestError> {
+    authenticate(&rpcstate, &headers)?;
+
+    let purged = state.purge_finished(Duration::from_secs(params.older_than_secs))?;
+
+    Ok(json_response(json!({
+        "purged": purged,
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MaintenanceParams {
+    /// Only purge rows that finished at least this many seconds ago.
+    #[serde(default = "default_purge_age_secs")]
+    purge_older_than_secs: u64,
+
+    /// Requeue rows that a prover picked up at least this many seconds ago
+    /// without finishing them.
+    #[serde(default = "default_requeue_age_secs")]
+    requeue_older_than_secs: u64,
+}
+
+fn default_requeue_age_secs() -> u64 {
+    6 * 60 * 60
+}
+
+/// Purge old finished rows, requeue stale executing rows, and vacuum the
+/// database. Requires authentication.
+pub async fn run_maintenance(
+    State((rpcstate, state)): State<(NeptuneRPCServer, PoolState)>,
+    Query(params): Query<MaintenanceParams>,
+    headers: HeaderMap,
+) -> Result<ErasedJson, RestError> {
+    authenticate(&rpcstate, &headers)?;
+
+    let summary = state.run_maintenance(
+        Duration::from_secs(params.purge_older_than_secs),
+        Duration::from_secs(params.requeue_older_than_secs),
+    )?;
+
+    Ok(json_response(summary))
+}